@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::hash::Hash;
+use std::net::SocketAddr;
 use std::ops::Deref;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -62,12 +63,14 @@ use sui_storage::{
     write_ahead_log::{DBTxGuard, TxGuard, WriteAheadLog},
     IndexStore,
 };
-use sui_types::committee::EpochId;
+use sui_types::checkpoint_access::AccessClass;
+use sui_types::committee::{EpochId, EpochIdExt};
 use sui_types::crypto::{AuthorityKeyPair, NetworkKeyPair};
 use sui_types::event::{Event, EventID};
 use sui_types::messages_checkpoint::{
-    AuthenticatedCheckpoint, CertifiedCheckpointSummary, CheckpointFragmentMessage,
-    CheckpointRequest, CheckpointRequestType, CheckpointResponse, CheckpointSequenceNumber,
+    AuthenticatedCheckpoint, CheckpointDetail, CheckpointFragmentMessage, CheckpointRequest,
+    CheckpointRequestType, CheckpointResponse, CheckpointSequenceNumber,
+    VerifiedCertifiedCheckpointSummary,
 };
 use sui_types::object::{Owner, PastObjectRead};
 use sui_types::query::{EventQuery, TransactionQuery};
@@ -1388,13 +1391,62 @@ impl AuthorityState {
         &self,
         request: &CheckpointRequest,
     ) -> Result<CheckpointResponse, SuiError> {
+        self.handle_checkpoint_request_from(request, None)
+    }
+
+    /// Handle a checkpoint request from the client at `peer`, if known, as allowed by the
+    /// access policy of the checkpoint store.
+    pub fn handle_checkpoint_request_from(
+        &self,
+        request: &CheckpointRequest,
+        peer: Option<SocketAddr>,
+    ) -> Result<CheckpointResponse, SuiError> {
+        if let Some(client_epoch) = request.client_epoch {
+            let server_epoch = self.epoch();
+            if client_epoch != server_epoch {
+                return Ok(CheckpointResponse::EpochMismatch { server_epoch });
+            }
+        }
         let mut checkpoint_store = self.checkpoints.lock();
+        request.check_detail(checkpoint_store.served_detail)?;
+        if let Some(policy) = &checkpoint_store.access_policy {
+            policy.evaluate(request, AccessClass::of(request), peer)?;
+        }
+        let contents = request.detail.contains(CheckpointDetail::CONTENTS);
         match &request.request_type {
-            CheckpointRequestType::AuthenticatedCheckpoint(seq) => {
-                checkpoint_store.handle_authenticated_checkpoint(seq, request.detail)
-            }
+            CheckpointRequestType::AuthenticatedCheckpoint(seq) => match &request.prefix_filter {
+                Some(prefix) => {
+                    checkpoint_store.handle_authenticated_checkpoint_bucket(seq, prefix, contents)
+                }
+                None if request.detail.contains(CheckpointDetail::CONTENTS_METADATA) => {
+                    checkpoint_store.handle_authenticated_checkpoint_metadata(seq)
+                }
+                None => match seq {
+                    Some(seq)
+                        if request
+                            .detail
+                            .contains(CheckpointDetail::NOT_AVAILABLE_ATTESTATION) =>
+                    {
+                        checkpoint_store.handle_authenticated_checkpoint_or_attest(
+                            *seq,
+                            contents,
+                            self.epoch(),
+                        )
+                    }
+                    _ => checkpoint_store.handle_authenticated_checkpoint(seq, contents),
+                },
+            },
             CheckpointRequestType::CheckpointProposal => {
-                checkpoint_store.handle_proposal(request.detail)
+                checkpoint_store.handle_proposal(request.detail.proposal_contents())
+            }
+            CheckpointRequestType::LatestCheckpointSequenceNumber => {
+                checkpoint_store.handle_latest_sequence_number()
+            }
+            CheckpointRequestType::Bootstrap(bootstrap) => {
+                checkpoint_store.handle_bootstrap(bootstrap)
+            }
+            CheckpointRequestType::CheckpointProposalDiff(diff) => {
+                checkpoint_store.handle_proposal_diff(diff)
             }
         }
     }
@@ -1406,7 +1458,7 @@ impl AuthorityState {
         struct Locals {
             from_db: Option<AuthenticatedCheckpoint>,
             latest_sequence_sent: Option<CheckpointSequenceNumber>,
-            subscriber: broadcast::Receiver<CertifiedCheckpointSummary>,
+            subscriber: broadcast::Receiver<VerifiedCertifiedCheckpointSummary>,
             exit: bool,
         }
 
@@ -1435,7 +1487,7 @@ impl AuthorityState {
             } else {
                 match locals.subscriber.recv().await {
                     Ok(checkpoint) => {
-                        let sequence_number = *checkpoint.summary.sequence_number();
+                        let sequence_number = *checkpoint.data().sequence_number();
                         if locals.latest_sequence_sent.is_none()
                             || sequence_number > locals.latest_sequence_sent.unwrap()
                         {
@@ -1443,7 +1495,9 @@ impl AuthorityState {
                             Some((
                                 Ok(CheckpointStreamResponseItem {
                                     first_available_sequence: 0,
-                                    checkpoint: AuthenticatedCheckpoint::Certified(checkpoint),
+                                    checkpoint: AuthenticatedCheckpoint::Certified(
+                                        checkpoint.into_inner(),
+                                    ),
                                 }),
                                 locals,
                             ))
@@ -1666,7 +1720,8 @@ impl AuthorityState {
             Box::new(store.clone()),
             LogCheckpointOutput::boxed(),
             0,
-        );
+        )
+        .unwrap();
 
         // add the object_basics module
         AuthorityState::new(
@@ -1744,7 +1799,7 @@ impl AuthorityState {
         // TODO: It's likely safer to do the following operations atomically, in case this function
         // gets called from different threads. It cannot happen today, but worth the caution.
         fp_ensure!(
-            self.epoch() + 1 == new_committee.epoch,
+            new_committee.epoch.is_successor_of(self.epoch()),
             SuiError::from("Invalid new epoch to sign and update")
         );
 
@@ -2328,7 +2383,15 @@ impl AuthorityState {
                 data.verify(&self.committee.load()).map_err(|err|{
                     warn!(
                         "Ignoring malformed checkpoint signature (failed to verify) from {}, sequence {}: {:?}",
-                        transaction.consensus_output.certificate.header.author, data.summary.summary.sequence_number, err
+                        transaction.consensus_output.certificate.header.author, data.summary.data().sequence_number, err
+                    );
+                })?;
+            }
+            ConsensusTransactionKind::CheckpointSignatureBatch(batch) => {
+                batch.verify(&self.committee.load()).map_err(|err| {
+                    warn!(
+                        "Ignoring malformed checkpoint signature batch (failed to verify) from {}: {:?}",
+                        transaction.consensus_output.certificate.header.author, err
                     );
                 })?;
             }
@@ -2395,7 +2458,7 @@ impl AuthorityState {
                     CheckpointFragmentMessage::Header(header) => {
                         debug!(
                             ?consensus_index,
-                            cp_seq=?header.proposer.summary.sequence_number,
+                            cp_seq=?header.proposer.data().sequence_number,
                             proposer=?header.proposer.authority().concise(),
                             other=?header.other.authority().concise(),
                             chunk_count=?header.chunk_count,
@@ -2442,6 +2505,12 @@ impl AuthorityState {
             ConsensusTransactionKind::CheckpointSignature(info) => {
                 self.checkpoint_service.notify_checkpoint_signature(info)
             }
+            ConsensusTransactionKind::CheckpointSignatureBatch(batch) => {
+                (*batch).into_messages().try_for_each(|info| {
+                    self.checkpoint_service
+                        .notify_checkpoint_signature(Box::new(info))
+                })
+            }
         }
     }
 
@@ -16,12 +16,14 @@ use prometheus::{
 };
 use sui_types::{
     base_types::{AuthorityName, ExecutionDigests},
+    checkpoint_response_consistency::ResponseConsistencyChecker,
     error::{SuiError, SuiResult},
     messages::CertifiedTransaction,
     messages_checkpoint::{
-        AuthenticatedCheckpoint, CertifiedCheckpointSummary, CheckpointContents, CheckpointDigest,
-        CheckpointFragment, CheckpointProposal, CheckpointRequest, CheckpointResponse,
-        CheckpointSequenceNumber, SignedCheckpointSummary,
+        AuthenticatedCheckpoint, CertifiedCheckpointSummary, CheckpointContents,
+        CheckpointFragment, CheckpointId, CheckpointProposal, CheckpointRequest,
+        CheckpointResponse, CheckpointSequenceNumber, LocalProposal, RemoteProposal,
+        SignedCheckpointSummary,
     },
 };
 use tokio::time::Instant;
@@ -301,10 +303,10 @@ where
         let next_checkpoint = state_checkpoints.lock().next_checkpoint();
         // First sync until before the latest checkpoint. We will special
         // handle the latest checkpoint latter.
-        if next_checkpoint < checkpoint.summary.sequence_number {
+        if next_checkpoint < checkpoint.data().sequence_number {
             info!(
                 cp_seq=?next_checkpoint,
-                latest_cp_seq=?checkpoint.summary.sequence_number,
+                latest_cp_seq=?checkpoint.data().sequence_number,
                 "Checkpoint is behind the latest in the network, start syncing",
             );
             // TODO: The sync process only works within an epoch.
@@ -360,7 +362,7 @@ where
     // (5) Execute all transactions in the checkpoint and sign it.
     sync_and_sign_new_checkpoint(
         active_authority,
-        my_proposal.signed_summary.auth_signature.epoch,
+        my_proposal.signed_summary.epoch(),
         *my_proposal.sequence_number(),
         transactions,
     )
@@ -519,7 +521,7 @@ where
     for state in &final_state.responses {
         if let Some(AuthenticatedCheckpoint::Certified(cert)) = &state.1 {
             if let Some(old_cert) = &highest_certificate_cert {
-                if cert.summary.sequence_number > old_cert.summary.sequence_number {
+                if cert.data().sequence_number > old_cert.data().sequence_number {
                     highest_certificate_cert = Some(cert.clone());
                 }
             } else {
@@ -529,9 +531,8 @@ where
     }
 
     // Attempt to construct a newer checkpoint from signed summaries.
-    #[allow(clippy::type_complexity)]
     let mut partial_checkpoints: BTreeMap<
-        (CheckpointSequenceNumber, CheckpointDigest),
+        CheckpointId,
         Vec<(AuthorityName, SignedCheckpointSummary)>,
     > = BTreeMap::new();
     final_state.responses.iter().for_each(|(auth, checkpoint)| {
@@ -539,14 +540,14 @@ where
             // We are interested in this signed checkpoint only if it is
             // newer than the highest known cert checkpoint.
             if let Some(newest_checkpoint) = &highest_certificate_cert {
-                if newest_checkpoint.summary.sequence_number >= signed.summary.sequence_number {
+                if newest_checkpoint.data().sequence_number >= signed.data().sequence_number {
                     return;
                 }
             }
 
-            // Collect signed checkpoints by sequence number and digest.
+            // Collect signed checkpoints by identity.
             partial_checkpoints
-                .entry((signed.summary.sequence_number, signed.summary.digest()))
+                .entry(CheckpointId::from(signed))
                 .or_insert_with(Vec::new)
                 .push((*auth, signed.clone()));
         }
@@ -556,7 +557,7 @@ where
     // sequence numbers. If we find a valid checkpoint we are sure this is the highest.
     partial_checkpoints
         .iter()
-        .for_each(|((seq, _digest), signed)| {
+        .for_each(|(id, signed)| {
             let weight: StakeUnit = signed
                 .iter()
                 .map(|(auth, _)| net.committee.weight(auth))
@@ -568,12 +569,12 @@ where
             //           the checkpoint for others to download.
             if weight >= net.committee.validity_threshold() {
                 // Try to construct a valid checkpoint.
-                let certificate = CertifiedCheckpointSummary::aggregate(
-                    signed.iter().map(|(_, signed)| signed.clone()).collect(),
+                let certificate = CertifiedCheckpointSummary::aggregate_refs(
+                    signed.iter().map(|(_, signed)| signed),
                     &net.committee,
                 );
                 if let Ok(cert) = certificate {
-                    debug!(cp_seq=?seq, "A checkpoint certificate is formed from the network");
+                    debug!(cp_seq=?id.sequence_number, "A checkpoint certificate is formed from the network");
                     highest_certificate_cert = Some(cert);
                 }
             }
@@ -601,13 +602,13 @@ where
         NewCert,
         Nothing,
     }
-    let seq = checkpoint.summary.sequence_number;
+    let seq = checkpoint.data().sequence_number;
     let action = match latest_local_checkpoint {
         None => Action::NewCert,
-        Some(AuthenticatedCheckpoint::Certified(c)) if c.summary.sequence_number + 1 == seq => {
+        Some(AuthenticatedCheckpoint::Certified(c)) if c.data().sequence_number + 1 == seq => {
             Action::NewCert
         }
-        Some(AuthenticatedCheckpoint::Signed(s)) if s.summary.sequence_number == seq => {
+        Some(AuthenticatedCheckpoint::Signed(s)) if s.data().sequence_number == seq => {
             Action::Promote
         }
         Some(a) => {
@@ -626,7 +627,7 @@ where
                 .lock()
                 .promote_signed_checkpoint_to_cert(checkpoint, committee)?;
             info!(
-                cp_seq=?checkpoint.summary.sequence_number(),
+                cp_seq=?checkpoint.data().sequence_number(),
                 "Updated local signed checkpoint to certificate",
             );
             Ok(true)
@@ -647,8 +648,9 @@ where
                 .collect::<SuiResult<_>>()?;
             let (_, contents) = get_one_checkpoint_with_contents(
                 active_authority.net.load().clone(),
-                checkpoint.summary.sequence_number,
+                checkpoint.data().sequence_number,
                 &available_authorities,
+                &ResponseConsistencyChecker::new(),
             )
             .await?;
             process_new_checkpoint_certificate(
@@ -660,7 +662,7 @@ where
             )
             .await?;
             info!(
-                cp_seq=?checkpoint.summary.sequence_number(),
+                cp_seq=?checkpoint.data().sequence_number(),
                 "Stored new checkpoint certificate",
             );
             Ok(true)
@@ -691,12 +693,17 @@ where
         .map(|&&x| x)
         .collect();
 
+    // The responses of the authorities during this sync, to skip those contradicting
+    // themselves.
+    let session = ResponseConsistencyChecker::new();
+
     // Check if the latest checkpoint is merely a signed checkpoint, and if
     // so download a full certificate for it.
     if let Some(AuthenticatedCheckpoint::Signed(signed)) = &latest_checkpoint {
-        let seq = *signed.summary.sequence_number();
+        let seq = *signed.data().sequence_number();
         debug!(name = ?state.name, ?seq, "Partial Sync",);
-        let (past, _) = get_one_checkpoint(net.clone(), seq, false, &available_authorities).await?;
+        let (past, _) =
+            get_one_checkpoint(net.clone(), seq, false, &available_authorities, &session).await?;
 
         checkpoint_db
             .lock()
@@ -707,10 +714,11 @@ where
         .map(|chk| chk.summary().sequence_number + 1)
         .unwrap_or(0);
 
-    for seq in full_sync_start..latest_known_checkpoint.summary.sequence_number {
+    for seq in full_sync_start..latest_known_checkpoint.data().sequence_number {
         debug!(name = ?state.name, ?seq, "Full Sync",);
         let (past, contents) =
-            get_one_checkpoint_with_contents(net.clone(), seq, &available_authorities).await?;
+            get_one_checkpoint_with_contents(net.clone(), seq, &available_authorities, &session)
+                .await?;
 
         process_new_checkpoint_certificate(
             active_authority.clone(),
@@ -722,6 +730,15 @@ where
         .await?;
     }
 
+    for conflict in session.take_conflicts() {
+        error!(
+            authority = ?conflict.authority,
+            cp_seq = ?conflict.first.sequence_number(),
+            "Authority served conflicting checkpoints during sync: {:?}",
+            conflict
+        );
+    }
+
     Ok(())
 }
 
@@ -735,7 +752,35 @@ async fn process_new_checkpoint_certificate<A>(
 where
     A: AuthorityAPI + Send + Sync + 'static + Clone,
 {
-    let epoch = checkpoint_cert.summary.epoch;
+    // Peers asking for the checkpoint being executed are served from memory, and its contents
+    // stay cached until it is stored.
+    let seq = checkpoint_cert.data().sequence_number;
+    let cache = checkpoint_db.lock().contents_cache();
+    cache.pin(seq);
+    cache.insert(seq, Arc::new(contents.clone()));
+    let result = execute_checkpoint_certificate(
+        active_authority,
+        checkpoint_db,
+        committee,
+        checkpoint_cert,
+        contents,
+    )
+    .await;
+    cache.unpin(seq);
+    result
+}
+
+async fn execute_checkpoint_certificate<A>(
+    active_authority: Arc<ActiveAuthority<A>>,
+    checkpoint_db: &Arc<Mutex<CheckpointStore>>,
+    committee: &Committee,
+    checkpoint_cert: &CertifiedCheckpointSummary,
+    contents: &CheckpointContents,
+) -> SuiResult
+where
+    A: AuthorityAPI + Send + Sync + 'static + Clone,
+{
+    let epoch = checkpoint_cert.data().epoch;
     let errors = active_authority
         .node_sync_handle()
         .sync_checkpoint_cert_transactions(epoch, contents)
@@ -753,7 +798,7 @@ where
 
     if !errors.is_empty() {
         let error = "Failed to sync transactions in checkpoint".to_string();
-        error!(cp_seq=?checkpoint_cert.summary.sequence_number, "{}", error);
+        error!(cp_seq=?checkpoint_cert.data().sequence_number, "{}", error);
         return Err(SuiError::CheckpointingError { error });
     }
 
@@ -766,11 +811,12 @@ pub async fn get_one_checkpoint_with_contents<A>(
     net: Arc<AuthorityAggregator<A>>,
     sequence_number: CheckpointSequenceNumber,
     available_authorities: &BTreeSet<AuthorityName>,
+    session: &ResponseConsistencyChecker,
 ) -> Result<(CertifiedCheckpointSummary, CheckpointContents), SuiError>
 where
     A: AuthorityAPI + Send + Sync + 'static + Clone,
 {
-    get_one_checkpoint(net, sequence_number, true, available_authorities)
+    get_one_checkpoint(net, sequence_number, true, available_authorities, session)
         .await
         // unwrap ok because of true param above.
         .map(|ok| (ok.0, ok.1.unwrap()))
@@ -778,13 +824,14 @@ where
 
 /// Gets one checkpoint certificate and optionally its contents. Note this must be
 /// given a checkpoint number that the validator knows exists, for examples because
-/// they have seen a subsequent certificate.
+/// they have seen a subsequent certificate. Responses are recorded in `session`.
 #[allow(clippy::collapsible_match)]
 pub async fn get_one_checkpoint<A>(
     net: Arc<AuthorityAggregator<A>>,
     sequence_number: CheckpointSequenceNumber,
     contents: bool,
     available_authorities: &BTreeSet<AuthorityName>,
+    session: &ResponseConsistencyChecker,
 ) -> Result<(CertifiedCheckpointSummary, Option<CheckpointContents>), SuiError>
 where
     A: AuthorityAPI + Send + Sync + 'static + Clone,
@@ -795,17 +842,22 @@ where
         available_authorities,
         // Loop forever until we get the cert from someone.
         None,
+        session,
     )
     .await
 }
 
+/// The number of transactions the proposals of two authorities are expected to differ by, which
+/// sizes the sketch sent in diff requests. Larger differences fall back to the full proposal.
+const EXPECTED_PROPOSAL_DIFFERENCE: usize = 256;
+
 /// Attempt to construct checkpoint content based on the fragments received so far.
 /// If it didn't't succeed, pick an authority at random that we haven't seen fragments
 /// with yet, make a new fragment and send to consensus.
 pub async fn create_fragments<A>(
     active_authority: Arc<ActiveAuthority<A>>,
     checkpoint_db: Arc<Mutex<CheckpointStore>>,
-    my_proposal: &CheckpointProposal,
+    my_proposal: &LocalProposal,
     committee: &Committee,
 ) -> Option<BTreeSet<ExecutionDigests>>
 where
@@ -857,6 +909,37 @@ where
     // Get a client
     let client = active_authority.net.load().authority_clients[&authority].clone();
 
+    // Ask for the difference to our proposal first, which only transfers the transactions the
+    // proposals differ by. Any failure falls back to downloading the full proposal.
+    match client
+        .handle_checkpoint(CheckpointRequest::proposal_diff(
+            my_proposal.sketch(EXPECTED_PROPOSAL_DIFFERENCE),
+        ))
+        .await
+    {
+        Ok(CheckpointResponse::CheckpointProposalDiff {
+            response: Some(response),
+        }) => match my_proposal.fragment_from_diff_response(&response) {
+            Ok(fragment) => {
+                augment_and_submit_fragment(active_authority, &checkpoint_db, fragment).await;
+                return None;
+            }
+            Err(err) => {
+                debug!(
+                    validator=?authority.concise(),
+                    "Cannot build a fragment from the proposal diff: {err:?}"
+                );
+            }
+        },
+        Ok(_) => {}
+        Err(err) => {
+            debug!(
+                validator=?authority.concise(),
+                "Error querying the proposal diff: {err:?}"
+            );
+        }
+    }
+
     // TODO: We should make this a loop and exit until the first success.
     match client
         .handle_checkpoint(CheckpointRequest::proposal(true))
@@ -871,10 +954,10 @@ where
             {
                 // Check if there is a latest checkpoint
                 if let Some(prev) = prev_cert {
-                    if prev.summary.sequence_number >= next_cp_seq {
+                    if prev.data().sequence_number >= next_cp_seq {
                         // We are now behind, stop the process
                         debug!(
-                            latest_cp_cert_seq=?prev.summary.sequence_number,
+                            latest_cp_cert_seq=?prev.data().sequence_number,
                             expected_cp_seq=?next_cp_seq,
                             "We are behind, abort checkpoint construction process",
                         );
@@ -892,42 +975,37 @@ where
                 }
 
                 // Check the proposal is also for the same checkpoint sequence number
-                if &proposal.as_ref().unwrap().summary.sequence_number
+                if &proposal.as_ref().unwrap().data().sequence_number
                     != my_proposal.sequence_number()
                 {
                     // Target validator may be byzantine or behind, ignore it.
                     debug!(
                         validator=?authority.concise(),
-                        received_cp_seq=proposal.as_ref().unwrap().summary.sequence_number,
+                        received_cp_seq=proposal.as_ref().unwrap().data().sequence_number,
                         "Queried validator is behind"
                     );
                     return None;
                 }
 
-                let other_proposal = CheckpointProposal::new_from_signed_proposal_summary(
-                    proposal.as_ref().unwrap().clone(),
-                    proposal_contents.as_ref().unwrap().clone(),
-                );
-
-                let fragment = my_proposal.fragment_with(&other_proposal);
-
-                // We need to augment the fragment with the missing transactions
-                match augment_fragment_with_diff_transactions(active_authority.clone(), fragment)
-                    .await
-                {
-                    Ok(fragment) => {
-                        // On success send the fragment to consensus
-                        if let Err(err) = checkpoint_db
-                            .lock()
-                            .submit_local_fragment_to_consensus(&fragment)
-                        {
-                            warn!("Error submitting local fragment to consensus: {err:?}");
-                        }
-                    }
+                let other_proposal = match RemoteProposal::new(
+                    CheckpointProposal::new_from_signed_proposal_summary(
+                        proposal.as_ref().unwrap().clone(),
+                        proposal_contents.as_ref().unwrap().clone(),
+                    ),
+                    &active_authority.state.name,
+                ) {
+                    Ok(other_proposal) => other_proposal,
                     Err(err) => {
-                        warn!("Error augmenting the fragment: {err:?}");
+                        warn!(
+                            validator=?authority.concise(),
+                            "Invalid proposal received: {err:?}"
+                        );
+                        return None;
                     }
-                }
+                };
+
+                let fragment = my_proposal.fragment_with_remote(&other_proposal);
+                augment_and_submit_fragment(active_authority, &checkpoint_db, fragment).await;
             }
         }
         Err(err) => {
@@ -940,6 +1018,30 @@ where
     None
 }
 
+/// Augment a fragment with its missing transactions, and send it to consensus.
+async fn augment_and_submit_fragment<A>(
+    active_authority: Arc<ActiveAuthority<A>>,
+    checkpoint_db: &Mutex<CheckpointStore>,
+    fragment: CheckpointFragment,
+) where
+    A: AuthorityAPI + Send + Sync + 'static + Clone,
+{
+    match augment_fragment_with_diff_transactions(active_authority, fragment).await {
+        Ok(fragment) => {
+            // On success send the fragment to consensus
+            if let Err(err) = checkpoint_db
+                .lock()
+                .submit_local_fragment_to_consensus(&fragment)
+            {
+                warn!("Error submitting local fragment to consensus: {err:?}");
+            }
+        }
+        Err(err) => {
+            warn!("Error augmenting the fragment: {err:?}");
+        }
+    }
+}
+
 /// Given a fragment with this authority as the proposer and another authority as the counterpart,
 /// augment the fragment with all actual certificates corresponding to the differences. Some will
 /// come from the local database, but others will come from downloading them from the other
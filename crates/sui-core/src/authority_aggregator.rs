@@ -20,6 +20,7 @@ use sui_metrics::{monitored_future, spawn_monitored_task};
 use sui_network::{
     default_mysten_network_config, DEFAULT_CONNECT_TIMEOUT_SEC, DEFAULT_REQUEST_TIMEOUT_SEC,
 };
+use sui_types::checkpoint_response_consistency::ResponseConsistencyChecker;
 use sui_types::crypto::{AuthorityPublicKeyBytes, AuthoritySignInfo};
 use sui_types::object::{Object, ObjectFormatOptions, ObjectRead};
 use sui_types::sui_system_state::SuiSystemState;
@@ -44,7 +45,7 @@ use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::string::ToString;
 use std::sync::Arc;
 use std::time::Duration;
-use sui_types::committee::{CommitteeWithNetAddresses, StakeUnit};
+use sui_types::committee::{CommitteeWithNetAddresses, EpochIdExt, StakeUnit};
 use tokio::sync::mpsc::Receiver;
 use tokio::time::{sleep, timeout};
 
@@ -327,7 +328,7 @@ impl<A> AuthorityAggregator<A> {
         // gets called from different threads. It cannot happen today, but worth the caution.
         let new_committee = committee.committee;
         fp_ensure!(
-            self.committee.epoch + 1 == new_committee.epoch,
+            new_committee.epoch.is_successor_of(self.committee.epoch),
             SuiError::AdvanceEpochError {
                 error: format!(
                     "Trying to advance from epoch {} to epoch {}",
@@ -2058,6 +2059,8 @@ where
         .await
     }
 
+    /// Responses are recorded in `session`, so that an authority contradicting an earlier
+    /// response of the session is skipped.
     pub async fn get_certified_checkpoint(
         &self,
         sequence_number: CheckpointSequenceNumber,
@@ -2065,6 +2068,7 @@ where
         // authorities known to have the checkpoint we are requesting.
         authorities: &BTreeSet<AuthorityName>,
         timeout_total: Option<Duration>,
+        session: &ResponseConsistencyChecker,
     ) -> SuiResult<(CertifiedCheckpointSummary, Option<CheckpointContents>)> {
         let request = CheckpointRequest::authenticated(Some(sequence_number), request_contents);
         self.quorum_once_with_timeout(
@@ -2073,7 +2077,7 @@ where
             |_, client| {
                 let r = request.clone();
                 Box::pin(async move {
-                    let resp = client.handle_checkpoint(r).await?;
+                    let resp = client.handle_checkpoint_in_session(r, session).await?;
 
                     if let CheckpointResponse::AuthenticatedCheckpoint {
                         checkpoint: Some(AuthenticatedCheckpoint::Certified(past)),
@@ -578,9 +578,10 @@ impl Validator for ValidatorService {
         &self,
         request: tonic::Request<CheckpointRequest>,
     ) -> Result<tonic::Response<CheckpointResponse>, tonic::Status> {
+        let peer = request.remote_addr();
         let request = request.into_inner();
 
-        let response = self.state.handle_checkpoint_request(&request)?;
+        let response = self.state.handle_checkpoint_request_from(&request, peer)?;
 
         return Ok(tonic::Response::new(response));
     }
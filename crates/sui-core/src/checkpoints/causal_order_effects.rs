@@ -63,8 +63,7 @@ pub trait EffectsStore {
             .iter()
             .map(|e| {
                 // We have checked above all transactions have effects so unwrap is ok.
-                let e = &e.as_ref().unwrap();
-                ExecutionDigests::new(e.transaction_digest, e.digest())
+                e.as_ref().unwrap().execution_digests()
             })
             .collect::<Vec<ExecutionDigests>>();
 
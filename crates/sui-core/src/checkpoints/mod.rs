@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod causal_order_effects;
+pub mod pipeline;
 pub mod reconstruction;
+pub mod waiter;
 
 #[cfg(test)]
 #[path = "./tests/checkpoint_tests.rs"]
@@ -15,8 +17,16 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 use std::{path::Path, sync::Arc};
 use sui_storage::default_db_options;
+use sui_types::checkpoint_access::CheckpointAccessPolicy;
+use sui_types::checkpoint_cache::ContentsCache;
+use sui_types::checkpoint_construction::{CheckpointConstructionState, StateDump};
+use sui_types::checkpoint_notifier::CertifiedCheckpointNotifier;
+use sui_types::checkpoint_object_changes::ObjectChanges;
+use sui_types::checkpoint_proposal_diff::CheckpointProposalDiffRequest;
+use sui_types::checkpoint_signing_guard::PersistedSigningGuard;
+use sui_types::checkpoint_trace::TxCheckpointTrace;
 use sui_types::messages_checkpoint::{
-    CheckpointFragmentMessage, CheckpointProposal, CheckpointProposalContents,
+    CheckpointFragmentMessage, CheckpointProposalContents, DecodeLimits, LocalProposal,
     SignedCheckpointFragmentMessage,
 };
 use sui_types::{
@@ -26,13 +36,15 @@ use sui_types::{
     error::{SuiError, SuiResult},
     fp_ensure,
     messages_checkpoint::{
-        AuthenticatedCheckpoint, CertifiedCheckpointSummary, CheckpointContents, CheckpointDigest,
-        CheckpointFragment, CheckpointResponse, CheckpointSequenceNumber, CheckpointSummary,
-        SignedCheckpointSummary,
+        AuthenticatedCheckpoint, CertifiedCheckpointSummary, CheckpointBootstrapRequest,
+        CheckpointBootstrapResponse, CheckpointContents, CheckpointDetail, CheckpointDigest,
+        CheckpointFragment, CheckpointId, CheckpointResponse, CheckpointSequenceInfo,
+        CheckpointSequenceNumber, DigestPrefix, NotAvailableAttestation,
+        ProvisionalCheckpointSummary, SignedCheckpointSequenceInfo, SignedNotAvailableAttestation,
+        SigningPolicy, VerifiedCertifiedCheckpointSummary,
     },
 };
-use tap::TapFallible;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, watch};
 use tracing::{debug, error, info};
 use typed_store::rocks::DBOptions;
 use typed_store::traits::TypedStoreDebug;
@@ -46,6 +58,7 @@ use typed_store_derive::DBMapUtils;
 use crate::authority::StableSyncAuthoritySigner;
 use crate::checkpoints::causal_order_effects::{CausalOrder, EffectsStore};
 use crate::checkpoints::reconstruction::SpanGraph;
+use crate::checkpoints::waiter::CheckpointWaiter;
 
 pub type DBLabel = usize;
 const LOCALS: DBLabel = 0;
@@ -54,6 +67,9 @@ const LOCALS: DBLabel = 0;
 // TODO: Make this bigger.
 pub const CHECKPOINT_COUNT_PER_EPOCH: u64 = 3;
 
+/// The memory budget of the contents cache of a store, see `CheckpointStore::contents_cache`.
+pub const DEFAULT_CONTENTS_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
 #[derive(Clone, Serialize, Deserialize, Debug, Default)]
 pub struct CheckpointLocals {
     /// The next checkpoint certificate number expected.
@@ -70,7 +86,7 @@ pub struct CheckpointLocals {
 
     // The current checkpoint proposal if any
     #[serde(skip)]
-    pub current_proposal: Option<CheckpointProposal>,
+    pub current_proposal: Option<LocalProposal>,
 
     /// The checkpoint sequence number that we are currently actively constructing through
     /// the span graph using fragments.
@@ -226,13 +242,46 @@ pub struct CheckpointStore {
     /// Whether reconfiguration is enabled.
     pub enable_reconfig: bool,
 
+    /// The details of checkpoints served to clients. Requests for other details are
+    /// rejected.
+    pub served_detail: CheckpointDetail,
+
+    /// Decides which checkpoint requests are served to which clients. All requests are
+    /// served if unset.
+    pub access_policy: Option<Arc<dyn CheckpointAccessPolicy + Send + Sync>>,
+
+    /// Reviews the summaries of new checkpoints before they are signed.
+    pub signing_policy: Option<Arc<dyn SigningPolicy + Send + Sync>>,
+
+    /// Refuses to sign the summaries and proposals that would make this authority
+    /// equivocate, e.g. after its database is restored from a backup. Nothing is refused if
+    /// unset. Set when the store is opened, since reopening signs the proposal again.
+    signing_guard: Option<Arc<PersistedSigningGuard>>,
+
+    /// Records the proposals, fragments and checkpoints of selected transactions. Nothing is
+    /// traced if unset.
+    pub tx_trace: Option<Arc<TxCheckpointTrace>>,
+
+    /// The contents served to clients, shared with the checkpoint executor which pins the
+    /// checkpoint it executes.
+    contents_cache: Arc<ContentsCache>,
+
     /// Consensus sender
     sender: Option<Box<dyn ConsensusSender>>,
 
     /// DBMap tables
     pub tables: CheckpointStoreTables,
 
-    notify_new_checkpoint_tx: broadcast::Sender<CertifiedCheckpointSummary>,
+    /// The proposals, fragments and signatures of the checkpoints under construction, kept
+    /// in memory until they are certified.
+    construction_state: CheckpointConstructionState,
+
+    checkpoint_notifier: CertifiedCheckpointNotifier,
+
+    /// The highest certified checkpoint stored, watched by `CheckpointWaiter`s.
+    certified_watermark_tx: watch::Sender<Option<CheckpointSequenceNumber>>,
+    // Kept so that updates of the watermark are never lost for lack of receivers.
+    certified_watermark_rx: watch::Receiver<Option<CheckpointSequenceNumber>>,
 }
 
 impl CheckpointStore {
@@ -243,6 +292,17 @@ impl CheckpointStore {
         Ok(self.tables.checkpoints.get(&seq)?)
     }
 
+    /// The stored checkpoint with identity `id`, if the checkpoint stored at its sequence
+    /// number is that very checkpoint.
+    pub fn get_checkpoint_by_id(
+        &self,
+        id: &CheckpointId,
+    ) -> Result<Option<AuthenticatedCheckpoint>, SuiError> {
+        Ok(self
+            .get_checkpoint(id.sequence_number)?
+            .filter(|checkpoint| CheckpointId::from(checkpoint) == *id))
+    }
+
     // TODO: there might be more efficient ways to implement this.
     pub fn get_checkpoints_of_epoch(&self, epoch: EpochId) -> Vec<AuthenticatedCheckpoint> {
         self.tables
@@ -266,8 +326,21 @@ impl CheckpointStore {
     }
 
     /// Subscribe to new checkpoints.
-    pub fn subscribe_to_checkpoints(&self) -> broadcast::Receiver<CertifiedCheckpointSummary> {
-        self.notify_new_checkpoint_tx.subscribe()
+    pub fn subscribe_to_checkpoints(
+        &self,
+    ) -> broadcast::Receiver<VerifiedCertifiedCheckpointSummary> {
+        self.checkpoint_notifier.subscribe()
+    }
+
+    /// The notifier of the new certified checkpoints stored, which can be shared with the
+    /// components that need to subscribe without access to the store.
+    pub fn checkpoint_notifier(&self) -> CertifiedCheckpointNotifier {
+        self.checkpoint_notifier.clone()
+    }
+
+    /// Wait for certified checkpoints to be stored.
+    pub fn waiter(&self) -> CheckpointWaiter {
+        CheckpointWaiter::new(self.certified_watermark_rx.clone())
     }
 
     // Manage persistent local variables
@@ -278,6 +351,7 @@ impl CheckpointStore {
         cur_committee: &Committee,
         name: AuthorityName,
         secret: StableSyncAuthoritySigner,
+        signing_guard: Option<&PersistedSigningGuard>,
     ) -> SuiResult<CheckpointLocals> {
         // Loads locals from disk, or inserts initial locals
         let mut locals = match tables.locals.get(&LOCALS)? {
@@ -294,15 +368,23 @@ impl CheckpointStore {
                 .filter(|(_, seq)| seq < locals.proposal_next_transaction.as_ref().unwrap())
                 .map(|(digest, _)| digest);
             let transactions = CheckpointProposalContents::new(transactions);
-            let proposal = CheckpointProposal::new(
+            match LocalProposal::new(
                 cur_committee.epoch,
                 checkpoint_sequence,
                 name,
                 &*secret,
                 transactions,
-            );
-
-            locals.current_proposal = Some(proposal);
+                signing_guard,
+            ) {
+                Ok(proposal) => locals.current_proposal = Some(proposal),
+                // E.g. the database was restored from a backup older than the proposal last
+                // signed. A proposal is only made again once the guard allows it.
+                Err(err) => {
+                    error!(cp_seq=?checkpoint_sequence, "Not restoring the proposal: {}", err);
+                    locals.current_proposal = None;
+                    locals.proposal_next_transaction = None;
+                }
+            }
         }
 
         tables.advance_checkpoint_construction_state(&mut locals, cur_committee)?;
@@ -337,6 +419,40 @@ impl CheckpointStore {
         Ok(())
     }
 
+    /// The cache of the contents served to clients.
+    pub fn contents_cache(&self) -> Arc<ContentsCache> {
+        self.contents_cache.clone()
+    }
+
+    /// Serve contents from `cache` instead of the default one, e.g. to size it differently or
+    /// to share it with other servers.
+    pub fn set_contents_cache(&mut self, cache: Arc<ContentsCache>) {
+        self.contents_cache = cache;
+    }
+
+    /// The contents of checkpoint `seq`, from the cache or else from the database.
+    fn get_contents(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> SuiResult<Option<Arc<CheckpointContents>>> {
+        if let Some(contents) = self.contents_cache.get(seq) {
+            return Ok(Some(contents));
+        }
+        let contents = self.tables.checkpoint_contents.get(&seq)?.map(Arc::new);
+        if let Some(contents) = &contents {
+            self.contents_cache.insert(seq, contents.clone());
+        }
+        Ok(contents)
+    }
+
+    /// Split fragments and check the fragment messages received with the limits of the
+    /// network, instead of the defaults.
+    pub fn set_decode_limits(&mut self, limits: DecodeLimits) -> SuiResult {
+        limits.validate()?;
+        self.construction_state.set_limits(limits);
+        Ok(())
+    }
+
     /// Open a checkpoint store to use to generate checkpoints, incl the information
     /// needed to sign new checkpoints.
     pub fn open(
@@ -346,6 +462,28 @@ impl CheckpointStore {
         name: AuthorityName,
         secret: StableSyncAuthoritySigner,
         enable_reconfig: bool,
+    ) -> Result<CheckpointStore, SuiError> {
+        Self::open_with_signing_guard(
+            path,
+            db_options,
+            current_committee,
+            name,
+            secret,
+            enable_reconfig,
+            None,
+        )
+    }
+
+    /// Same as `open`, with every summary and proposal signed by the store, including the
+    /// proposal restored from the database, checked against `signing_guard`.
+    pub fn open_with_signing_guard(
+        path: &Path,
+        db_options: Option<Options>,
+        current_committee: &Committee,
+        name: AuthorityName,
+        secret: StableSyncAuthoritySigner,
+        enable_reconfig: bool,
+        signing_guard: Option<Arc<PersistedSigningGuard>>,
     ) -> Result<CheckpointStore, SuiError> {
         let tables =
             CheckpointStoreTables::open_tables_read_write(path.to_path_buf(), db_options, None);
@@ -354,19 +492,42 @@ impl CheckpointStore {
             current_committee,
             name,
             secret.clone(),
+            signing_guard.as_deref(),
         )?);
-        let (notify_new_checkpoint_tx, _) = broadcast::channel(16);
+        let highest_certified = tables
+            .checkpoints
+            .iter()
+            .skip_to_last()
+            .reverse()
+            .find(|(_, ckp)| matches!(ckp, AuthenticatedCheckpoint::Certified(_)))
+            .map(|(seq, _)| seq);
+        let (certified_watermark_tx, certified_watermark_rx) = watch::channel(highest_certified);
         Ok(CheckpointStore {
             name,
             secret,
             memory_locals,
             enable_reconfig,
+            served_detail: CheckpointDetail::SERVED,
+            access_policy: None,
+            signing_policy: None,
+            signing_guard,
+            tx_trace: None,
+            contents_cache: Arc::new(ContentsCache::new(DEFAULT_CONTENTS_CACHE_BYTES)),
             sender: None,
             tables,
-            notify_new_checkpoint_tx,
+            construction_state: CheckpointConstructionState::new(),
+            checkpoint_notifier: CertifiedCheckpointNotifier::default(),
+            certified_watermark_tx,
+            certified_watermark_rx,
         })
     }
 
+    /// The state of the checkpoint under construction, for operators to inspect a stuck
+    /// node, see `CheckpointConstructionState::import`.
+    pub fn export_construction_state(&self) -> StateDump {
+        self.construction_state.export()
+    }
+
     // Define handlers for request
 
     pub fn handle_proposal(&mut self, detail: bool) -> Result<CheckpointResponse, SuiError> {
@@ -383,8 +544,8 @@ impl CheckpointStore {
         };
 
         let prev_cert = match &signed_proposal {
-            Some(proposal) if proposal.summary.sequence_number > 0 => {
-                let seq = proposal.summary.sequence_number;
+            Some(proposal) if proposal.data().sequence_number > 0 => {
+                let seq = proposal.data().sequence_number;
                 let checkpoint = self.tables.checkpoints.get(&(seq - 1))?;
                 match checkpoint {
                     Some(AuthenticatedCheckpoint::Signed(_)) | None => {
@@ -410,6 +571,19 @@ impl CheckpointStore {
         })
     }
 
+    /// Answer a diff request with the difference to the current proposal, see
+    /// `checkpoint_proposal_diff`.
+    pub fn handle_proposal_diff(
+        &mut self,
+        request: &CheckpointProposalDiffRequest,
+    ) -> SuiResult<CheckpointResponse> {
+        let response = match &self.get_locals().current_proposal {
+            Some(proposal) => Some(proposal.answer_diff_request(request)?),
+            None => None,
+        };
+        Ok(CheckpointResponse::CheckpointProposalDiff { response })
+    }
+
     pub fn handle_authenticated_checkpoint(
         &mut self,
         seq: &Option<CheckpointSequenceNumber>,
@@ -421,9 +595,8 @@ impl CheckpointStore {
         };
         let contents = match (&checkpoint, detail) {
             (Some(c), true) => self
-                .tables
-                .checkpoint_contents
-                .get(&c.summary().sequence_number)?,
+                .get_contents(c.summary().sequence_number)?
+                .map(|contents| contents.as_ref().clone()),
             _ => None,
         };
         Ok(CheckpointResponse::AuthenticatedCheckpoint {
@@ -432,6 +605,186 @@ impl CheckpointStore {
         })
     }
 
+    /// Same as `handle_authenticated_checkpoint` for checkpoint `seq`, answering with an
+    /// attestation signed for `epoch` if the checkpoint is not stored yet.
+    pub fn handle_authenticated_checkpoint_or_attest(
+        &mut self,
+        seq: CheckpointSequenceNumber,
+        detail: bool,
+        epoch: EpochId,
+    ) -> SuiResult<CheckpointResponse> {
+        let response = self.handle_authenticated_checkpoint(&Some(seq), detail)?;
+        if !matches!(
+            response,
+            CheckpointResponse::AuthenticatedCheckpoint {
+                checkpoint: None,
+                ..
+            }
+        ) {
+            return Ok(response);
+        }
+        let latest_seq = self
+            .latest_stored_checkpoint()
+            .map(|checkpoint| checkpoint.sequence_number());
+        // A checkpoint missing below the latest one, e.g. pruned, is not attested.
+        if latest_seq.map_or(false, |latest| latest >= seq) {
+            return Ok(response);
+        }
+        let attestation = NotAvailableAttestation {
+            epoch,
+            requested_seq: seq,
+            latest_seq,
+        };
+        Ok(CheckpointResponse::NotAvailable {
+            attestation: SignedNotAvailableAttestation::new(attestation, self.name, &*self.secret),
+        })
+    }
+
+    /// Serve the metadata of the contents of a checkpoint instead of the contents.
+    pub fn handle_authenticated_checkpoint_metadata(
+        &mut self,
+        seq: &Option<CheckpointSequenceNumber>,
+    ) -> SuiResult<CheckpointResponse> {
+        let checkpoint = match seq {
+            Some(s) => self.tables.checkpoints.get(s)?,
+            None => self.latest_stored_checkpoint(),
+        };
+        let metadata = match &checkpoint {
+            Some(c) => self
+                .get_contents(c.summary().sequence_number)?
+                .map(|contents| contents.metadata()),
+            None => None,
+        };
+        Ok(CheckpointResponse::AuthenticatedCheckpointMetadata {
+            checkpoint,
+            metadata,
+        })
+    }
+
+    pub fn handle_authenticated_checkpoint_bucket(
+        &mut self,
+        seq: &Option<CheckpointSequenceNumber>,
+        prefix: &DigestPrefix,
+        detail: bool,
+    ) -> SuiResult<CheckpointResponse> {
+        prefix.check()?;
+        let checkpoint = match seq {
+            Some(s) => self.tables.checkpoints.get(s)?,
+            None => self.latest_stored_checkpoint(),
+        };
+        let bucket = match &checkpoint {
+            Some(c) => self
+                .get_contents(c.summary().sequence_number)?
+                .map(|contents| contents.bucket(prefix))
+                .transpose()?,
+            None => None,
+        };
+        let bucket_digest = bucket.as_ref().map(|b| b.digest());
+        Ok(CheckpointResponse::AuthenticatedCheckpointBucket {
+            checkpoint,
+            bucket_digest,
+            bucket: if detail { bucket } else { None },
+        })
+    }
+
+    pub fn handle_latest_sequence_number(&self) -> SuiResult<CheckpointResponse> {
+        let info = self
+            .tables
+            .checkpoints
+            .iter()
+            .skip_to_last()
+            .reverse()
+            .find(|(_, ckp)| matches!(ckp, AuthenticatedCheckpoint::Certified(_)))
+            .map(|(_, ckp)| {
+                let summary = ckp.summary();
+                SignedCheckpointSequenceInfo::new(
+                    summary.epoch,
+                    CheckpointSequenceInfo::from(summary),
+                    self.name,
+                    &*self.secret,
+                )
+            });
+        Ok(CheckpointResponse::LatestCheckpointSequenceNumber { info })
+    }
+
+    pub fn handle_bootstrap(
+        &self,
+        request: &CheckpointBootstrapRequest,
+    ) -> SuiResult<CheckpointResponse> {
+        let not_certified = Ok(CheckpointResponse::Bootstrap { bundle: None });
+        let checkpoint = match self.tables.checkpoints.get(&request.seq)? {
+            Some(AuthenticatedCheckpoint::Certified(checkpoint)) => checkpoint,
+            _ => return not_certified,
+        };
+        fp_ensure!(
+            request.from_trusted_epoch <= checkpoint.data().epoch,
+            SuiError::from(
+                format!(
+                    "Checkpoint {} is from epoch {}, before the trusted epoch {}",
+                    request.seq,
+                    checkpoint.data().epoch,
+                    request.from_trusted_epoch
+                )
+                .as_str()
+            )
+        );
+        let contents = match self.get_contents(request.seq)? {
+            Some(contents) => contents.as_ref().clone(),
+            None => return not_certified,
+        };
+
+        let mut epoch_change_proofs = Vec::new();
+        for epoch in request.from_trusted_epoch..checkpoint.data().epoch {
+            let seq = match self.last_checkpoint_of_epoch(epoch, request.seq)? {
+                Some(seq) => seq,
+                None => return not_certified,
+            };
+            match self.tables.checkpoints.get(&seq)? {
+                Some(AuthenticatedCheckpoint::Certified(proof))
+                    if proof.data().next_epoch_committee.is_some() =>
+                {
+                    epoch_change_proofs.push(proof)
+                }
+                _ => return not_certified,
+            }
+        }
+
+        Ok(CheckpointResponse::Bootstrap {
+            bundle: Some(CheckpointBootstrapResponse {
+                checkpoint,
+                contents,
+                epoch_change_proofs,
+            }),
+        })
+    }
+
+    /// Binary search the last checkpoint of `epoch` among the checkpoints before `before`,
+    /// which must all be stored from the lowest one not pruned. Returns None if the last
+    /// checkpoint of `epoch` was pruned.
+    fn last_checkpoint_of_epoch(
+        &self,
+        epoch: EpochId,
+        before: CheckpointSequenceNumber,
+    ) -> SuiResult<Option<CheckpointSequenceNumber>> {
+        let lowest = match self.tables.checkpoints.iter().next() {
+            Some((seq, _)) => seq,
+            None => return Ok(None),
+        };
+        let (mut low, mut high) = (lowest, before);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let checkpoint = self.tables.checkpoints.get(&mid)?.ok_or_else(|| {
+                SuiError::from(format!("Checkpoint {mid} is not stored").as_str())
+            })?;
+            if checkpoint.epoch() <= epoch {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low.checked_sub(1).filter(|seq| *seq >= lowest))
+    }
+
     pub fn sign_new_checkpoint<'a>(
         &mut self,
         epoch: EpochId,
@@ -453,24 +806,44 @@ impl CheckpointStore {
         let (causally_ordered_transactions, gas_cost_summary) =
             effects_store.get_causal_order_and_gas_summary_from_effects(transactions, self)?;
 
-        // Create a causal order of all transactions in the checkpoint.
-        let ordered_contents = CheckpointContents::new_with_causally_ordered_transactions(
-            causally_ordered_transactions.into_iter(),
+        // Commit to the objects written by the transactions left after the causal ordering.
+        let effects = effects_store
+            .get_effects(causally_ordered_transactions.iter().map(|d| &d.transaction))?;
+        fp_ensure!(
+            effects.iter().all(Option::is_some),
+            SuiError::from("Cannot commit to the object changes of unexecuted transactions.")
         );
+        let object_changes = ObjectChanges::from_effects(effects.iter().flatten());
 
-        let summary = CheckpointSummary::new(
+        // Create a causal order of all transactions in the checkpoint.
+        let mut provisional = ProvisionalCheckpointSummary::new(
             epoch,
             sequence_number,
-            &ordered_contents,
             previous_digest,
-            gas_cost_summary,
             next_epoch_committee,
         );
-
-        let checkpoint = AuthenticatedCheckpoint::Signed(
-            SignedCheckpointSummary::new_from_summary(summary, self.name, &*self.secret),
+        provisional.set_contents(
+            CheckpointContents::new_with_causally_ordered_transactions(
+                causally_ordered_transactions.into_iter(),
+            ),
+            gas_cost_summary,
         );
-        self.handle_internal_set_checkpoint(&checkpoint, &ordered_contents)
+        provisional.set_object_changes(&object_changes);
+        let finalized = provisional.finalize();
+
+        let signed = match &self.signing_policy {
+            Some(policy) => {
+                finalized.sign_with_policy(self.name, &*self.secret, policy.as_ref())?
+            }
+            None => finalized.sign(self.name, &*self.secret),
+        };
+        // The guard is persisted before the signature is stored or sent anywhere.
+        if let Some(guard) = &self.signing_guard {
+            guard.record_summary(signed.data())?;
+        }
+        self.construction_state.add_signature(signed.clone());
+        let checkpoint = AuthenticatedCheckpoint::Signed(signed);
+        self.handle_internal_set_checkpoint(&checkpoint, finalized.contents())
     }
 
     /// Call this function internally to update the latest checkpoint.
@@ -564,7 +937,7 @@ impl CheckpointStore {
         // Does the fragment event suggest it is for the current round?
         let next_checkpoint_seq = self.next_checkpoint();
         fp_ensure!(
-            fragment.proposer.summary.sequence_number == next_checkpoint_seq,
+            fragment.proposer.data().sequence_number == next_checkpoint_seq,
             SuiError::GenericAuthorityError {
                 error: format!(
                     "Incorrect sequence number, expected {}",
@@ -602,8 +975,9 @@ impl CheckpointStore {
 
         // Send to consensus for sequencing.
         if let Some(sender) = &self.sender {
-            let messages = fragment.to_signed_message_chunks(&*self.secret);
-            let seq = fragment.proposer.summary.sequence_number;
+            let messages = fragment
+                .to_signed_message_chunks_with(&*self.secret, self.construction_state.limits());
+            let seq = fragment.proposer.data().sequence_number;
             debug!(
                 cp_seq=?seq,
                 proposer=?self.name.concise(),
@@ -634,7 +1008,8 @@ impl CheckpointStore {
         fragment: CheckpointFragment,
         committee: &Committee,
     ) -> SuiResult {
-        let chunks = fragment.to_signed_message_chunks(&*self.secret);
+        let chunks =
+            fragment.to_signed_message_chunks_with(&*self.secret, self.construction_state.limits());
         for chunk in chunks {
             self.handle_internal_fragment(seq.clone(), chunk.message, committee)?;
             seq.next_transaction_index += 1;
@@ -651,6 +1026,8 @@ impl CheckpointStore {
         fragment: CheckpointFragmentMessage,
         committee: &Committee,
     ) -> SuiResult {
+        fragment.validate_with(self.construction_state.limits())?;
+
         // Ensure we have not already processed this fragment.
         if let Some((last_seq, _)) = self.tables.fragments.iter().skip_to_last().next() {
             if seq <= last_seq {
@@ -661,14 +1038,33 @@ impl CheckpointStore {
 
         // Save the new fragment in the DB
         self.tables.fragments.insert(&seq, &fragment)?;
+        if let Err(err) = self
+            .construction_state
+            .add_fragment_message(fragment.clone())
+        {
+            debug!(
+                ?seq,
+                "Fragment message not added to the construction state: {err}"
+            );
+        }
 
         let locals = self.get_locals();
         let mut new_locals = locals.as_ref().clone();
-        new_locals.in_construction_checkpoint.add_fragment_to_span(
+        let next_checkpoint = new_locals.in_construction_checkpoint_seq;
+        if let Some(fragment) = new_locals.in_construction_checkpoint.assemble_fragment(
             committee,
-            new_locals.in_construction_checkpoint_seq,
+            next_checkpoint,
             fragment,
-        );
+        ) {
+            if let Some(trace) = &self.tx_trace {
+                trace.record_fragment(&fragment);
+            }
+            new_locals.in_construction_checkpoint.add_fragment(
+                committee,
+                next_checkpoint,
+                fragment,
+            );
+        }
         self.tables
             .advance_checkpoint_construction_state(&mut new_locals, committee)?;
         self.set_locals(locals, new_locals)?;
@@ -726,7 +1122,7 @@ impl CheckpointStore {
     /// the sequence of fragments received.
     pub fn reconstruct_contents(
         &mut self,
-        our_proposal: &CheckpointProposal,
+        our_proposal: &LocalProposal,
     ) -> SuiResult<BTreeSet<ExecutionDigests>> {
         let next_sequence_number = self.next_checkpoint();
 
@@ -756,15 +1152,16 @@ impl CheckpointStore {
         {
             // We are included in the proposal, so we can go ahead and construct the
             // full checkpoint!
-            let mut contents = our_proposal.transactions.clone();
-            contents.transactions.extend(
+            let contents = our_proposal.transactions.transactions.union(
                 // Add all items missing to reach then global waypoint
-                reconstructed.global.authority_waypoints[&self.name]
+                &reconstructed.global.authority_waypoints[&self.name]
                     .items
-                    .clone(),
+                    .iter()
+                    .copied()
+                    .collect(),
             );
 
-            return Ok(contents.transactions.into_iter().collect());
+            return Ok(contents.iter().copied().collect());
         }
 
         // Strategy 2 to reconstruct checkpoint -- There is a link between us and the checkpoint set
@@ -813,15 +1210,15 @@ impl CheckpointStore {
         checkpoint: &CertifiedCheckpointSummary,
         committee: &Committee,
     ) -> SuiResult {
-        checkpoint.verify(committee, None)?;
+        let verified = VerifiedCertifiedCheckpointSummary::new(checkpoint.clone(), committee)?;
         match self.latest_stored_checkpoint() {
             Some(AuthenticatedCheckpoint::Signed(s)) => {
-                if s.summary != checkpoint.summary {
+                if s.data() != checkpoint.data() {
                     error!(
-                        cp_seq=checkpoint.summary.sequence_number,
+                        cp_seq=checkpoint.data().sequence_number,
                         "Local signed checkpoint is not the same as the checkpoint cert. Most likely local checkpoint has forked. cert: {}, local signed: {}",
-                        checkpoint.summary,
-                        s.summary,
+                        checkpoint.data(),
+                        s.data(),
                     );
                     panic!();
                 }
@@ -830,11 +1227,11 @@ impl CheckpointStore {
                 unreachable!("Can never call promote_signed_checkpoint_to_cert when there is no signed checkpoint locally");
             }
         }
-        let seq = checkpoint.summary.sequence_number();
+        let seq = checkpoint.data().sequence_number();
         self.tables
             .checkpoints
             .insert(seq, &AuthenticatedCheckpoint::Certified(checkpoint.clone()))?;
-        self.notify_new_checkpoint(checkpoint.clone());
+        self.notify_new_checkpoint(verified);
 
         self.clear_proposal(*seq + 1, committee)?;
         Ok(())
@@ -862,7 +1259,7 @@ impl CheckpointStore {
         contents: &CheckpointContents,
         committee: &Committee,
     ) -> SuiResult {
-        let seq = checkpoint.summary.sequence_number();
+        let seq = checkpoint.data().sequence_number();
         debug_assert!(self.tables.checkpoints.get(seq)?.is_none());
         // Check and process contents
         checkpoint.verify(committee, Some(contents))?;
@@ -871,19 +1268,23 @@ impl CheckpointStore {
             &AuthenticatedCheckpoint::Certified(checkpoint.clone()),
             contents,
         )?;
-        self.notify_new_checkpoint(checkpoint.clone());
+        self.notify_new_checkpoint(VerifiedCertifiedCheckpointSummary::new_unchecked(
+            checkpoint.clone(),
+        ));
         self.clear_proposal(*seq + 1, committee)?;
         Ok(())
     }
 
-    fn notify_new_checkpoint(&self, ckpt: CertifiedCheckpointSummary) {
-        let sequence = ckpt.summary.sequence_number;
-        let _ = self.notify_new_checkpoint_tx.send(ckpt).tap_err(|_| {
+    fn notify_new_checkpoint(&self, ckpt: VerifiedCertifiedCheckpointSummary) {
+        let sequence = ckpt.data().sequence_number;
+        // Cannot fail, the store holds a receiver.
+        let _ = self.certified_watermark_tx.send(Some(sequence));
+        if self.checkpoint_notifier.notify(ckpt) == 0 {
             debug!(
                 ?sequence,
                 "notify_new_checkpoint failed - no subscribers at this time"
             )
-        });
+        }
     }
 
     // TODO: We need to make the call to this atomic with the caller-side db changes.
@@ -898,6 +1299,10 @@ impl CheckpointStore {
         new_locals.current_proposal = None;
         new_locals.proposal_next_transaction = None;
         new_locals.next_checkpoint = new_expected_next_checkpoint;
+        // Everything below the next checkpoint is certified.
+        if let Some(certified_seq) = new_expected_next_checkpoint.checked_sub(1) {
+            self.construction_state.prune_below(certified_seq);
+        }
         self.tables
             .advance_checkpoint_construction_state(&mut new_locals, committee)?;
         self.set_locals(locals, new_locals)
@@ -993,7 +1398,7 @@ impl CheckpointStore {
     // Helper write functions
 
     /// Set the next checkpoint proposal.
-    pub fn set_proposal(&mut self, epoch: EpochId) -> Result<CheckpointProposal, SuiError> {
+    pub fn set_proposal(&mut self, epoch: EpochId) -> Result<LocalProposal, SuiError> {
         // Check that:
         // - there is no current proposal.
         // - there are no unprocessed transactions.
@@ -1022,19 +1427,29 @@ impl CheckpointStore {
             transactions.transactions
         );
 
-        let checkpoint_proposal = CheckpointProposal::new(
+        let checkpoint_proposal = LocalProposal::new(
             epoch,
             checkpoint_sequence,
             self.name,
             &*self.secret,
             transactions,
-        );
+            self.signing_guard.as_deref(),
+        )?;
 
         // Record the checkpoint in the locals
         let mut new_locals = locals.as_ref().clone();
         new_locals.current_proposal = Some(checkpoint_proposal.clone());
         new_locals.proposal_next_transaction = Some(next_local_tx_sequence);
         self.set_locals(locals, new_locals)?;
+        if let Err(conflict) = self
+            .construction_state
+            .add_proposal(checkpoint_proposal.clone().into_inner())
+        {
+            error!(cp_seq=?checkpoint_sequence, "Own proposal conflicts with an earlier one: {}", SuiError::from(conflict));
+        }
+        if let Some(trace) = &self.tx_trace {
+            trace.record_proposal(&checkpoint_proposal);
+        }
 
         Ok(checkpoint_proposal)
     }
@@ -1119,6 +1534,9 @@ impl CheckpointStore {
 
         // Write to the database.
         batch.write()?;
+        if let Some(trace) = &self.tx_trace {
+            trace.record_checkpoint(seq, transactions);
+        }
 
         Ok(())
     }
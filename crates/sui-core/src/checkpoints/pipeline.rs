@@ -0,0 +1,376 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use sui_types::checkpoint_batching::{AdaptiveBatcher, AdaptiveBatcherConfig};
+use sui_types::checkpoint_execution::CheckpointExecutionRequest;
+use sui_types::committee::Committee;
+use sui_types::error::{SuiError, SuiResult};
+use sui_types::fp_ensure;
+use sui_types::messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointContents, CheckpointSequenceNumber,
+};
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Fetches certified checkpoints and their contents, e.g. from peers or an archive.
+#[async_trait]
+pub trait CheckpointFetcher: Send + Sync + 'static {
+    async fn fetch(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> SuiResult<(CertifiedCheckpointSummary, CheckpointContents)>;
+
+    /// Fetch the checkpoints of `range`, in sequence, with at most `concurrency` fetches in
+    /// flight. Fetchers able to request a range at once, rather than one checkpoint at a
+    /// time, should override this.
+    async fn fetch_range(
+        &self,
+        range: Range<CheckpointSequenceNumber>,
+        concurrency: usize,
+    ) -> SuiResult<Vec<(CertifiedCheckpointSummary, CheckpointContents)>> {
+        stream::iter(range)
+            .map(|seq| self.fetch(seq))
+            .buffered(concurrency)
+            .try_collect()
+            .await
+    }
+}
+
+/// Checks a fetched checkpoint before it is executed. Verification is CPU bound, and runs on
+/// blocking threads.
+pub trait CheckpointVerifier: Send + Sync + 'static {
+    fn verify(
+        &self,
+        summary: CertifiedCheckpointSummary,
+        contents: CheckpointContents,
+    ) -> SuiResult<CheckpointExecutionRequest>;
+}
+
+/// Executes verified checkpoints, which it receives in sequence.
+#[async_trait]
+pub trait CheckpointExecutorSink: Send + Sync + 'static {
+    async fn execute(&self, request: CheckpointExecutionRequest) -> SuiResult;
+}
+
+/// Verifies checkpoints against a single committee, so pipelines using it must not run past
+/// the end of its epoch.
+pub struct CommitteeCheckpointVerifier {
+    committee: Committee,
+}
+
+impl CommitteeCheckpointVerifier {
+    pub fn new(committee: Committee) -> Self {
+        Self { committee }
+    }
+}
+
+impl CheckpointVerifier for CommitteeCheckpointVerifier {
+    fn verify(
+        &self,
+        summary: CertifiedCheckpointSummary,
+        contents: CheckpointContents,
+    ) -> SuiResult<CheckpointExecutionRequest> {
+        CheckpointExecutionRequest::new(summary, contents, &self.committee)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CheckpointPipelineConfig {
+    /// How many checkpoints are fetched at once.
+    pub fetch_concurrency: usize,
+    /// How many checkpoints are verified at once.
+    pub verify_concurrency: usize,
+    /// How many checkpoints can wait between two stages. Once the queue in front of a stage
+    /// is full, the stages before it wait, so that a slow executor does not make the
+    /// pipeline fetch the whole range ahead of it.
+    pub queue_capacity: usize,
+}
+
+impl CheckpointPipelineConfig {
+    /// Check that every stage can make progress.
+    pub fn validate(&self) -> SuiResult {
+        fp_ensure!(
+            self.fetch_concurrency > 0,
+            SuiError::from("Checkpoint pipeline fetch concurrency must be positive")
+        );
+        fp_ensure!(
+            self.verify_concurrency > 0,
+            SuiError::from("Checkpoint pipeline verify concurrency must be positive")
+        );
+        fp_ensure!(
+            self.queue_capacity > 0,
+            SuiError::from("Checkpoint pipeline queue capacity must be positive")
+        );
+        Ok(())
+    }
+}
+
+impl Default for CheckpointPipelineConfig {
+    fn default() -> Self {
+        Self {
+            fetch_concurrency: 8,
+            verify_concurrency: 4,
+            queue_capacity: 16,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PipelineStage {
+    Fetch,
+    Verify,
+    Execute,
+}
+
+/// The highest checkpoint that went through each stage. Every stage handles checkpoints in
+/// sequence, so every checkpoint of the range below a watermark went through its stage too.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PipelineWatermarks {
+    pub fetched: Option<CheckpointSequenceNumber>,
+    pub verified: Option<CheckpointSequenceNumber>,
+    pub executed: Option<CheckpointSequenceNumber>,
+}
+
+struct StageWatermark {
+    tx: watch::Sender<Option<CheckpointSequenceNumber>>,
+    // Kept so that updates of the watermark are never lost for lack of receivers.
+    rx: watch::Receiver<Option<CheckpointSequenceNumber>>,
+}
+
+impl StageWatermark {
+    fn new() -> Self {
+        let (tx, rx) = watch::channel(None);
+        Self { tx, rx }
+    }
+
+    fn set(&self, seq: CheckpointSequenceNumber) {
+        let _ = self.tx.send(Some(seq));
+    }
+
+    fn get(&self) -> Option<CheckpointSequenceNumber> {
+        *self.rx.borrow()
+    }
+}
+
+/// Streams checkpoints from a fetcher, through a pool of verifiers, to an executor. The
+/// stages run concurrently, connected by bounded queues, and checkpoints leave every stage
+/// in sequence.
+pub struct CheckpointPipeline<F, V, E> {
+    fetcher: Arc<F>,
+    verifier: Arc<V>,
+    executor: Arc<E>,
+    config: CheckpointPipelineConfig,
+    /// Fetch ranges of checkpoints sized by an `AdaptiveBatcher`, instead of one checkpoint
+    /// at a time, if set.
+    batching: Option<AdaptiveBatcherConfig>,
+    fetched: StageWatermark,
+    verified: StageWatermark,
+    executed: StageWatermark,
+}
+
+impl<F, V, E> CheckpointPipeline<F, V, E>
+where
+    F: CheckpointFetcher,
+    V: CheckpointVerifier,
+    E: CheckpointExecutorSink,
+{
+    pub fn new(
+        fetcher: Arc<F>,
+        verifier: Arc<V>,
+        executor: Arc<E>,
+        config: CheckpointPipelineConfig,
+    ) -> SuiResult<Self> {
+        config.validate()?;
+        Ok(Self {
+            fetcher,
+            verifier,
+            executor,
+            config,
+            batching: None,
+            fetched: StageWatermark::new(),
+            verified: StageWatermark::new(),
+            executed: StageWatermark::new(),
+        })
+    }
+
+    /// Fetch ranges of checkpoints with `CheckpointFetcher::fetch_range`, sized to the
+    /// latency and size of the previous responses.
+    pub fn with_adaptive_batching(mut self, config: AdaptiveBatcherConfig) -> Self {
+        self.batching = Some(config);
+        self
+    }
+
+    pub fn watermarks(&self) -> PipelineWatermarks {
+        PipelineWatermarks {
+            fetched: self.fetched.get(),
+            verified: self.verified.get(),
+            executed: self.executed.get(),
+        }
+    }
+
+    /// Follow the watermark of `stage`, e.g. to wait for a checkpoint to be executed.
+    pub fn subscribe(
+        &self,
+        stage: PipelineStage,
+    ) -> watch::Receiver<Option<CheckpointSequenceNumber>> {
+        match stage {
+            PipelineStage::Fetch => self.fetched.rx.clone(),
+            PipelineStage::Verify => self.verified.rx.clone(),
+            PipelineStage::Execute => self.executed.rx.clone(),
+        }
+    }
+
+    /// Fetch, verify and execute the checkpoints of `range`, in sequence. Resolves once the
+    /// last one is executed, or with the first error of any stage, at which point the whole
+    /// pipeline stops. Checkpoints executed before the error stay executed, so a failed run
+    /// can resume from the executed watermark. Runs must not overlap.
+    pub async fn run(&self, range: Range<CheckpointSequenceNumber>) -> SuiResult {
+        let (fetched_tx, fetched_rx) = mpsc::channel(self.config.queue_capacity);
+        let (verified_tx, verified_rx) = mpsc::channel(self.config.queue_capacity);
+        tokio::try_join!(
+            self.fetch_stage(range, fetched_tx),
+            self.verify_stage(fetched_rx, verified_tx),
+            self.execute_stage(verified_rx),
+        )?;
+        Ok(())
+    }
+
+    async fn fetch_stage(
+        &self,
+        range: Range<CheckpointSequenceNumber>,
+        sender: mpsc::Sender<(CertifiedCheckpointSummary, CheckpointContents)>,
+    ) -> SuiResult {
+        if let Some(config) = &self.batching {
+            let batcher = AdaptiveBatcher::new(config.clone());
+            return self.fetch_batches(range, batcher, sender).await;
+        }
+        let mut fetched = stream::iter(range)
+            .map(|seq| async move { (seq, self.fetcher.fetch(seq).await) })
+            .buffered(self.config.fetch_concurrency);
+        while let Some((seq, result)) = fetched.next().await {
+            let (summary, contents) = result?;
+            fp_ensure!(
+                summary.data().sequence_number == seq,
+                SuiError::from(
+                    format!(
+                        "Fetched checkpoint {} instead of checkpoint {seq}",
+                        summary.data().sequence_number
+                    )
+                    .as_str()
+                )
+            );
+            if sender.send((summary, contents)).await.is_err() {
+                // A later stage stopped, and reports why.
+                return Ok(());
+            }
+            self.fetched.set(seq);
+        }
+        Ok(())
+    }
+
+    async fn fetch_batches(
+        &self,
+        range: Range<CheckpointSequenceNumber>,
+        mut batcher: AdaptiveBatcher,
+        sender: mpsc::Sender<(CertifiedCheckpointSummary, CheckpointContents)>,
+    ) -> SuiResult {
+        let mut next = range.start;
+        while next < range.end {
+            let batch = batcher.next_range(next, range.end);
+            let started = Instant::now();
+            let checkpoints = match self
+                .fetcher
+                .fetch_range(batch.clone(), self.config.fetch_concurrency)
+                .await
+            {
+                Ok(checkpoints) => checkpoints,
+                Err(e) => {
+                    // The batch may have been too large to be served: retry a smaller one,
+                    // until the batch cannot shrink any further.
+                    let size = batcher.next_batch_size();
+                    batcher.record_failure();
+                    if batcher.next_batch_size() < size {
+                        continue;
+                    }
+                    return Err(e);
+                }
+            };
+            fp_ensure!(
+                checkpoints.len() as u64 == batch.end - batch.start,
+                SuiError::from(
+                    format!(
+                        "Fetched {} checkpoints for the {} checkpoints of {batch:?}",
+                        checkpoints.len(),
+                        batch.end - batch.start
+                    )
+                    .as_str()
+                )
+            );
+            let bytes = checkpoints
+                .iter()
+                .map(|checkpoint| bcs::serialized_size(checkpoint).unwrap_or_default() as u64)
+                .sum();
+            batcher.record_response(checkpoints.len() as u64, bytes, started.elapsed());
+            for ((summary, contents), seq) in checkpoints.into_iter().zip(batch) {
+                fp_ensure!(
+                    summary.data().sequence_number == seq,
+                    SuiError::from(
+                        format!(
+                            "Fetched checkpoint {} instead of checkpoint {seq}",
+                            summary.data().sequence_number
+                        )
+                        .as_str()
+                    )
+                );
+                if sender.send((summary, contents)).await.is_err() {
+                    return Ok(());
+                }
+                self.fetched.set(seq);
+                next = seq + 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn verify_stage(
+        &self,
+        receiver: mpsc::Receiver<(CertifiedCheckpointSummary, CheckpointContents)>,
+        sender: mpsc::Sender<CheckpointExecutionRequest>,
+    ) -> SuiResult {
+        let mut verified = ReceiverStream::new(receiver)
+            .map(|(summary, contents)| {
+                let verifier = self.verifier.clone();
+                tokio::task::spawn_blocking(move || verifier.verify(summary, contents))
+            })
+            .buffered(self.config.verify_concurrency);
+        while let Some(result) = verified.next().await {
+            let request = result.map_err(|e| {
+                SuiError::from(format!("Checkpoint verification failed to complete: {e}").as_str())
+            })??;
+            let seq = request.sequence_number();
+            if sender.send(request).await.is_err() {
+                return Ok(());
+            }
+            self.verified.set(seq);
+        }
+        Ok(())
+    }
+
+    async fn execute_stage(
+        &self,
+        mut receiver: mpsc::Receiver<CheckpointExecutionRequest>,
+    ) -> SuiResult {
+        while let Some(request) = receiver.recv().await {
+            let seq = request.sequence_number();
+            self.executor.execute(request).await?;
+            self.executed.set(seq);
+        }
+        Ok(())
+    }
+}
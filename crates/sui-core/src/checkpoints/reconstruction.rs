@@ -1,16 +1,17 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::cmp::max;
+use std::cmp::{max, Reverse};
 use std::collections::{BTreeMap, HashMap, VecDeque};
 use tracing::{debug, error};
 
 use sui_types::base_types::ExecutionDigests;
+use sui_types::checkpoint_construction::{ProposalConflict, ProposalSlotMap};
 use sui_types::committee::StakeUnit;
 use sui_types::error::{SuiError, SuiResult};
 use sui_types::messages_checkpoint::{
-    CheckpointFragment, CheckpointFragmentMessage, CheckpointProposalSummary,
-    CheckpointSequenceNumber, PartialCheckpointFragment,
+    CheckpointFragment, CheckpointFragmentMessage, CheckpointSequenceNumber,
+    PartialCheckpointFragment, SignedCheckpointProposalSummary,
 };
 use sui_types::{
     base_types::AuthorityName,
@@ -57,7 +58,7 @@ pub struct InProgressSpanGraph {
 
     /// Proposals from each validator seen so far. This is needed to detect potential conflicting
     /// fragments.
-    proposals_used: HashMap<AuthorityName, CheckpointProposalSummary>,
+    proposals_used: ProposalSlotMap<SignedCheckpointProposalSummary>,
 
     partial_fragments: BTreeMap<
         (CheckpointSequenceNumber, AuthorityName, AuthorityName),
@@ -160,6 +161,8 @@ impl InProgressSpanGraph {
 #[derive(Clone, Debug)]
 pub struct CompletedSpanGraph {
     active_links: VecDeque<CheckpointFragment>,
+    /// Evidence of the conflicting proposals seen while constructing the checkpoint.
+    proposal_conflicts: Vec<ProposalConflict>,
 }
 
 impl SpanGraph {
@@ -209,50 +212,74 @@ impl SpanGraph {
         next_checkpoint: CheckpointSequenceNumber,
         message: CheckpointFragmentMessage,
     ) {
+        if let Some(frag) = self.assemble_fragment(committee, next_checkpoint, message) {
+            self.add_fragment(committee, next_checkpoint, frag);
+        }
+    }
+
+    /// Add a fragment message to the fragment it is part of, and return the fragment once
+    /// all its messages are received, without adding it to the span graph.
+    pub fn assemble_fragment(
+        &mut self,
+        committee: &Committee,
+        next_checkpoint: CheckpointSequenceNumber,
+        message: CheckpointFragmentMessage,
+    ) -> Option<CheckpointFragment> {
         if matches!(&self, Self::Uninitialized) {
             self.initialize(committee, next_checkpoint);
         }
         if let Self::InProgress(span) = self {
             // Ignore if the fragment is for a different checkpoint.
             if message.proposer_sequence_number() != span.next_checkpoint {
-                return;
+                return None;
             }
 
-            let frag = match span.add_to_partial_fragment(message, committee) {
+            match span.add_to_partial_fragment(message, committee) {
                 Err(err) => {
                     error!(
                         "Failed to add partial fragment to the span graph: {:?}",
                         err
                     );
-                    return;
+                    None
                 }
-                Ok(Some(frag)) => frag,
-                Ok(None) => {
-                    return;
-                }
-            };
+                Ok(frag) => frag,
+            }
+        } else {
+            None
+        }
+    }
 
-            // Check the checkpoint summary of the proposal is the same as the previous one.
-            // Otherwise ignore the link.
-            let n1 = frag.proposer.authority();
-            if *span
-                .proposals_used
-                .entry(*n1)
-                .or_insert_with(|| frag.proposer.summary.clone())
-                != frag.proposer.summary
-            {
+    /// Add a complete, verified fragment to the span graph, as `add_fragment_to_span` does
+    /// once all the messages of a fragment are received.
+    pub fn add_fragment(
+        &mut self,
+        committee: &Committee,
+        next_checkpoint: CheckpointSequenceNumber,
+        frag: CheckpointFragment,
+    ) {
+        if matches!(&self, Self::Uninitialized) {
+            self.initialize(committee, next_checkpoint);
+        }
+        if let Self::InProgress(span) = self {
+            // Ignore if the fragment is for a different checkpoint.
+            if *frag.proposer_sequence_number() != span.next_checkpoint {
                 return;
             }
 
-            let n2 = frag.other.authority();
-            if *span
-                .proposals_used
-                .entry(*n2)
-                .or_insert_with(|| frag.other.summary.clone())
-                != frag.other.summary
-            {
-                return;
+            // Check the checkpoint summary of the proposal is the same as the previous one.
+            // Otherwise ignore the link, keeping the evidence of the conflict.
+            for proposal in [&frag.proposer, &frag.other] {
+                if let Err(conflict) = span.proposals_used.insert(proposal.clone()) {
+                    error!(
+                        authority = ?conflict.authority(),
+                        seq = conflict.sequence_number(),
+                        "Conflicting checkpoint proposals received in fragments"
+                    );
+                    return;
+                }
             }
+            let n1 = frag.proposer.authority();
+            let n2 = frag.other.authority();
 
             // Add to the links we will consider.
             span.fragments_used.push(frag.clone());
@@ -276,7 +303,11 @@ impl SpanGraph {
                     next_cp_seq = span.next_checkpoint,
                     "Checkpoint construction completed"
                 );
-                *self = Self::Completed(CompletedSpanGraph { active_links });
+                let proposal_conflicts = span.proposals_used.take_conflicts();
+                *self = Self::Completed(CompletedSpanGraph {
+                    active_links,
+                    proposal_conflicts,
+                });
             }
         }
     }
@@ -285,6 +316,36 @@ impl SpanGraph {
         matches!(self, Self::Completed(_))
     }
 
+    /// The stake a fragment would bring together: the total stake of the two trees it would
+    /// merge, or 0 if its authorities are already in the same tree, or if the fragment cannot
+    /// be added.
+    pub fn marginal_stake(&self, committee: &Committee, frag: &CheckpointFragment) -> StakeUnit {
+        let n1 = frag.proposer.authority();
+        let n2 = frag.other.authority();
+        match self {
+            Self::Uninitialized if n1 != n2 => committee.weight(n1) + committee.weight(n2),
+            Self::InProgress(span) if *frag.proposer_sequence_number() == span.next_checkpoint => {
+                let (top1, weight1) = span.top_node(n1);
+                let (top2, weight2) = span.top_node(n2);
+                if top1 == top2 {
+                    0
+                } else {
+                    weight1 + weight2
+                }
+            }
+            _ => 0,
+        }
+    }
+
+    /// Evidence of the conflicting proposals seen in fragments for this checkpoint.
+    pub fn proposal_conflicts(&self) -> &[ProposalConflict] {
+        match self {
+            Self::Uninitialized => &[],
+            Self::InProgress(span) => span.proposals_used.conflicts(),
+            Self::Completed(span) => &span.proposal_conflicts,
+        }
+    }
+
     pub fn construct_checkpoint(&self) -> SuiResult<FragmentReconstruction> {
         if let Self::Completed(span) = &self {
             let mut global = GlobalCheckpoint::new();
@@ -328,9 +389,67 @@ impl SpanGraph {
             nodes,
             next_checkpoint,
             fragments_used: Vec::new(),
-            proposals_used: HashMap::new(),
+            proposals_used: ProposalSlotMap::new(),
             partial_fragments: BTreeMap::new(),
             max_weight_seen: 0,
         })
     }
 }
+
+/// Complete fragments waiting to be added to a span graph, handed out by decreasing marginal
+/// stake (see `SpanGraph::marginal_stake`), so that when many fragments arrive at once the
+/// graph reaches a quorum with fewer of them.
+///
+/// The order in which fragments are added decides the checkpoint, so all authorities must
+/// push the same fragments and poll at the same points of the consensus output, e.g. at the
+/// end of each batch. Ties are broken by the order in which fragments were pushed.
+#[derive(Clone, Debug, Default)]
+pub struct FragmentPriorityQueue {
+    pending: VecDeque<CheckpointFragment>,
+}
+
+impl FragmentPriorityQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, frag: CheckpointFragment) {
+        self.pending.push_back(frag);
+    }
+
+    /// Remove the fragment bringing the most stake together in `span`. Adding a fragment
+    /// changes the marginal stake of the others, so it is computed on each poll, over at
+    /// most one fragment per pair of authorities.
+    pub fn poll(&mut self, committee: &Committee, span: &SpanGraph) -> Option<CheckpointFragment> {
+        let (index, _) = self
+            .pending
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, frag)| (span.marginal_stake(committee, frag), Reverse(*index)))?;
+        self.pending.remove(index)
+    }
+
+    /// Add pending fragments to `span` by priority, until it is completed. Fragments left
+    /// over stay in the queue.
+    pub fn drain_into(
+        &mut self,
+        committee: &Committee,
+        next_checkpoint: CheckpointSequenceNumber,
+        span: &mut SpanGraph,
+    ) {
+        while !span.is_completed() {
+            match self.poll(committee, span) {
+                Some(frag) => span.add_fragment(committee, next_checkpoint, frag),
+                None => break,
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
@@ -11,6 +11,11 @@ use crate::{
     authority_batch::batch_tests::init_state_parameters_from_rng,
     authority_client::{LocalAuthorityClient, NetworkAuthorityClientMetrics},
     checkpoints::causal_order_effects::TestEffectsStore,
+    checkpoints::pipeline::{
+        CheckpointExecutorSink, CheckpointFetcher, CheckpointPipeline, CheckpointPipelineConfig,
+        CommitteeCheckpointVerifier, PipelineStage, PipelineWatermarks,
+    },
+    checkpoints::reconstruction::FragmentPriorityQueue,
     safe_client::SafeClientMetrics,
 };
 use rand::prelude::StdRng;
@@ -20,11 +25,20 @@ use std::{collections::HashSet, env, fs, path::PathBuf, sync::Arc, time::Duratio
 use sui_types::{
     base_types::{AuthorityName, ObjectID},
     batch::UpdateItem,
+    checkpoint_batching::AdaptiveBatcherConfig,
+    checkpoint_execution::CheckpointExecutionRequest,
+    checkpoint_signing_guard::{
+        PersistedSigningGuard, SignedHighWaterMark, SigningGuard, SigningGuardPersistence,
+    },
+    checkpoint_trace::TxCheckpointTrace,
     crypto::{get_key_pair_from_rng, AccountKeyPair, AuthorityKeyPair, KeypairTraits},
+    gas::GasCostSummary,
     messages::{CertifiedTransaction, ExecutionStatus},
-    messages_checkpoint::CheckpointRequest,
+    messages_checkpoint::{
+        CheckpointProposal, CheckpointRequest, RemoteProposal, SignedCheckpointSummary,
+    },
     object::Object,
-    utils::{make_committee_key, make_committee_key_num},
+    utils::{make_committee_key, make_committee_key_num, CheckpointChainBuilder},
     waypoint::GlobalCheckpoint,
 };
 
@@ -158,13 +172,13 @@ async fn crash_recovery() {
     assert_eq!(locals.next_transaction_sequence, 7);
 
     assert_eq!(
-        &proposal.signed_summary.summary,
-        &locals
+        proposal.signed_summary.data(),
+        locals
             .current_proposal
             .as_ref()
             .unwrap()
             .signed_summary
-            .summary
+            .data()
     );
 }
 
@@ -342,15 +356,15 @@ async fn make_diffs() {
     let p3 = cps3.set_proposal(committee.epoch).unwrap();
     let p4 = cps4.set_proposal(committee.epoch).unwrap();
 
-    let diff12 = p1.fragment_with(&p2);
-    let diff23 = p2.fragment_with(&p3);
+    let diff12 = fragment_between(&p1, &p2);
+    let diff23 = fragment_between(&p2, &p3);
 
     let mut global = GlobalCheckpoint::<AuthorityName, ExecutionDigests>::new();
     global.insert(diff12.data.diff.clone()).unwrap();
     global.insert(diff23.data.diff).unwrap();
 
     // P4 proposal not selected
-    let diff41 = p4.fragment_with(&p1);
+    let diff41 = fragment_between(&p4, &p1);
     let all_items4 = global
         .checkpoint_items(&diff41.data.diff, p4.transactions().cloned().collect())
         .unwrap();
@@ -364,6 +378,87 @@ async fn make_diffs() {
     assert_eq!(all_items1, all_items4);
 }
 
+struct NoPersistence;
+
+impl SigningGuardPersistence for NoPersistence {
+    fn persist(&self, _guard: &SigningGuard) -> SuiResult {
+        Ok(())
+    }
+}
+
+/// A guard restored after the database, which remembers another proposal for checkpoint 0.
+fn guard_with_other_proposal(committee: &Committee) -> Arc<PersistedSigningGuard> {
+    let guard = SigningGuard {
+        summary: None,
+        proposal: Some(SignedHighWaterMark {
+            epoch: committee.epoch,
+            sequence_number: 0,
+            digest: [1; 32],
+        }),
+    };
+    Arc::new(PersistedSigningGuard::new(guard, Box::new(NoPersistence)))
+}
+
+#[tokio::test]
+async fn signing_guard_refuses_conflicting_proposal() {
+    let mut rng = StdRng::from_seed(RNG_SEED);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let path = env::temp_dir().join(format!("SC_{:?}", ObjectID::random()));
+    fs::create_dir(&path).unwrap();
+    let mut cps = CheckpointStore::open_with_signing_guard(
+        &path,
+        None,
+        &committee,
+        keys[0].public().into(),
+        Arc::pin(keys[0].copy()),
+        false,
+        Some(guard_with_other_proposal(&committee)),
+    )
+    .unwrap();
+    cps.update_processed_transactions(&[(1, ExecutionDigests::random())])
+        .unwrap();
+
+    assert!(matches!(
+        cps.set_proposal(committee.epoch),
+        Err(SuiError::ConflictingCheckpointSignature { .. })
+    ));
+    assert!(cps.get_locals().current_proposal.is_none());
+}
+
+#[tokio::test]
+async fn signing_guard_refuses_restored_proposal() {
+    let mut rng = StdRng::from_seed(RNG_SEED);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let path = env::temp_dir().join(format!("SC_{:?}", ObjectID::random()));
+    fs::create_dir(&path).unwrap();
+    let open = |signing_guard| {
+        CheckpointStore::open_with_signing_guard(
+            &path,
+            None,
+            &committee,
+            keys[0].public().into(),
+            Arc::pin(keys[0].copy()),
+            false,
+            signing_guard,
+        )
+        .unwrap()
+    };
+
+    let mut cps = open(None);
+    cps.update_processed_transactions(&[(1, ExecutionDigests::random())])
+        .unwrap();
+    cps.set_proposal(committee.epoch).unwrap();
+    drop(cps);
+
+    // Reopening signs the proposal again, which the guard refuses.
+    let mut cps = open(Some(guard_with_other_proposal(&committee)));
+    assert!(cps.get_locals().current_proposal.is_none());
+    assert!(matches!(
+        cps.set_proposal(committee.epoch),
+        Err(SuiError::ConflictingCheckpointSignature { .. })
+    ));
+}
+
 #[tokio::test]
 async fn latest_proposal() {
     let (committee, _keys, mut stores) = random_ckpoint_store();
@@ -431,9 +526,9 @@ async fn latest_proposal() {
 
         let current_proposal = proposal.unwrap();
         current_proposal
-            .verify(&committee, None)
+            .verify_with_contents(&committee, None)
             .expect("no signature error");
-        assert_eq!(current_proposal.summary.sequence_number, 0);
+        assert_eq!(current_proposal.data().sequence_number, 0);
     } else {
         panic!("Unexpected response");
     }
@@ -454,9 +549,25 @@ async fn latest_proposal() {
 
         let current_proposal = proposal.unwrap();
         current_proposal
-            .verify(&committee, proposal_contents.as_ref())
+            .verify_with_contents(&committee, proposal_contents.as_ref())
             .expect("no signature error");
-        assert_eq!(current_proposal.summary.sequence_number, 0);
+        assert_eq!(current_proposal.data().sequence_number, 0);
+    } else {
+        panic!("Unexpected response");
+    }
+
+    // Check the difference to the proposal of another validator, sent as a sketch
+    let response = cps1
+        .handle_proposal_diff(&p2.sketch(10))
+        .expect("no errors");
+    if let CheckpointResponse::CheckpointProposalDiff {
+        response: Some(response),
+    } = response
+    {
+        let fragment = p2
+            .fragment_from_diff_response(&response)
+            .expect("diff matches the proposals");
+        assert!(fragment.data.diff.check());
     } else {
         panic!("Unexpected response");
     }
@@ -577,9 +688,9 @@ async fn latest_proposal() {
 
         let current_proposal = proposal.unwrap();
         current_proposal
-            .verify(&committee, None)
+            .verify_with_contents(&committee, None)
             .expect("no signature error");
-        assert_eq!(current_proposal.summary.sequence_number, 1);
+        assert_eq!(current_proposal.data().sequence_number, 1);
     } else {
         panic!("Unexpected response");
     }
@@ -721,7 +832,9 @@ async fn set_get_checkpoint() {
         contents,
     } = response
     {
-        signed.verify(&committee, contents.as_ref()).unwrap();
+        signed
+            .verify_with_contents(&committee, contents.as_ref())
+            .unwrap();
     } else {
         panic!("Unexpected response");
     }
@@ -738,6 +851,10 @@ async fn set_get_checkpoint() {
         };
     }
 
+    // The contents were read from the database once, then served from memory.
+    let stats = cps1.contents_cache().stats();
+    assert_eq!((stats.misses, stats.hits, stats.entries), (1, 1, 1));
+
     // --- TEST 2 ---
 
     // We can set the checkpoint cert to those that have it
@@ -788,6 +905,187 @@ async fn set_get_checkpoint() {
     ));
 }
 
+/// Returns a store holding checkpoint 0 signed, and the certificate of that checkpoint.
+fn signed_checkpoint_zero() -> (Committee, CheckpointStore, CertifiedCheckpointSummary) {
+    let (committee, _keys, mut stores) = random_ckpoint_store();
+    let items: BTreeSet<_> = (0..3).map(|_| ExecutionDigests::random()).collect();
+    let batch: Vec<_> = items
+        .iter()
+        .enumerate()
+        .map(|(u, c)| (u as u64, *c))
+        .collect();
+    let mut signed = Vec::new();
+    for (_, cps) in stores.iter_mut().take(3) {
+        cps.handle_internal_batch(0, &batch).unwrap();
+        cps.sign_new_checkpoint(
+            committee.epoch,
+            0,
+            items.iter(),
+            TestEffectsStore::default(),
+            None,
+        )
+        .unwrap();
+        match cps.handle_authenticated_checkpoint(&Some(0), true).unwrap() {
+            CheckpointResponse::AuthenticatedCheckpoint {
+                checkpoint: Some(AuthenticatedCheckpoint::Signed(summary)),
+                ..
+            } => signed.push(summary),
+            _ => unreachable!(),
+        };
+    }
+    let cert = CertifiedCheckpointSummary::aggregate(signed, &committee).unwrap();
+    let (_, cps) = stores.swap_remove(0);
+    (committee, cps, cert)
+}
+
+#[tokio::test]
+async fn checkpoint_contents_bucket() {
+    let (_, cps, _) = signed_checkpoint_zero();
+    let contents = cps.tables.checkpoint_contents.get(&0).unwrap().unwrap();
+
+    // Buckets of the empty prefix hold the whole contents.
+    if let CheckpointResponse::AuthenticatedCheckpointBucket {
+        bucket_digest,
+        bucket: Some(bucket),
+        ..
+    } = cps
+        .handle_authenticated_checkpoint_bucket(&Some(0), &DigestPrefix::empty(), true)
+        .unwrap()
+    {
+        bucket.verify().unwrap();
+        assert_eq!(bucket.transactions.len(), contents.size());
+        assert_eq!(bucket_digest, Some(bucket.digest()));
+    } else {
+        panic!("Unexpected response");
+    }
+}
+
+#[tokio::test]
+async fn latest_checkpoint_sequence_number() {
+    let (committee, mut cps, cert) = signed_checkpoint_zero();
+
+    // Only certified checkpoints are reported as the latest sequence number.
+    assert!(matches!(
+        cps.handle_latest_sequence_number().unwrap(),
+        CheckpointResponse::LatestCheckpointSequenceNumber { info: None }
+    ));
+    cps.promote_signed_checkpoint_to_cert(&cert, &committee)
+        .unwrap();
+    if let CheckpointResponse::LatestCheckpointSequenceNumber { info: Some(info) } =
+        cps.handle_latest_sequence_number().unwrap()
+    {
+        info.verify(&committee).unwrap();
+        assert_eq!(info.info.sequence_number, 0);
+        assert_eq!(info.info.digest, cert.data().digest());
+    } else {
+        panic!("Unexpected response");
+    }
+}
+
+#[tokio::test]
+async fn checkpoint_bootstrap() {
+    let (committee, mut cps, cert) = signed_checkpoint_zero();
+    let epoch = committee.epoch;
+    let bootstrap = CheckpointBootstrapRequest {
+        seq: 0,
+        from_trusted_epoch: epoch,
+    };
+
+    // Only certified checkpoints are bundled.
+    assert!(matches!(
+        cps.handle_bootstrap(&bootstrap).unwrap(),
+        CheckpointResponse::Bootstrap { bundle: None }
+    ));
+    cps.promote_signed_checkpoint_to_cert(&cert, &committee)
+        .unwrap();
+
+    // No epoch change is needed to bootstrap from the epoch of the checkpoint.
+    if let CheckpointResponse::Bootstrap {
+        bundle: Some(bundle),
+    } = cps.handle_bootstrap(&bootstrap).unwrap()
+    {
+        assert!(bundle.epoch_change_proofs.is_empty());
+        bundle.verify(&bootstrap, &committee).unwrap();
+    } else {
+        panic!("Unexpected response");
+    }
+    assert!(cps
+        .handle_bootstrap(&CheckpointBootstrapRequest {
+            seq: 0,
+            from_trusted_epoch: epoch + 1,
+        })
+        .is_err());
+}
+
+#[tokio::test]
+async fn checkpoint_not_available_attestation() {
+    // A store without checkpoints attests that it does not have one.
+    let (committee, _keys, mut stores) = random_ckpoint_store();
+    let (_, cps) = stores.pop().unwrap();
+    if let CheckpointResponse::NotAvailable { attestation } = cps
+        .handle_authenticated_checkpoint_or_attest(0, true, committee.epoch)
+        .unwrap()
+    {
+        attestation.verify(&committee).unwrap();
+        assert_eq!(attestation.authority(), &cps.name);
+        assert_eq!(attestation.attestation.latest_seq, None);
+    } else {
+        panic!("Unexpected response");
+    }
+
+    // Stored checkpoints are served, later ones attested as not available.
+    let (committee, cps, _) = signed_checkpoint_zero();
+    assert!(matches!(
+        cps.handle_authenticated_checkpoint_or_attest(0, false, committee.epoch)
+            .unwrap(),
+        CheckpointResponse::AuthenticatedCheckpoint {
+            checkpoint: Some(_),
+            ..
+        }
+    ));
+    if let CheckpointResponse::NotAvailable { attestation } = cps
+        .handle_authenticated_checkpoint_or_attest(5, false, committee.epoch)
+        .unwrap()
+    {
+        attestation.verify(&committee).unwrap();
+        assert_eq!(attestation.attestation.latest_seq, Some(0));
+    } else {
+        panic!("Unexpected response");
+    }
+}
+
+#[tokio::test]
+async fn checkpoint_contents_metadata() {
+    let (_, cps, _) = signed_checkpoint_zero();
+    let contents = cps.tables.checkpoint_contents.get(&0).unwrap().unwrap();
+
+    // The metadata of the contents can be served instead of the contents.
+    if let CheckpointResponse::AuthenticatedCheckpointMetadata {
+        checkpoint: Some(checkpoint),
+        metadata: Some(metadata),
+    } = cps
+        .handle_authenticated_checkpoint_metadata(&Some(0))
+        .unwrap()
+    {
+        metadata.verify(checkpoint.summary()).unwrap();
+        assert_eq!(metadata.count, contents.size() as u64);
+    } else {
+        panic!("Unexpected response");
+    }
+}
+
+#[tokio::test]
+async fn checkpoint_lookup_by_id() {
+    let (_, cps, _) = signed_checkpoint_zero();
+    let checkpoint = cps.tables.checkpoints.get(&0).unwrap().unwrap();
+
+    // Checkpoints can be looked up by identity, which includes the digest.
+    let mut id = CheckpointId::from(&checkpoint);
+    assert!(cps.get_checkpoint_by_id(&id).unwrap().is_some());
+    id.digest = [0; 32];
+    assert!(cps.get_checkpoint_by_id(&id).unwrap().is_none());
+}
+
 #[tokio::test]
 async fn checkpoint_integration() {
     telemetry_subscribers::init_for_testing();
@@ -795,6 +1093,7 @@ async fn checkpoint_integration() {
     let mut rng = StdRng::from_seed(RNG_SEED);
     let (keys, committee) = make_committee_key(&mut rng);
     let k = keys[0].copy();
+    let chain = CheckpointChainBuilder::new(keys, committee.clone()).build(&mut rng);
 
     // Setup
 
@@ -855,17 +1154,10 @@ async fn checkpoint_integration() {
 
             // Turn the signed checkpoint to a cert. This is required to make progress.
             let checkpoint = match cps.latest_stored_checkpoint().unwrap() {
-                AuthenticatedCheckpoint::Signed(s) => s.summary,
+                AuthenticatedCheckpoint::Signed(s) => s.into_data(),
                 _ => unreachable!(),
             };
-            let signatures: Vec<_> = keys
-                .iter()
-                .map(|key| {
-                    let name = key.public().into();
-                    SignedCheckpointSummary::new_from_summary(checkpoint.clone(), name, key)
-                })
-                .collect();
-            let cert = CertifiedCheckpointSummary::aggregate(signatures, &committee).unwrap();
+            let cert = chain.certify(checkpoint);
             cps.promote_signed_checkpoint_to_cert(&cert, &committee)
                 .unwrap();
 
@@ -1174,7 +1466,7 @@ async fn set_fragment_external() {
     let p2 = cps2.set_proposal(committee.epoch).unwrap();
     let _p3 = cps3.set_proposal(committee.epoch).unwrap();
 
-    let mut fragment12 = p1.fragment_with(&p2);
+    let mut fragment12 = fragment_between(&p1, &p2);
     for digest in [t1, t2, t3, t4, t5].into_iter() {
         let tx = crate::test_utils::create_fake_transaction();
         let mut sigs: Vec<AuthoritySignInfo> = Vec::new();
@@ -1322,6 +1614,68 @@ async fn set_fragment_reconstruct_two_mutual() {
     assert!(!span.is_completed());
 }
 
+#[tokio::test]
+async fn test_fragment_priority_queue() {
+    let (committee, _, mut test_stores) = random_ckpoint_store_num(4);
+
+    let txs = create_random_tx_certs(test_stores.iter(), &committee, 2);
+    let digests: Vec<_> = txs.keys().collect();
+
+    for (_, cps) in &mut test_stores {
+        cps.update_processed_transactions(&[(1, *digests[0]), (2, *digests[1])])
+            .unwrap();
+    }
+
+    let proposals: Vec<_> = test_stores
+        .iter_mut()
+        .map(|(_, cps)| cps.set_proposal(committee.epoch).unwrap())
+        .collect();
+    let fragment01 = make_fragment(&proposals[0], &proposals[1], &txs);
+    let fragment10 = make_fragment(&proposals[1], &proposals[0], &txs);
+    let fragment23 = make_fragment(&proposals[2], &proposals[3], &txs);
+    let fragment30 = make_fragment(&proposals[3], &proposals[0], &txs);
+
+    // Added in arrival order, all four fragments are needed.
+    let span = SpanGraph::new_for_testing(
+        &committee,
+        0,
+        vec![fragment01.clone(), fragment10.clone(), fragment23.clone()],
+    );
+    assert!(!span.is_completed());
+
+    let mut queue = FragmentPriorityQueue::new();
+    for fragment in [&fragment01, &fragment10, &fragment23, &fragment30] {
+        queue.push(fragment.clone());
+    }
+    let mut span = SpanGraph::default();
+    let pair_stake = |fragment: &CheckpointFragment| {
+        committee.weight(fragment.proposer.authority())
+            + committee.weight(fragment.other.authority())
+    };
+    assert_eq!(
+        span.marginal_stake(&committee, &fragment01),
+        pair_stake(&fragment01)
+    );
+
+    // Ties are broken by arrival order.
+    let first = queue.poll(&committee, &span).unwrap();
+    assert_eq!(first.proposer.authority(), fragment01.proposer.authority());
+    span.add_fragment(&committee, 0, first);
+    assert_eq!(span.marginal_stake(&committee, &fragment10), 0);
+    assert_eq!(
+        span.marginal_stake(&committee, &fragment30),
+        pair_stake(&fragment01) + committee.weight(fragment30.proposer.authority())
+    );
+
+    // The fragment linking a third authority to the first two completes the graph, leaving
+    // the others in the queue.
+    queue.drain_into(&committee, 0, &mut span);
+    assert!(span.is_completed());
+    assert_eq!(queue.len(), 2);
+    let reconstruction = span.construct_checkpoint().unwrap();
+    assert_eq!(reconstruction.global.authority_waypoints.len(), 3);
+}
+
 #[derive(Clone)]
 struct TestConsensus {
     sender: Arc<std::sync::Mutex<std::sync::mpsc::Sender<SignedCheckpointFragmentMessage>>>,
@@ -1353,6 +1707,45 @@ impl TestConsensus {
     }
 }
 
+#[tokio::test]
+async fn test_tx_checkpoint_trace() {
+    let (committee, _keys, mut cp_stores) = random_ckpoint_store();
+    let txs = create_random_tx_certs(cp_stores.iter(), &committee, 3);
+    let digests: Vec<_> = txs.keys().copied().collect();
+
+    let (_, mut cps1) = cp_stores.pop().unwrap();
+    let (_, mut cps2) = cp_stores.pop().unwrap();
+    let trace = Arc::new(TxCheckpointTrace::new(10));
+    assert!(trace.select(digests[0].transaction));
+    cps1.tx_trace = Some(trace.clone());
+
+    cps1.update_processed_transactions(&[(1, digests[0]), (2, digests[1])])
+        .unwrap();
+    cps2.update_processed_transactions(&[(1, digests[1]), (2, digests[2])])
+        .unwrap();
+    let p1 = cps1.set_proposal(committee.epoch).unwrap();
+    let p2 = cps2.set_proposal(committee.epoch).unwrap();
+
+    // The fragment carries the traced transaction, missing from the proposal of cps2.
+    let fragment = make_fragment(&p2, &p1, &txs);
+    let mut seq = ExecutionIndices::default();
+    for message in fragment.to_message_chunks() {
+        cps1.handle_internal_fragment(seq.clone(), message, &committee)
+            .unwrap();
+        seq.next_transaction_index += 1;
+    }
+    let contents =
+        CheckpointContents::new_with_causally_ordered_transactions(digests[..2].iter().copied());
+    cps1.update_new_checkpoint(0, &contents).unwrap();
+
+    let timeline = trace.timeline(&digests[0].transaction).unwrap();
+    assert_eq!(timeline.proposals.len(), 1);
+    assert_eq!(timeline.proposals[0].authority, cps1.name);
+    assert_eq!(timeline.fragments.len(), 1);
+    assert_eq!(timeline.fragments[0].proposer, cps2.name);
+    assert_eq!(timeline.checkpoint.unwrap().sequence_number, 0);
+}
+
 #[tokio::test]
 async fn test_fragment_full_flow() {
     let (committee, _keys, mut test_stores) = random_ckpoint_store_num(2 * 3 + 1);
@@ -1493,8 +1886,8 @@ async fn test_slow_fragment() {
         .iter_mut()
         .map(|(_, cp)| cp.set_proposal(0).unwrap())
         .collect();
-    let fragment12 = proposals[1].fragment_with(&proposals[2]);
-    let fragment23 = proposals[2].fragment_with(&proposals[3]);
+    let fragment12 = fragment_between(&proposals[1], &proposals[2]);
+    let fragment23 = fragment_between(&proposals[2], &proposals[3]);
     let mut index = ExecutionIndices::default();
     cp_stores.iter_mut().for_each(|(_, cp)| {
         cp.handle_fragment_for_testing(&mut index, fragment12.clone(), &committee)
@@ -1544,8 +1937,8 @@ async fn test_slow_fragment() {
         .iter_mut()
         .map(|(_, cp)| cp.set_proposal(0).unwrap())
         .collect();
-    let fragment12 = proposals[1].fragment_with(&proposals[2]);
-    let fragment23 = proposals[2].fragment_with(&proposals[3]);
+    let fragment12 = fragment_between(&proposals[1], &proposals[2]);
+    let fragment23 = fragment_between(&proposals[2], &proposals[3]);
     cp_stores.iter_mut().skip(1).for_each(|(_, cp)| {
         cp.handle_fragment_for_testing(&mut index, fragment12.clone(), &committee)
             .unwrap();
@@ -1894,11 +2287,25 @@ async fn checkpoint_messaging_flow() {
         }
     }
 
+    // A client in another epoch is told so instead of being served a proposal.
+    for client in setup.aggregator.authority_clients.values() {
+        let response = client
+            .handle_checkpoint(
+                CheckpointRequest::proposal(true).with_client_epoch(setup.committee.epoch + 1),
+            )
+            .await
+            .expect("No issues");
+        assert!(matches!(
+            response,
+            CheckpointResponse::EpochMismatch { server_epoch } if server_epoch == setup.committee.epoch
+        ));
+    }
+
     // Step 2 -- make fragments using the proposals.
     let proposal_len = proposals.len();
     for (i, (auth, proposal)) in proposals.iter().enumerate() {
-        let p0 = proposal.fragment_with(&proposals[(i + 1) % proposal_len].1);
-        let p1 = proposal.fragment_with(&proposals[(i + 3) % proposal_len].1);
+        let p0 = fragment_between(proposal, &proposals[(i + 1) % proposal_len].1);
+        let p1 = fragment_between(proposal, &proposals[(i + 3) % proposal_len].1);
 
         let authority = setup.get_authority(auth);
         authority
@@ -2023,9 +2430,9 @@ async fn test_no_more_fragments() {
     let p1 = proposals.pop().unwrap();
     let p0 = proposals.pop().unwrap();
 
-    let f01 = p0.fragment_with(&p1);
-    let f02 = p0.fragment_with(&p2);
-    let f30 = p3.fragment_with(&p0);
+    let f01 = fragment_between(&p0, &p1);
+    let f02 = fragment_between(&p0, &p2);
+    let f30 = fragment_between(&p3, &p0);
 
     // put in fragment 0-1 and no checkpoint can be formed
 
@@ -2100,6 +2507,237 @@ async fn test_no_more_fragments() {
         .is_ok());
 }
 
+#[tokio::test]
+async fn test_checkpoint_waiter() {
+    let (committee, keys, mut stores) = random_ckpoint_store();
+    let (_, mut cps) = stores.pop().unwrap();
+    let mut rng = StdRng::from_seed(RNG_SEED);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone())
+        .checkpoints_per_epoch(2)
+        .build(&mut rng);
+
+    let waiter = cps.waiter();
+    assert_eq!(waiter.watermark(), None);
+    assert!(matches!(
+        waiter.wait_for(0, Duration::from_millis(10)).await,
+        Err(SuiError::CheckpointWaitTimeout {
+            sequence_number: 0,
+            watermark: None,
+        })
+    ));
+
+    let pending = tokio::spawn({
+        let waiter = waiter.clone();
+        async move { waiter.wait_for(1, Duration::from_secs(10)).await }
+    });
+    let (cert0, contents0) = &chain.checkpoints[0];
+    cps.process_synced_checkpoint_certificate(cert0, contents0, &committee)
+        .unwrap();
+    assert!(matches!(
+        waiter.wait_for(1, Duration::from_millis(10)).await,
+        Err(SuiError::CheckpointWaitTimeout {
+            sequence_number: 1,
+            watermark: Some(0),
+        })
+    ));
+    let (cert1, contents1) = &chain.checkpoints[1];
+    cps.process_synced_checkpoint_certificate(cert1, contents1, &committee)
+        .unwrap();
+    assert_eq!(pending.await.unwrap().unwrap(), 1);
+
+    // Checkpoints already stored resolve immediately.
+    assert_eq!(
+        waiter.wait_for(0, Duration::from_millis(10)).await.unwrap(),
+        1
+    );
+    assert_eq!(cps.waiter().watermark(), Some(1));
+}
+
+struct ChainFetcher {
+    checkpoints: Vec<(CertifiedCheckpointSummary, CheckpointContents)>,
+    fetched: std::sync::atomic::AtomicUsize,
+    in_flight: std::sync::atomic::AtomicUsize,
+    max_in_flight: std::sync::atomic::AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl CheckpointFetcher for ChainFetcher {
+    async fn fetch(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> SuiResult<(CertifiedCheckpointSummary, CheckpointContents)> {
+        use std::sync::atomic::Ordering;
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        // Later checkpoints are fetched faster, so that fetches complete out of order.
+        let delay = self.checkpoints.len().saturating_sub(seq as usize) as u64;
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.fetched.fetch_add(1, Ordering::SeqCst);
+        self.checkpoints
+            .get(seq as usize)
+            .cloned()
+            .ok_or_else(|| SuiError::from("No such checkpoint"))
+    }
+}
+
+struct RecordingExecutor {
+    /// Every execution takes a permit, so that tests can hold the executor back.
+    gate: tokio::sync::Semaphore,
+    executed: Mutex<Vec<CheckpointSequenceNumber>>,
+}
+
+#[async_trait::async_trait]
+impl CheckpointExecutorSink for RecordingExecutor {
+    async fn execute(&self, request: CheckpointExecutionRequest) -> SuiResult {
+        self.gate.acquire().await.unwrap().forget();
+        self.executed.lock().push(request.sequence_number());
+        Ok(())
+    }
+}
+
+/// A pipeline over a chain of `count` checkpoints, whose checkpoint `corrupted`, if any, is
+/// fetched with other contents.
+fn checkpoint_pipeline(
+    count: u64,
+    permits: usize,
+    corrupted: Option<usize>,
+) -> (
+    Arc<ChainFetcher>,
+    Arc<RecordingExecutor>,
+    CheckpointPipeline<ChainFetcher, CommitteeCheckpointVerifier, RecordingExecutor>,
+) {
+    let mut rng = StdRng::from_seed(RNG_SEED);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone())
+        .checkpoints_per_epoch(count)
+        .build(&mut rng);
+    let mut checkpoints = chain.checkpoints;
+    if let Some(seq) = corrupted {
+        checkpoints[seq].1 =
+            CheckpointContents::new_with_causally_ordered_transactions([].into_iter());
+    }
+    let fetcher = Arc::new(ChainFetcher {
+        checkpoints,
+        fetched: Default::default(),
+        in_flight: Default::default(),
+        max_in_flight: Default::default(),
+    });
+    let executor = Arc::new(RecordingExecutor {
+        gate: tokio::sync::Semaphore::new(permits),
+        executed: Mutex::new(Vec::new()),
+    });
+    let pipeline = CheckpointPipeline::new(
+        fetcher.clone(),
+        Arc::new(CommitteeCheckpointVerifier::new(committee)),
+        executor.clone(),
+        CheckpointPipelineConfig {
+            fetch_concurrency: 4,
+            verify_concurrency: 2,
+            queue_capacity: 2,
+        },
+    )
+    .unwrap();
+    (fetcher, executor, pipeline)
+}
+
+#[test]
+fn test_checkpoint_pipeline_config() {
+    CheckpointPipelineConfig::default().validate().unwrap();
+    for config in [
+        CheckpointPipelineConfig {
+            fetch_concurrency: 0,
+            ..Default::default()
+        },
+        CheckpointPipelineConfig {
+            verify_concurrency: 0,
+            ..Default::default()
+        },
+        CheckpointPipelineConfig {
+            queue_capacity: 0,
+            ..Default::default()
+        },
+    ] {
+        assert!(config.validate().is_err());
+    }
+}
+
+#[tokio::test]
+async fn test_checkpoint_pipeline() {
+    let (fetcher, executor, pipeline) = checkpoint_pipeline(20, 0, None);
+    let pipeline = Arc::new(pipeline);
+    let run = tokio::spawn({
+        let pipeline = pipeline.clone();
+        async move { pipeline.run(0..20).await }
+    });
+
+    // With the executor held back, the bounded queues stop the fetcher well before the end.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let fetched = fetcher.fetched.load(std::sync::atomic::Ordering::SeqCst);
+    assert!(fetched > 0 && fetched < 20, "fetched {fetched}");
+    let watermarks = pipeline.watermarks();
+    assert!(watermarks.fetched.unwrap() < 19);
+    assert_eq!(watermarks.executed, None);
+
+    executor.gate.add_permits(20);
+    run.await.unwrap().unwrap();
+    assert_eq!(*executor.executed.lock(), (0..20).collect::<Vec<_>>());
+    assert_eq!(
+        pipeline.watermarks(),
+        PipelineWatermarks {
+            fetched: Some(19),
+            verified: Some(19),
+            executed: Some(19),
+        }
+    );
+    assert_eq!(
+        *pipeline.subscribe(PipelineStage::Execute).borrow(),
+        Some(19)
+    );
+}
+
+#[tokio::test]
+async fn test_checkpoint_pipeline_failure() {
+    let (_, executor, pipeline) = checkpoint_pipeline(10, 10, Some(5));
+
+    // A checkpoint failing verification stops the pipeline after its predecessors.
+    assert!(pipeline.run(0..10).await.is_err());
+    assert_eq!(*executor.executed.lock(), (0..5).collect::<Vec<_>>());
+    assert_eq!(pipeline.watermarks().executed, Some(4));
+
+    // So does a checkpoint that cannot be fetched.
+    assert!(pipeline.run(10..12).await.is_err());
+    assert_eq!(pipeline.watermarks().executed, Some(4));
+}
+
+#[tokio::test]
+async fn test_checkpoint_pipeline_adaptive_batching() {
+    let (fetcher, executor, pipeline) = checkpoint_pipeline(20, 20, None);
+    let pipeline = pipeline.with_adaptive_batching(AdaptiveBatcherConfig {
+        initial_batch: 3,
+        increase: 2,
+        ..Default::default()
+    });
+
+    pipeline.run(0..20).await.unwrap();
+    assert_eq!(*executor.executed.lock(), (0..20).collect::<Vec<_>>());
+    assert_eq!(
+        fetcher.fetched.load(std::sync::atomic::Ordering::SeqCst),
+        20
+    );
+    // Batches larger than the fetch concurrency are not fetched all at once.
+    assert_eq!(
+        fetcher
+            .max_in_flight
+            .load(std::sync::atomic::Ordering::SeqCst),
+        4
+    );
+
+    // A range that cannot be fetched is retried in smaller batches, down to one checkpoint.
+    assert!(pipeline.run(20..22).await.is_err());
+    assert_eq!(pipeline.watermarks().executed, Some(19));
+}
+
 fn create_random_tx_certs<'a>(
     cp_stores: impl Iterator<Item = &'a (PathBuf, CheckpointStore)> + Clone,
     committee: &Committee,
@@ -2111,12 +2749,22 @@ fn create_random_tx_certs<'a>(
         .collect()
 }
 
+/// The fragment the validator of `proposal` builds on receiving `other` from another validator.
+pub(crate) fn fragment_between(
+    proposal: &CheckpointProposal,
+    other: &CheckpointProposal,
+) -> CheckpointFragment {
+    let local = LocalProposal::from_proposal(proposal.clone(), proposal.name()).unwrap();
+    let remote = RemoteProposal::new(other.clone(), proposal.name()).unwrap();
+    local.fragment_with_remote(&remote)
+}
+
 fn make_fragment(
     proposal1: &CheckpointProposal,
     proposal2: &CheckpointProposal,
     txs: &BTreeMap<ExecutionDigests, CertifiedTransaction>,
 ) -> CheckpointFragment {
-    let mut fragment = proposal1.fragment_with(proposal2);
+    let mut fragment = fragment_between(proposal1, proposal2);
     let certs = fragment
         .data
         .diff
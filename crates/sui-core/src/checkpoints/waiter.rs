@@ -0,0 +1,57 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use sui_types::error::{SuiError, SuiResult};
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use tokio::sync::watch;
+
+/// Waits for certified checkpoints to be stored by the `CheckpointStore`, for callers such
+/// as RPC handlers, executors and tests that need a checkpoint before they can proceed.
+#[derive(Clone)]
+pub struct CheckpointWaiter {
+    /// The highest certified checkpoint stored, if any. Certified checkpoints are stored in
+    /// sequence, so every checkpoint below it is available too.
+    watermark: watch::Receiver<Option<CheckpointSequenceNumber>>,
+}
+
+impl CheckpointWaiter {
+    pub(crate) fn new(watermark: watch::Receiver<Option<CheckpointSequenceNumber>>) -> Self {
+        Self { watermark }
+    }
+
+    /// The highest certified checkpoint stored, if any.
+    pub fn watermark(&self) -> Option<CheckpointSequenceNumber> {
+        *self.watermark.borrow()
+    }
+
+    /// Resolves to the current watermark once the certified checkpoint `seq` is stored, or
+    /// fails with `CheckpointWaitTimeout` if it is not stored within `timeout`.
+    pub async fn wait_for(
+        &self,
+        seq: CheckpointSequenceNumber,
+        timeout: Duration,
+    ) -> SuiResult<CheckpointSequenceNumber> {
+        let mut watermark = self.watermark.clone();
+        let wait = async {
+            loop {
+                let current = *watermark.borrow_and_update();
+                if let Some(current) = current.filter(|current| *current >= seq) {
+                    return Ok(current);
+                }
+                watermark
+                    .changed()
+                    .await
+                    .map_err(|_| SuiError::from("Checkpoint store was closed"))?;
+            }
+        };
+        match tokio::time::timeout(timeout, wait).await {
+            Ok(result) => result,
+            Err(_) => Err(SuiError::CheckpointWaitTimeout {
+                sequence_number: seq,
+                watermark: *watermark.borrow(),
+            }),
+        }
+    }
+}
@@ -8,17 +8,23 @@ use sui_types::base_types::AuthorityName;
 use sui_types::error::SuiResult;
 use sui_types::messages::ConsensusTransaction;
 use sui_types::messages_checkpoint::{
-    CheckpointContents, CheckpointSignatureMessage, CheckpointSummary, SignedCheckpointSummary,
+    CheckpointSignatureBatch, CheckpointSignatureMessage, FinalizedCheckpoint,
+    MAX_CHECKPOINT_SIGNATURE_BATCH,
 };
 use tracing::{debug, info};
 
 #[async_trait]
 pub trait CheckpointOutput: Sync + Send + 'static {
-    async fn checkpoint_created(
-        &self,
-        summary: &CheckpointSummary,
-        contents: &CheckpointContents,
-    ) -> SuiResult;
+    async fn checkpoint_created(&self, checkpoint: &FinalizedCheckpoint) -> SuiResult;
+
+    /// Called with the consecutive checkpoints created in one pass of the builder, which
+    /// holds more than one checkpoint while the builder catches up.
+    async fn checkpoints_created(&self, checkpoints: &[FinalizedCheckpoint]) -> SuiResult {
+        for checkpoint in checkpoints {
+            self.checkpoint_created(checkpoint).await?;
+        }
+        Ok(())
+    }
 }
 
 pub struct SubmitCheckpointToConsensus<T> {
@@ -37,32 +43,39 @@ impl LogCheckpointOutput {
 
 #[async_trait]
 impl<T: SubmitToConsensus> CheckpointOutput for SubmitCheckpointToConsensus<T> {
-    async fn checkpoint_created(
-        &self,
-        summary: &CheckpointSummary,
-        contents: &CheckpointContents,
-    ) -> SuiResult {
-        LogCheckpointOutput
-            .checkpoint_created(summary, contents)
-            .await?;
-        let summary = SignedCheckpointSummary::new_from_summary(
-            summary.clone(),
-            self.authority,
-            &*self.signer,
-        );
+    async fn checkpoint_created(&self, checkpoint: &FinalizedCheckpoint) -> SuiResult {
+        LogCheckpointOutput.checkpoint_created(checkpoint).await?;
+        let summary = checkpoint.sign(self.authority, &*self.signer);
         let message = CheckpointSignatureMessage { summary };
         let transaction = ConsensusTransaction::new_checkpoint_signature_message(message);
         self.sender.submit_to_consensus(&transaction).await
     }
+
+    async fn checkpoints_created(&self, checkpoints: &[FinalizedCheckpoint]) -> SuiResult {
+        for chunk in checkpoints.chunks(MAX_CHECKPOINT_SIGNATURE_BATCH) {
+            if let [checkpoint] = chunk {
+                self.checkpoint_created(checkpoint).await?;
+                continue;
+            }
+            let mut summaries = Vec::with_capacity(chunk.len());
+            for checkpoint in chunk {
+                LogCheckpointOutput.checkpoint_created(checkpoint).await?;
+                summaries.push(checkpoint.sign(self.authority, &*self.signer));
+            }
+            let epoch = chunk[0].summary().epoch;
+            let batch =
+                CheckpointSignatureBatch::new(summaries, self.authority, epoch, &*self.signer)?;
+            let transaction = ConsensusTransaction::new_checkpoint_signature_batch(batch);
+            self.sender.submit_to_consensus(&transaction).await?;
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl CheckpointOutput for LogCheckpointOutput {
-    async fn checkpoint_created(
-        &self,
-        summary: &CheckpointSummary,
-        contents: &CheckpointContents,
-    ) -> SuiResult {
+    async fn checkpoint_created(&self, checkpoint: &FinalizedCheckpoint) -> SuiResult {
+        let (summary, contents) = (checkpoint.summary(), checkpoint.contents());
         debug!(
             "Including following transactions in checkpoint {}: {:?}",
             summary.sequence_number, contents
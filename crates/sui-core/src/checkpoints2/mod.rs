@@ -12,17 +12,21 @@ pub use crate::checkpoints2::checkpoint_output::{
 };
 use futures::future::{select, Either};
 use futures::FutureExt;
+use parking_lot::Mutex;
 use std::collections::HashSet;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 use sui_metrics::spawn_monitored_task;
 use sui_types::base_types::{EpochId, TransactionDigest};
-use sui_types::error::SuiResult;
+use sui_types::checkpoint_object_changes::ObjectChanges;
+use sui_types::error::{SuiError, SuiResult};
+use sui_types::fp_ensure;
 use sui_types::gas::GasCostSummary;
 use sui_types::messages::TransactionEffects;
 use sui_types::messages_checkpoint::{
     CheckpointContents, CheckpointSequenceNumber, CheckpointSignatureMessage, CheckpointSummary,
+    EpochBoundaryTable, EpochClosePolicy, FinalizedCheckpoint, ProvisionalCheckpointSummary,
 };
 use tokio::sync::{oneshot, Notify};
 use tracing::{debug, error, info};
@@ -63,6 +67,8 @@ pub struct CheckpointBuilder {
     output: Box<dyn CheckpointOutput>,
     exit: oneshot::Receiver<()>,
     epoch: EpochId,
+    epoch_boundaries: Mutex<EpochBoundaryTable>,
+    epoch_close_policy: EpochClosePolicy,
 }
 
 impl CheckpointBuilder {
@@ -73,24 +79,45 @@ impl CheckpointBuilder {
         output: Box<dyn CheckpointOutput>,
         exit: oneshot::Receiver<()>,
         epoch: EpochId,
-    ) -> Self {
-        Self {
+        epoch_close_policy: EpochClosePolicy,
+    ) -> SuiResult<Self> {
+        // Rebuild the epoch boundaries from the checkpoints created so far.
+        let mut epoch_boundaries = EpochBoundaryTable::new();
+        for (_, summary) in tables.checkpoint_summary.iter() {
+            if summary.next_epoch_committee.is_some() {
+                epoch_boundaries.insert(&summary)?;
+            }
+        }
+        Ok(Self {
             tables,
             notify,
             effects_store,
             output,
             exit,
             epoch,
-        }
+            epoch_boundaries: Mutex::new(epoch_boundaries),
+            epoch_close_policy,
+        })
     }
 
     async fn run(mut self) {
         loop {
+            // Checkpoints created in this pass are handed to the output together, so that
+            // a builder catching up publishes its signatures in batches.
+            let mut created = vec![];
             for (height, roots) in self.tables.pending_checkpoints.iter() {
-                if let Err(e) = self.make_checkpoint(height, roots).await {
-                    error!("Error while making checkpoint, will retry in 1s: {:?}", e);
-                    tokio::time::sleep(Duration::from_secs(1)).await;
-                    continue;
+                match self.make_checkpoint(height, roots).await {
+                    Ok(checkpoint) => created.extend(checkpoint),
+                    Err(e) => {
+                        error!("Error while making checkpoint, will retry in 1s: {:?}", e);
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        continue;
+                    }
+                }
+            }
+            if !created.is_empty() {
+                if let Err(e) = self.output.checkpoints_created(&created).await {
+                    error!("Error while publishing created checkpoints: {:?}", e);
                 }
             }
             match select(&mut self.exit, self.notify.notified().boxed()).await {
@@ -107,34 +134,36 @@ impl CheckpointBuilder {
         &self,
         height: CheckpointCommitHeight,
         roots: Vec<TransactionDigest>,
-    ) -> SuiResult {
+    ) -> SuiResult<Option<FinalizedCheckpoint>> {
         let roots = self.effects_store.notify_read(roots).await?;
         let unsorted = self.complete_checkpoint(roots)?;
         let sorted = CasualOrder::casual_sort(unsorted);
-        self.write_checkpoint(height, sorted).await?;
-        Ok(())
+        self.write_checkpoint(height, sorted).await
     }
 
     async fn write_checkpoint(
         &self,
         height: CheckpointCommitHeight,
         l: Vec<TransactionEffects>,
-    ) -> SuiResult {
+    ) -> SuiResult<Option<FinalizedCheckpoint>> {
         let mut batch = self.tables.pending_checkpoints.batch();
+        let mut created = None;
         if !l.is_empty() {
             // Only create checkpoint if content is not empty
-            batch = self.create_checkpoint(batch, l).await?;
+            let (new_batch, checkpoint) = self.create_checkpoint(batch, l).await?;
+            batch = new_batch;
+            created = Some(checkpoint);
         }
         batch = batch.delete_batch(&self.tables.pending_checkpoints, [height])?;
         batch.write()?;
-        Ok(())
+        Ok(created)
     }
 
     async fn create_checkpoint(
         &self,
         mut batch: DBBatch,
         l: Vec<TransactionEffects>,
-    ) -> SuiResult<DBBatch> {
+    ) -> SuiResult<(DBBatch, FinalizedCheckpoint)> {
         let last_checkpoint = self.tables.checkpoint_summary.iter().skip_to_last().next();
         let previous_digest = last_checkpoint.as_ref().map(|(_, c)| c.digest());
         let sequence_number = last_checkpoint
@@ -144,24 +173,25 @@ impl CheckpointBuilder {
             l.iter().map(TransactionEffects::execution_digests),
         );
         let gas_cost_summary = GasCostSummary::new_from_txn_effects(l.iter());
-        let summary = CheckpointSummary::new(
+        let mut provisional = ProvisionalCheckpointSummary::new(
             self.epoch, // todo - need to figure out how this is updated
             sequence_number,
-            &contents,
             previous_digest,
-            gas_cost_summary,
             None, //todo
         );
-
-        self.output.checkpoint_created(&summary, &contents).await?;
+        provisional.set_contents(contents, gas_cost_summary);
+        provisional.set_object_changes(&ObjectChanges::from_effects(l.iter()));
+        // The contents are frozen before the checkpoint is stored and handed out for signing.
+        let checkpoint = provisional.finalize();
+        self.check_epoch_close(checkpoint.summary())?;
 
         batch = batch.insert_batch(
             &self.tables.checkpoint_content,
-            [(sequence_number, contents)],
+            [(sequence_number, checkpoint.contents())],
         )?;
         batch = batch.insert_batch(
             &self.tables.checkpoint_summary,
-            [(sequence_number, summary)],
+            [(sequence_number, checkpoint.summary())],
         )?;
         for txn in l.iter() {
             batch = batch.insert_batch(
@@ -169,7 +199,32 @@ impl CheckpointBuilder {
                 [(txn.transaction_digest, sequence_number)],
             )?;
         }
-        Ok(batch)
+        Ok((batch, checkpoint))
+    }
+
+    /// Make sure the checkpoint only closes the epoch where the policy allows it, and record
+    /// the new epoch boundary if it does.
+    fn check_epoch_close(&self, summary: &CheckpointSummary) -> SuiResult {
+        let mut epoch_boundaries = self.epoch_boundaries.lock();
+        if epoch_boundaries.first_seq_of(self.epoch).is_none() {
+            // The checkpoints closing the previous epochs were not recorded, since the
+            // summaries built so far do not carry the next committee. The checkpoint belongs
+            // to the epoch of the builder, but its position in the epoch is unknown, so that
+            // the policy cannot be applied.
+            fp_ensure!(
+                summary.epoch == self.epoch,
+                SuiError::WrongEpoch {
+                    expected_epoch: self.epoch,
+                    actual_epoch: summary.epoch,
+                }
+            );
+            return Ok(());
+        }
+        self.epoch_close_policy.check(summary, &epoch_boundaries)?;
+        if summary.next_epoch_committee.is_some() {
+            epoch_boundaries.insert(summary)?;
+        }
+        Ok(())
     }
 
     /// For the given roots return complete list of effects to include in checkpoint
@@ -229,7 +284,23 @@ impl CheckpointService {
         effects_store: Box<dyn EffectsNotifyRead>,
         output: Box<dyn CheckpointOutput>,
         epoch: EpochId,
-    ) -> Arc<Self> {
+    ) -> SuiResult<Arc<Self>> {
+        Self::spawn_with_close_policy(
+            path,
+            effects_store,
+            output,
+            epoch,
+            EpochClosePolicy::default(),
+        )
+    }
+
+    pub fn spawn_with_close_policy(
+        path: &Path,
+        effects_store: Box<dyn EffectsNotifyRead>,
+        output: Box<dyn CheckpointOutput>,
+        epoch: EpochId,
+        epoch_close_policy: EpochClosePolicy,
+    ) -> SuiResult<Arc<Self>> {
         let notify = Arc::new(Notify::new());
 
         let tables = CheckpointStoreTables::open_tables_read_write(path.to_path_buf(), None, None);
@@ -244,15 +315,16 @@ impl CheckpointService {
             output,
             exit_rcv,
             epoch,
-        );
+            epoch_close_policy,
+        )?;
 
         spawn_monitored_task!(builder.run());
 
-        Arc::new(Self {
+        Ok(Arc::new(Self {
             tables,
             notify,
             _exit: exit_snd,
-        })
+        }))
     }
 
     pub fn notify_checkpoint(
@@ -282,9 +354,9 @@ impl CheckpointService {
     pub fn notify_checkpoint_signature(&self, info: Box<CheckpointSignatureMessage>) -> SuiResult {
         info!(
             "Received signature for checkpoint sequence {}, digest {} from {}",
-            info.summary.summary.sequence_number,
-            hex::encode(info.summary.summary.digest()),
-            info.summary.auth_signature.authority
+            info.summary.data().sequence_number,
+            hex::encode(info.summary.data().digest()),
+            info.summary.authority()
         );
         Ok(()) // todo
     }
@@ -293,8 +365,14 @@ impl CheckpointService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::consensus_adapter::SubmitToConsensus;
     use async_trait::async_trait;
+    use fastcrypto::traits::KeyPair;
+    use rand::rngs::OsRng;
     use std::collections::HashMap;
+    use sui_types::base_types::AuthorityName;
+    use sui_types::messages::{ConsensusTransaction, ConsensusTransactionKind};
+    use sui_types::utils::make_committee_key;
     use tempfile::tempdir;
     use tokio::sync::mpsc;
 
@@ -310,7 +388,7 @@ mod tests {
         let store = Box::new(store);
 
         let checkpoint_service =
-            CheckpointService::spawn(tempdir.path(), store, Box::new(output), 0);
+            CheckpointService::spawn(tempdir.path(), store, Box::new(output), 0).unwrap();
         checkpoint_service.notify_checkpoint(0, vec![d(4)]).unwrap();
         // Verify that sending same digests at same height is noop
         checkpoint_service.notify_checkpoint(0, vec![d(4)]).unwrap();
@@ -332,6 +410,77 @@ mod tests {
         assert_eq!(c2s.sequence_number, 1);
     }
 
+    #[tokio::test]
+    pub async fn checkpoint_builder_after_reconfiguration_test() {
+        let tempdir = tempdir().unwrap();
+        let mut store: HashMap<TransactionDigest, TransactionEffects> = HashMap::new();
+        store.insert(d(1), e(d(1), vec![]));
+        store.insert(d(2), e(d(2), vec![]));
+        let (output, mut result) = mpsc::channel::<(CheckpointContents, CheckpointSummary)>(10);
+
+        // No checkpoint recorded the end of epoch 0: checkpoints are still built in epoch 1.
+        let checkpoint_service =
+            CheckpointService::spawn(tempdir.path(), Box::new(store), Box::new(output), 1).unwrap();
+        checkpoint_service.notify_checkpoint(0, vec![d(1)]).unwrap();
+        checkpoint_service.notify_checkpoint(1, vec![d(2)]).unwrap();
+
+        let (_, c1s) = result.recv().await.unwrap();
+        let (_, c2s) = result.recv().await.unwrap();
+        assert_eq!((c1s.epoch, c1s.sequence_number), (1, 0));
+        assert_eq!((c2s.epoch, c2s.sequence_number), (1, 1));
+    }
+
+    #[tokio::test]
+    pub async fn checkpoint_signatures_batched_test() {
+        let tempdir = tempdir().unwrap();
+        let (keys, committee) = make_committee_key(&mut OsRng);
+        let authority: AuthorityName = keys[0].public().into();
+        let mut store: HashMap<TransactionDigest, TransactionEffects> = HashMap::new();
+        store.insert(d(1), e(d(1), vec![]));
+        store.insert(d(2), e(d(2), vec![]));
+        store.insert(d(3), e(d(3), vec![]));
+        let (sender, mut result) = mpsc::channel::<ConsensusTransaction>(10);
+        let output = SubmitCheckpointToConsensus {
+            sender,
+            signer: Arc::pin(keys[0].copy()),
+            authority,
+        };
+
+        // The heights are pending before the builder starts, as if it was catching up.
+        let tables =
+            CheckpointStoreTables::open_tables_read_write(tempdir.path().to_path_buf(), None, None);
+        for (height, digest) in [(0, d(1)), (1, d(2)), (2, d(3))] {
+            tables
+                .pending_checkpoints
+                .insert(&height, &vec![digest])
+                .unwrap();
+        }
+        drop(tables);
+        let _checkpoint_service =
+            CheckpointService::spawn(tempdir.path(), Box::new(store), Box::new(output), 0).unwrap();
+
+        let transaction = result.recv().await.unwrap();
+        transaction.verify(&committee).unwrap();
+        let batch = match transaction.kind {
+            ConsensusTransactionKind::CheckpointSignatureBatch(batch) => batch,
+            kind => panic!("Expected a signature batch, got {:?}", kind),
+        };
+        assert_eq!(batch.authority(), &authority);
+        let sequence_numbers: Vec<_> = batch
+            .into_messages()
+            .map(|message| message.summary.data().sequence_number)
+            .collect();
+        assert_eq!(sequence_numbers, vec![0, 1, 2]);
+    }
+
+    #[async_trait]
+    impl SubmitToConsensus for mpsc::Sender<ConsensusTransaction> {
+        async fn submit_to_consensus(&self, transaction: &ConsensusTransaction) -> SuiResult {
+            self.try_send(transaction.clone()).unwrap();
+            Ok(())
+        }
+    }
+
     #[async_trait]
     impl EffectsNotifyRead for HashMap<TransactionDigest, TransactionEffects> {
         async fn notify_read(
@@ -357,12 +506,9 @@ mod tests {
 
     #[async_trait::async_trait]
     impl CheckpointOutput for mpsc::Sender<(CheckpointContents, CheckpointSummary)> {
-        async fn checkpoint_created(
-            &self,
-            summary: &CheckpointSummary,
-            contents: &CheckpointContents,
-        ) -> SuiResult {
-            self.try_send((contents.clone(), summary.clone())).unwrap();
+        async fn checkpoint_created(&self, checkpoint: &FinalizedCheckpoint) -> SuiResult {
+            self.try_send((checkpoint.contents().clone(), checkpoint.summary().clone()))
+                .unwrap();
             Ok(())
         }
     }
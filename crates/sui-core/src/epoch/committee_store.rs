@@ -5,7 +5,7 @@ use rocksdb::Options;
 use std::path::PathBuf;
 use sui_storage::default_db_options;
 use sui_types::base_types::ObjectID;
-use sui_types::committee::{Committee, EpochId};
+use sui_types::committee::{Committee, EpochId, EpochIdExt};
 use sui_types::error::{SuiError, SuiResult};
 use typed_store::rocks::{DBMap, DBOptions};
 use typed_store::traits::TypedStoreDebug;
@@ -56,7 +56,7 @@ impl CommitteeStore {
     pub fn insert_new_committee(&self, new_committee: &Committee) -> SuiResult {
         let latest_committee = self.get_latest_committee();
         fp_ensure!(
-            latest_committee.epoch + 1 == new_committee.epoch,
+            new_committee.epoch.is_successor_of(latest_committee.epoch),
             SuiError::from("Unexpected new epoch number")
         );
         self.committee_map
@@ -26,6 +26,7 @@ use sui_types::{
 
 use crate::authority::AuthorityState;
 use crate::checkpoints::causal_order_effects::TestEffectsStore;
+use crate::checkpoints::checkpoint_tests::fragment_between;
 use crate::checkpoints::reconstruction::SpanGraph;
 use crate::{
     authority_active::ActiveAuthority,
@@ -252,8 +253,8 @@ async fn test_consensus_pause_after_last_fragment() {
             state.checkpoints.lock().set_proposal(0).unwrap()
         })
         .collect();
-    let fragment01 = proposals[0].fragment_with(&proposals[1]);
-    let fragment12 = proposals[1].fragment_with(&proposals[2]);
+    let fragment01 = fragment_between(&proposals[0], &proposals[1]);
+    let fragment12 = fragment_between(&proposals[1], &proposals[2]);
     let mut index = ExecutionIndices::default();
     states.iter().for_each(|state| {
         // Send the first fragment to every validator, and make sure ater this, none of them
@@ -15,10 +15,12 @@ use prometheus::{
 };
 use std::sync::Arc;
 use sui_types::batch::{AuthorityBatch, SignedBatch, TxSequenceNumber, UpdateItem};
+use sui_types::checkpoint_proposal_diff::CheckpointProposalDiffResponse;
+use sui_types::checkpoint_response_consistency::ResponseConsistencyChecker;
 use sui_types::crypto::AuthorityPublicKeyBytes;
 use sui_types::messages_checkpoint::{
-    AuthenticatedCheckpoint, CheckpointRequest, CheckpointRequestType, CheckpointResponse,
-    CheckpointSequenceNumber,
+    validate_response, AuthenticatedCheckpoint, CheckpointDetail, CheckpointRequest,
+    CheckpointRequestType, CheckpointResponse, CheckpointSequenceNumber, DigestPrefix,
 };
 use sui_types::{base_types::*, committee::*, fp_ensure};
 use sui_types::{
@@ -732,14 +734,91 @@ where
         }
     }
 
+    fn verify_checkpoint_bucket_response(
+        &self,
+        seq: Option<CheckpointSequenceNumber>,
+        detail: bool,
+        prefix: &DigestPrefix,
+        response: &CheckpointResponse,
+    ) -> SuiResult {
+        if let CheckpointResponse::AuthenticatedCheckpointBucket {
+            checkpoint,
+            bucket_digest,
+            bucket,
+        } = response
+        {
+            self.verify_checkpoint_sequence(seq, checkpoint)?;
+            self.verify_contents_exist(detail, bucket_digest, bucket)?;
+            fp_ensure!(
+                checkpoint.is_some() || bucket_digest.is_none(),
+                SuiError::from("Bucket digest returned without a checkpoint")
+            );
+            if let Some(bucket) = bucket {
+                fp_ensure!(
+                    &bucket.prefix == prefix,
+                    SuiError::from("Bucket prefix inconsistent with request")
+                );
+                bucket.verify()?;
+                fp_ensure!(
+                    Some(bucket.digest()) == *bucket_digest,
+                    SuiError::from("Bucket digest inconsistent with bucket")
+                );
+            }
+            // The bucket is not covered by the signatures, only the checkpoint is verified.
+            match checkpoint {
+                Some(c) => c.verify(&self.get_committee(&c.summary().epoch)?, None),
+                None => Ok(()),
+            }
+        } else {
+            Err(SuiError::from(
+                "Invalid AuthorityCheckpointInfo type in the response",
+            ))
+        }
+    }
+
     fn verify_checkpoint_response(
         &self,
         request: &CheckpointRequest,
         response: &CheckpointResponse,
     ) -> SuiResult {
+        validate_response(request, response)?;
+        if let CheckpointResponse::EpochMismatch { .. } = response {
+            return Ok(());
+        }
         // Verify response data was correct for request
         match &request.request_type {
             CheckpointRequestType::AuthenticatedCheckpoint(seq) => {
+                if let Some(prefix) = &request.prefix_filter {
+                    return self.verify_checkpoint_bucket_response(
+                        *seq,
+                        request.detail.contains(CheckpointDetail::CONTENTS),
+                        prefix,
+                        response,
+                    );
+                }
+                if let CheckpointResponse::NotAvailable { attestation } = &response {
+                    fp_ensure!(
+                        attestation.authority() == &self.address,
+                        SuiError::from("Unavailability attested by another authority")
+                    );
+                    return attestation
+                        .verify(&self.get_committee(&attestation.attestation.epoch)?);
+                }
+                if let CheckpointResponse::AuthenticatedCheckpointMetadata {
+                    checkpoint,
+                    metadata,
+                } = &response
+                {
+                    self.verify_checkpoint_sequence(*seq, checkpoint)?;
+                    self.verify_contents_exist(true, checkpoint, metadata)?;
+                    return match (checkpoint, metadata) {
+                        (Some(c), Some(metadata)) => {
+                            c.verify(&self.get_committee(&c.summary().epoch)?, None)?;
+                            metadata.verify(c.summary())
+                        }
+                        _ => Ok(()),
+                    };
+                }
                 if let CheckpointResponse::AuthenticatedCheckpoint {
                     checkpoint,
                     contents,
@@ -747,7 +826,11 @@ where
                 {
                     // Checks that the sequence number is correct.
                     self.verify_checkpoint_sequence(*seq, checkpoint)?;
-                    self.verify_contents_exist(request.detail, checkpoint, contents)?;
+                    self.verify_contents_exist(
+                        request.detail.contains(CheckpointDetail::CONTENTS),
+                        checkpoint,
+                        contents,
+                    )?;
                     // Verify signature.
                     match checkpoint {
                         Some(c) => {
@@ -771,32 +854,96 @@ where
                 {
                     // Verify signature.
                     if let Some(signed_proposal) = proposal {
-                        let mut committee =
-                            self.get_committee(&signed_proposal.auth_signature.epoch)?;
-                        signed_proposal.verify(&committee, proposal_contents.as_ref())?;
-                        if signed_proposal.summary.sequence_number > 0 {
+                        let mut committee = self.get_committee(&signed_proposal.epoch())?;
+                        signed_proposal
+                            .verify_with_contents(&committee, proposal_contents.as_ref())?;
+                        if signed_proposal.data().sequence_number > 0 {
                             let cert = prev_cert.as_ref().ok_or_else(|| {
                                 SuiError::from("No checkpoint cert provided along with proposal")
                             })?;
-                            if cert.auth_signature.epoch != signed_proposal.auth_signature.epoch {
+                            if cert.auth_sig().epoch != signed_proposal.epoch() {
                                 // It's possible that the previous checkpoint cert is from the
                                 // previous epoch, and in that case we verify them using different
                                 // committee.
                                 fp_ensure!(
-                                    cert.auth_signature.epoch + 1
-                                        == signed_proposal.auth_signature.epoch,
+                                    signed_proposal
+                                        .epoch()
+                                        .is_successor_of(cert.auth_sig().epoch),
                                     SuiError::from("Unexpected epoch for checkpoint cert")
                                 );
-                                committee = self.get_committee(&cert.auth_signature.epoch)?;
+                                committee = self.get_committee(&cert.auth_sig().epoch)?;
                             }
                             cert.verify(&committee, None)?;
                             fp_ensure!(
-                                signed_proposal.summary.sequence_number - 1 == cert.summary.sequence_number,
+                                signed_proposal.data().sequence_number - 1 == cert.data().sequence_number,
                                 SuiError::from("Checkpoint proposal sequence number inconsistent with previous cert")
                             );
                         }
                     }
-                    self.verify_contents_exist(request.detail, proposal, proposal_contents)
+                    self.verify_contents_exist(
+                        request.detail.proposal_contents(),
+                        proposal,
+                        proposal_contents,
+                    )
+                } else {
+                    Err(SuiError::from(
+                        "Invalid AuthorityCheckpointInfo type in the response",
+                    ))
+                }
+            }
+            CheckpointRequestType::LatestCheckpointSequenceNumber => {
+                if let CheckpointResponse::LatestCheckpointSequenceNumber { info } = &response {
+                    match info {
+                        Some(signed_info) => {
+                            fp_ensure!(
+                                signed_info.authority() == &self.address,
+                                SuiError::ByzantineAuthoritySuspicion {
+                                    authority: self.address,
+                                    reason: "Sequence number info not signed by the responder"
+                                        .to_string(),
+                                }
+                            );
+                            signed_info
+                                .verify(&self.get_committee(&signed_info.auth_signature.epoch)?)
+                        }
+                        None => Ok(()),
+                    }
+                } else {
+                    Err(SuiError::from(
+                        "Invalid AuthorityCheckpointInfo type in the response",
+                    ))
+                }
+            }
+            CheckpointRequestType::Bootstrap(bootstrap) => {
+                if let CheckpointResponse::Bootstrap { bundle } = &response {
+                    match bundle {
+                        Some(bundle) => bundle.verify(
+                            bootstrap,
+                            &self.get_committee(&bootstrap.from_trusted_epoch)?,
+                        ),
+                        None => Ok(()),
+                    }
+                } else {
+                    Err(SuiError::from(
+                        "Invalid AuthorityCheckpointInfo type in the response",
+                    ))
+                }
+            }
+            CheckpointRequestType::CheckpointProposalDiff(_) => {
+                if let CheckpointResponse::CheckpointProposalDiff { response } = &response {
+                    // The diff itself is checked against the waypoints of the proposals when
+                    // the fragment is built.
+                    match response {
+                        Some(CheckpointProposalDiffResponse::Diff { proposal, .. }) => proposal
+                            .verify_with_contents(&self.get_committee(&proposal.epoch())?, None),
+                        Some(CheckpointProposalDiffResponse::Contents { proposal, contents }) => {
+                            proposal.verify_with_contents(
+                                &self.get_committee(&proposal.epoch())?,
+                                Some(contents),
+                            )
+                        }
+                        None => Ok(()),
+                    }
                 } else {
                     Err(SuiError::from(
                         "Invalid AuthorityCheckpointInfo type in the response",
@@ -821,6 +968,22 @@ where
         Ok(resp)
     }
 
+    /// Same as `handle_checkpoint`, also recording the response in `checker`, the checker
+    /// of the current session. A response contradicting an earlier answer of this authority
+    /// to the same request is an error, and the evidence is kept by the checker.
+    pub async fn handle_checkpoint_in_session(
+        &self,
+        request: CheckpointRequest,
+        checker: &ResponseConsistencyChecker,
+    ) -> Result<CheckpointResponse, SuiError> {
+        let resp = self.handle_checkpoint(request.clone()).await?;
+        if let Some(conflict) = checker.record(self.address, &request, &resp) {
+            error!(authority=?self.address, "Authority served conflicting checkpoints");
+            return Err(conflict.into());
+        }
+        Ok(resp)
+    }
+
     pub async fn handle_checkpoint_stream(
         &self,
         request: CheckpointStreamRequest,
@@ -97,7 +97,8 @@ pub(crate) async fn init_state(
         Box::new(store.clone()),
         LogCheckpointOutput::boxed(),
         0,
-    );
+    )
+    .unwrap();
 
     AuthorityState::new(
         name,
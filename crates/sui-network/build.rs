@@ -111,6 +111,10 @@ fn main() -> Result<()> {
         .out_dir(&out_dir)
         .compile(&[validator_service]);
 
+    if env::var_os("CARGO_FEATURE_CHECKPOINT_SERVICE").is_some() {
+        build_checkpoint_service(&out_dir, codec_path);
+    }
+
     build_anemo_services(&out_dir);
 
     println!("cargo:rerun-if-changed=build.rs");
@@ -119,6 +123,47 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+fn build_checkpoint_service(out_dir: &Path, codec_path: &str) {
+    let checkpoint_service = Service::builder()
+        .name("Checkpoint")
+        .package("sui.checkpoint")
+        .comment("The checkpoint read protocol, served without the rest of the Validator interface")
+        .method(
+            Method::builder()
+                .name("checkpoint")
+                .route_name("Checkpoint")
+                .input_type("sui_types::messages_checkpoint::CheckpointRequest")
+                .output_type("sui_types::messages_checkpoint::CheckpointResponse")
+                .codec_path(codec_path)
+                .build(),
+        )
+        .method(
+            Method::builder()
+                .name("checkpoint_contents")
+                .route_name("CheckpointContents")
+                .input_type("sui_types::messages_checkpoint::CheckpointContentsStreamRequest")
+                .output_type("sui_types::messages_checkpoint::CheckpointContentsChunk")
+                .server_streaming()
+                .codec_path(codec_path)
+                .build(),
+        )
+        .method(
+            Method::builder()
+                .name("checkpoint_info")
+                .route_name("FollowCheckpointStream")
+                .input_type("sui_types::messages::CheckpointStreamRequest")
+                .output_type("sui_types::messages::CheckpointStreamResponseItem")
+                .server_streaming()
+                .codec_path(codec_path)
+                .build(),
+        )
+        .build();
+
+    Builder::new()
+        .out_dir(out_dir)
+        .compile(&[checkpoint_service]);
+}
+
 fn build_anemo_services(out_dir: &Path) {
     let discovery = anemo_build::manual::Service::builder()
         .name("Discovery")
@@ -143,7 +188,21 @@ fn build_anemo_services(out_dir: &Path) {
         )
         .build();
 
+    let checkpoint_fragments = anemo_build::manual::Service::builder()
+        .name("CheckpointFragments")
+        .package("sui")
+        .method(
+            anemo_build::manual::Method::builder()
+                .name("push_fragment_message")
+                .route_name("PushFragmentMessage")
+                .request_type("sui_types::messages_checkpoint::SignedCheckpointFragmentMessage")
+                .response_type("()")
+                .codec_path("anemo::rpc::codec::BincodeCodec")
+                .build(),
+        )
+        .build();
+
     anemo_build::manual::Builder::new()
         .out_dir(out_dir)
-        .compile(&[discovery]);
+        .compile(&[discovery, checkpoint_fragments]);
 }
@@ -9,3 +9,14 @@ pub use validator::{
     validator_client::ValidatorClient,
     validator_server::{Validator, ValidatorServer},
 };
+
+#[cfg(feature = "checkpoint-service")]
+mod checkpoint {
+    include!(concat!(env!("OUT_DIR"), "/sui.checkpoint.Checkpoint.rs"));
+}
+
+#[cfg(feature = "checkpoint-service")]
+pub use checkpoint::{
+    checkpoint_client::CheckpointClient,
+    checkpoint_server::{Checkpoint, CheckpointServer},
+};
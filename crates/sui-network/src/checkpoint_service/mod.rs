@@ -0,0 +1,142 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Serving the checkpoint read protocol over gRPC from any store of checkpoints, e.g. on a
+//! fullnode or in tools, without the rest of the validator server.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use sui_types::error::{SuiError, SuiResult};
+use sui_types::messages::{CheckpointStreamRequest, CheckpointStreamResponseItem};
+use sui_types::messages_checkpoint::{
+    CheckpointContents, CheckpointContentsChunk, CheckpointContentsStreamRequest,
+    CheckpointRequest, CheckpointResponse, CheckpointSequenceNumber, DecodeLimits,
+};
+use tonic::transport::Channel;
+
+use crate::api::{Checkpoint, CheckpointClient, CheckpointServer};
+
+#[cfg(test)]
+mod tests;
+
+pub type CheckpointStreamResponseItemStream =
+    BoxStream<'static, SuiResult<CheckpointStreamResponseItem>>;
+
+/// The checkpoint data served by a `CheckpointService`.
+#[async_trait]
+pub trait CheckpointDataSource: Send + Sync + 'static {
+    fn handle_checkpoint_request(
+        &self,
+        request: &CheckpointRequest,
+    ) -> SuiResult<CheckpointResponse>;
+
+    fn checkpoint_contents(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> SuiResult<Option<CheckpointContents>>;
+
+    async fn follow_checkpoints(
+        &self,
+        request: CheckpointStreamRequest,
+    ) -> SuiResult<CheckpointStreamResponseItemStream>;
+}
+
+pub struct CheckpointService<S> {
+    source: Arc<S>,
+}
+
+impl<S: CheckpointDataSource> CheckpointService<S> {
+    pub fn new(source: Arc<S>) -> Self {
+        Self { source }
+    }
+
+    pub fn into_server(self) -> CheckpointServer<Self> {
+        CheckpointServer::new(self)
+    }
+}
+
+#[async_trait]
+impl<S: CheckpointDataSource> Checkpoint for CheckpointService<S> {
+    async fn checkpoint(
+        &self,
+        request: tonic::Request<CheckpointRequest>,
+    ) -> Result<tonic::Response<CheckpointResponse>, tonic::Status> {
+        let request = request.into_inner();
+
+        let response = self.source.handle_checkpoint_request(&request)?;
+
+        Ok(tonic::Response::new(response))
+    }
+
+    type CheckpointContentsStream =
+        BoxStream<'static, Result<CheckpointContentsChunk, tonic::Status>>;
+
+    async fn checkpoint_contents(
+        &self,
+        request: tonic::Request<CheckpointContentsStreamRequest>,
+    ) -> Result<tonic::Response<Self::CheckpointContentsStream>, tonic::Status> {
+        let request = request.into_inner();
+        let contents = self
+            .source
+            .checkpoint_contents(request.sequence_number)?
+            .ok_or_else(|| {
+                tonic::Status::not_found(format!(
+                    "No contents for checkpoint {}",
+                    request.sequence_number
+                ))
+            })?;
+        let chunks =
+            contents.to_stream_chunks(request.sequence_number, request.chunk_transactions());
+
+        Ok(tonic::Response::new(Box::pin(
+            futures::stream::iter(chunks).map(Ok),
+        )))
+    }
+
+    type FollowCheckpointStreamStream =
+        BoxStream<'static, Result<CheckpointStreamResponseItem, tonic::Status>>;
+
+    async fn checkpoint_info(
+        &self,
+        request: tonic::Request<CheckpointStreamRequest>,
+    ) -> Result<tonic::Response<Self::FollowCheckpointStreamStream>, tonic::Status> {
+        let request = request.into_inner();
+        let xstream = self.source.follow_checkpoints(request).await?;
+
+        let response = xstream.map_err(tonic::Status::from);
+
+        Ok(tonic::Response::new(Box::pin(response)))
+    }
+}
+
+/// Download the contents of a checkpoint from a `CheckpointService`, bounded by `limits`. The
+/// digest of the result still has to be checked against the checkpoint summary.
+pub async fn fetch_checkpoint_contents(
+    client: &mut CheckpointClient<Channel>,
+    sequence_number: CheckpointSequenceNumber,
+    limits: &DecodeLimits,
+) -> SuiResult<CheckpointContents> {
+    let chunks: Vec<CheckpointContentsChunk> = client
+        .checkpoint_contents(CheckpointContentsStreamRequest::new(sequence_number))
+        .await?
+        .into_inner()
+        .try_collect()
+        .await
+        .map_err(SuiError::from)?;
+    check_sequence_number(&chunks, sequence_number)?;
+    CheckpointContents::from_stream_chunks(chunks, limits)
+}
+
+fn check_sequence_number(
+    chunks: &[CheckpointContentsChunk],
+    sequence_number: CheckpointSequenceNumber,
+) -> SuiResult {
+    if chunks.iter().any(|c| c.sequence_number != sequence_number) {
+        return Err(SuiError::from(
+            format!("Received contents of another checkpoint than {sequence_number}").as_str(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,100 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use super::*;
+use sui_types::base_types::ExecutionDigests;
+
+/// Serves the contents of a fixed set of checkpoints.
+struct StaticSource {
+    contents: BTreeMap<CheckpointSequenceNumber, CheckpointContents>,
+}
+
+#[async_trait]
+impl CheckpointDataSource for StaticSource {
+    fn handle_checkpoint_request(
+        &self,
+        _request: &CheckpointRequest,
+    ) -> SuiResult<CheckpointResponse> {
+        Err(SuiError::UnsupportedFeatureError {
+            error: "checkpoint requests".to_string(),
+        })
+    }
+
+    fn checkpoint_contents(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> SuiResult<Option<CheckpointContents>> {
+        Ok(self.contents.get(&sequence_number).cloned())
+    }
+
+    async fn follow_checkpoints(
+        &self,
+        _request: CheckpointStreamRequest,
+    ) -> SuiResult<CheckpointStreamResponseItemStream> {
+        Ok(Box::pin(futures::stream::empty()))
+    }
+}
+
+fn service() -> (CheckpointService<StaticSource>, CheckpointContents) {
+    let contents = CheckpointContents::new_with_causally_ordered_transactions(
+        (0..5).map(|_| ExecutionDigests::random()),
+    );
+    let source = StaticSource {
+        contents: [(1, contents.clone())].into_iter().collect(),
+    };
+    (CheckpointService::new(Arc::new(source)), contents)
+}
+
+#[tokio::test]
+async fn test_stream_checkpoint_contents() {
+    let (service, contents) = service();
+    let request = CheckpointContentsStreamRequest {
+        sequence_number: 1,
+        max_chunk_transactions: 2,
+    };
+    let chunks: Vec<_> = service
+        .checkpoint_contents(tonic::Request::new(request))
+        .await
+        .unwrap()
+        .into_inner()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(chunks.len(), 3);
+    check_sequence_number(&chunks, 1).unwrap();
+    assert!(check_sequence_number(&chunks, 2).is_err());
+    let rebuilt = CheckpointContents::from_stream_chunks(chunks, &DecodeLimits::default()).unwrap();
+    assert_eq!(rebuilt.digest(), contents.digest());
+}
+
+#[tokio::test]
+async fn test_missing_checkpoint_contents() {
+    let (service, _) = service();
+    let status = service
+        .checkpoint_contents(tonic::Request::new(CheckpointContentsStreamRequest::new(2)))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_source_errors_are_returned() {
+    let (service, _) = service();
+    assert!(service
+        .checkpoint(tonic::Request::new(
+            CheckpointRequest::latest_sequence_number()
+        ))
+        .await
+        .is_err());
+    let items: Vec<_> = service
+        .checkpoint_info(tonic::Request::new(CheckpointStreamRequest::default()))
+        .await
+        .unwrap()
+        .into_inner()
+        .collect()
+        .await;
+    assert!(items.is_empty());
+}
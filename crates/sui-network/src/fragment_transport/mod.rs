@@ -0,0 +1,45 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Transports carrying the chunked checkpoint fragment protocol over direct validator links,
+//! as an alternative to sequencing multi-MB fragments through consensus. They carry the same
+//! `SignedCheckpointFragmentMessage`s as consensus does, so receivers verify them the same way.
+
+use async_trait::async_trait;
+use sui_types::base_types::AuthorityName;
+use sui_types::error::SuiResult;
+use sui_types::messages_checkpoint::SignedCheckpointFragmentMessage;
+use tokio::sync::broadcast;
+
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/sui.CheckpointFragments.rs"));
+}
+mod quic;
+mod tcp;
+#[cfg(test)]
+mod tests;
+
+pub use generated::{
+    checkpoint_fragments_client::CheckpointFragmentsClient,
+    checkpoint_fragments_server::{CheckpointFragments, CheckpointFragmentsServer},
+};
+pub use quic::{FragmentInbox, QuicFragmentTransport};
+pub use tcp::{HelloSigner, TcpFragmentTransport};
+
+/// The number of received messages buffered for each subscriber. Subscribers lagging further
+/// behind miss messages.
+pub const FRAGMENT_INBOX_CAPACITY: usize = 1024;
+
+#[async_trait]
+pub trait FragmentTransport: Send + Sync + 'static {
+    /// Send a fragment header or chunk to another validator.
+    async fn send_chunk(
+        &self,
+        to: &AuthorityName,
+        message: SignedCheckpointFragmentMessage,
+    ) -> SuiResult;
+
+    /// Receive the messages sent to this validator from now on, in arrival order. Messages
+    /// are not verified by the transport.
+    fn subscribe(&self) -> broadcast::Receiver<SignedCheckpointFragmentMessage>;
+}
@@ -0,0 +1,92 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+
+use anemo::{Network, PeerId, Request, Response};
+use async_trait::async_trait;
+use sui_types::base_types::AuthorityName;
+use sui_types::error::{SuiError, SuiResult};
+use sui_types::messages_checkpoint::SignedCheckpointFragmentMessage;
+use tokio::sync::broadcast;
+
+use super::{
+    CheckpointFragments, CheckpointFragmentsClient, CheckpointFragmentsServer, FragmentTransport,
+    FRAGMENT_INBOX_CAPACITY,
+};
+
+/// Receives the fragment messages pushed by other validators over anemo.
+#[derive(Clone)]
+pub struct FragmentInbox {
+    sender: broadcast::Sender<SignedCheckpointFragmentMessage>,
+}
+
+impl FragmentInbox {
+    /// Returns the inbox and the service to add to the anemo router before the network starts.
+    pub fn new() -> (Self, CheckpointFragmentsServer<Self>) {
+        let (sender, _) = broadcast::channel(FRAGMENT_INBOX_CAPACITY);
+        let inbox = Self { sender };
+        (inbox.clone(), CheckpointFragmentsServer::new(inbox))
+    }
+}
+
+#[anemo::async_trait]
+impl CheckpointFragments for FragmentInbox {
+    async fn push_fragment_message(
+        &self,
+        request: Request<SignedCheckpointFragmentMessage>,
+    ) -> Result<Response<()>, anemo::rpc::Status> {
+        // Messages received while nobody is subscribed are dropped.
+        let _ = self.sender.send(request.into_body());
+        Ok(Response::new(()))
+    }
+}
+
+/// Sends fragment messages over the QUIC connections of an anemo network.
+pub struct QuicFragmentTransport {
+    network: Network,
+    peers: HashMap<AuthorityName, PeerId>,
+    inbox: FragmentInbox,
+}
+
+impl QuicFragmentTransport {
+    pub fn new(
+        network: Network,
+        peers: HashMap<AuthorityName, PeerId>,
+        inbox: FragmentInbox,
+    ) -> Self {
+        Self {
+            network,
+            peers,
+            inbox,
+        }
+    }
+}
+
+#[async_trait]
+impl FragmentTransport for QuicFragmentTransport {
+    async fn send_chunk(
+        &self,
+        to: &AuthorityName,
+        message: SignedCheckpointFragmentMessage,
+    ) -> SuiResult {
+        let peer = self
+            .peers
+            .get(to)
+            .and_then(|peer_id| self.network.peer(*peer_id))
+            .ok_or_else(|| SuiError::ClientIoError {
+                error: format!("Not connected to validator {to}"),
+            })?;
+        CheckpointFragmentsClient::new(peer)
+            .push_fragment_message(message)
+            .await
+            .map_err(|status| SuiError::ClientIoError {
+                error: format!("Failed to send fragment message to {to}: {status:?}"),
+            })?;
+        Ok(())
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<SignedCheckpointFragmentMessage> {
+        self.inbox.sender.subscribe()
+    }
+}
@@ -0,0 +1,280 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sui_types::base_types::AuthorityName;
+use sui_types::crypto::{AuthoritySignature, SuiAuthoritySignature};
+use sui_types::error::{SuiError, SuiResult};
+use sui_types::intent::{Intent, IntentScope};
+use sui_types::messages_checkpoint::SignedCheckpointFragmentMessage;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, Mutex};
+use tracing::debug;
+
+use super::{FragmentTransport, FRAGMENT_INBOX_CAPACITY};
+
+/// The largest frame accepted, well above the size of a fragment chunk.
+pub const MAX_FRAGMENT_FRAME_SIZE: u32 = 16 << 20;
+
+/// The largest frame accepted before the peer authenticated, enough for its `Hello`.
+pub const MAX_HELLO_FRAME_SIZE: u32 = 1 << 10;
+
+/// The time a peer has to answer the challenge, after which the connection is closed.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The signer of the `Hello`s sent by a transport.
+pub type HelloSigner = Arc<dyn signature::Signer<AuthoritySignature> + Send + Sync>;
+
+/// Sent by the connecting validator first, signing the `Challenge` of the listener.
+#[derive(Serialize, Deserialize)]
+struct Hello {
+    authority: AuthorityName,
+    signature: AuthoritySignature,
+}
+
+/// What the `Hello` signs: the random nonce the listener sent on the new connection, and the
+/// listener, so that the answer cannot be relayed to another validator.
+#[derive(Serialize)]
+struct Challenge {
+    listener: AuthorityName,
+    nonce: [u8; 32],
+}
+
+fn challenge_intent() -> Intent {
+    Intent::default_with_scope(IntentScope::FragmentTransportChallenge)
+}
+
+/// Sends fragment messages as length prefixed bincode frames over plain TCP connections, one
+/// per peer. A connection starts with the connecting validator answering a challenge of the
+/// listener, which only reads full size frames from the validators it knows.
+pub struct TcpFragmentTransport {
+    authority: AuthorityName,
+    signer: HelloSigner,
+    peers: HashMap<AuthorityName, SocketAddr>,
+    connections: Mutex<HashMap<AuthorityName, TcpStream>>,
+    sender: broadcast::Sender<SignedCheckpointFragmentMessage>,
+}
+
+impl TcpFragmentTransport {
+    /// Listen for messages of the given peers on `address`, and send messages to them as
+    /// `authority`. Returns the transport and the address it listens on.
+    pub async fn bind(
+        address: SocketAddr,
+        authority: AuthorityName,
+        signer: HelloSigner,
+        peers: HashMap<AuthorityName, SocketAddr>,
+    ) -> io::Result<(Self, SocketAddr)> {
+        let listener = TcpListener::bind(address).await?;
+        let local_address = listener.local_addr()?;
+        let (sender, _) = broadcast::channel(FRAGMENT_INBOX_CAPACITY);
+        let known = Arc::new(peers.keys().copied().collect());
+        tokio::spawn(accept_loop(listener, authority, known, sender.clone()));
+        let transport = Self {
+            authority,
+            signer,
+            peers,
+            connections: Mutex::new(HashMap::new()),
+            sender,
+        };
+        Ok((transport, local_address))
+    }
+
+    /// Connect to the listener of `to` and answer its challenge.
+    pub(super) async fn connect(
+        &self,
+        to: &AuthorityName,
+        address: &SocketAddr,
+    ) -> io::Result<TcpStream> {
+        let mut stream = TcpStream::connect(address).await?;
+        let mut nonce = [0u8; 32];
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, stream.read_exact(&mut nonce))
+            .await
+            .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??;
+        let challenge = Challenge {
+            listener: *to,
+            nonce,
+        };
+        let hello = Hello {
+            authority: self.authority,
+            signature: AuthoritySignature::new_secure(
+                &challenge,
+                challenge_intent(),
+                &*self.signer,
+            ),
+        };
+        let frame = bincode::serialize(&hello).expect("Serialization should not fail");
+        write_frame(&mut stream, &frame).await?;
+        Ok(stream)
+    }
+}
+
+#[async_trait]
+impl FragmentTransport for TcpFragmentTransport {
+    async fn send_chunk(
+        &self,
+        to: &AuthorityName,
+        message: SignedCheckpointFragmentMessage,
+    ) -> SuiResult {
+        let io_error = |err: io::Error| SuiError::ClientIoError {
+            error: format!("Failed to send fragment message to {to}: {err}"),
+        };
+        let address = self.peers.get(to).ok_or_else(|| SuiError::ClientIoError {
+            error: format!("Unknown validator {to}"),
+        })?;
+        let frame = bincode::serialize(&message).expect("Serialization should not fail");
+        if frame.len() > MAX_FRAGMENT_FRAME_SIZE as usize {
+            return Err(SuiError::from("Fragment message is too large for a frame"));
+        }
+
+        let mut connections = self.connections.lock().await;
+        if !connections.contains_key(to) {
+            let stream = self.connect(to, address).await.map_err(io_error)?;
+            connections.insert(*to, stream);
+        }
+        let stream = connections.get_mut(to).unwrap();
+        let result = write_frame(stream, &frame).await;
+        if result.is_err() {
+            // Reconnect on the next message.
+            connections.remove(to);
+        }
+        result.map_err(io_error)
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<SignedCheckpointFragmentMessage> {
+        self.sender.subscribe()
+    }
+}
+
+async fn write_frame(stream: &mut TcpStream, frame: &[u8]) -> io::Result<()> {
+    stream.write_u32(frame.len() as u32).await?;
+    stream.write_all(frame).await
+}
+
+/// Read the next frame into `frame`. Returns false if the peer closed the connection, and an
+/// error if the frame is larger than `max_size`.
+async fn read_frame(
+    stream: &mut TcpStream,
+    frame: &mut Vec<u8>,
+    max_size: u32,
+) -> io::Result<bool> {
+    let len = match stream.read_u32().await {
+        Ok(len) => len,
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+        Err(err) => return Err(err),
+    };
+    if len > max_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {len} bytes"),
+        ));
+    }
+    frame.resize(len as usize, 0);
+    stream.read_exact(frame).await?;
+    Ok(true)
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    authority: AuthorityName,
+    known: Arc<HashSet<AuthorityName>>,
+    sender: broadcast::Sender<SignedCheckpointFragmentMessage>,
+) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                tokio::spawn(read_frames(
+                    stream,
+                    peer,
+                    authority,
+                    known.clone(),
+                    sender.clone(),
+                ));
+            }
+            Err(err) => debug!("Failed to accept fragment connection: {err}"),
+        }
+    }
+}
+
+/// Check that the peer is one of the `known` validators, answering the challenge of
+/// `listener`, before reading any full size frame.
+async fn authenticate(
+    stream: &mut TcpStream,
+    listener: AuthorityName,
+    known: &HashSet<AuthorityName>,
+) -> io::Result<AuthorityName> {
+    let invalid = |error: String| io::Error::new(io::ErrorKind::InvalidData, error);
+    let challenge = Challenge {
+        listener,
+        nonce: rand::random(),
+    };
+    stream.write_all(&challenge.nonce).await?;
+    let mut frame = Vec::new();
+    if !read_frame(stream, &mut frame, MAX_HELLO_FRAME_SIZE).await? {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let hello: Hello =
+        bincode::deserialize(&frame).map_err(|err| invalid(format!("invalid hello: {err}")))?;
+    if !known.contains(&hello.authority) {
+        return Err(invalid(format!("unknown validator {}", hello.authority)));
+    }
+    hello
+        .signature
+        .verify_secure(&challenge, challenge_intent(), hello.authority)
+        .map_err(|err| invalid(format!("invalid hello signature: {err}")))?;
+    Ok(hello.authority)
+}
+
+async fn read_frames(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    listener: AuthorityName,
+    known: Arc<HashSet<AuthorityName>>,
+    sender: broadcast::Sender<SignedCheckpointFragmentMessage>,
+) {
+    // Peers that never answer the challenge do not keep their connection open.
+    let authority = match tokio::time::timeout(
+        HANDSHAKE_TIMEOUT,
+        authenticate(&mut stream, listener, &known),
+    )
+    .await
+    .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))
+    {
+        Ok(authority) => authority,
+        Err(err) => {
+            debug!("Closing fragment connection from {peer}: {err}");
+            return;
+        }
+    };
+    let mut frame = Vec::new();
+    loop {
+        match read_frame(&mut stream, &mut frame, MAX_FRAGMENT_FRAME_SIZE).await {
+            Ok(true) => {}
+            // The peer closed the connection.
+            Ok(false) => return,
+            Err(err) => {
+                debug!("Closing fragment connection from {authority} at {peer}: {err}");
+                return;
+            }
+        }
+        match bincode::deserialize(&frame) {
+            Ok(message) => {
+                // Messages received while nobody is subscribed are dropped.
+                let _ = sender.send(message);
+            }
+            Err(err) => {
+                debug!(
+                    "Closing fragment connection from {authority} at {peer}: invalid frame: {err}"
+                );
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use super::tcp::MAX_FRAGMENT_FRAME_SIZE;
+use super::*;
+use crate::utils::build_network;
+use sui_types::crypto::{get_key_pair, AuthorityKeyPair, KeypairTraits};
+use sui_types::messages_checkpoint::{CheckpointFragmentMessage, CheckpointFragmentMessageChunk};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+fn signed_chunk() -> (AuthorityName, SignedCheckpointFragmentMessage) {
+    let (_, key): (_, AuthorityKeyPair) = get_key_pair();
+    let name: AuthorityName = key.public().into();
+    let message = CheckpointFragmentMessage::Chunk(Box::new(CheckpointFragmentMessageChunk {
+        sequence_number: 1,
+        proposer: name,
+        other: AuthorityName::ZERO,
+        header_digest: [0; 32],
+        chunk_id: 0,
+        content: vec![1, 2, 3],
+    }));
+    (name, SignedCheckpointFragmentMessage::new(message, 0, &key))
+}
+
+async fn check_received(
+    received: &mut broadcast::Receiver<SignedCheckpointFragmentMessage>,
+    expected: &SignedCheckpointFragmentMessage,
+) {
+    let message = received.recv().await.unwrap();
+    message.verify(0).unwrap();
+    assert_eq!(
+        message.message.message_key(),
+        expected.message.message_key()
+    );
+}
+
+fn authority() -> (AuthorityName, HelloSigner) {
+    let (_, key): (_, AuthorityKeyPair) = get_key_pair();
+    (key.public().into(), Arc::new(key))
+}
+
+#[tokio::test]
+async fn tcp_transport() {
+    let (_, message) = signed_chunk();
+    let localhost: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let (sender_name, sender_signer) = authority();
+    let (receiver_name, receiver_signer) = authority();
+    let (receiver, address) = TcpFragmentTransport::bind(
+        localhost,
+        receiver_name,
+        receiver_signer,
+        HashMap::from([(sender_name, localhost)]),
+    )
+    .await
+    .unwrap();
+    let mut received = receiver.subscribe();
+    let (sender, _) = TcpFragmentTransport::bind(
+        localhost,
+        sender_name,
+        sender_signer,
+        HashMap::from([(receiver_name, address)]),
+    )
+    .await
+    .unwrap();
+
+    // Both messages go through the same connection.
+    for _ in 0..2 {
+        sender
+            .send_chunk(&receiver_name, message.clone())
+            .await
+            .unwrap();
+    }
+    for _ in 0..2 {
+        check_received(&mut received, &message).await;
+    }
+
+    assert!(sender
+        .send_chunk(&AuthorityName::ZERO, message)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn tcp_transport_refuses_unknown_validators() {
+    let localhost: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let (receiver_name, receiver_signer) = authority();
+    let (_, address) =
+        TcpFragmentTransport::bind(localhost, receiver_name, receiver_signer, HashMap::new())
+            .await
+            .unwrap();
+
+    // A large frame is refused before the peer authenticated.
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    let mut nonce = [0u8; 32];
+    stream.read_exact(&mut nonce).await.unwrap();
+    stream.write_u32(MAX_FRAGMENT_FRAME_SIZE).await.unwrap();
+    assert_eq!(stream.read(&mut [0u8; 1]).await.unwrap(), 0);
+
+    // So is a validator the listener does not know.
+    let (sender_name, sender_signer) = authority();
+    let (sender, _) = TcpFragmentTransport::bind(
+        localhost,
+        sender_name,
+        sender_signer,
+        HashMap::from([(receiver_name, address)]),
+    )
+    .await
+    .unwrap();
+    let mut stream = sender.connect(&receiver_name, &address).await.unwrap();
+    stream.write_u32(0).await.unwrap();
+    assert_eq!(stream.read(&mut [0u8; 1]).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn tcp_transport_refuses_relayed_challenges() {
+    let localhost: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let (sender_name, sender_signer) = authority();
+    let (receiver_name, receiver_signer) = authority();
+    let (_, address) = TcpFragmentTransport::bind(
+        localhost,
+        receiver_name,
+        receiver_signer,
+        HashMap::from([(sender_name, localhost)]),
+    )
+    .await
+    .unwrap();
+    let (sender, _) = TcpFragmentTransport::bind(
+        localhost,
+        sender_name,
+        sender_signer,
+        HashMap::from([(receiver_name, address)]),
+    )
+    .await
+    .unwrap();
+
+    // A challenge answered for another listener is refused.
+    let mut stream = sender
+        .connect(&AuthorityName::ZERO, &address)
+        .await
+        .unwrap();
+    stream.write_u32(0).await.unwrap();
+    assert_eq!(stream.read(&mut [0u8; 1]).await.unwrap(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn tcp_transport_closes_silent_peers() {
+    let localhost: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let (receiver_name, receiver_signer) = authority();
+    let (_, address) =
+        TcpFragmentTransport::bind(localhost, receiver_name, receiver_signer, HashMap::new())
+            .await
+            .unwrap();
+
+    // A peer not answering the challenge is disconnected after the handshake timeout.
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    let mut nonce = [0u8; 32];
+    stream.read_exact(&mut nonce).await.unwrap();
+    assert_eq!(stream.read(&mut [0u8; 1]).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn quic_transport() {
+    let (name, message) = signed_chunk();
+    let (inbox_1, server) = FragmentInbox::new();
+    let network_1 = build_network(|router| router.add_rpc_service(server));
+    let (inbox_2, server) = FragmentInbox::new();
+    let network_2 = build_network(|router| router.add_rpc_service(server));
+    let peer_id_2 = network_1.connect(network_2.local_addr()).await.unwrap();
+
+    let sender = QuicFragmentTransport::new(network_1, HashMap::from([(name, peer_id_2)]), inbox_1);
+    let receiver = QuicFragmentTransport::new(network_2, HashMap::new(), inbox_2);
+    let mut received = receiver.subscribe();
+
+    sender.send_chunk(&name, message.clone()).await.unwrap();
+    check_received(&mut received, &message).await;
+
+    assert!(receiver.send_chunk(&name, message).await.is_err());
+}
@@ -5,7 +5,10 @@ use mysten_network::config::Config;
 use std::time::Duration;
 
 pub mod api;
+#[cfg(feature = "checkpoint-service")]
+pub mod checkpoint_service;
 pub mod discovery;
+pub mod fragment_transport;
 pub mod utils;
 
 pub use tonic;
@@ -180,7 +180,7 @@ impl SuiNode {
             Box::new(store.clone()),
             checkpoint_output,
             committee.epoch,
-        );
+        )?;
 
         let state = Arc::new(
             AuthorityState::new(
@@ -122,7 +122,7 @@ fn tx_sequence_table_default_config() -> DBOptions {
 // If not for multiple authorities per process, it should really be one per process.
 impl LockServiceImpl {
     fn get_tx_sequence(&self, tx: TransactionDigest) -> SuiResult<Option<TxSequenceNumber>> {
-        self.tx_sequence.get(&tx).map_err(SuiError::StorageError)
+        self.tx_sequence.get(&tx).map_err(SuiError::from)
     }
 
     /// Returns the state of a single lock.
@@ -130,9 +130,7 @@ impl LockServiceImpl {
     /// * Some(None) - lock exists and is initialized, but not locked to a particular transaction
     /// * Some(Some(lock_info)) - lock exists and set to some transaction.
     fn get_lock(&self, object: ObjectRef) -> Result<Option<Option<LockInfo>>, SuiError> {
-        self.transaction_lock
-            .get(&object)
-            .map_err(SuiError::StorageError)
+        self.transaction_lock.get(&object).map_err(SuiError::from)
     }
 
     /// Checks multiple object locks exist.
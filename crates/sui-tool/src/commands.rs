@@ -22,7 +22,7 @@ use futures::stream::StreamExt;
 use clap::*;
 use sui_core::authority::MAX_ITEMS_LIMIT;
 use sui_types::messages_checkpoint::{
-    CheckpointRequest, CheckpointResponse, CheckpointSequenceNumber,
+    validate_response, CheckpointRequest, CheckpointResponse, CheckpointSequenceNumber,
 };
 use sui_types::object::ObjectFormatOptions;
 
@@ -529,11 +529,10 @@ impl ToolCommand {
                 let committee = genesis.committee()?;
 
                 for (name, client) in clients {
-                    let resp = client
-                        .handle_checkpoint(CheckpointRequest::authenticated(sequence_number, true))
-                        .await
-                        .unwrap();
+                    let request = CheckpointRequest::authenticated(sequence_number, true);
+                    let resp = client.handle_checkpoint(request.clone()).await.unwrap();
                     println!("Validator: {:?}\n", name);
+                    validate_response(&request, &resp)?;
                     match resp {
                         CheckpointResponse::AuthenticatedCheckpoint {
                             checkpoint,
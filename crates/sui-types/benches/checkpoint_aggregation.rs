@@ -0,0 +1,70 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#[macro_use]
+extern crate criterion;
+
+use std::sync::Arc;
+
+use criterion::Criterion;
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use sui_types::base_types::ExecutionDigests;
+use sui_types::committee::Committee;
+use sui_types::gas::GasCostSummary;
+use sui_types::messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointContents, ProvisionalCheckpointSummary,
+    SharedCheckpointSummary, SharedSignedCheckpointSummary, SignedCheckpointSummary,
+};
+use sui_types::utils::make_committee_key_num;
+
+/// Signed summaries of an end of epoch checkpoint, which carries the next committee.
+fn signed_summaries(committee_size: usize) -> (Committee, Vec<SignedCheckpointSummary>) {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key_num(committee_size, &mut rng);
+    let contents = CheckpointContents::new_with_causally_ordered_transactions(
+        (0..1000).map(|_| ExecutionDigests::random()),
+    );
+    let mut checkpoint =
+        ProvisionalCheckpointSummary::new(committee.epoch, 1, None, Some(committee.clone()));
+    checkpoint.set_contents(contents, GasCostSummary::default());
+    let checkpoint = checkpoint.finalize();
+    let signed = keys
+        .iter()
+        .map(|key| checkpoint.sign(key.public().into(), key))
+        .collect();
+    (committee, signed)
+}
+
+/// The same signatures, all sharing one summary.
+fn share(owned: &[SignedCheckpointSummary]) -> Vec<SharedSignedCheckpointSummary> {
+    let summary = Arc::new(owned[0].data().clone());
+    owned
+        .iter()
+        .map(|signed| SharedCheckpointSummary {
+            summary: summary.clone(),
+            auth_signature: signed.auth_sig().clone(),
+        })
+        .collect()
+}
+
+fn aggregation_benchmark(c: &mut Criterion) {
+    let (committee, owned) = signed_summaries(100);
+    let shared = share(&owned);
+
+    let mut group = c.benchmark_group("checkpoint_aggregation");
+    group.bench_function("aggregate_cloned", |b| {
+        b.iter(|| CertifiedCheckpointSummary::aggregate(owned.clone(), &committee).unwrap())
+    });
+    group.bench_function("aggregate_refs", |b| {
+        b.iter(|| CertifiedCheckpointSummary::aggregate_refs(&owned, &committee).unwrap())
+    });
+    group.bench_function("aggregate_shared", |b| {
+        b.iter(|| CertifiedCheckpointSummary::aggregate_shared(&shared, &committee).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, aggregation_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,81 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#[macro_use]
+extern crate criterion;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::Criterion;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use sui_types::committee::Committee;
+use sui_types::messages_checkpoint::CertifiedCheckpointSummary;
+use sui_types::utils::{make_committee_key, CheckpointChainBuilder};
+
+/// Counts the allocations made by the benchmarked code.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn count_allocations<R>(f: impl FnOnce() -> R) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    drop(f());
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// The certified checkpoints of a long epoch, as downloaded during sync.
+fn certified_checkpoints(count: u64) -> (Committee, Vec<CertifiedCheckpointSummary>) {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone())
+        .checkpoints_per_epoch(count)
+        .build(&mut rng);
+    let checkpoints = chain.checkpoints.into_iter().map(|(c, _)| c).collect();
+    (committee, checkpoints)
+}
+
+fn verify_each(committee: &Committee, checkpoints: &[CertifiedCheckpointSummary]) {
+    for checkpoint in checkpoints {
+        checkpoint.verify(committee, None).unwrap();
+    }
+}
+
+fn verification_benchmark(c: &mut Criterion) {
+    let (committee, checkpoints) = certified_checkpoints(2000);
+
+    // Batches reuse the buffers of a single obligation.
+    let one_by_one = count_allocations(|| verify_each(&committee, &checkpoints));
+    let batched = count_allocations(|| {
+        CertifiedCheckpointSummary::verify_batch(&committee, &checkpoints).unwrap()
+    });
+    assert!(batched < one_by_one);
+
+    let mut group = c.benchmark_group("checkpoint_verification");
+    group.sample_size(10);
+    group.bench_function("one_by_one", |b| {
+        b.iter(|| verify_each(&committee, &checkpoints))
+    });
+    group.bench_function("batched", |b| {
+        b.iter(|| CertifiedCheckpointSummary::verify_batch(&committee, &checkpoints).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, verification_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,108 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#[macro_use]
+extern crate criterion;
+
+use criterion::Criterion;
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use sui_types::base_types::{
+    random_object_ref, AuthorityName, ExecutionDigests, TransactionEffectsDigest,
+};
+use sui_types::committee::Committee;
+use sui_types::crypto::{get_key_pair, AccountKeyPair, AuthorityKeyPair};
+use sui_types::messages::{CertifiedTransaction, SignedTransaction, Transaction, TransactionData};
+use sui_types::messages_checkpoint::{
+    CheckpointFragment, CheckpointProposalContents, LocalProposal, RemoteProposal,
+};
+use sui_types::utils::make_committee_key;
+
+const CERT_COUNT: usize = 1_000;
+
+fn make_cert(keys: &[AuthorityKeyPair], committee: &Committee) -> CertifiedTransaction {
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let transaction = Transaction::from_data_and_signer(
+        TransactionData::new_transfer(
+            sender,
+            random_object_ref(),
+            sender,
+            random_object_ref(),
+            10000,
+        ),
+        &sender_key,
+    )
+    .verify()
+    .unwrap()
+    .into_message();
+    let sigs = keys
+        .iter()
+        .map(|key| {
+            SignedTransaction::new(
+                committee.epoch,
+                transaction.clone(),
+                key,
+                key.public().into(),
+            )
+            .auth_sig()
+            .clone()
+        })
+        .collect();
+    CertifiedTransaction::new(transaction, sigs, committee).unwrap()
+}
+
+/// A fragment between an empty proposal and one with `CERT_COUNT` transactions, which
+/// carries a cert for each of them.
+fn fragment() -> (Committee, CheckpointFragment) {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let certs: Vec<_> = (0..CERT_COUNT)
+        .map(|_| {
+            let cert = make_cert(&keys, &committee);
+            let digests = ExecutionDigests::new(*cert.digest(), TransactionEffectsDigest::random());
+            (digests, cert)
+        })
+        .collect();
+    let name = |key: &AuthorityKeyPair| -> AuthorityName { key.public().into() };
+    let empty = LocalProposal::new(
+        committee.epoch,
+        1,
+        name(&keys[0]),
+        &keys[0],
+        CheckpointProposalContents::new(std::iter::empty()),
+        None,
+    )
+    .unwrap();
+    // The proposal of the other authority, as signed by itself.
+    let full = LocalProposal::new(
+        committee.epoch,
+        1,
+        name(&keys[1]),
+        &keys[1],
+        CheckpointProposalContents::new(certs.iter().map(|(digests, _)| *digests)),
+        None,
+    )
+    .unwrap();
+    let full = RemoteProposal::new(full.into_inner(), &name(&keys[0])).unwrap();
+    let mut fragment = empty.fragment_with_remote(&full);
+    fragment.data.certs = certs.into_iter().collect();
+    (committee, fragment)
+}
+
+fn fragment_verification(c: &mut Criterion) {
+    let (committee, fragment) = fragment();
+
+    let mut group = c.benchmark_group("fragment_verification");
+    group.sample_size(10);
+    group.bench_function("sequential", |b| {
+        b.iter(|| fragment.verify(&committee).unwrap())
+    });
+    group.bench_function("batched", |b| {
+        b.iter(|| fragment.verify_batched(&committee, false).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, fragment_verification);
+criterion_main!(benches);
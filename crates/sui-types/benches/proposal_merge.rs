@@ -0,0 +1,57 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#[macro_use]
+extern crate criterion;
+
+use std::collections::BTreeSet;
+
+use criterion::Criterion;
+use sui_types::base_types::ExecutionDigests;
+use sui_types::messages_checkpoint::ExecutionDigestSet;
+
+const PROPOSAL_SIZE: usize = 100_000;
+
+/// Two proposals sharing most of their transactions, as is usual during construction.
+fn proposals() -> (Vec<ExecutionDigests>, Vec<ExecutionDigests>) {
+    let shared: Vec<_> = (0..PROPOSAL_SIZE * 9 / 10)
+        .map(|_| ExecutionDigests::random())
+        .collect();
+    let extra = |n| (0..n).map(|_| ExecutionDigests::random());
+    let first = shared
+        .iter()
+        .copied()
+        .chain(extra(PROPOSAL_SIZE / 10))
+        .collect();
+    let second = shared
+        .into_iter()
+        .chain(extra(PROPOSAL_SIZE / 10))
+        .collect();
+    (first, second)
+}
+
+fn proposal_merge(c: &mut Criterion) {
+    let (first, second) = proposals();
+
+    let first_tree: BTreeSet<_> = first.iter().copied().collect();
+    let second_tree: BTreeSet<_> = second.iter().copied().collect();
+    c.bench_function("proposal_merge/btree_set", |b| {
+        b.iter(|| {
+            let mut merged = first_tree.clone();
+            merged.extend(second_tree.iter().copied());
+            merged
+        })
+    });
+
+    let first_set: ExecutionDigestSet = first.into_iter().collect();
+    let second_set: ExecutionDigestSet = second.into_iter().collect();
+    c.bench_function("proposal_merge/execution_digest_set", |b| {
+        b.iter(|| first_set.union(&second_set))
+    });
+    c.bench_function("proposal_merge/execution_digest_set_difference", |b| {
+        b.iter(|| first_set.difference(&second_set).count())
+    });
+}
+
+criterion_group!(benches, proposal_merge);
+criterion_main!(benches);
@@ -0,0 +1,81 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Follows a checkpoint chain with `sui_types::light_client`, using only the
+//! `checkpoint-client` feature. CI builds it for `wasm32-unknown-unknown`, and runs it on the
+//! host:
+//!
+//! cargo run -p sui-types --no-default-features --features checkpoint-client --example light_client
+
+use std::collections::BTreeMap;
+
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sui_types::base_types::{AuthorityName, TransactionDigest, TransactionEffectsDigest};
+use sui_types::crypto::{get_key_pair_from_rng, AuthorityKeyPair};
+use sui_types::gas::GasCostSummary;
+use sui_types::light_client::{
+    CertifiedCheckpointSummary, CheckpointContents, Committee, ExecutionDigests, LightClient,
+};
+use sui_types::messages_checkpoint::ProvisionalCheckpointSummary;
+
+/// What the authorities of `committee` would serve: a certified checkpoint, BCS encoded as it
+/// crosses the network, and its contents.
+fn certified_checkpoint(
+    keys: &[AuthorityKeyPair],
+    committee: &Committee,
+    transactions: &[ExecutionDigests],
+) -> (Vec<u8>, CheckpointContents) {
+    let mut provisional = ProvisionalCheckpointSummary::new(committee.epoch, 0, None, None);
+    provisional.set_contents(
+        CheckpointContents::new_with_causally_ordered_transactions(transactions.iter().cloned()),
+        GasCostSummary::default(),
+    );
+    let finalized = provisional.finalize();
+    let signed = keys
+        .iter()
+        .map(|key| finalized.sign(key.public().into(), key))
+        .collect();
+    let certified = CertifiedCheckpointSummary::aggregate(signed, committee).unwrap();
+    (
+        bcs::to_bytes(&certified).unwrap(),
+        finalized.contents().clone(),
+    )
+}
+
+fn main() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let keys: Vec<AuthorityKeyPair> = (0..4).map(|_| get_key_pair_from_rng(&mut rng).1).collect();
+    let voting_rights: BTreeMap<AuthorityName, _> =
+        keys.iter().map(|key| (key.public().into(), 1)).collect();
+    let committee = Committee::new(0, voting_rights).unwrap();
+    let transactions: Vec<_> = (0..3)
+        .map(|_| {
+            ExecutionDigests::new(
+                TransactionDigest::new(rng.gen()),
+                TransactionEffectsDigest(rng.gen()),
+            )
+        })
+        .collect();
+    let (bytes, contents) = certified_checkpoint(&keys, &committee, &transactions);
+
+    // The light client only trusts the committee, and checks everything else.
+    let mut client = LightClient::new(committee);
+    let checkpoint: CertifiedCheckpointSummary = bcs::from_bytes(&bytes).unwrap();
+    client.verify_checkpoint(&checkpoint).unwrap();
+
+    let proof = contents.prove_inclusion(&transactions[1]).unwrap();
+    client
+        .verify_inclusion(&checkpoint, &transactions[1], &proof)
+        .unwrap();
+    assert!(client
+        .verify_inclusion(&checkpoint, &transactions[0], &proof)
+        .is_err());
+
+    println!(
+        "Verified checkpoint {} and the inclusion of transaction {:?}",
+        checkpoint.data().sequence_number,
+        transactions[1].transaction
+    );
+}
@@ -0,0 +1,118 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Authorization of checkpoint requests. Each request falls in an `AccessClass`, and servers
+//! ask a `CheckpointAccessPolicy` whether to serve it, e.g. to serve summaries to anyone but
+//! contents only to known peers.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, SocketAddr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{SuiError, SuiResult};
+use crate::messages_checkpoint::{CheckpointDetail, CheckpointRequest, CheckpointRequestType};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_access_tests.rs"]
+mod checkpoint_access_tests;
+
+/// The authorization a checkpoint request needs, from least to most privileged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum AccessClass {
+    /// Summaries, signatures, sequence numbers and the metadata of contents.
+    PublicSummary,
+    /// Contents of checkpoints and buckets, and proposals.
+    PeerContents,
+    /// Details authorities do not serve by default, see `CheckpointDetail::SERVED`.
+    AdminDebug,
+}
+
+impl AccessClass {
+    pub fn of(request: &CheckpointRequest) -> Self {
+        if !(request.detail - CheckpointDetail::SERVED).is_empty() {
+            Self::AdminDebug
+        } else if matches!(
+            request.request_type,
+            CheckpointRequestType::CheckpointProposal
+                | CheckpointRequestType::CheckpointProposalDiff(_)
+        ) || request.detail.proposal_contents()
+        {
+            Self::PeerContents
+        } else {
+            Self::PublicSummary
+        }
+    }
+
+    /// Fail unless a client granted `granted` may be served requests of this class.
+    pub fn check_granted(&self, granted: AccessClass) -> SuiResult {
+        fp_ensure!(
+            *self <= granted,
+            SuiError::CheckpointAccessDenied {
+                required: self.to_string(),
+                granted: granted.to_string(),
+            }
+        );
+        Ok(())
+    }
+}
+
+impl Display for AccessClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::PublicSummary => "public summary",
+            Self::PeerContents => "peer contents",
+            Self::AdminDebug => "admin debug",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Decides which checkpoint requests a server answers. `peer` is the address of the client,
+/// if the transport knows it.
+pub trait CheckpointAccessPolicy {
+    fn evaluate(
+        &self,
+        request: &CheckpointRequest,
+        class: AccessClass,
+        peer: Option<SocketAddr>,
+    ) -> SuiResult;
+}
+
+/// Grants each known peer address its own class, and other clients a default class.
+#[derive(Clone, Debug)]
+pub struct PeerAccessPolicy {
+    pub default: AccessClass,
+    pub peers: HashMap<IpAddr, AccessClass>,
+}
+
+impl PeerAccessPolicy {
+    pub fn new(default: AccessClass) -> Self {
+        Self {
+            default,
+            peers: HashMap::new(),
+        }
+    }
+
+    pub fn with_peer(mut self, peer: IpAddr, class: AccessClass) -> Self {
+        self.peers.insert(peer, class);
+        self
+    }
+
+    pub fn granted(&self, peer: Option<SocketAddr>) -> AccessClass {
+        peer.and_then(|peer| self.peers.get(&peer.ip()).copied())
+            .unwrap_or(self.default)
+    }
+}
+
+impl CheckpointAccessPolicy for PeerAccessPolicy {
+    fn evaluate(
+        &self,
+        _request: &CheckpointRequest,
+        class: AccessClass,
+        peer: Option<SocketAddr>,
+    ) -> SuiResult {
+        class.check_granted(self.granted(peer))
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Accounts for the storage used by certified checkpoints and their contents, per epoch and
+//! per component, to help operators plan pruning and archival budgets, and audits archives of
+//! checkpoint contents.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::committee::EpochId;
+use crate::error::{SuiError, SuiResult};
+use crate::messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointContents, CheckpointSequenceNumber, CheckpointSummary,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_archive_tests.rs"]
+mod checkpoint_archive_tests;
+
+/// The bytes stored for the checkpoints of one epoch, as serialized in the checkpoint store.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochArchiveUsage {
+    pub epoch: EpochId,
+    pub checkpoints: u64,
+    pub transactions: u64,
+    pub summary_bytes: u64,
+    pub signature_bytes: u64,
+    pub contents_bytes: u64,
+}
+
+impl EpochArchiveUsage {
+    pub fn total_bytes(&self) -> u64 {
+        self.summary_bytes + self.signature_bytes + self.contents_bytes
+    }
+}
+
+/// The usage of every tracked epoch, with a projection of the growth of the archive.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveUsageReport {
+    pub epochs: Vec<EpochArchiveUsage>,
+    pub total_bytes: u64,
+    pub mean_bytes_per_checkpoint: u64,
+    /// The mean usage of the completed epochs, or of the current epoch so far if none is
+    /// completed yet.
+    pub projected_bytes_per_epoch: u64,
+}
+
+impl ArchiveUsageReport {
+    /// The projected size of the archive once `epochs` more epochs are stored.
+    pub fn projected_total_bytes(&self, epochs: u64) -> u64 {
+        self.total_bytes
+            .saturating_add(self.projected_bytes_per_epoch.saturating_mul(epochs))
+    }
+}
+
+/// Accumulates the size of certified checkpoints and their contents, fed in sequence order.
+#[derive(Default)]
+pub struct ArchiveUsageTracker {
+    epochs: BTreeMap<EpochId, EpochArchiveUsage>,
+    last_recorded: Option<CheckpointSequenceNumber>,
+}
+
+impl ArchiveUsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Account for a stored checkpoint, and its contents if they are stored too. Returns
+    /// false, without accounting for anything, if a checkpoint with the same or a higher
+    /// sequence number was already recorded.
+    pub fn record(
+        &mut self,
+        checkpoint: &CertifiedCheckpointSummary,
+        contents: Option<&CheckpointContents>,
+    ) -> bool {
+        let seq = checkpoint.data().sequence_number;
+        if self.last_recorded.map_or(false, |last| seq <= last) {
+            return false;
+        }
+        self.last_recorded = Some(seq);
+
+        let epoch = checkpoint.data().epoch;
+        let usage = self
+            .epochs
+            .entry(epoch)
+            .or_insert_with(|| EpochArchiveUsage {
+                epoch,
+                ..Default::default()
+            });
+        usage.checkpoints += 1;
+        usage.summary_bytes += bincode::serialized_size(checkpoint.data()).unwrap();
+        usage.signature_bytes += bincode::serialized_size(checkpoint.auth_sig()).unwrap();
+        if let Some(contents) = contents {
+            usage.transactions += contents.size() as u64;
+            usage.contents_bytes += bincode::serialized_size(contents).unwrap();
+        }
+        true
+    }
+
+    pub fn epoch(&self, epoch: EpochId) -> Option<&EpochArchiveUsage> {
+        self.epochs.get(&epoch)
+    }
+
+    pub fn report(&self) -> ArchiveUsageReport {
+        let epochs: Vec<_> = self.epochs.values().cloned().collect();
+        let total_bytes = epochs.iter().map(|e| e.total_bytes()).sum();
+        let checkpoints: u64 = epochs.iter().map(|e| e.checkpoints).sum();
+        // The last epoch is still in progress, unless it is the only one.
+        let completed = match epochs.len() {
+            0 | 1 => &epochs[..],
+            n => &epochs[..n - 1],
+        };
+        let projected_bytes_per_epoch = if completed.is_empty() {
+            0
+        } else {
+            completed.iter().map(|e| e.total_bytes()).sum::<u64>() / completed.len() as u64
+        };
+        ArchiveUsageReport {
+            epochs,
+            total_bytes,
+            mean_bytes_per_checkpoint: if checkpoints == 0 {
+                0
+            } else {
+                total_bytes / checkpoints
+            },
+            projected_bytes_per_epoch,
+        }
+    }
+}
+
+/// Append the contents of a checkpoint to a contents archive read by `verify_archive`: the
+/// length of the bincode serialization of the contents, as a little endian u64, followed by
+/// that serialization.
+pub fn write_archive_contents(mut writer: impl Write, contents: &CheckpointContents) -> SuiResult {
+    let bytes = bincode::serialize(contents).expect("Serialization should not fail");
+    writer
+        .write_all(&(bytes.len() as u64).to_le_bytes())
+        .and_then(|_| writer.write_all(&bytes))
+        .map_err(|e| SuiError::from(format!("Failed to write archive contents: {e}").as_str()))
+}
+
+/// Check a contents archive, as written by `write_archive_contents`, against the summaries of
+/// the checkpoints it holds, in order, in a single pass over `reader`. Fails on the first
+/// contents not matching the digest of its summary, or if the archive holds more or fewer
+/// contents than there are summaries. Returns the number of bytes read.
+pub fn verify_archive(reader: impl Read, summaries: &[CheckpointSummary]) -> SuiResult<u64> {
+    verify_archive_records(reader, summaries, |record| Ok(Cow::Borrowed(record)))
+}
+
+/// Same as `verify_archive`, for archives whose records are encoded: `decode` turns a record
+/// back into the bincode serialization of the contents.
+pub(crate) fn verify_archive_records(
+    mut reader: impl Read,
+    summaries: &[CheckpointSummary],
+    mut decode: impl FnMut(&[u8]) -> Result<Cow<'_, [u8]>, String>,
+) -> SuiResult<u64> {
+    let read_error = |seq: CheckpointSequenceNumber, e: std::io::Error| {
+        SuiError::from(format!("Failed to read the contents of checkpoint {seq}: {e}").as_str())
+    };
+    let mut offset = 0;
+    let mut buffer = Vec::new();
+    for summary in summaries {
+        let seq = summary.sequence_number;
+        let mut length = [0u8; 8];
+        reader
+            .read_exact(&mut length)
+            .map_err(|e| read_error(seq, e))?;
+        let length = u64::from_le_bytes(length);
+
+        // Read through `take`, so that a corrupted length cannot allocate more than the
+        // archive holds.
+        buffer.clear();
+        let read = (&mut reader)
+            .take(length)
+            .read_to_end(&mut buffer)
+            .map_err(|e| read_error(seq, e))?;
+        fp_ensure!(
+            read as u64 == length,
+            SuiError::from(
+                format!("Archive ends within the contents of checkpoint {seq} at byte {offset}")
+                    .as_str()
+            )
+        );
+        let decoded = decode(&buffer).map_err(|e| {
+            SuiError::from(
+                format!("Invalid record of checkpoint {seq} at byte {offset}: {e}").as_str(),
+            )
+        })?;
+        let contents: CheckpointContents = bincode::deserialize(&decoded).map_err(|e| {
+            SuiError::from(
+                format!("Invalid contents of checkpoint {seq} at byte {offset}: {e}").as_str(),
+            )
+        })?;
+        fp_ensure!(
+            bincode::serialized_size(&contents).unwrap() == decoded.len() as u64,
+            SuiError::from(
+                format!("Trailing bytes in the contents of checkpoint {seq} at byte {offset}")
+                    .as_str()
+            )
+        );
+        fp_ensure!(
+            contents.digest() == summary.content_digest,
+            SuiError::from(
+                format!("Contents of checkpoint {seq} at byte {offset} do not match its digest")
+                    .as_str()
+            )
+        );
+        offset += 8 + length;
+    }
+    let mut trailing = [0u8; 1];
+    fp_ensure!(
+        matches!(reader.read(&mut trailing), Ok(0)),
+        SuiError::from(format!("Archive holds trailing data after byte {offset}").as_str())
+    );
+    Ok(offset)
+}
@@ -0,0 +1,199 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Contents archives whose records are compressed with zstd, optionally with a dictionary
+//! trained on a sample of checkpoints. Records are small and share most of their structure,
+//! so compressing them one by one gains little without a dictionary.
+//!
+//! A compressed archive starts with a header: the magic `SCAZ`, the format version as a u8,
+//! and the id of the dictionary as a little endian u32, 0 if records are compressed without
+//! one. Records then follow, framed as in `write_archive_contents`: the length of the record
+//! as a little endian u64, then the record, made of the length of the serialization of the
+//! contents as a little endian u64 followed by the zstd frame compressing it. Offsets in
+//! errors are counted from the end of the header.
+
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+use crate::checkpoint_archive::verify_archive_records;
+use crate::error::{SuiError, SuiResult};
+use crate::messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointContents, CheckpointSummary, DecodeLimits,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_archive_compression_tests.rs"]
+mod checkpoint_archive_compression_tests;
+
+pub const COMPRESSED_ARCHIVE_MAGIC: [u8; 4] = *b"SCAZ";
+pub const COMPRESSED_ARCHIVE_VERSION: u8 = 1;
+const HEADER_LEN: usize = 9;
+/// Starts every zstd dictionary, followed by the id of the dictionary.
+const DICTIONARY_MAGIC: u32 = 0xEC30A437;
+
+/// A zstd dictionary, identified in archive headers by the id it holds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveDictionary {
+    id: u32,
+    bytes: Vec<u8>,
+}
+
+impl ArchiveDictionary {
+    /// Train a dictionary of at most `max_size` bytes on the serialization of sample
+    /// contents and certified summaries. A few thousand samples are usually needed.
+    pub fn train(
+        contents: &[CheckpointContents],
+        checkpoints: &[CertifiedCheckpointSummary],
+        max_size: usize,
+    ) -> SuiResult<Self> {
+        let samples: Vec<Vec<u8>> = contents
+            .iter()
+            .map(|contents| bincode::serialize(contents).expect("Serialization should not fail"))
+            .chain(checkpoints.iter().map(|checkpoint| {
+                bincode::serialize(checkpoint).expect("Serialization should not fail")
+            }))
+            .collect();
+        let bytes = zstd::dict::from_samples(&samples, max_size).map_err(|e| {
+            SuiError::from(format!("Failed to train an archive dictionary: {e}").as_str())
+        })?;
+        Self::from_bytes(bytes)
+    }
+
+    /// A dictionary as trained by zstd, e.g. stored next to the archives using it.
+    pub fn from_bytes(bytes: Vec<u8>) -> SuiResult<Self> {
+        fp_ensure!(
+            bytes.len() >= 8 && bytes[0..4] == DICTIONARY_MAGIC.to_le_bytes(),
+            SuiError::from("Not a zstd dictionary")
+        );
+        let id = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        fp_ensure!(
+            id != 0,
+            SuiError::from("Archive dictionaries must have an id")
+        );
+        Ok(Self { id, bytes })
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Writes a compressed archive, see the module documentation.
+pub struct CompressedArchiveWriter<W> {
+    writer: W,
+    compressor: zstd::bulk::Compressor<'static>,
+}
+
+impl<W: Write> CompressedArchiveWriter<W> {
+    /// Start an archive compressed at `level`, with `dictionary` if set, by writing its
+    /// header.
+    pub fn new(
+        mut writer: W,
+        level: i32,
+        dictionary: Option<&ArchiveDictionary>,
+    ) -> SuiResult<Self> {
+        let compressor = match dictionary {
+            Some(dictionary) => zstd::bulk::Compressor::with_dictionary(level, &dictionary.bytes),
+            None => zstd::bulk::Compressor::new(level),
+        }
+        .map_err(|e| SuiError::from(format!("Failed to set up compression: {e}").as_str()))?;
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(&COMPRESSED_ARCHIVE_MAGIC);
+        header.push(COMPRESSED_ARCHIVE_VERSION);
+        header.extend_from_slice(&dictionary.map_or(0, |d| d.id).to_le_bytes());
+        writer.write_all(&header).map_err(write_error)?;
+        Ok(Self { writer, compressor })
+    }
+
+    /// Append the contents of the next checkpoint.
+    pub fn append(&mut self, contents: &CheckpointContents) -> SuiResult {
+        let bytes = bincode::serialize(contents).expect("Serialization should not fail");
+        let frame = self
+            .compressor
+            .compress(&bytes)
+            .map_err(|e| SuiError::from(format!("Failed to compress contents: {e}").as_str()))?;
+        let mut record = Vec::with_capacity(8 + frame.len());
+        record.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+        record.extend_from_slice(&frame);
+        self.writer
+            .write_all(&(record.len() as u64).to_le_bytes())
+            .and_then(|_| self.writer.write_all(&record))
+            .map_err(write_error)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+fn write_error(e: std::io::Error) -> SuiError {
+    SuiError::from(format!("Failed to write archive contents: {e}").as_str())
+}
+
+/// The id of the dictionary an archive is compressed with, 0 if none, read from its header.
+pub fn read_archive_dictionary_id(mut reader: impl Read) -> SuiResult<u32> {
+    let mut header = [0u8; HEADER_LEN];
+    reader
+        .read_exact(&mut header)
+        .map_err(|e| SuiError::from(format!("Failed to read the archive header: {e}").as_str()))?;
+    fp_ensure!(
+        header[0..4] == COMPRESSED_ARCHIVE_MAGIC,
+        SuiError::from("Not a compressed checkpoint archive")
+    );
+    fp_ensure!(
+        header[4] == COMPRESSED_ARCHIVE_VERSION,
+        SuiError::from(format!("Unsupported archive version {}", header[4]).as_str())
+    );
+    Ok(u32::from_le_bytes(header[5..9].try_into().unwrap()))
+}
+
+/// Same as `verify_archive`, for a compressed archive. `dictionary` must be the one of the
+/// archive header. Records cannot decompress to more bytes than a fragment may hold under
+/// `limits`.
+pub fn verify_compressed_archive(
+    mut reader: impl Read,
+    dictionary: Option<&ArchiveDictionary>,
+    summaries: &[CheckpointSummary],
+    limits: &DecodeLimits,
+) -> SuiResult<u64> {
+    let dictionary_id = read_archive_dictionary_id(&mut reader)?;
+    let expected_id = dictionary.map_or(0, |d| d.id);
+    fp_ensure!(
+        dictionary_id == expected_id,
+        SuiError::from(
+            format!("Archive is compressed with dictionary {dictionary_id}, not {expected_id}")
+                .as_str()
+        )
+    );
+    let mut decompressor = match dictionary {
+        Some(dictionary) => zstd::bulk::Decompressor::with_dictionary(&dictionary.bytes),
+        None => zstd::bulk::Decompressor::new(),
+    }
+    .map_err(|e| SuiError::from(format!("Failed to set up decompression: {e}").as_str()))?;
+    let max_bytes = limits.max_fragment_bytes;
+
+    let read = verify_archive_records(reader, summaries, |record| {
+        if record.len() < 8 {
+            return Err("record too short".to_string());
+        }
+        let length = u64::from_le_bytes(record[0..8].try_into().unwrap());
+        if length > max_bytes {
+            return Err(format!("record decompresses to {length} bytes"));
+        }
+        let bytes = decompressor
+            .decompress(&record[8..], length as usize)
+            .map_err(|e| e.to_string())?;
+        if bytes.len() as u64 != length {
+            return Err(format!(
+                "record decompresses to {} bytes instead of {length}",
+                bytes.len()
+            ));
+        }
+        Ok(Cow::Owned(bytes))
+    })?;
+    Ok(HEADER_LEN as u64 + read)
+}
@@ -0,0 +1,161 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Audit of the checkpoints served by authorities: the responses of every authority are
+//! compared with the certified checkpoint of the same sequence number, and conflicting
+//! responses are kept as evidence of misbehavior.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::AuthorityName;
+use crate::committee::Committee;
+use crate::error::{SuiError, SuiResult};
+use crate::messages_checkpoint::{
+    AuthenticatedCheckpoint, CertifiedCheckpointSummary, CheckpointSequenceNumber,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_audit_tests.rs"]
+mod checkpoint_audit_tests;
+
+/// An authority served a checkpoint conflicting with the certified checkpoint of the same
+/// sequence number.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MisbehaviorReport {
+    pub served_by: AuthorityName,
+    pub certified: CertifiedCheckpointSummary,
+    pub served: AuthenticatedCheckpoint,
+}
+
+impl MisbehaviorReport {
+    /// Check that both checkpoints are authentic for `committee`, the committee of their
+    /// epoch, and that they conflict.
+    pub fn verify(&self, committee: &Committee) -> SuiResult {
+        let served = self.served.summary();
+        let certified = self.certified.data();
+        fp_ensure!(
+            served.sequence_number == certified.sequence_number,
+            SuiError::from("Misbehavior report compares different checkpoints")
+        );
+        fp_ensure!(
+            served.digest() != certified.digest(),
+            SuiError::from("Misbehavior report does not contain conflicting checkpoints")
+        );
+        self.certified.verify(committee, None)?;
+        self.served.verify(committee, None)
+    }
+
+    /// The authorities that signed both conflicting checkpoints. Does not check the
+    /// signatures: see `verify`.
+    pub fn accused(&self, committee: &Committee) -> SuiResult<Vec<AuthorityName>> {
+        match &self.served {
+            AuthenticatedCheckpoint::Signed(signed) => Ok(vec![*signed.authority()]),
+            AuthenticatedCheckpoint::Certified(served) => {
+                let certified_signers = self
+                    .certified
+                    .signatory_authorities(committee)
+                    .collect::<SuiResult<Vec<_>>>()?;
+                served
+                    .signatory_authorities(committee)
+                    .filter(|name| {
+                        name.as_ref()
+                            .map_or(true, |name| certified_signers.contains(name))
+                    })
+                    .map(|name| name.map(|name| *name))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Records the checkpoints served by every authority, and flags the ones that conflict with
+/// the certified checkpoints. Responses are expected to be verified before being recorded.
+#[derive(Default)]
+pub struct AuthorityResponseAuditor {
+    /// Responses for sequence numbers without a known certified checkpoint.
+    pending: BTreeMap<CheckpointSequenceNumber, BTreeMap<AuthorityName, AuthenticatedCheckpoint>>,
+    certified: BTreeMap<CheckpointSequenceNumber, CertifiedCheckpointSummary>,
+    reports: Vec<MisbehaviorReport>,
+}
+
+impl AuthorityResponseAuditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the checkpoint served by `authority`. Returns the report if it conflicts with
+    /// the certified checkpoint of its sequence number. Otherwise, if that checkpoint is not
+    /// known yet, the response is kept until it is, replacing any earlier response of the
+    /// authority for the same sequence number.
+    pub fn record_response(
+        &mut self,
+        authority: AuthorityName,
+        served: AuthenticatedCheckpoint,
+    ) -> Option<&MisbehaviorReport> {
+        let seq = served.sequence_number();
+        match self.certified.get(&seq) {
+            Some(certified) => {
+                let report = conflict(authority, certified, served)?;
+                self.reports.push(report);
+                self.reports.last()
+            }
+            None => {
+                self.pending
+                    .entry(seq)
+                    .or_default()
+                    .insert(authority, served);
+                None
+            }
+        }
+    }
+
+    /// Record the certified checkpoint of its sequence number, and return the reports of the
+    /// pending responses conflicting with it.
+    pub fn record_certified(
+        &mut self,
+        certified: CertifiedCheckpointSummary,
+    ) -> &[MisbehaviorReport] {
+        let seq = certified.data().sequence_number;
+        let start = self.reports.len();
+        for (authority, served) in self.pending.remove(&seq).unwrap_or_default() {
+            self.reports.extend(conflict(authority, &certified, served));
+        }
+        self.certified.insert(seq, certified);
+        &self.reports[start..]
+    }
+
+    /// Every report so far.
+    pub fn reports(&self) -> &[MisbehaviorReport] {
+        &self.reports
+    }
+
+    /// The reports about the checkpoints served by `authority`.
+    pub fn reports_for<'a>(
+        &'a self,
+        authority: &'a AuthorityName,
+    ) -> impl Iterator<Item = &'a MisbehaviorReport> {
+        self.reports
+            .iter()
+            .filter(move |r| &r.served_by == authority)
+    }
+
+    /// Forget the responses and certified checkpoints below `seq`. Reports are kept.
+    pub fn prune_below(&mut self, seq: CheckpointSequenceNumber) {
+        self.pending = self.pending.split_off(&seq);
+        self.certified = self.certified.split_off(&seq);
+    }
+}
+
+fn conflict(
+    served_by: AuthorityName,
+    certified: &CertifiedCheckpointSummary,
+    served: AuthenticatedCheckpoint,
+) -> Option<MisbehaviorReport> {
+    (served.summary().digest() != certified.data().digest()).then(|| MisbehaviorReport {
+        served_by,
+        certified: certified.clone(),
+        served,
+    })
+}
@@ -0,0 +1,185 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Advertisement of the chunks of checkpoint contents held by peers, so that the contents
+//! of a checkpoint can be downloaded from several peers in parallel, and signed attestations
+//! that a node held the contents at some time, to audit the availability of data served.
+
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use crate::base_types::SuiAddress;
+use crate::crypto::{Signature, SuiSignature};
+use crate::error::{SuiError, SuiResult};
+use crate::messages_checkpoint::{CheckpointContentsDigest, CheckpointDigest, CheckpointSummary};
+use crate::sui_serde::SuiBitmap;
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_availability_tests.rs"]
+mod checkpoint_availability_tests;
+
+/// The chunks of the contents of a checkpoint held by a peer, by `CheckpointContentsChunk`
+/// index.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ContentsAvailability {
+    pub checkpoint_digest: CheckpointDigest,
+    #[serde_as(as = "SuiBitmap")]
+    pub chunk_bitmap: RoaringBitmap,
+}
+
+/// A chunk to request, and the index of the peer to request it from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkAssignment {
+    pub chunk: u32,
+    pub peer: usize,
+}
+
+impl ContentsAvailability {
+    /// No chunk of the checkpoint is held.
+    pub fn new(checkpoint_digest: CheckpointDigest) -> Self {
+        Self {
+            checkpoint_digest,
+            chunk_bitmap: RoaringBitmap::new(),
+        }
+    }
+
+    /// All `chunk_count` chunks of the checkpoint are held.
+    pub fn complete(checkpoint_digest: CheckpointDigest, chunk_count: u32) -> Self {
+        let mut availability = Self::new(checkpoint_digest);
+        availability.chunk_bitmap.insert_range(0..chunk_count);
+        availability
+    }
+
+    pub fn set(&mut self, chunk: u32) {
+        self.chunk_bitmap.insert(chunk);
+    }
+
+    pub fn has(&self, chunk: u32) -> bool {
+        self.chunk_bitmap.contains(chunk)
+    }
+
+    pub fn chunk_count(&self) -> u64 {
+        self.chunk_bitmap.len()
+    }
+
+    /// Add the chunks held according to `other`, which must be about the same checkpoint.
+    pub fn merge(&mut self, other: &ContentsAvailability) -> SuiResult {
+        fp_ensure!(
+            self.checkpoint_digest == other.checkpoint_digest,
+            SuiError::from("Cannot merge the availability of different checkpoints")
+        );
+        self.chunk_bitmap |= &other.chunk_bitmap;
+        Ok(())
+    }
+
+    /// Pick up to `max_chunks` chunks missing here but held by some of `peers`, rarest first,
+    /// so that chunks held by few peers are fetched before those peers go away. Each chunk
+    /// is assigned to the least loaded of its holders. Peers advertising another checkpoint
+    /// are ignored.
+    pub fn select_missing(
+        &self,
+        peers: &[ContentsAvailability],
+        max_chunks: usize,
+    ) -> Vec<ChunkAssignment> {
+        let peers: Vec<_> = peers
+            .iter()
+            .enumerate()
+            .filter(|(_, peer)| peer.checkpoint_digest == self.checkpoint_digest)
+            .collect();
+        let mut available = RoaringBitmap::new();
+        for (_, peer) in &peers {
+            available |= &peer.chunk_bitmap;
+        }
+        let missing = available - &self.chunk_bitmap;
+
+        // Ties are broken by chunk index, to fetch the contents roughly in order.
+        let mut by_rarity: Vec<_> = missing
+            .iter()
+            .map(|chunk| {
+                let holders = peers.iter().filter(|(_, peer)| peer.has(chunk)).count();
+                (holders, chunk)
+            })
+            .collect();
+        by_rarity.sort_unstable();
+
+        let mut load = vec![0usize; peers.len()];
+        by_rarity
+            .into_iter()
+            .take(max_chunks)
+            .map(|(_, chunk)| {
+                let (slot, (peer, _)) = peers
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, peer))| peer.has(chunk))
+                    .min_by_key(|(slot, _)| load[*slot])
+                    .expect("Missing chunks are held by some peer");
+                load[slot] += 1;
+                ChunkAssignment { chunk, peer: *peer }
+            })
+            .collect()
+    }
+}
+
+/// A statement by `signer` that it held the contents of a checkpoint at `timestamp_ms`,
+/// milliseconds since the Unix epoch. The time is as claimed by the signer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AvailabilityAttestation {
+    pub checkpoint_digest: CheckpointDigest,
+    pub content_digest: CheckpointContentsDigest,
+    pub timestamp_ms: u64,
+    pub signer: SuiAddress,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedAvailabilityAttestation {
+    pub attestation: AvailabilityAttestation,
+    pub signature: Signature,
+}
+
+impl AvailabilityAttestation {
+    /// Attest to holding the contents of the checkpoint of `summary`.
+    pub fn new(summary: &CheckpointSummary, timestamp_ms: u64, signer: SuiAddress) -> Self {
+        Self {
+            checkpoint_digest: summary.digest(),
+            content_digest: summary.content_digest,
+            timestamp_ms,
+            signer,
+        }
+    }
+
+    /// Sign with the key of `signer`.
+    pub fn sign(self, secret: &dyn signature::Signer<Signature>) -> SignedAvailabilityAttestation {
+        let signature = Signature::new(&self, secret);
+        SignedAvailabilityAttestation {
+            attestation: self,
+            signature,
+        }
+    }
+}
+
+impl SignedAvailabilityAttestation {
+    /// Check that the attestation is signed by its signer.
+    pub fn verify(&self) -> SuiResult {
+        self.signature
+            .verify(&self.attestation, self.attestation.signer)
+    }
+
+    /// Same as `verify`, also checking that the attestation is about the checkpoint of
+    /// `summary`.
+    pub fn verify_for(&self, summary: &CheckpointSummary) -> SuiResult {
+        fp_ensure!(
+            self.attestation.checkpoint_digest == summary.digest()
+                && self.attestation.content_digest == summary.content_digest,
+            SuiError::from(
+                format!(
+                    "Availability attestation is not about checkpoint {}",
+                    summary.sequence_number
+                )
+                .as_str()
+            )
+        );
+        self.verify()
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sizing of the batches of checkpoints requested at once while catching up, adapted to the
+//! size of the responses and the time they take, since checkpoints range from empty to
+//! several megabytes.
+
+use std::ops::Range;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::messages_checkpoint::CheckpointSequenceNumber;
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_batching_tests.rs"]
+mod checkpoint_batching_tests;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdaptiveBatcherConfig {
+    /// Bounds of the batch size, in checkpoints.
+    pub min_batch: u64,
+    pub max_batch: u64,
+    pub initial_batch: u64,
+    /// Checkpoints added to the batch size after each response within targets.
+    pub increase: u64,
+    /// The percentage of the batch size kept after a response missing a target, or a
+    /// failed request.
+    pub decrease_percent: u64,
+    /// Responses slower than this are too large.
+    pub target_latency: Duration,
+    /// Responses larger than this are too large. Batches are also sized so that their
+    /// expected response stays below it.
+    pub max_response_bytes: u64,
+}
+
+impl Default for AdaptiveBatcherConfig {
+    fn default() -> Self {
+        Self {
+            min_batch: 1,
+            max_batch: 1_000,
+            initial_batch: 10,
+            increase: 10,
+            decrease_percent: 50,
+            target_latency: Duration::from_secs(2),
+            max_response_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// Adjusts the size of range requests the AIMD way: the batch grows additively while
+/// responses stay within the latency and size targets, and shrinks multiplicatively as soon
+/// as one does not. The batch is further capped by the expected size of its response, from
+/// a running average of the bytes per checkpoint observed.
+#[derive(Clone, Debug)]
+pub struct AdaptiveBatcher {
+    config: AdaptiveBatcherConfig,
+    batch_size: u64,
+    /// Exponentially weighted, with a weight of 1/4 for the latest response.
+    bytes_per_checkpoint: Option<u64>,
+}
+
+impl AdaptiveBatcher {
+    pub fn new(config: AdaptiveBatcherConfig) -> Self {
+        let batch_size = config
+            .initial_batch
+            .clamp(config.min_batch.max(1), config.max_batch.max(1));
+        Self {
+            config,
+            batch_size,
+            bytes_per_checkpoint: None,
+        }
+    }
+
+    pub fn config(&self) -> &AdaptiveBatcherConfig {
+        &self.config
+    }
+
+    /// The number of checkpoints to request next.
+    pub fn next_batch_size(&self) -> u64 {
+        let min_batch = self.config.min_batch.max(1);
+        match self.bytes_per_checkpoint {
+            Some(bytes) if bytes > 0 => {
+                let fitting = self.config.max_response_bytes / bytes;
+                self.batch_size.min(fitting.max(min_batch))
+            }
+            _ => self.batch_size,
+        }
+    }
+
+    /// The range to request next, starting at `next` and not reaching `end`.
+    pub fn next_range(
+        &self,
+        next: CheckpointSequenceNumber,
+        end: CheckpointSequenceNumber,
+    ) -> Range<CheckpointSequenceNumber> {
+        next..end
+            .min(next.saturating_add(self.next_batch_size()))
+            .max(next)
+    }
+
+    /// Record the response to a request for `checkpoints` checkpoints, which were `bytes`
+    /// long and took `latency`.
+    pub fn record_response(&mut self, checkpoints: u64, bytes: u64, latency: Duration) {
+        // Only full batches show that a larger one would fit.
+        let full = checkpoints >= self.next_batch_size();
+        if checkpoints > 0 {
+            let sample = bytes / checkpoints;
+            self.bytes_per_checkpoint = Some(match self.bytes_per_checkpoint {
+                Some(average) => (average * 3 + sample) / 4,
+                None => sample,
+            });
+        }
+        if latency > self.config.target_latency || bytes > self.config.max_response_bytes {
+            self.decrease();
+        } else if full {
+            self.batch_size = self
+                .batch_size
+                .saturating_add(self.config.increase)
+                .min(self.config.max_batch.max(1));
+        }
+    }
+
+    /// Record a request that failed or timed out.
+    pub fn record_failure(&mut self) {
+        self.decrease();
+    }
+
+    fn decrease(&mut self) {
+        self.batch_size = (self.batch_size * self.config.decrease_percent / 100)
+            .max(self.config.min_batch.max(1));
+    }
+}
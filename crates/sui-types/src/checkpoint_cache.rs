@@ -0,0 +1,349 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::committee::{PreparedCommittee, SignerSetsIndex};
+use crate::error::SuiResult;
+use crate::messages_checkpoint::{CheckpointContents, CheckpointSequenceNumber};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_cache_tests.rs"]
+mod checkpoint_cache_tests;
+
+/// Hit/miss counters of a ContentsCache.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentsCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Number of contents that were not cached because they did not fit in the budget.
+    pub rejected: u64,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+struct CacheEntry {
+    contents: Arc<CheckpointContents>,
+    bytes: usize,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct ContentsCacheInner {
+    entries: HashMap<CheckpointSequenceNumber, CacheEntry>,
+    /// Recency index: last use tick -> sequence number. The smallest tick is evicted first.
+    recency: BTreeMap<u64, CheckpointSequenceNumber>,
+    pinned: BTreeSet<CheckpointSequenceNumber>,
+    tick: u64,
+    stats: ContentsCacheStats,
+}
+
+impl ContentsCacheInner {
+    fn touch(&mut self, seq: CheckpointSequenceNumber) -> Option<Arc<CheckpointContents>> {
+        self.tick += 1;
+        let tick = self.tick;
+        let entry = self.entries.get_mut(&seq)?;
+        self.recency.remove(&entry.last_used);
+        entry.last_used = tick;
+        self.recency.insert(tick, seq);
+        Some(entry.contents.clone())
+    }
+
+    fn remove(&mut self, seq: CheckpointSequenceNumber) -> Option<CacheEntry> {
+        let entry = self.entries.remove(&seq)?;
+        self.recency.remove(&entry.last_used);
+        self.stats.bytes -= entry.bytes;
+        self.stats.entries -= 1;
+        Some(entry)
+    }
+
+    fn pinned_bytes(&self, except: CheckpointSequenceNumber) -> usize {
+        self.pinned
+            .iter()
+            .filter(|seq| **seq != except)
+            .filter_map(|seq| self.entries.get(seq))
+            .map(|entry| entry.bytes)
+            .sum()
+    }
+
+    /// Evict the least recently used, unpinned entries until the contents of `seq`, of
+    /// `needed` bytes, fit in the budget in place of its current entry, if any. The entry
+    /// of `seq` is never evicted. Returns false, without evicting anything, if that is not
+    /// possible.
+    fn make_room(&mut self, seq: CheckpointSequenceNumber, needed: usize, budget: usize) -> bool {
+        if self.pinned_bytes(seq) + needed > budget {
+            return false;
+        }
+        let replaced = self.entries.get(&seq).map_or(0, |entry| entry.bytes);
+        while self.stats.bytes - replaced + needed > budget {
+            let victim = self
+                .recency
+                .values()
+                .find(|victim| **victim != seq && !self.pinned.contains(victim))
+                .copied()
+                .expect("unpinned entries must exist while over budget");
+            self.remove(victim);
+            self.stats.evictions += 1;
+        }
+        true
+    }
+}
+
+/// An in-memory cache of checkpoint contents bounded by the serialized size of the
+/// contents it holds. Least recently used entries are evicted first, except for pinned
+/// entries (e.g. the checkpoint currently being executed) which are never evicted.
+/// Contents that cannot fit in the budget are served but not cached, so a burst of large
+/// checkpoints cannot grow the memory usage beyond the budget.
+pub struct ContentsCache {
+    budget_bytes: usize,
+    inner: Mutex<ContentsCacheInner>,
+}
+
+impl ContentsCache {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            budget_bytes,
+            inner: Mutex::new(ContentsCacheInner::default()),
+        }
+    }
+
+    pub fn budget_bytes(&self) -> usize {
+        self.budget_bytes
+    }
+
+    pub fn get(&self, seq: CheckpointSequenceNumber) -> Option<Arc<CheckpointContents>> {
+        let mut inner = self.inner.lock().unwrap();
+        let result = inner.touch(seq);
+        if result.is_some() {
+            inner.stats.hits += 1;
+        } else {
+            inner.stats.misses += 1;
+        }
+        result
+    }
+
+    pub fn contains(&self, seq: CheckpointSequenceNumber) -> bool {
+        self.inner.lock().unwrap().entries.contains_key(&seq)
+    }
+
+    /// Insert contents in the cache, evicting older entries if needed. Returns false if the
+    /// contents could not be cached within the budget, in which case the current entry for
+    /// `seq`, if any, is kept.
+    pub fn insert(&self, seq: CheckpointSequenceNumber, contents: Arc<CheckpointContents>) -> bool {
+        let bytes = bcs::serialized_size(contents.as_ref())
+            .expect("Checkpoint contents serialization should not fail");
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.make_room(seq, bytes, self.budget_bytes) {
+            inner.stats.rejected += 1;
+            return false;
+        }
+        inner.remove(seq);
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.entries.insert(
+            seq,
+            CacheEntry {
+                contents,
+                bytes,
+                last_used: tick,
+            },
+        );
+        inner.recency.insert(tick, seq);
+        inner.stats.bytes += bytes;
+        inner.stats.entries += 1;
+        true
+    }
+
+    pub fn remove(&self, seq: CheckpointSequenceNumber) -> Option<Arc<CheckpointContents>> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.pinned.remove(&seq);
+        inner.remove(seq).map(|entry| entry.contents)
+    }
+
+    /// Pin a sequence number so that its contents are never evicted. Pinning applies to
+    /// contents inserted after the call as well.
+    pub fn pin(&self, seq: CheckpointSequenceNumber) {
+        self.inner.lock().unwrap().pinned.insert(seq);
+    }
+
+    pub fn unpin(&self, seq: CheckpointSequenceNumber) {
+        self.inner.lock().unwrap().pinned.remove(&seq);
+    }
+
+    pub fn stats(&self) -> ContentsCacheStats {
+        self.inner.lock().unwrap().stats.clone()
+    }
+
+    /// The index of the cache, to be persisted across restarts and given to a `CacheWarmer`.
+    pub fn dump_index(&self) -> ContentsCacheIndex {
+        let inner = self.inner.lock().unwrap();
+        ContentsCacheIndex {
+            recency: inner.recency.values().copied().collect(),
+            pinned: inner.pinned.iter().copied().collect(),
+        }
+    }
+
+    /// Returns the cached contents for `seq`, or runs `loader` to fetch them and caches the
+    /// result. The cache lock is not held while the loader runs, so concurrent misses on the
+    /// same sequence number may load it more than once.
+    pub async fn get_or_fetch<F, Fut>(
+        &self,
+        seq: CheckpointSequenceNumber,
+        loader: F,
+    ) -> SuiResult<Arc<CheckpointContents>>
+    where
+        F: FnOnce(CheckpointSequenceNumber) -> Fut,
+        Fut: Future<Output = SuiResult<CheckpointContents>>,
+    {
+        if let Some(contents) = self.get(seq) {
+            return Ok(contents);
+        }
+        let contents = Arc::new(loader(seq).await?);
+        self.insert(seq, contents.clone());
+        Ok(contents)
+    }
+}
+
+/// Which sequence numbers a `ContentsCache` holds, from the least to the most recently used,
+/// and which are pinned. The contents themselves are not part of the index, and are loaded
+/// back from storage by a `CacheWarmer`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentsCacheIndex {
+    pub recency: Vec<CheckpointSequenceNumber>,
+    pub pinned: Vec<CheckpointSequenceNumber>,
+}
+
+/// Reads the contents of checkpoints from storage. None if the contents are not stored, e.g.
+/// since they were pruned.
+pub trait ContentsLoader {
+    fn load(&self, seq: CheckpointSequenceNumber) -> SuiResult<Option<CheckpointContents>>;
+}
+
+impl<F> ContentsLoader for F
+where
+    F: Fn(CheckpointSequenceNumber) -> SuiResult<Option<CheckpointContents>>,
+{
+    fn load(&self, seq: CheckpointSequenceNumber) -> SuiResult<Option<CheckpointContents>> {
+        self(seq)
+    }
+}
+
+/// What a `CacheWarmer` loaded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarmupStats {
+    pub loaded: usize,
+    pub bytes: usize,
+    /// Sequence numbers of the index whose contents are no longer stored.
+    pub missing: usize,
+    /// Signer sets prepared in the committee of the verification cache.
+    pub signer_sets: usize,
+}
+
+/// Repopulates a `ContentsCache` after a restart, so that peers are served from memory right
+/// away. Contents are loaded by priority: pinned sequence numbers first, then those of the
+/// index dumped before the restart from the most recently used, then the latest checkpoints
+/// from `latest_seq` down, which peers catching up are the most likely to ask for. The
+/// verification cache, the signer sets of a `PreparedCommittee`, can be restored as well.
+pub struct CacheWarmer<'a, L> {
+    cache: &'a ContentsCache,
+    loader: L,
+    index: ContentsCacheIndex,
+    signer_sets: Option<(&'a PreparedCommittee, SignerSetsIndex)>,
+}
+
+impl<'a, L: ContentsLoader> CacheWarmer<'a, L> {
+    pub fn new(cache: &'a ContentsCache, loader: L, index: ContentsCacheIndex) -> Self {
+        Self {
+            cache,
+            loader,
+            index,
+            signer_sets: None,
+        }
+    }
+
+    /// Also prepare the signer sets of `index` in `committee`, dumped before the restart with
+    /// `PreparedCommittee::dump_signer_sets`. They are not counted in the budget.
+    pub fn with_signer_sets(
+        mut self,
+        committee: &'a PreparedCommittee,
+        index: SignerSetsIndex,
+    ) -> Self {
+        self.signer_sets = Some((committee, index));
+        self
+    }
+
+    /// Load contents up to `budget` bytes, or the budget of the cache if lower. Sequence
+    /// numbers above `latest_seq` are skipped, and pins of the index are restored.
+    pub fn warm(
+        &self,
+        latest_seq: CheckpointSequenceNumber,
+        budget: usize,
+    ) -> SuiResult<WarmupStats> {
+        let budget = budget.min(self.cache.budget_bytes());
+        for seq in &self.index.pinned {
+            self.cache.pin(*seq);
+        }
+        let indexed: Vec<_> = self
+            .index
+            .pinned
+            .iter()
+            .chain(self.index.recency.iter().rev())
+            .copied()
+            .filter(|seq| *seq <= latest_seq)
+            .collect();
+
+        let mut stats = WarmupStats::default();
+        if let Some((committee, index)) = &self.signer_sets {
+            stats.signer_sets = committee.prepare_signer_sets(index)?;
+        }
+        let mut loaded = Vec::new();
+        let mut seen = BTreeSet::new();
+        let mut add = |seq, contents: CheckpointContents, stats: &mut WarmupStats| {
+            let bytes = bcs::serialized_size(&contents)
+                .expect("Checkpoint contents serialization should not fail");
+            if stats.bytes + bytes > budget {
+                return false;
+            }
+            stats.bytes += bytes;
+            stats.loaded += 1;
+            loaded.push((seq, Arc::new(contents)));
+            true
+        };
+        for seq in indexed {
+            if !seen.insert(seq) || self.cache.contains(seq) {
+                continue;
+            }
+            match self.loader.load(seq)? {
+                Some(contents) => {
+                    if !add(seq, contents, &mut stats) {
+                        break;
+                    }
+                }
+                None => stats.missing += 1,
+            }
+        }
+        // Fill the rest of the budget with the latest checkpoints, down to the first one
+        // missing from storage.
+        for seq in (0..=latest_seq).rev() {
+            if !seen.insert(seq) || self.cache.contains(seq) {
+                continue;
+            }
+            match self.loader.load(seq)? {
+                Some(contents) if add(seq, contents, &mut stats) => {}
+                _ => break,
+            }
+        }
+
+        // Insert by increasing priority, so that the most important contents are the most
+        // recently used.
+        for (seq, contents) in loaded.into_iter().rev() {
+            self.cache.insert(seq, contents);
+        }
+        Ok(stats)
+    }
+}
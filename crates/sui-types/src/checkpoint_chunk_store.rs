@@ -0,0 +1,309 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A content-addressed store of byte chunks. Chunks are keyed by the sha3 digest of their
+//! bytes, so that a chunk received twice, or stored by several callers, is only stored once.
+//!
+//! Contents chunks, fragment chunks and archive records can be spilled to a store with the
+//! helpers below. The buffers that hold them in memory, i.e. `ContentsCache`, the chunks of
+//! `PartialCheckpointFragment` and the archive verification, still keep their own bytes and
+//! are not backed by a store yet.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{ErrorKind, Read};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use fastcrypto::hash::{HashFunction, Sha3_256};
+
+use crate::checkpoint_archive::verify_archive_records;
+use crate::error::{SuiError, SuiResult};
+#[cfg(feature = "checkpoint-validator")]
+use crate::messages_checkpoint::PartialCheckpointFragment;
+use crate::messages_checkpoint::{
+    CheckpointContents, CheckpointContentsChunk, CheckpointSequenceNumber, CheckpointSummary,
+    DecodeLimits,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_chunk_store_tests.rs"]
+mod checkpoint_chunk_store_tests;
+
+/// The sha3 digest of the bytes of a chunk.
+pub type ChunkDigest = [u8; 32];
+
+pub fn chunk_digest(bytes: &[u8]) -> ChunkDigest {
+    let mut hasher = Sha3_256::default();
+    hasher.update(bytes);
+    hasher.finalize().into()
+}
+
+/// Stores chunks by digest. `put` rejects bytes not matching their digest, and stores may
+/// drop chunks on their own, so callers must be ready to fetch a chunk again.
+pub trait ChunkStore {
+    fn put(&self, digest: ChunkDigest, bytes: Vec<u8>) -> SuiResult;
+    fn get(&self, digest: &ChunkDigest) -> SuiResult<Option<Vec<u8>>>;
+    fn contains(&self, digest: &ChunkDigest) -> SuiResult<bool>;
+    /// Remove a chunk, returning whether it was stored.
+    fn evict(&self, digest: &ChunkDigest) -> SuiResult<bool>;
+}
+
+fn check_digest(digest: &ChunkDigest, bytes: &[u8]) -> SuiResult {
+    fp_ensure!(
+        chunk_digest(bytes) == *digest,
+        SuiError::from(format!("Chunk does not match digest {}", hex::encode(digest)).as_str())
+    );
+    Ok(())
+}
+
+/// Store a chunk, returning its digest.
+pub fn put_chunk(store: &dyn ChunkStore, bytes: Vec<u8>) -> SuiResult<ChunkDigest> {
+    let digest = chunk_digest(&bytes);
+    store.put(digest, bytes)?;
+    Ok(digest)
+}
+
+fn get_required(store: &dyn ChunkStore, digest: &ChunkDigest) -> SuiResult<Vec<u8>> {
+    store.get(digest)?.ok_or_else(|| {
+        SuiError::from(format!("Chunk {} is not stored", hex::encode(digest)).as_str())
+    })
+}
+
+/// Keeps chunks in memory, up to a budget in bytes, evicting the oldest chunks first.
+pub struct InMemoryChunkStore {
+    budget_bytes: usize,
+    inner: Mutex<InMemoryChunks>,
+}
+
+#[derive(Default)]
+struct InMemoryChunks {
+    chunks: HashMap<ChunkDigest, (u64, Vec<u8>)>,
+    /// Insertion tick -> digest, the smallest tick being evicted first.
+    order: BTreeMap<u64, ChunkDigest>,
+    tick: u64,
+    bytes: usize,
+}
+
+impl InMemoryChunkStore {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            budget_bytes,
+            inner: Mutex::new(InMemoryChunks::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.inner.lock().unwrap().bytes
+    }
+}
+
+impl ChunkStore for InMemoryChunkStore {
+    fn put(&self, digest: ChunkDigest, bytes: Vec<u8>) -> SuiResult {
+        check_digest(&digest, &bytes)?;
+        let mut inner = self.inner.lock().unwrap();
+        if inner.chunks.contains_key(&digest) || bytes.len() > self.budget_bytes {
+            return Ok(());
+        }
+        while inner.bytes + bytes.len() > self.budget_bytes {
+            let (&tick, &oldest) = inner
+                .order
+                .iter()
+                .next()
+                .expect("Stored chunks are in the order");
+            inner.order.remove(&tick);
+            let (_, evicted) = inner.chunks.remove(&oldest).unwrap();
+            inner.bytes -= evicted.len();
+        }
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.bytes += bytes.len();
+        inner.order.insert(tick, digest);
+        inner.chunks.insert(digest, (tick, bytes));
+        Ok(())
+    }
+
+    fn get(&self, digest: &ChunkDigest) -> SuiResult<Option<Vec<u8>>> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner.chunks.get(digest).map(|(_, bytes)| bytes.clone()))
+    }
+
+    fn contains(&self, digest: &ChunkDigest) -> SuiResult<bool> {
+        Ok(self.inner.lock().unwrap().chunks.contains_key(digest))
+    }
+
+    fn evict(&self, digest: &ChunkDigest) -> SuiResult<bool> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.chunks.remove(digest) {
+            Some((tick, bytes)) => {
+                inner.order.remove(&tick);
+                inner.bytes -= bytes.len();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Keeps each chunk in a file of a directory, named by the hex of its digest. Chunks are
+/// written to a temporary file first, so that a crash never leaves a partial chunk, and are
+/// checked against their digest when read back.
+pub struct DiskChunkStore {
+    dir: PathBuf,
+}
+
+impl DiskChunkStore {
+    pub fn open(dir: impl Into<PathBuf>) -> SuiResult<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(|e| io_error("create", &dir, e))?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, digest: &ChunkDigest) -> PathBuf {
+        self.dir.join(hex::encode(digest))
+    }
+}
+
+fn io_error(action: &str, path: &std::path::Path, e: std::io::Error) -> SuiError {
+    SuiError::from(format!("Failed to {action} {}: {e}", path.display()).as_str())
+}
+
+impl ChunkStore for DiskChunkStore {
+    fn put(&self, digest: ChunkDigest, bytes: Vec<u8>) -> SuiResult {
+        check_digest(&digest, &bytes)?;
+        let path = self.path(&digest);
+        if path.exists() {
+            return Ok(());
+        }
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, &bytes).map_err(|e| io_error("write", &temp, e))?;
+        std::fs::rename(&temp, &path).map_err(|e| io_error("rename", &temp, e))
+    }
+
+    fn get(&self, digest: &ChunkDigest) -> SuiResult<Option<Vec<u8>>> {
+        let path = self.path(digest);
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                check_digest(digest, &bytes)?;
+                Ok(Some(bytes))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error("read", &path, e)),
+        }
+    }
+
+    fn contains(&self, digest: &ChunkDigest) -> SuiResult<bool> {
+        Ok(self.path(digest).exists())
+    }
+
+    fn evict(&self, digest: &ChunkDigest) -> SuiResult<bool> {
+        let path = self.path(digest);
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(io_error("remove", &path, e)),
+        }
+    }
+}
+
+/// Store the BCS serialization of the stream chunks of `contents`, returning their digests in
+/// order.
+pub fn put_contents_chunks(
+    store: &dyn ChunkStore,
+    contents: &CheckpointContents,
+    sequence_number: CheckpointSequenceNumber,
+    max_chunk_transactions: usize,
+) -> SuiResult<Vec<ChunkDigest>> {
+    contents
+        .to_stream_chunks(sequence_number, max_chunk_transactions)
+        .iter()
+        .map(|chunk| {
+            put_chunk(
+                store,
+                bcs::to_bytes(chunk).expect("Serialization should not fail"),
+            )
+        })
+        .collect()
+}
+
+/// Reassemble contents from stream chunks stored by `put_contents_chunks`.
+pub fn get_contents_chunks(
+    store: &dyn ChunkStore,
+    digests: &[ChunkDigest],
+) -> SuiResult<CheckpointContents> {
+    let chunks = digests
+        .iter()
+        .map(|digest| {
+            let bytes = get_required(store, digest)?;
+            bcs::from_bytes::<CheckpointContentsChunk>(&bytes)
+                .map_err(|e| SuiError::from(format!("Invalid contents chunk: {e}").as_str()))
+        })
+        .collect::<SuiResult<Vec<_>>>()?;
+    // Stored by `put_contents_chunks` from contents this node already holds.
+    CheckpointContents::from_stream_chunks(chunks, &DecodeLimits::default())
+}
+
+/// Store the chunks received so far for a fragment, returning their digests by chunk id.
+#[cfg(feature = "checkpoint-validator")]
+pub fn put_fragment_chunks(
+    store: &dyn ChunkStore,
+    partial: &PartialCheckpointFragment,
+) -> SuiResult<BTreeMap<u32, ChunkDigest>> {
+    partial
+        .chunks
+        .iter()
+        .map(|(chunk_id, content)| Ok((*chunk_id, put_chunk(store, content.clone())?)))
+        .collect()
+}
+
+/// Add chunks stored by `put_fragment_chunks` back to the fragment they belong to.
+#[cfg(feature = "checkpoint-validator")]
+pub fn get_fragment_chunks(
+    store: &dyn ChunkStore,
+    partial: &mut PartialCheckpointFragment,
+    digests: &BTreeMap<u32, ChunkDigest>,
+) -> SuiResult {
+    for (chunk_id, digest) in digests {
+        fp_ensure!(
+            *chunk_id < partial.chunk_count,
+            SuiError::from(
+                format!(
+                    "chunk_id ({:?}) out of bound ({:?})",
+                    chunk_id, partial.chunk_count
+                )
+                .as_str()
+            )
+        );
+        partial
+            .chunks
+            .insert(*chunk_id, get_required(store, digest)?);
+    }
+    Ok(())
+}
+
+/// Same as `verify_archive`, also storing the record of each checkpoint, i.e. the bincode
+/// serialization of its contents. Returns the digests of the records, in order.
+pub fn verify_archive_into_store(
+    reader: impl Read,
+    summaries: &[CheckpointSummary],
+    store: &dyn ChunkStore,
+) -> SuiResult<Vec<ChunkDigest>> {
+    let mut records = Vec::with_capacity(summaries.len());
+    verify_archive_records(reader, summaries, |record| {
+        records.push(record.to_vec());
+        Ok(Cow::Borrowed(record))
+    })?;
+    // Records are only stored once the whole archive is verified.
+    records
+        .into_iter()
+        .map(|record| put_chunk(store, record))
+        .collect()
+}
@@ -0,0 +1,438 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Commitments to checkpoint contents, computed in one streaming pass over the transactions
+//! so that contents fetched from mirrors can be verified while they are downloaded, and
+//! proofs that a transaction is in contents committed to by a Merkle root.
+
+use std::cell::Cell;
+
+use fastcrypto::hash::{HashFunction, Sha3_256};
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::ExecutionDigests;
+use crate::error::{SuiError, SuiResult};
+use crate::messages_checkpoint::{
+    CheckpointContents, CheckpointContentsDigest, CheckpointContentsV2, CheckpointSummary,
+    ConsensusCommitRef, VersionedCheckpointContents,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_commitment_tests.rs"]
+mod checkpoint_commitment_tests;
+
+pub type ContentsMerkleRoot = [u8; 32];
+
+const LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+const ROOT_TAG: u8 = 2;
+
+/// Both commitments to a list of transactions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentsCommitments {
+    /// The legacy digest, equal to `CheckpointContents::digest`.
+    pub flat: CheckpointContentsDigest,
+    /// Root of a binary Merkle tree over the transactions, in checkpoint order. The last node
+    /// of a level with an odd number of nodes is promoted to the next level, and the root
+    /// hashes the number of transactions with the top node of the tree.
+    pub merkle_root: ContentsMerkleRoot,
+}
+
+/// The commitments a summary carries, depending on its version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExpectedContentsCommitment {
+    Flat(CheckpointContentsDigest),
+    MerkleRoot(ContentsMerkleRoot),
+    Both {
+        flat: CheckpointContentsDigest,
+        merkle_root: ContentsMerkleRoot,
+    },
+}
+
+impl From<&CheckpointSummary> for ExpectedContentsCommitment {
+    fn from(summary: &CheckpointSummary) -> Self {
+        Self::Both {
+            flat: summary.content_digest,
+            merkle_root: summary.contents_merkle_root,
+        }
+    }
+}
+
+impl ContentsCommitments {
+    pub fn verify(&self, expected: &ExpectedContentsCommitment) -> SuiResult {
+        let (flat, merkle_root) = match expected {
+            ExpectedContentsCommitment::Flat(flat) => (Some(flat), None),
+            ExpectedContentsCommitment::MerkleRoot(root) => (None, Some(root)),
+            ExpectedContentsCommitment::Both { flat, merkle_root } => {
+                (Some(flat), Some(merkle_root))
+            }
+        };
+        fp_ensure!(
+            flat.map_or(true, |flat| *flat == self.flat),
+            SuiError::from("Checkpoint contents digest mismatch")
+        );
+        fp_ensure!(
+            merkle_root.map_or(true, |root| *root == self.merkle_root),
+            SuiError::from("Checkpoint contents Merkle root mismatch")
+        );
+        Ok(())
+    }
+}
+
+/// Computes the flat digest and the Merkle root of checkpoint contents simultaneously, from
+/// transactions fed in checkpoint order. The number of transactions must be known upfront,
+/// since the flat digest hashes the serialized length first.
+pub struct DualCommitmentHasher {
+    flat: Sha3_256,
+    /// Roots of the complete subtrees built so far, with their height, largest first.
+    subtrees: Vec<(u32, [u8; 32])>,
+    expected: usize,
+    received: usize,
+}
+
+impl DualCommitmentHasher {
+    pub fn new(transactions: usize) -> Self {
+        let mut flat = Sha3_256::default();
+        // Mirrors the `Signable` encoding of `CheckpointContents`: the type name, then the
+        // BCS encoding of its only field.
+        flat.update(b"CheckpointContents::");
+        flat.update(uleb128(transactions));
+        Self {
+            flat,
+            subtrees: Vec::new(),
+            expected: transactions,
+            received: 0,
+        }
+    }
+
+    pub fn update(&mut self, digests: &ExecutionDigests) {
+        let bytes = bcs::to_bytes(digests).expect("Serialization should not fail");
+        self.flat.update(&bytes);
+
+        let mut node = (0, hash_leaf(&bytes));
+        while let Some((height, left)) = self.subtrees.last() {
+            if *height != node.0 {
+                break;
+            }
+            node = (node.0 + 1, hash_node(left, &node.1));
+            self.subtrees.pop();
+        }
+        self.subtrees.push(node);
+        self.received += 1;
+    }
+
+    pub fn finalize(self) -> SuiResult<ContentsCommitments> {
+        fp_ensure!(
+            self.received == self.expected,
+            SuiError::from(
+                format!(
+                    "Expected {} transactions, hashed {}",
+                    self.expected, self.received
+                )
+                .as_str()
+            )
+        );
+        // Promoting odd nodes is the same as folding the remaining subtrees from the right.
+        let merkle_root = self
+            .subtrees
+            .iter()
+            .rev()
+            .map(|(_, root)| *root)
+            .reduce(|right, left| hash_node(&left, &right))
+            .map_or([0; 32], |top| hash_root(self.received as u64, &top));
+        Ok(ContentsCommitments {
+            flat: self.flat.finalize().into(),
+            merkle_root,
+        })
+    }
+
+    /// Hash `transactions` in one pass and check them against the commitments of a summary.
+    pub fn verify_stream<'a>(
+        expected: &ExpectedContentsCommitment,
+        len: usize,
+        transactions: impl IntoIterator<Item = &'a ExecutionDigests>,
+    ) -> SuiResult<ContentsCommitments> {
+        let mut hasher = Self::new(len);
+        transactions
+            .into_iter()
+            .for_each(|digests| hasher.update(digests));
+        let commitments = hasher.finalize()?;
+        commitments.verify(expected)?;
+        Ok(commitments)
+    }
+}
+
+/// Contents built by appending transactions as they are ordered, e.g. as the fragments of a
+/// checkpoint resolve, whose digest can be read at any point. Transactions and consensus
+/// commits are serialized once, when appended, and the digest is kept until the next append.
+///
+/// The digest is the one of the finalized contents, whose encoding starts with the number of
+/// transactions. A hasher state cannot be carried over appends, since every append changes
+/// that prefix: the first read of the digest after an append hashes the serialized
+/// transactions again, and later reads are free.
+#[derive(Clone, Debug, Default)]
+pub struct ContentsAccumulator {
+    transactions: Vec<ExecutionDigests>,
+    /// The BCS encoding of `transactions`, without its length prefix.
+    encoded: Vec<u8>,
+    consensus_commits: Option<Vec<ConsensusCommitRef>>,
+    /// The BCS encoding of `consensus_commits`, without its length prefix.
+    encoded_commits: Vec<u8>,
+    digest: Cell<Option<CheckpointContentsDigest>>,
+}
+
+impl ContentsAccumulator {
+    /// Accumulates version 1 contents.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accumulates version 2 contents, see `CheckpointContentsV2`.
+    pub fn new_with_consensus_commits() -> Self {
+        Self {
+            consensus_commits: Some(Vec::new()),
+            ..Self::default()
+        }
+    }
+
+    /// Append transactions, which must follow the ones already appended in causal order.
+    pub fn append(&mut self, transactions: impl IntoIterator<Item = ExecutionDigests>) {
+        for digests in transactions {
+            bcs::serialize_into(&mut self.encoded, &digests)
+                .expect("Serialization should not fail");
+            self.transactions.push(digests);
+            self.digest.set(None);
+        }
+    }
+
+    /// Append the consensus commits the transactions were sequenced in. Only version 2
+    /// contents reference consensus commits.
+    pub fn append_consensus_commits(
+        &mut self,
+        commits: impl IntoIterator<Item = ConsensusCommitRef>,
+    ) -> SuiResult {
+        let consensus_commits = self.consensus_commits.as_mut().ok_or_else(|| {
+            SuiError::from("Version 1 checkpoint contents have no consensus commits")
+        })?;
+        for commit in commits {
+            bcs::serialize_into(&mut self.encoded_commits, &commit)
+                .expect("Serialization should not fail");
+            consensus_commits.push(commit);
+            self.digest.set(None);
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// The digest of the contents accumulated so far, equal to
+    /// `VersionedCheckpointContents::digest` of the finalized contents. Version 2 contents
+    /// are encoded as their transactions followed by their consensus commits.
+    pub fn digest(&self) -> CheckpointContentsDigest {
+        if let Some(digest) = self.digest.get() {
+            return digest;
+        }
+        let mut hasher = Sha3_256::default();
+        match &self.consensus_commits {
+            None => hasher.update(b"CheckpointContents::"),
+            Some(_) => hasher.update(b"CheckpointContentsV2::"),
+        }
+        hasher.update(uleb128(self.transactions.len()));
+        hasher.update(&self.encoded);
+        if let Some(consensus_commits) = &self.consensus_commits {
+            hasher.update(uleb128(consensus_commits.len()));
+            hasher.update(&self.encoded_commits);
+        }
+        let digest = hasher.finalize().into();
+        self.digest.set(Some(digest));
+        digest
+    }
+
+    pub fn finalize(self) -> VersionedCheckpointContents {
+        let contents = CheckpointContents::new_with_causally_ordered_transactions(
+            self.transactions.into_iter(),
+        );
+        match self.consensus_commits {
+            Some(consensus_commits) => VersionedCheckpointContents::V2(CheckpointContentsV2 {
+                contents,
+                consensus_commits,
+            }),
+            None => VersionedCheckpointContents::V1(contents),
+        }
+    }
+}
+
+/// A proof that a transaction is in the contents of a checkpoint, against the Merkle root
+/// of its contents: the siblings of the nodes on the path from the leaf of the transaction
+/// up to the root. Levels where the node has no sibling, being the promoted last node of the
+/// level, have no sibling in the proof. Object changes are proven the same way, see
+/// `checkpoint_object_changes`.
+///
+/// The root commits to the number of leaves, and for a given number of leaves the index
+/// fixes which levels have a sibling and on which side, so a proof only verifies for the
+/// position it was made for.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// The position of the leaf, e.g. of the transaction in the contents.
+    pub index: u64,
+    /// The number of leaves, e.g. of transactions in the contents.
+    pub leaves: u64,
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl MerkleProof {
+    /// A proof for the leaf at `index` of a tree over the hashes of `leaves`.
+    pub(crate) fn new(mut level: Vec<[u8; 32]>, index: usize) -> Self {
+        let leaves = level.len() as u64;
+        let mut siblings = Vec::new();
+        let mut position = index;
+        while level.len() > 1 {
+            if position % 2 == 1 {
+                siblings.push(level[position - 1]);
+            } else if position + 1 < level.len() {
+                siblings.push(level[position + 1]);
+            }
+            level = next_level(&level);
+            position /= 2;
+        }
+        Self {
+            index: index as u64,
+            leaves,
+            siblings,
+        }
+    }
+
+    /// The Merkle root of contents holding `digests` at the position of the proof.
+    pub fn root(&self, digests: &ExecutionDigests) -> SuiResult<ContentsMerkleRoot> {
+        self.root_of_leaf(hash_leaf(
+            &bcs::to_bytes(digests).expect("Serialization should not fail"),
+        ))
+    }
+
+    /// The root of a tree holding the leaf of hash `leaf` at the position of the proof.
+    pub(crate) fn root_of_leaf(&self, leaf: [u8; 32]) -> SuiResult<[u8; 32]> {
+        fp_ensure!(
+            self.index < self.leaves,
+            SuiError::from(
+                format!(
+                    "Merkle proof index {} out of bound ({})",
+                    self.index, self.leaves
+                )
+                .as_str()
+            )
+        );
+        let malformed = || SuiError::from("Merkle proof has the wrong number of siblings");
+        let mut node = leaf;
+        let mut siblings = self.siblings.iter();
+        let (mut index, mut width) = (self.index, self.leaves);
+        while width > 1 {
+            if index % 2 == 1 {
+                node = hash_node(siblings.next().ok_or_else(malformed)?, &node);
+            } else if index + 1 < width {
+                node = hash_node(&node, siblings.next().ok_or_else(malformed)?);
+            }
+            index /= 2;
+            width = (width + 1) / 2;
+        }
+        fp_ensure!(siblings.next().is_none(), malformed());
+        Ok(hash_root(self.leaves, &node))
+    }
+
+    pub fn verify(&self, root: &ContentsMerkleRoot, digests: &ExecutionDigests) -> SuiResult {
+        fp_ensure!(
+            self.root(digests)? == *root,
+            SuiError::from(
+                format!("Merkle proof does not prove inclusion of {:?}", digests).as_str()
+            )
+        );
+        Ok(())
+    }
+}
+
+impl CheckpointContents {
+    pub fn commitments(&self) -> ContentsCommitments {
+        let mut hasher = DualCommitmentHasher::new(self.size());
+        self.iter().for_each(|digests| hasher.update(digests));
+        hasher
+            .finalize()
+            .expect("All transactions of the contents were hashed")
+    }
+
+    /// A proof that `digests` is in the contents, against `ContentsCommitments::merkle_root`.
+    /// None if the contents do not hold `digests`.
+    pub fn prove_inclusion(&self, digests: &ExecutionDigests) -> Option<MerkleProof> {
+        let index = self.iter().position(|d| d == digests)?;
+        let leaves = self
+            .iter()
+            .map(|d| hash_leaf(&bcs::to_bytes(d).expect("Serialization should not fail")))
+            .collect();
+        Some(MerkleProof::new(leaves, index))
+    }
+}
+
+/// The root of a tree over the hashes of `leaves`, promoting the last node of odd levels, as
+/// built by `DualCommitmentHasher`.
+pub(crate) fn merkle_root(mut level: Vec<[u8; 32]>) -> [u8; 32] {
+    if level.is_empty() {
+        return [0; 32];
+    }
+    let leaves = level.len() as u64;
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    hash_root(leaves, &level[0])
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_node(left, right),
+            [single] => *single,
+            _ => unreachable!("Chunks hold one or two nodes"),
+        })
+        .collect()
+}
+
+pub(crate) fn hash_leaf(bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha3_256::default();
+    hasher.update([LEAF_TAG]);
+    hasher.update(bytes);
+    hasher.finalize().into()
+}
+
+fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha3_256::default();
+    hasher.update([NODE_TAG]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Binds the number of leaves to the top node of the tree.
+fn hash_root(leaves: u64, top: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha3_256::default();
+    hasher.update([ROOT_TAG]);
+    hasher.update(leaves.to_le_bytes());
+    hasher.update(top);
+    hasher.finalize().into()
+}
+
+fn uleb128(mut value: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
@@ -0,0 +1,1178 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Validator-side helpers used while constructing checkpoints: pools of transactions
+//! waiting to be proposed, the bookkeeping around proposals, and the fragment-free path
+//! building checkpoints from consensus commits.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::{AuthorityName, ExecutionDigests};
+use crate::batch::TxSequenceNumber;
+use crate::checkpoint_commitment::ContentsAccumulator;
+use crate::committee::{Committee, EpochId, StakeUnit};
+use crate::error::{SuiError, SuiResult};
+use crate::gas::GasCostSummary;
+use crate::messages_checkpoint::{
+    CheckpointContents, CheckpointContentsDigest, CheckpointDigest, CheckpointFragment,
+    CheckpointFragmentMessage, CheckpointProposal, CheckpointProposalContents,
+    CheckpointSequenceNumber, CheckpointSummary, ConsensusCommitRef, DecodeLimits,
+    FragmentHeaderDigest, PartialCheckpointFragment, SignedCheckpointProposalSummary,
+    SignedCheckpointSummary, VersionedCheckpointContents,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_construction_tests.rs"]
+mod checkpoint_construction_tests;
+
+/// Hooks allowing a PendingExecutionPool to be mirrored in persistent storage.
+/// A hook returning an error aborts the corresponding pool operation, leaving
+/// the pool unchanged.
+pub trait PendingExecutionPersistence: Send + Sync {
+    fn persist_inserted(&self, entries: &[(TxSequenceNumber, ExecutionDigests)]) -> SuiResult;
+
+    fn persist_removed(&self, entries: &[(TxSequenceNumber, ExecutionDigests)]) -> SuiResult;
+}
+
+/// Executed transactions that are not yet included in any checkpoint, keyed by their
+/// execution sequence number. Proposals are created by draining the oldest entries, and
+/// the entries of an abandoned proposal are put back with `reinsert`.
+#[derive(Default)]
+pub struct PendingExecutionPool {
+    pending: BTreeMap<TxSequenceNumber, ExecutionDigests>,
+    sequence_of: HashMap<ExecutionDigests, TxSequenceNumber>,
+    persistence: Option<Box<dyn PendingExecutionPersistence>>,
+}
+
+impl PendingExecutionPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_with_persistence(persistence: Box<dyn PendingExecutionPersistence>) -> Self {
+        Self {
+            persistence: Some(persistence),
+            ..Default::default()
+        }
+    }
+
+    /// Build a pool from entries previously persisted through the hooks, without
+    /// calling them again. Fails if two entries share a sequence number or a transaction.
+    pub fn restore(
+        entries: impl IntoIterator<Item = (TxSequenceNumber, ExecutionDigests)>,
+        persistence: Option<Box<dyn PendingExecutionPersistence>>,
+    ) -> SuiResult<Self> {
+        let mut pool = Self {
+            persistence,
+            ..Default::default()
+        };
+        for (seq, digests) in entries {
+            pool.insert_unpersisted(seq, digests)?;
+        }
+        Ok(pool)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn contains(&self, digests: &ExecutionDigests) -> bool {
+        self.sequence_of.contains_key(digests)
+    }
+
+    /// Add executed transactions to the pool. Transactions already pending are ignored.
+    /// Returns the number of transactions added. Fails, without adding anything, if a
+    /// sequence number is already taken by another transaction.
+    pub fn insert(
+        &mut self,
+        entries: impl IntoIterator<Item = (TxSequenceNumber, ExecutionDigests)>,
+    ) -> SuiResult<usize> {
+        let mut new_entries = BTreeMap::new();
+        let mut seen = HashSet::new();
+        for (seq, digests) in entries {
+            if self.sequence_of.contains_key(&digests) || !seen.insert(digests) {
+                continue;
+            }
+            let taken = self
+                .pending
+                .get(&seq)
+                .or_else(|| new_entries.get(&seq))
+                .copied();
+            if let Some(taken) = taken {
+                return Err(Self::sequence_collision(seq, &taken, &digests));
+            }
+            new_entries.insert(seq, digests);
+        }
+        let new_entries: Vec<_> = new_entries.into_iter().collect();
+        if let Some(persistence) = &self.persistence {
+            persistence.persist_inserted(&new_entries)?;
+        }
+        for (seq, digests) in &new_entries {
+            self.insert_unpersisted(*seq, *digests)?;
+        }
+        Ok(new_entries.len())
+    }
+
+    /// Put back the entries of a proposal that was abandoned, so that they are proposed again.
+    pub fn reinsert(
+        &mut self,
+        entries: Vec<(TxSequenceNumber, ExecutionDigests)>,
+    ) -> SuiResult<usize> {
+        self.insert(entries)
+    }
+
+    /// Atomically remove and return the (at most) `max` oldest pending transactions.
+    pub fn drain_up_to(
+        &mut self,
+        max: usize,
+    ) -> SuiResult<Vec<(TxSequenceNumber, ExecutionDigests)>> {
+        let drained: Vec<_> = self
+            .pending
+            .iter()
+            .take(max)
+            .map(|(seq, digests)| (*seq, *digests))
+            .collect();
+        self.remove_entries(drained)
+    }
+
+    /// Drain at most `max` transactions into proposal contents. The returned entries must be
+    /// passed to `reinsert` if the proposal is abandoned.
+    pub fn drain_proposal(
+        &mut self,
+        max: usize,
+    ) -> SuiResult<(
+        CheckpointProposalContents,
+        Vec<(TxSequenceNumber, ExecutionDigests)>,
+    )> {
+        let drained = self.drain_up_to(max)?;
+        let contents = CheckpointProposalContents::new(drained.iter().map(|(_, d)| *d));
+        Ok((contents, drained))
+    }
+
+    /// Remove the transactions included in a checkpoint. Returns the entries removed.
+    pub fn remove_checkpointed(
+        &mut self,
+        contents: &CheckpointContents,
+    ) -> SuiResult<Vec<(TxSequenceNumber, ExecutionDigests)>> {
+        let included: Vec<_> = contents
+            .iter()
+            .filter_map(|digests| self.sequence_of.get(digests).map(|seq| (*seq, *digests)))
+            .collect();
+        self.remove_entries(included)
+    }
+
+    fn insert_unpersisted(
+        &mut self,
+        seq: TxSequenceNumber,
+        digests: ExecutionDigests,
+    ) -> SuiResult {
+        if let Some(taken) = self.pending.get(&seq) {
+            return Err(Self::sequence_collision(seq, taken, &digests));
+        }
+        if let Some(other_seq) = self.sequence_of.get(&digests) {
+            return Err(SuiError::from(
+                format!(
+                    "Transaction {:?} is pending at sequence numbers {} and {}",
+                    digests.transaction, other_seq, seq
+                )
+                .as_str(),
+            ));
+        }
+        self.pending.insert(seq, digests);
+        self.sequence_of.insert(digests, seq);
+        Ok(())
+    }
+
+    fn sequence_collision(
+        seq: TxSequenceNumber,
+        taken: &ExecutionDigests,
+        digests: &ExecutionDigests,
+    ) -> SuiError {
+        SuiError::from(
+            format!(
+                "Sequence number {} of transaction {:?} is taken by transaction {:?}",
+                seq, digests.transaction, taken.transaction
+            )
+            .as_str(),
+        )
+    }
+
+    fn remove_entries(
+        &mut self,
+        entries: Vec<(TxSequenceNumber, ExecutionDigests)>,
+    ) -> SuiResult<Vec<(TxSequenceNumber, ExecutionDigests)>> {
+        if entries.is_empty() {
+            return Ok(entries);
+        }
+        if let Some(persistence) = &self.persistence {
+            persistence.persist_removed(&entries)?;
+        }
+        for (seq, digests) in &entries {
+            self.pending.remove(seq);
+            self.sequence_of.remove(digests);
+        }
+        Ok(entries)
+    }
+}
+
+/// The checks applied to proposals received from other validators.
+#[derive(Clone, Debug)]
+pub struct ProposalValidationPolicy {
+    /// The maximum number of transactions in a proposal.
+    pub max_transactions: usize,
+    /// Reject proposals including transactions known to be invalid.
+    pub reject_known_invalid: bool,
+}
+
+impl Default for ProposalValidationPolicy {
+    fn default() -> Self {
+        Self {
+            max_transactions: 100_000,
+            reject_known_invalid: true,
+        }
+    }
+}
+
+/// What the local validator knows when it receives a proposal.
+pub struct ProposalLocalState<'a> {
+    pub committee: &'a Committee,
+    /// The sequence number of the checkpoint currently being constructed.
+    pub next_sequence_number: CheckpointSequenceNumber,
+    /// Transactions this validator knows cannot be included in a checkpoint.
+    pub known_invalid: &'a HashSet<ExecutionDigests>,
+}
+
+/// Validates a proposal of another validator before it is relayed to the checkpoint logic.
+pub struct ProposalValidator {
+    policy: ProposalValidationPolicy,
+}
+
+impl ProposalValidator {
+    pub fn new(policy: ProposalValidationPolicy) -> Self {
+        Self { policy }
+    }
+
+    pub fn policy(&self) -> &ProposalValidationPolicy {
+        &self.policy
+    }
+
+    /// Run all the checks of the policy, the cheap ones first, ending with the signature of
+    /// the proposal and its consistency with the proposed transactions.
+    pub fn validate(&self, proposal: &CheckpointProposal, local: &ProposalLocalState) -> SuiResult {
+        let signed_summary = &proposal.signed_summary;
+        fp_ensure!(
+            signed_summary.epoch() == local.committee.epoch,
+            SuiError::WrongEpoch {
+                expected_epoch: local.committee.epoch,
+                actual_epoch: signed_summary.epoch(),
+            }
+        );
+        fp_ensure!(
+            *proposal.sequence_number() == local.next_sequence_number,
+            SuiError::from(
+                format!(
+                    "Proposal for checkpoint {} while constructing checkpoint {}",
+                    proposal.sequence_number(),
+                    local.next_sequence_number
+                )
+                .as_str()
+            )
+        );
+        let size = proposal.transactions.transactions.len();
+        fp_ensure!(
+            size <= self.policy.max_transactions,
+            SuiError::from(
+                format!(
+                    "Proposal has {} transactions, more than the limit of {}",
+                    size, self.policy.max_transactions
+                )
+                .as_str()
+            )
+        );
+        if self.policy.reject_known_invalid {
+            if let Some(invalid) = proposal
+                .transactions()
+                .find(|digests| local.known_invalid.contains(digests))
+            {
+                return Err(SuiError::from(
+                    format!("Proposal includes invalid transaction {:?}", invalid).as_str(),
+                ));
+            }
+        }
+        signed_summary.verify_with_contents(local.committee, Some(&proposal.transactions))
+    }
+}
+
+/// How long a validator waits for the proposals of a checkpoint before building fragments
+/// from the ones it received. Times are measured from the moment the validator starts
+/// waiting for the proposals of the sequence number, and the deadline only depends on when
+/// each proposal arrived, so that validators seeing the same arrivals stop at the same time.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalTimeoutSchedule {
+    /// The wait after the first proposal, when no coverage threshold is reached.
+    pub base_delay: Duration,
+    /// Shorter waits once enough stake proposed: the share of the total stake, in basis
+    /// points, and the wait after the proposal reaching it.
+    pub coverage_thresholds: Vec<(u64, Duration)>,
+    /// The longest wait, whether proposals arrived or not.
+    pub max_wait: Duration,
+}
+
+impl Default for ProposalTimeoutSchedule {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_millis(500),
+            coverage_thresholds: vec![
+                (6_667, Duration::from_millis(100)),
+                (10_000, Duration::ZERO),
+            ],
+            max_wait: Duration::from_secs(2),
+        }
+    }
+}
+
+/// What ended the wait for proposals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProposalTimeoutTrigger {
+    MaxWait,
+    BaseDelay,
+    Coverage { basis_points: u64 },
+}
+
+/// When to stop waiting for proposals, and the proposals received by then.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProposalTimeout {
+    pub deadline: Duration,
+    pub trigger: ProposalTimeoutTrigger,
+    /// The authorities whose proposals arrived by the deadline, in order of arrival.
+    pub included: Vec<AuthorityName>,
+    pub included_stake: StakeUnit,
+}
+
+impl ProposalTimeoutSchedule {
+    /// Compute the deadline from the arrival times of proposals. Only the first proposal of
+    /// each member of `committee` counts, and proposals arriving at the same time are taken
+    /// in authority order, so the result does not depend on the order of `arrivals`.
+    pub fn evaluate(
+        &self,
+        committee: &Committee,
+        arrivals: &[(AuthorityName, Duration)],
+    ) -> ProposalTimeout {
+        let mut arrivals: Vec<_> = arrivals
+            .iter()
+            .filter(|(authority, _)| committee.weight(authority) > 0)
+            .map(|(authority, at)| (*at, *authority))
+            .collect();
+        arrivals.sort();
+
+        let mut timeout = ProposalTimeout {
+            deadline: self.max_wait,
+            trigger: ProposalTimeoutTrigger::MaxWait,
+            included: Vec::new(),
+            included_stake: 0,
+        };
+        let shorten = |timeout: &mut ProposalTimeout, deadline, trigger| {
+            if deadline < timeout.deadline {
+                timeout.deadline = deadline;
+                timeout.trigger = trigger;
+            }
+        };
+        let mut seen = HashSet::new();
+        for (at, authority) in arrivals {
+            // The deadline never falls below the arrival that set it, so no later proposal
+            // can arrive in time once one is late.
+            if at > timeout.deadline {
+                break;
+            }
+            if !seen.insert(authority) {
+                continue;
+            }
+            if timeout.included.is_empty() {
+                shorten(
+                    &mut timeout,
+                    at + self.base_delay,
+                    ProposalTimeoutTrigger::BaseDelay,
+                );
+            }
+            timeout.included.push(authority);
+            timeout.included_stake += committee.weight(&authority);
+            for (basis_points, delay) in &self.coverage_thresholds {
+                let covered = u128::from(timeout.included_stake) * 10_000
+                    >= u128::from(*basis_points) * u128::from(committee.total_votes);
+                if covered {
+                    shorten(
+                        &mut timeout,
+                        at + *delay,
+                        ProposalTimeoutTrigger::Coverage {
+                            basis_points: *basis_points,
+                        },
+                    );
+                }
+            }
+        }
+        timeout
+    }
+
+    /// Whether to stop waiting at `now`, given the proposals that arrived so far.
+    pub fn is_expired(
+        &self,
+        committee: &Committee,
+        arrivals: &[(AuthorityName, Duration)],
+        now: Duration,
+    ) -> bool {
+        now >= self.evaluate(committee, arrivals).deadline
+    }
+}
+
+/// A commit of the consensus DAG, with the transactions it sequenced in consensus order.
+#[derive(Clone, Debug)]
+pub struct OrderedConsensusCommit {
+    pub commit: ConsensusCommitRef,
+    pub transactions: Vec<ExecutionDigests>,
+    /// The gas costs of the transactions of the commit.
+    pub gas_cost_summary: GasCostSummary,
+}
+
+/// Builds checkpoints directly from ordered consensus output: since every validator sees the
+/// same commits in the same order, no proposals or fragments are needed. Checkpoints are only
+/// cut at commit boundaries, so a checkpoint may exceed the target size by the size of one
+/// commit.
+///
+/// Not used by validators yet, which still build checkpoints from proposals and fragments:
+/// switching to it needs a protocol flag, so that all validators switch at the same epoch.
+pub struct ConsensusCommitCheckpointBuilder {
+    epoch: EpochId,
+    next_sequence_number: CheckpointSequenceNumber,
+    previous_digest: Option<CheckpointDigest>,
+    target_transactions: usize,
+    last_round: Option<u64>,
+    /// Transactions sequenced so far in the epoch, as consensus may sequence a transaction
+    /// more than once.
+    sequenced: HashSet<ExecutionDigests>,
+    pending: ContentsAccumulator,
+    pending_gas: GasCostSummary,
+}
+
+impl ConsensusCommitCheckpointBuilder {
+    pub fn new(
+        epoch: EpochId,
+        next_sequence_number: CheckpointSequenceNumber,
+        previous_digest: Option<CheckpointDigest>,
+        target_transactions: usize,
+    ) -> Self {
+        Self {
+            epoch,
+            next_sequence_number,
+            previous_digest,
+            target_transactions: target_transactions.max(1),
+            last_round: None,
+            sequenced: HashSet::new(),
+            pending: ContentsAccumulator::new_with_consensus_commits(),
+            pending_gas: GasCostSummary::default(),
+        }
+    }
+
+    pub fn next_sequence_number(&self) -> CheckpointSequenceNumber {
+        self.next_sequence_number
+    }
+
+    /// The number of transactions waiting for the next checkpoint.
+    pub fn pending_transactions(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// The digest the contents of the next checkpoint would have if it were cut now.
+    pub fn pending_digest(&self) -> CheckpointContentsDigest {
+        self.pending.digest()
+    }
+
+    /// Add the next commit of consensus, whose round must be higher than that of the previous
+    /// one. Returns the checkpoint it completes, if any.
+    pub fn push_commit(
+        &mut self,
+        commit: OrderedConsensusCommit,
+    ) -> SuiResult<Option<(CheckpointSummary, VersionedCheckpointContents)>> {
+        if let Some(last_round) = self.last_round {
+            fp_ensure!(
+                commit.commit.round > last_round,
+                SuiError::from(
+                    format!(
+                        "Consensus commit of round {} received after round {}",
+                        commit.commit.round, last_round
+                    )
+                    .as_str()
+                )
+            );
+        }
+        self.last_round = Some(commit.commit.round);
+        let sequenced = &mut self.sequenced;
+        self.pending.append(
+            commit
+                .transactions
+                .into_iter()
+                .filter(|digests| sequenced.insert(*digests)),
+        );
+        self.pending
+            .append_consensus_commits([commit.commit])
+            .expect("Pending contents are version 2");
+        self.pending_gas.computation_cost += commit.gas_cost_summary.computation_cost;
+        self.pending_gas.storage_cost += commit.gas_cost_summary.storage_cost;
+        self.pending_gas.storage_rebate += commit.gas_cost_summary.storage_rebate;
+
+        Ok(if self.pending.len() >= self.target_transactions {
+            Some(self.cut(None))
+        } else {
+            None
+        })
+    }
+
+    /// Cut the last checkpoint of the epoch from whatever is pending, even if nothing is.
+    pub fn finish_epoch(
+        &mut self,
+        next_epoch_committee: Committee,
+    ) -> (CheckpointSummary, VersionedCheckpointContents) {
+        let checkpoint = self.cut(Some(next_epoch_committee));
+        self.epoch += 1;
+        // Consensus rounds start over with the committee of the new epoch.
+        self.last_round = None;
+        self.sequenced.clear();
+        checkpoint
+    }
+
+    fn cut(
+        &mut self,
+        next_epoch_committee: Option<Committee>,
+    ) -> (CheckpointSummary, VersionedCheckpointContents) {
+        let contents = std::mem::replace(
+            &mut self.pending,
+            ContentsAccumulator::new_with_consensus_commits(),
+        )
+        .finalize();
+        let mut summary = CheckpointSummary::new(
+            self.epoch,
+            self.next_sequence_number,
+            contents.contents(),
+            self.previous_digest,
+            std::mem::take(&mut self.pending_gas),
+            next_epoch_committee,
+        );
+        // Commit to the version 2 contents, consensus commits included.
+        summary.content_digest = contents.digest();
+        self.next_sequence_number += 1;
+        self.previous_digest = Some(summary.digest());
+        (summary, contents)
+    }
+}
+
+/// Two different proposals signed by the same authority for the same sequence number, which
+/// anyone can check against the committee of their epoch.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProposalConflict {
+    pub first: SignedCheckpointProposalSummary,
+    pub second: SignedCheckpointProposalSummary,
+}
+
+impl ProposalConflict {
+    pub fn authority(&self) -> &AuthorityName {
+        self.first.authority()
+    }
+
+    pub fn sequence_number(&self) -> CheckpointSequenceNumber {
+        self.first.data().sequence_number
+    }
+
+    /// Check that this is evidence of equivocation by a member of `committee`.
+    pub fn verify(&self, committee: &Committee) -> SuiResult {
+        fp_ensure!(
+            self.first.authority() == self.second.authority()
+                && self.first.data().sequence_number == self.second.data().sequence_number,
+            SuiError::from("Conflicting proposals are for different slots")
+        );
+        fp_ensure!(
+            self.first.data() != self.second.data(),
+            SuiError::from("Conflicting proposals are identical")
+        );
+        self.first.verify_signature(committee)?;
+        self.second.verify_signature(committee)
+    }
+}
+
+impl From<ProposalConflict> for SuiError {
+    fn from(conflict: ProposalConflict) -> Self {
+        SuiError::from(
+            format!(
+                "Authority {:?} made conflicting proposals for checkpoint {}",
+                conflict.authority(),
+                conflict.sequence_number()
+            )
+            .as_str(),
+        )
+    }
+}
+
+/// A proposal stored in a `ProposalSlotMap`.
+pub trait SlotProposal {
+    fn signed_summary(&self) -> &SignedCheckpointProposalSummary;
+}
+
+impl SlotProposal for CheckpointProposal {
+    fn signed_summary(&self) -> &SignedCheckpointProposalSummary {
+        &self.signed_summary
+    }
+}
+
+impl SlotProposal for SignedCheckpointProposalSummary {
+    fn signed_summary(&self) -> &SignedCheckpointProposalSummary {
+        self
+    }
+}
+
+/// Proposals keyed by (sequence number, authority), with at most one proposal per slot. A
+/// different proposal for a taken slot is refused, and kept along with the accepted one as
+/// evidence of equivocation until taken with `take_conflicts`, or until the slot is removed.
+/// One piece of evidence per slot is enough, so that an equivocating authority cannot grow
+/// the map beyond its slots.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProposalSlotMap<P> {
+    slots: BTreeMap<CheckpointSequenceNumber, BTreeMap<AuthorityName, P>>,
+    /// Sorted by slot, at most one per slot.
+    conflicts: Vec<ProposalConflict>,
+}
+
+impl<P> Default for ProposalSlotMap<P> {
+    fn default() -> Self {
+        Self {
+            slots: BTreeMap::new(),
+            conflicts: Vec::new(),
+        }
+    }
+}
+
+impl<P: SlotProposal> ProposalSlotMap<P> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept a proposal for a free slot, returning true, or ignore a copy of the accepted
+    /// proposal, returning false. A conflicting proposal is refused with the evidence.
+    pub fn insert(&mut self, proposal: P) -> Result<bool, ProposalConflict> {
+        let signed = proposal.signed_summary();
+        let slot = self.slots.entry(signed.data().sequence_number).or_default();
+        match slot.get(signed.authority()) {
+            None => {
+                slot.insert(*signed.authority(), proposal);
+                Ok(true)
+            }
+            Some(accepted) if accepted.signed_summary().data() == signed.data() => Ok(false),
+            Some(accepted) => {
+                let conflict = ProposalConflict {
+                    first: accepted.signed_summary().clone(),
+                    second: signed.clone(),
+                };
+                self.add_conflict(conflict.clone());
+                Err(conflict)
+            }
+        }
+    }
+
+    /// Keep `conflict` as the evidence for its slot, unless the slot already has some.
+    fn add_conflict(&mut self, conflict: ProposalConflict) {
+        let slot = |c: &ProposalConflict| (c.sequence_number(), *c.authority());
+        if let Err(index) = self.conflicts.binary_search_by_key(&slot(&conflict), slot) {
+            self.conflicts.insert(index, conflict);
+        }
+    }
+
+    pub fn get(&self, seq: CheckpointSequenceNumber, authority: &AuthorityName) -> Option<&P> {
+        self.slots.get(&seq)?.get(authority)
+    }
+
+    /// The proposals accepted at `seq`, by authority.
+    pub fn at(&self, seq: CheckpointSequenceNumber) -> impl Iterator<Item = (&AuthorityName, &P)> {
+        self.slots.get(&seq).into_iter().flatten()
+    }
+
+    /// All the proposals accepted, by increasing sequence number.
+    pub fn iter(&self) -> impl Iterator<Item = (CheckpointSequenceNumber, &AuthorityName, &P)> {
+        self.slots
+            .iter()
+            .flat_map(|(seq, slot)| slot.iter().map(move |(name, p)| (*seq, name, p)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// The lowest and highest sequence numbers with accepted proposals.
+    pub fn sequence_range(&self) -> Option<(CheckpointSequenceNumber, CheckpointSequenceNumber)> {
+        Some((*self.slots.keys().next()?, *self.slots.keys().next_back()?))
+    }
+
+    /// Remove the proposals below `seq`, returning them, and the evidence of conflicts for
+    /// their slots.
+    pub fn remove_below(&mut self, seq: CheckpointSequenceNumber) -> Vec<P> {
+        let removed = self
+            .conflicts
+            .partition_point(|c| c.sequence_number() < seq);
+        self.conflicts.drain(..removed);
+        let kept = self.slots.split_off(&seq);
+        std::mem::replace(&mut self.slots, kept)
+            .into_values()
+            .flat_map(|slot| slot.into_values())
+            .collect()
+    }
+
+    pub fn conflicts(&self) -> &[ProposalConflict] {
+        &self.conflicts
+    }
+
+    pub fn take_conflicts(&mut self) -> Vec<ProposalConflict> {
+        std::mem::take(&mut self.conflicts)
+    }
+}
+
+/// The proposals of every authority for the last `retention` sequence numbers, so that
+/// operators can audit what was proposed at a given height and which of the proposed
+/// transactions the final checkpoint left out. Serializable, to be persisted across restarts.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProposalHistory {
+    retention: u64,
+    proposals: ProposalSlotMap<CheckpointProposal>,
+}
+
+impl ProposalHistory {
+    pub fn new(retention: u64) -> Self {
+        Self {
+            retention: retention.max(1),
+            proposals: ProposalSlotMap::new(),
+        }
+    }
+
+    pub fn retention(&self) -> u64 {
+        self.retention
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proposals.is_empty()
+    }
+
+    /// The range of sequence numbers currently retained.
+    pub fn retained_range(&self) -> Option<(CheckpointSequenceNumber, CheckpointSequenceNumber)> {
+        self.proposals.sequence_range()
+    }
+
+    /// Record a proposal, and forget the sequence numbers falling out of the retention window.
+    /// Proposals already outside of the window are ignored. A proposal conflicting with the
+    /// one recorded for the same authority and sequence number is refused, and kept as
+    /// evidence, see `conflicts`.
+    pub fn record(&mut self, proposal: CheckpointProposal) -> SuiResult {
+        fp_ensure!(
+            proposal.signed_summary.data().content_digest == proposal.transactions.digest(),
+            SuiError::from("Proposal contents don't match the proposal summary")
+        );
+        let seq = *proposal.sequence_number();
+        let latest = self
+            .proposals
+            .sequence_range()
+            .map_or(seq, |(_, l)| seq.max(l));
+        let oldest_retained = (latest + 1).saturating_sub(self.retention);
+        if seq < oldest_retained {
+            return Ok(());
+        }
+        self.proposals.insert(proposal)?;
+        self.proposals.remove_below(oldest_retained);
+        Ok(())
+    }
+
+    pub fn get(
+        &self,
+        seq: CheckpointSequenceNumber,
+        authority: &AuthorityName,
+    ) -> Option<&CheckpointProposal> {
+        self.proposals.get(seq, authority)
+    }
+
+    /// Evidence of the conflicting proposals refused by `record`.
+    pub fn conflicts(&self) -> &[ProposalConflict] {
+        self.proposals.conflicts()
+    }
+
+    /// The proposal summaries of all authorities at `seq`.
+    pub fn by_sequence_number(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> impl Iterator<Item = (&AuthorityName, &SignedCheckpointProposalSummary)> {
+        self.proposals
+            .at(seq)
+            .map(|(name, proposal)| (name, &proposal.signed_summary))
+    }
+
+    /// The proposal summaries of `authority`, by increasing sequence number.
+    pub fn by_authority<'a>(
+        &'a self,
+        authority: &'a AuthorityName,
+    ) -> impl Iterator<
+        Item = (
+            CheckpointSequenceNumber,
+            &'a SignedCheckpointProposalSummary,
+        ),
+    > + 'a {
+        self.proposals
+            .iter()
+            .filter(move |(_, name, _)| *name == authority)
+            .map(|(seq, _, proposal)| (seq, &proposal.signed_summary))
+    }
+
+    /// The transactions `authority` proposed at `seq` that are not in `contents`, the final
+    /// contents of that checkpoint. None if the proposal is not retained.
+    pub fn dropped_transactions(
+        &self,
+        seq: CheckpointSequenceNumber,
+        authority: &AuthorityName,
+        contents: &CheckpointContents,
+    ) -> Option<Vec<ExecutionDigests>> {
+        let proposal = self.get(seq, authority)?;
+        let included: HashSet<_> = contents.iter().collect();
+        Some(
+            proposal
+                .transactions()
+                .filter(|digests| !included.contains(digests))
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+/// The bytes released by `CheckpointConstructionState::prune_below`, by kind of state.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrunedBytes {
+    pub proposals: u64,
+    pub fragments: u64,
+    pub partial_fragments: u64,
+    pub signatures: u64,
+}
+
+impl PrunedBytes {
+    pub fn total(&self) -> u64 {
+        self.proposals + self.fragments + self.partial_fragments + self.signatures
+    }
+}
+
+/// The format of `StateDump` produced by this version of the code.
+pub const STATE_DUMP_VERSION: u32 = 1;
+
+/// Versions of the fragment between two proposals reassembled at once for a sequence number,
+/// so that headers relayed by consensus cannot grow the state until the next `prune_below`.
+pub const MAX_PARTIAL_FRAGMENT_VERSIONS: usize = 2;
+
+/// The construction state of one sequence number, exported from a node with
+/// `CheckpointConstructionState::export` so that it can be imported and replayed elsewhere,
+/// e.g. to reproduce a node stuck on a checkpoint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StateDump {
+    pub version: u32,
+    /// None if there was no state to export.
+    pub sequence_number: Option<CheckpointSequenceNumber>,
+    pub proposals: Vec<CheckpointProposal>,
+    pub proposal_conflicts: Vec<ProposalConflict>,
+    pub fragments: Vec<CheckpointFragment>,
+    pub partial_fragments: Vec<PartialCheckpointFragment>,
+    pub signatures: Vec<SignedCheckpointSummary>,
+}
+
+/// The in-memory state kept while checkpoints are under construction: the proposals
+/// received, the fragments between them, fragments still being reassembled from chunks, and
+/// the signatures waiting to be aggregated, all by sequence number.
+#[derive(Default)]
+pub struct CheckpointConstructionState {
+    proposals: ProposalSlotMap<CheckpointProposal>,
+    fragments: BTreeMap<
+        CheckpointSequenceNumber,
+        BTreeMap<(AuthorityName, AuthorityName), CheckpointFragment>,
+    >,
+    partial_fragments: BTreeMap<
+        CheckpointSequenceNumber,
+        HashMap<FragmentHeaderDigest, PartialCheckpointFragment>,
+    >,
+    signatures: BTreeMap<CheckpointSequenceNumber, Vec<SignedCheckpointSummary>>,
+    limits: DecodeLimits,
+}
+
+impl CheckpointConstructionState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check fragment messages against the limits of the network, instead of the defaults.
+    pub fn set_limits(&mut self, limits: DecodeLimits) {
+        self.limits = limits;
+    }
+
+    /// The limits fragment messages are checked against, and fragments should be split with.
+    pub fn limits(&self) -> &DecodeLimits {
+        &self.limits
+    }
+
+    /// Add a proposal, returning false if it was already added. A proposal conflicting with
+    /// the one added for the same authority and sequence number is refused.
+    pub fn add_proposal(&mut self, proposal: CheckpointProposal) -> Result<bool, ProposalConflict> {
+        self.proposals.insert(proposal)
+    }
+
+    pub fn proposals(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> impl Iterator<Item = &CheckpointProposal> {
+        self.proposals.at(seq).map(|(_, proposal)| proposal)
+    }
+
+    /// Evidence of the conflicting proposals refused by `add_proposal`, which is kept until
+    /// taken.
+    pub fn take_proposal_conflicts(&mut self) -> Vec<ProposalConflict> {
+        self.proposals.take_conflicts()
+    }
+
+    /// Add a fragment message, returning the fragment it completes if any. Complete
+    /// fragments are also kept, see `fragments`.
+    pub fn add_fragment_message(
+        &mut self,
+        message: CheckpointFragmentMessage,
+    ) -> SuiResult<Option<CheckpointFragment>> {
+        message.validate_with(&self.limits)?;
+        let seq = message.proposer_sequence_number();
+        let partials = self.partial_fragments.entry(seq).or_default();
+        let header_digest = match message {
+            CheckpointFragmentMessage::Header(header) => {
+                let header_digest = header.digest();
+                if !partials.contains_key(&header_digest) {
+                    let (proposer, other) = (header.proposer.authority(), header.other.authority());
+                    let versions = partials
+                        .values()
+                        .filter(|p| {
+                            p.proposer.authority() == proposer && p.other.authority() == other
+                        })
+                        .count();
+                    fp_ensure!(
+                        versions < MAX_PARTIAL_FRAGMENT_VERSIONS,
+                        SuiError::from(
+                            format!(
+                                "Too many versions of the fragment of {proposer} with {other} at checkpoint {seq}"
+                            )
+                            .as_str()
+                        )
+                    );
+                    partials.insert(header_digest, PartialCheckpointFragment::new(*header));
+                }
+                header_digest
+            }
+            CheckpointFragmentMessage::Chunk(chunk) => {
+                let header_digest = chunk.header_digest;
+                partials
+                    .get_mut(&header_digest)
+                    .ok_or_else(|| SuiError::from("Fragment chunk received before its header"))?
+                    .add_chunk(*chunk)?;
+                header_digest
+            }
+        };
+        if !partials[&header_digest].is_complete() {
+            return Ok(None);
+        }
+        let fragment = partials.remove(&header_digest).unwrap().to_fragment()?;
+        self.fragments.entry(seq).or_default().insert(
+            (*fragment.proposer.authority(), *fragment.other.authority()),
+            fragment.clone(),
+        );
+        Ok(Some(fragment))
+    }
+
+    pub fn fragments(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> impl Iterator<Item = &CheckpointFragment> {
+        self.fragments
+            .get(&seq)
+            .into_iter()
+            .flat_map(|f| f.values())
+    }
+
+    /// Add the signature of an authority, returning false if it already signed the checkpoint.
+    pub fn add_signature(&mut self, signed: SignedCheckpointSummary) -> bool {
+        let signatures = self
+            .signatures
+            .entry(signed.data().sequence_number)
+            .or_default();
+        if signatures
+            .iter()
+            .any(|s| s.authority() == signed.authority())
+        {
+            return false;
+        }
+        signatures.push(signed);
+        true
+    }
+
+    pub fn signatures(&self, seq: CheckpointSequenceNumber) -> &[SignedCheckpointSummary] {
+        self.signatures.get(&seq).map_or(&[], |s| &s[..])
+    }
+
+    /// The lowest sequence number with some state, which is the checkpoint under construction
+    /// since the state of certified checkpoints is pruned. State for higher sequence numbers
+    /// is from authorities running ahead.
+    pub fn active_sequence_number(&self) -> Option<CheckpointSequenceNumber> {
+        [
+            self.proposals.sequence_range().map(|(low, _)| low),
+            self.fragments.keys().next().copied(),
+            self.partial_fragments.keys().next().copied(),
+            self.signatures.keys().next().copied(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    /// Capture the state of the active sequence number, see `active_sequence_number`.
+    pub fn export(&self) -> StateDump {
+        let seq = self.active_sequence_number();
+        let at = |s: &CheckpointSequenceNumber| Some(*s) == seq;
+        StateDump {
+            version: STATE_DUMP_VERSION,
+            sequence_number: seq,
+            proposals: seq
+                .into_iter()
+                .flat_map(|seq| self.proposals(seq).cloned())
+                .collect(),
+            proposal_conflicts: self
+                .proposals
+                .conflicts()
+                .iter()
+                .filter(|c| at(&c.sequence_number()))
+                .cloned()
+                .collect(),
+            fragments: seq
+                .into_iter()
+                .flat_map(|seq| self.fragments(seq).cloned())
+                .collect(),
+            partial_fragments: self
+                .partial_fragments
+                .iter()
+                .filter(|(s, _)| at(s))
+                .flat_map(|(_, p)| p.values().cloned())
+                .collect(),
+            signatures: seq
+                .into_iter()
+                .flat_map(|seq| self.signatures(seq).iter().cloned())
+                .collect(),
+        }
+    }
+
+    /// Rebuild the state captured by `export`. Signatures are not checked, as the committee
+    /// of the dump may not be known where it is replayed, but every entry must be for the
+    /// sequence number of the dump.
+    pub fn import(dump: StateDump) -> SuiResult<Self> {
+        fp_ensure!(
+            dump.version == STATE_DUMP_VERSION,
+            SuiError::from(format!("Unsupported state dump version {}", dump.version).as_str())
+        );
+        let mut state = Self::new();
+        let seq = match dump.sequence_number {
+            Some(seq) => seq,
+            None => {
+                fp_ensure!(
+                    dump.proposals.is_empty()
+                        && dump.proposal_conflicts.is_empty()
+                        && dump.fragments.is_empty()
+                        && dump.partial_fragments.is_empty()
+                        && dump.signatures.is_empty(),
+                    SuiError::from("State dump without sequence number holds state")
+                );
+                return Ok(state);
+            }
+        };
+        let check = |entry_seq: CheckpointSequenceNumber| -> SuiResult {
+            fp_ensure!(
+                entry_seq == seq,
+                SuiError::from(
+                    format!("State dump for checkpoint {seq} holds state for {entry_seq}").as_str()
+                )
+            );
+            Ok(())
+        };
+
+        for proposal in dump.proposals {
+            check(*proposal.sequence_number())?;
+            state.add_proposal(proposal)?;
+        }
+        for conflict in dump.proposal_conflicts {
+            check(conflict.sequence_number())?;
+            state.proposals.add_conflict(conflict);
+        }
+        for fragment in dump.fragments {
+            check(fragment.proposer.data().sequence_number)?;
+            state.fragments.entry(seq).or_default().insert(
+                (*fragment.proposer.authority(), *fragment.other.authority()),
+                fragment,
+            );
+        }
+        for partial in dump.partial_fragments {
+            check(partial.proposer.data().sequence_number)?;
+            state
+                .partial_fragments
+                .entry(seq)
+                .or_default()
+                .insert(partial.header_digest, partial);
+        }
+        for signed in dump.signatures {
+            check(signed.data().sequence_number)?;
+            state.add_signature(signed);
+        }
+        Ok(state)
+    }
+
+    /// Drop all the state of the sequence numbers up to and including `certified_seq`, which
+    /// is no longer needed once that checkpoint is certified.
+    pub fn prune_below(&mut self, certified_seq: CheckpointSequenceNumber) -> PrunedBytes {
+        fn split<V>(
+            map: &mut BTreeMap<CheckpointSequenceNumber, V>,
+            certified_seq: CheckpointSequenceNumber,
+        ) -> BTreeMap<CheckpointSequenceNumber, V> {
+            let kept = match certified_seq.checked_add(1) {
+                Some(seq) => map.split_off(&seq),
+                None => BTreeMap::new(),
+            };
+            std::mem::replace(map, kept)
+        }
+        PrunedBytes {
+            proposals: split(&mut self.proposals.slots, certified_seq)
+                .values()
+                .flat_map(|slot| slot.values())
+                .map(|p| bincode::serialized_size(p).unwrap())
+                .sum(),
+            fragments: split(&mut self.fragments, certified_seq)
+                .values()
+                .flat_map(|f| f.values())
+                .map(|f| bincode::serialized_size(f).unwrap())
+                .sum(),
+            partial_fragments: split(&mut self.partial_fragments, certified_seq)
+                .values()
+                .flat_map(|p| p.values())
+                .map(|p| {
+                    bincode::serialized_size(&p.proposer).unwrap()
+                        + bincode::serialized_size(&p.other).unwrap()
+                        + p.progress().bytes
+                })
+                .sum(),
+            signatures: split(&mut self.signatures, certified_seq)
+                .values()
+                .flatten()
+                .map(|s| bincode::serialized_size(s).unwrap())
+                .sum(),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The contract between checkpoint sync and the executors: what is handed over for execution,
+//! and what execution must report back.
+
+use std::time::Duration;
+
+use crate::base_types::TransactionEffectsDigest;
+use crate::committee::Committee;
+use crate::error::{SuiError, SuiResult};
+use crate::gas::GasCostSummary;
+use crate::messages::TransactionEffects;
+use crate::messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointContents, CheckpointSequenceNumber,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_execution_tests.rs"]
+mod checkpoint_execution_tests;
+
+/// A certified checkpoint and its contents, checked against each other. It can only be built
+/// through `new`, so executors never receive unverified checkpoints.
+#[derive(Clone, Debug)]
+pub struct CheckpointExecutionRequest {
+    verified_summary: CertifiedCheckpointSummary,
+    verified_contents: CheckpointContents,
+}
+
+impl CheckpointExecutionRequest {
+    pub fn new(
+        summary: CertifiedCheckpointSummary,
+        contents: CheckpointContents,
+        committee: &Committee,
+    ) -> SuiResult<Self> {
+        summary.verify(committee, Some(&contents))?;
+        Ok(Self {
+            verified_summary: summary,
+            verified_contents: contents,
+        })
+    }
+
+    pub fn sequence_number(&self) -> CheckpointSequenceNumber {
+        self.verified_summary.data().sequence_number
+    }
+
+    pub fn summary(&self) -> &CertifiedCheckpointSummary {
+        &self.verified_summary
+    }
+
+    pub fn contents(&self) -> &CheckpointContents {
+        &self.verified_contents
+    }
+
+    pub fn into_parts(self) -> (CertifiedCheckpointSummary, CheckpointContents) {
+        (self.verified_summary, self.verified_contents)
+    }
+
+    /// Check the invariants binding an execution result to this checkpoint: the effects are
+    /// the ones committed to by the checkpoint, in order, and the gas used is the one in the
+    /// summary.
+    pub fn check_result(&self, result: &CheckpointExecutionResult) -> SuiResult {
+        fp_ensure!(
+            result.sequence_number == self.sequence_number(),
+            SuiError::from(
+                format!(
+                    "Execution result of checkpoint {} reported for checkpoint {}",
+                    result.sequence_number,
+                    self.sequence_number()
+                )
+                .as_str()
+            )
+        );
+        fp_ensure!(
+            result.effects_digests.len() == self.verified_contents.size(),
+            SuiError::from(
+                format!(
+                    "Checkpoint {} has {} transactions, execution produced {} effects",
+                    self.sequence_number(),
+                    self.verified_contents.size(),
+                    result.effects_digests.len()
+                )
+                .as_str()
+            )
+        );
+        if let Some((expected, actual)) = self
+            .verified_contents
+            .iter()
+            .zip(&result.effects_digests)
+            .find(|(expected, actual)| expected.effects != **actual)
+        {
+            return Err(SuiError::from(
+                format!(
+                    "Execution of transaction {:?} produced effects {:?}, checkpoint has {:?}",
+                    expected.transaction, actual, expected.effects
+                )
+                .as_str(),
+            ));
+        }
+        let expected_gas = &self.verified_summary.data().gas_cost_summary;
+        fp_ensure!(
+            result.gas_used == *expected_gas,
+            SuiError::from(
+                format!(
+                    "Execution used {:?}, checkpoint summary has {:?}",
+                    result.gas_used, expected_gas
+                )
+                .as_str()
+            )
+        );
+        Ok(())
+    }
+}
+
+/// What executing a checkpoint produced.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckpointExecutionResult {
+    pub sequence_number: CheckpointSequenceNumber,
+    /// The effects of the transactions, in checkpoint order.
+    pub effects_digests: Vec<TransactionEffectsDigest>,
+    pub gas_used: GasCostSummary,
+    pub duration: Duration,
+}
+
+impl CheckpointExecutionResult {
+    /// Summarize the effects of the transactions of a checkpoint, in checkpoint order.
+    pub fn from_effects<'a>(
+        sequence_number: CheckpointSequenceNumber,
+        effects: impl Iterator<Item = &'a TransactionEffects> + Clone,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            sequence_number,
+            effects_digests: effects.clone().map(|effects| effects.digest()).collect(),
+            gas_used: GasCostSummary::new_from_txn_effects(effects),
+            duration,
+        }
+    }
+}
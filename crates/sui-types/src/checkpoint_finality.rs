@@ -0,0 +1,96 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Self-contained proofs that a transaction is final, for applications that do not follow
+//! the checkpoint chain themselves.
+
+use serde::{Deserialize, Serialize};
+
+use crate::committee::Committee;
+use crate::crypto::AuthoritySignInfoTrait;
+use crate::error::{SuiError, SuiResult};
+use crate::messages::{CertifiedTransaction, CertifiedTransactionEffects};
+use crate::messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointContents, CheckpointSummary,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_finality_tests.rs"]
+mod checkpoint_finality_tests;
+
+/// What the verifier of a `TransactionFinalityProof` trusts out of band.
+#[derive(Clone, Copy, Debug)]
+pub enum FinalityTrustRoot<'a> {
+    /// The committee of an epoch, e.g. the genesis committee.
+    Committee(&'a Committee),
+    /// The last checkpoint of an epoch, e.g. a waypoint. The committee of the next epoch is
+    /// the one it carries.
+    Checkpoint(&'a CheckpointSummary),
+}
+
+impl FinalityTrustRoot<'_> {
+    fn committee(&self) -> SuiResult<Committee> {
+        match self {
+            FinalityTrustRoot::Committee(committee) => Ok((*committee).clone()),
+            FinalityTrustRoot::Checkpoint(summary) => summary.next_committee(),
+        }
+    }
+}
+
+/// Everything needed to prove that a transaction is final: the transaction and effects
+/// certificates, the contents of the checkpoint including them, the certified checkpoint, and
+/// the certified last checkpoints of the epochs between the trust root and that checkpoint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransactionFinalityProof {
+    pub certificate: CertifiedTransaction,
+    pub effects: CertifiedTransactionEffects,
+    pub contents: CheckpointContents,
+    pub checkpoint: CertifiedCheckpointSummary,
+    /// The last checkpoint of each epoch from the epoch of the trust root to the epoch before
+    /// the one of `checkpoint`, in order.
+    pub epoch_change_proofs: Vec<CertifiedCheckpointSummary>,
+}
+
+impl TransactionFinalityProof {
+    /// Check the whole chain, from the trust root to the transaction.
+    pub fn verify(&self, root: FinalityTrustRoot<'_>) -> SuiResult {
+        let committee = CertifiedCheckpointSummary::verify_epoch_changes(
+            root.committee()?,
+            &self.epoch_change_proofs,
+        )?;
+
+        self.checkpoint.verify(&committee, Some(&self.contents))?;
+
+        let transaction = *self.certificate.digest();
+        fp_ensure!(
+            self.effects.effects.transaction_digest == transaction,
+            SuiError::from(
+                format!(
+                    "Effects are for transaction {:?}, not {:?}",
+                    self.effects.effects.transaction_digest, transaction
+                )
+                .as_str()
+            )
+        );
+        fp_ensure!(
+            self.contents
+                .iter()
+                .any(|d| d.transaction == transaction && d.effects == *self.effects.digest()),
+            SuiError::from(
+                format!(
+                    "Transaction {:?} with effects {:?} is not in checkpoint {}",
+                    transaction,
+                    self.effects.digest(),
+                    self.checkpoint.data().sequence_number
+                )
+                .as_str()
+            )
+        );
+
+        // Transactions are certified and executed in the epoch of their checkpoint.
+        self.certificate.verify_signature(&committee)?;
+        self.effects
+            .auth_signature
+            .verify(&self.effects.effects, &committee)
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Ingestion of verified checkpoints by indexers: checkpoints are fed to an `IndexerSink` in
+//! sequence, each exactly once across restarts as long as the sink commits the cursor it is
+//! given along with the indexed data.
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::ExecutionDigests;
+use crate::checkpoint_execution::CheckpointExecutionRequest;
+use crate::error::{SuiError, SuiResult};
+use crate::messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointDigest, CheckpointSequenceNumber,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_indexer_tests.rs"]
+mod checkpoint_indexer_tests;
+
+/// Where ingestion resumes: the next checkpoint to ingest, and the digest of the last one
+/// ingested, which the next checkpoint must link to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexerCursor {
+    pub next_sequence_number: CheckpointSequenceNumber,
+    /// None when starting from genesis, or from a checkpoint whose predecessor is unknown.
+    pub previous_digest: Option<CheckpointDigest>,
+}
+
+impl IndexerCursor {
+    /// Start ingesting at checkpoint `seq`, without checking what it links to.
+    pub fn start_at(seq: CheckpointSequenceNumber) -> Self {
+        Self {
+            next_sequence_number: seq,
+            previous_digest: None,
+        }
+    }
+}
+
+/// The receiving end of checkpoint ingestion. The calls for a checkpoint form a transaction:
+/// `begin_checkpoint`, `transaction` for each transaction in checkpoint order, then either
+/// `end_checkpoint`, which must commit the indexed data and the cursor atomically, or
+/// `abort_checkpoint`, which must discard everything since `begin_checkpoint`.
+pub trait IndexerSink {
+    fn begin_checkpoint(&mut self, summary: &CertifiedCheckpointSummary) -> SuiResult;
+
+    fn transaction(&mut self, digests: &ExecutionDigests, position: usize) -> SuiResult;
+
+    /// Commit the checkpoint, with the cursor to resume from after it.
+    fn end_checkpoint(
+        &mut self,
+        summary: &CertifiedCheckpointSummary,
+        cursor: IndexerCursor,
+    ) -> SuiResult;
+
+    fn abort_checkpoint(&mut self);
+}
+
+/// Feeds verified checkpoints to a sink, in sequence and exactly once.
+pub struct IndexerDriver<S> {
+    sink: S,
+    cursor: IndexerCursor,
+}
+
+impl<S: IndexerSink> IndexerDriver<S> {
+    /// Resume ingestion into `sink` from `cursor`, the last one committed by the sink.
+    pub fn new(sink: S, cursor: IndexerCursor) -> Self {
+        Self { sink, cursor }
+    }
+
+    pub fn cursor(&self) -> IndexerCursor {
+        self.cursor
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+
+    /// Feed a checkpoint to the sink, returning false if it was already ingested. A
+    /// checkpoint past the cursor, or not linking to the last one ingested, is refused. If the
+    /// sink fails, the checkpoint is aborted and the cursor stays put, so that it can be
+    /// ingested again.
+    pub fn ingest(&mut self, checkpoint: &CheckpointExecutionRequest) -> SuiResult<bool> {
+        let summary = checkpoint.summary();
+        let seq = summary.data().sequence_number;
+        if seq < self.cursor.next_sequence_number {
+            return Ok(false);
+        }
+        fp_ensure!(
+            seq == self.cursor.next_sequence_number,
+            SuiError::from(
+                format!(
+                    "Checkpoint {seq} ingested before checkpoint {}",
+                    self.cursor.next_sequence_number
+                )
+                .as_str()
+            )
+        );
+        if let Some(previous_digest) = self.cursor.previous_digest {
+            fp_ensure!(
+                summary.data().previous_digest == Some(previous_digest),
+                SuiError::from(
+                    format!("Checkpoint {seq} does not link to the last checkpoint ingested")
+                        .as_str()
+                )
+            );
+        }
+
+        let cursor = IndexerCursor {
+            next_sequence_number: seq + 1,
+            previous_digest: Some(summary.data().digest()),
+        };
+        let result = self.sink.begin_checkpoint(summary).and_then(|_| {
+            checkpoint
+                .contents()
+                .iter()
+                .enumerate()
+                .try_for_each(|(position, digests)| self.sink.transaction(digests, position))?;
+            self.sink.end_checkpoint(summary, cursor)
+        });
+        if let Err(error) = result {
+            self.sink.abort_checkpoint();
+            return Err(error);
+        }
+        self.cursor = cursor;
+        Ok(true)
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tracks when the signatures of each authority on a checkpoint are received, to help
+//! operators find the signers that consistently delay certification.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::AuthorityName;
+use crate::messages_checkpoint::{
+    CheckpointSequenceNumber, CheckpointSignatureMessage, SignedCheckpointSummary,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_latency_tests.rs"]
+mod checkpoint_latency_tests;
+
+/// Latency percentiles of the signatures received for one checkpoint. Delays are measured
+/// from the receipt of the first signature on that checkpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureLatencyReport {
+    pub sequence_number: CheckpointSequenceNumber,
+    pub signers: usize,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+    /// The authority whose signature arrived last.
+    pub slowest: AuthorityName,
+}
+
+/// Signature delays of one authority, over all the tracked checkpoints it signed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorityLatencySummary {
+    pub authority: AuthorityName,
+    pub samples: usize,
+    pub mean_ms: u64,
+    pub max_ms: u64,
+}
+
+struct CheckpointArrivals {
+    first_seen: Instant,
+    delays: BTreeMap<AuthorityName, Duration>,
+}
+
+/// Records the arrival time of signed checkpoints, per authority and sequence number.
+/// Only the `max_tracked` highest sequence numbers are kept.
+pub struct SignatureLatencyTracker {
+    max_tracked: usize,
+    arrivals: BTreeMap<CheckpointSequenceNumber, CheckpointArrivals>,
+}
+
+impl SignatureLatencyTracker {
+    pub fn new(max_tracked: usize) -> Self {
+        Self {
+            max_tracked,
+            arrivals: BTreeMap::new(),
+        }
+    }
+
+    pub fn record_signed(&mut self, signed: &SignedCheckpointSummary) {
+        self.record(signed.data().sequence_number, *signed.authority());
+    }
+
+    pub fn record_message(&mut self, message: &CheckpointSignatureMessage) {
+        self.record_signed(&message.summary);
+    }
+
+    pub fn record(&mut self, seq: CheckpointSequenceNumber, authority: AuthorityName) {
+        self.record_at(seq, authority, Instant::now());
+    }
+
+    /// Record a signature received at `now`. Only the first signature of an authority on a
+    /// checkpoint is taken into account.
+    pub fn record_at(
+        &mut self,
+        seq: CheckpointSequenceNumber,
+        authority: AuthorityName,
+        now: Instant,
+    ) {
+        if !self.arrivals.contains_key(&seq) {
+            if self.arrivals.len() >= self.max_tracked {
+                match self.arrivals.keys().next() {
+                    // Do not track checkpoints older than all the tracked ones.
+                    Some(oldest) if *oldest > seq => return,
+                    Some(oldest) => {
+                        let oldest = *oldest;
+                        self.arrivals.remove(&oldest);
+                    }
+                    None => return,
+                }
+            }
+            self.arrivals.insert(
+                seq,
+                CheckpointArrivals {
+                    first_seen: now,
+                    delays: BTreeMap::new(),
+                },
+            );
+        }
+        let arrivals = self.arrivals.get_mut(&seq).unwrap();
+        let delay = now.saturating_duration_since(arrivals.first_seen);
+        arrivals.delays.entry(authority).or_insert(delay);
+    }
+
+    pub fn report(&self, seq: CheckpointSequenceNumber) -> Option<SignatureLatencyReport> {
+        let arrivals = self.arrivals.get(&seq)?;
+        let mut delays: Vec<_> = arrivals
+            .delays
+            .iter()
+            .map(|(authority, delay)| (delay.as_millis() as u64, *authority))
+            .collect();
+        delays.sort();
+        let (max_ms, slowest) = *delays.last()?;
+        let percentile = |p: usize| delays[(delays.len() * p + 99) / 100 - 1].0;
+        Some(SignatureLatencyReport {
+            sequence_number: seq,
+            signers: delays.len(),
+            p50_ms: percentile(50),
+            p90_ms: percentile(90),
+            p99_ms: percentile(99),
+            max_ms,
+            slowest,
+        })
+    }
+
+    /// Reports for all tracked checkpoints, by increasing sequence number.
+    pub fn reports(&self) -> Vec<SignatureLatencyReport> {
+        self.arrivals
+            .keys()
+            .filter_map(|seq| self.report(*seq))
+            .collect()
+    }
+
+    /// Per authority delays over all tracked checkpoints, slowest authorities first.
+    pub fn authority_summaries(&self) -> Vec<AuthorityLatencySummary> {
+        let mut delays: BTreeMap<AuthorityName, Vec<u64>> = BTreeMap::new();
+        for arrivals in self.arrivals.values() {
+            for (authority, delay) in &arrivals.delays {
+                delays
+                    .entry(*authority)
+                    .or_default()
+                    .push(delay.as_millis() as u64);
+            }
+        }
+        let mut summaries: Vec<_> = delays
+            .into_iter()
+            .map(|(authority, delays)| AuthorityLatencySummary {
+                authority,
+                samples: delays.len(),
+                mean_ms: delays.iter().sum::<u64>() / delays.len() as u64,
+                max_ms: delays.iter().copied().max().unwrap_or_default(),
+            })
+            .collect();
+        summaries.sort_by(|a, b| b.mean_ms.cmp(&a.mean_ms));
+        summaries
+    }
+}
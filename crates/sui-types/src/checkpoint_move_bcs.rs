@@ -0,0 +1,316 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! BCS encodings of checkpoint summaries and quorum signatures that a Move verifier can
+//! decode. The layouts below are the contract with the framework-side verifier: changing
+//! either one requires changing the Move code in lockstep.
+//!
+//! `QuorumSignatureExport` is a fixed layout of a quorum signature, with the public keys of
+//! its signers, for verifiers that cannot look up the committee, such as EVM contracts. It
+//! is encoded by hand, so that it does not change with the serde representation of the
+//! types it is made from.
+
+use fastcrypto::traits::ToFromBytes;
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+
+use crate::committee::{Committee, EpochId};
+use crate::crypto::{AggregateAuthoritySignature, AuthorityPublicKey, AuthorityQuorumSignInfo};
+use crate::error::{SuiError, SuiResult};
+use crate::messages_checkpoint::CheckpointSummary;
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_move_bcs_tests.rs"]
+mod checkpoint_move_bcs_tests;
+
+/// The prefix of the message signed by authorities and hashed into the checkpoint digest,
+/// followed by the Move BCS encoding of the summary.
+pub const CHECKPOINT_SUMMARY_SIGNING_PREFIX: &[u8] = b"CheckpointSummaryV4::";
+
+/// The Move BCS layout of a `CheckpointSummary`. It is the exact encoding signed by
+/// authorities, so that Move can recompute the checkpoint digest from it.
+pub const CHECKPOINT_SUMMARY_MOVE_LAYOUT: &str = "\
+struct CheckpointSummary {
+    epoch: u64,
+    sequence_number: u64,
+    content_digest: [u8; 32],             // 32 bytes, no length prefix
+    contents_merkle_root: [u8; 32],
+    object_changes_commitment: option<[u8; 32]>,
+    previous_digest: option<[u8; 32]>,    // 0x00, or 0x01 followed by 32 bytes
+    gas_cost_summary: GasCostSummary,
+    next_epoch_committee: option<vector<(vector<u8>, u64)>>, // (authority public key, stake)
+}
+struct GasCostSummary {
+    computation_cost: u64,
+    storage_cost: u64,
+    storage_rebate: u64,
+}";
+
+/// The Move BCS layout of an `AuthorityQuorumSignInfo`.
+pub const QUORUM_SIGNATURE_MOVE_LAYOUT: &str = "\
+struct QuorumSignature {
+    epoch: u64,
+    signature: vector<u8>,                // the aggregated BLS12-381 signature
+    signers: vector<u64>,                 // committee indices of the signers, increasing
+}";
+
+impl CheckpointSummary {
+    /// Encode this summary in `CHECKPOINT_SUMMARY_MOVE_LAYOUT`.
+    pub fn to_move_bcs(&self) -> Vec<u8> {
+        bcs::to_bytes(self).expect("Message serialization should not fail")
+    }
+
+    pub fn from_move_bcs(bytes: &[u8]) -> SuiResult<Self> {
+        bcs::from_bytes(bytes).map_err(|e| {
+            SuiError::from(format!("Invalid checkpoint summary encoding: {e}").as_str())
+        })
+    }
+
+    /// The message signed by authorities, whose hash is the digest of this summary.
+    pub fn move_signing_message(&self) -> Vec<u8> {
+        let mut message = CHECKPOINT_SUMMARY_SIGNING_PREFIX.to_vec();
+        bcs::serialize_into(&mut message, self).expect("Message serialization should not fail");
+        message
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MoveQuorumSignature {
+    epoch: EpochId,
+    signature: Vec<u8>,
+    signers: Vec<u64>,
+}
+
+impl<const STRONG_THRESHOLD: bool> AuthorityQuorumSignInfo<STRONG_THRESHOLD> {
+    /// Encode this quorum signature in `QUORUM_SIGNATURE_MOVE_LAYOUT`.
+    pub fn to_move_bcs(&self) -> Vec<u8> {
+        bcs::to_bytes(&MoveQuorumSignature {
+            epoch: self.epoch,
+            signature: self.signature.as_ref().to_vec(),
+            signers: self.signers_map.iter().map(u64::from).collect(),
+        })
+        .expect("Message serialization should not fail")
+    }
+
+    /// Decode a quorum signature in `QUORUM_SIGNATURE_MOVE_LAYOUT`. Only the canonical
+    /// encoding is accepted, with strictly increasing signer indices.
+    pub fn from_move_bcs(bytes: &[u8]) -> SuiResult<Self> {
+        let decoded: MoveQuorumSignature = bcs::from_bytes(bytes).map_err(|e| {
+            SuiError::from(format!("Invalid quorum signature encoding: {e}").as_str())
+        })?;
+        fp_ensure!(
+            decoded.signers.windows(2).all(|w| w[0] < w[1]),
+            SuiError::from("Quorum signature signers must be strictly increasing")
+        );
+        let mut signers_map = RoaringBitmap::new();
+        for index in decoded.signers {
+            let index = u32::try_from(index)
+                .map_err(|_| SuiError::from("Quorum signature signer index out of range"))?;
+            signers_map.insert(index);
+        }
+        Ok(Self {
+            epoch: decoded.epoch,
+            signature: AggregateAuthoritySignature::from_bytes(&decoded.signature).map_err(
+                |_| SuiError::InvalidSignature {
+                    error: "Invalid aggregate signature in quorum signature".to_string(),
+                },
+            )?,
+            signers_map,
+        })
+    }
+}
+
+/// The version of `QUORUM_SIGNATURE_EXPORT_LAYOUT` produced by `QuorumSignatureExport`.
+pub const QUORUM_SIGNATURE_EXPORT_VERSION: u8 = 1;
+
+/// The layout of an encoded `QuorumSignatureExport`. Integers are big-endian, and nothing is
+/// padded.
+pub const QUORUM_SIGNATURE_EXPORT_LAYOUT: &str = "\
+version: u8                               // QUORUM_SIGNATURE_EXPORT_VERSION
+epoch: u64
+committee_size: u16                       // n
+signer_bitmap: [u8; (n + 7) / 8]          // committee index i is bit (0x80 >> i % 8) of
+                                          // byte i / 8, the bits past n are zero
+signature_length: u16
+signature: [u8; signature_length]         // the aggregated BLS12-381 signature
+signer_public_keys: [[u8; 96]; signers]   // one per bit set, in committee order";
+
+/// A quorum signature, with the public keys of its signers in committee order, so that a
+/// verifier can aggregate them and check the signature without the committee. The keys
+/// must still be checked to belong to the committee of the epoch, e.g. against a committee
+/// digest the verifier trusts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuorumSignatureExport {
+    epoch: EpochId,
+    committee_size: u16,
+    signer_bitmap: Vec<u8>,
+    signature: Vec<u8>,
+    signer_public_keys: Vec<[u8; AuthorityPublicKey::LENGTH]>,
+}
+
+impl QuorumSignatureExport {
+    /// Export `signature`, made by members of `committee`.
+    pub fn new<const STRONG_THRESHOLD: bool>(
+        signature: &AuthorityQuorumSignInfo<STRONG_THRESHOLD>,
+        committee: &Committee,
+    ) -> SuiResult<Self> {
+        fp_ensure!(
+            signature.epoch == committee.epoch,
+            SuiError::WrongEpoch {
+                expected_epoch: committee.epoch,
+                actual_epoch: signature.epoch,
+            }
+        );
+        let committee_size = u16::try_from(committee.num_members())
+            .map_err(|_| SuiError::from("Committee too large to export a quorum signature"))?;
+        let mut signer_bitmap = vec![0u8; bitmap_len(committee_size)];
+        let mut signer_public_keys = Vec::new();
+        for index in signature.signers_map.iter() {
+            let name = committee
+                .authority_by_index(index)
+                .ok_or_else(|| SuiError::from("Quorum signature signer is not in the committee"))?;
+            signer_bitmap[index as usize / 8] |= 0x80 >> (index % 8);
+            let mut key = [0u8; AuthorityPublicKey::LENGTH];
+            key.copy_from_slice(name.as_ref());
+            signer_public_keys.push(key);
+        }
+        Ok(Self {
+            epoch: signature.epoch,
+            committee_size,
+            signer_bitmap,
+            signature: signature.signature.as_ref().to_vec(),
+            signer_public_keys,
+        })
+    }
+
+    pub fn epoch(&self) -> EpochId {
+        self.epoch
+    }
+
+    pub fn committee_size(&self) -> u16 {
+        self.committee_size
+    }
+
+    /// The committee indices of the signers, increasing.
+    pub fn signer_indices(&self) -> Vec<u32> {
+        (0..u32::from(self.committee_size))
+            .filter(|i| self.signer_bitmap[*i as usize / 8] & (0x80 >> (i % 8)) != 0)
+            .collect()
+    }
+
+    pub fn signer_public_keys(&self) -> &[[u8; AuthorityPublicKey::LENGTH]] {
+        &self.signer_public_keys
+    }
+
+    /// Encode in `QUORUM_SIGNATURE_EXPORT_LAYOUT`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![QUORUM_SIGNATURE_EXPORT_VERSION];
+        bytes.extend_from_slice(&self.epoch.to_be_bytes());
+        bytes.extend_from_slice(&self.committee_size.to_be_bytes());
+        bytes.extend_from_slice(&self.signer_bitmap);
+        bytes.extend_from_slice(&(self.signature.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&self.signature);
+        for key in &self.signer_public_keys {
+            bytes.extend_from_slice(key);
+        }
+        bytes
+    }
+
+    /// Decode from `QUORUM_SIGNATURE_EXPORT_LAYOUT`. Only the canonical encoding is accepted:
+    /// the bits past the committee size are zero, there is one key per signer, and nothing
+    /// follows the last key. The signature and keys are not checked to be valid points.
+    pub fn decode(bytes: &[u8]) -> SuiResult<Self> {
+        let mut reader = ExportReader { bytes };
+        let version = reader.take(1)?[0];
+        fp_ensure!(
+            version == QUORUM_SIGNATURE_EXPORT_VERSION,
+            SuiError::from(
+                format!("Unsupported quorum signature export version {version}").as_str()
+            )
+        );
+        let epoch = u64::from_be_bytes(reader.take(8)?.try_into().unwrap());
+        let committee_size = u16::from_be_bytes(reader.take(2)?.try_into().unwrap());
+        let signer_bitmap = reader.take(bitmap_len(committee_size))?.to_vec();
+        let padding = signer_bitmap.len() * 8 - usize::from(committee_size);
+        fp_ensure!(
+            padding == 0 || signer_bitmap.last().unwrap() & ((1u8 << padding) - 1) == 0,
+            SuiError::from("Quorum signature export has signers past the committee size")
+        );
+        let signature_length = u16::from_be_bytes(reader.take(2)?.try_into().unwrap());
+        let signature = reader.take(usize::from(signature_length))?.to_vec();
+        let signers: u32 = signer_bitmap.iter().map(|byte| byte.count_ones()).sum();
+        let signer_public_keys = (0..signers)
+            .map(|_| Ok(reader.take(AuthorityPublicKey::LENGTH)?.try_into().unwrap()))
+            .collect::<SuiResult<_>>()?;
+        fp_ensure!(
+            reader.bytes.is_empty(),
+            SuiError::from("Trailing bytes after quorum signature export")
+        );
+        Ok(Self {
+            epoch,
+            committee_size,
+            signer_bitmap,
+            signature,
+            signer_public_keys,
+        })
+    }
+
+    /// The quorum signature exported, after checking that its signers are the members of
+    /// `committee` whose keys were exported.
+    pub fn to_quorum_signature<const STRONG_THRESHOLD: bool>(
+        &self,
+        committee: &Committee,
+    ) -> SuiResult<AuthorityQuorumSignInfo<STRONG_THRESHOLD>> {
+        fp_ensure!(
+            self.epoch == committee.epoch,
+            SuiError::WrongEpoch {
+                expected_epoch: committee.epoch,
+                actual_epoch: self.epoch,
+            }
+        );
+        fp_ensure!(
+            usize::from(self.committee_size) == committee.num_members(),
+            SuiError::from("Quorum signature export is for a committee of another size")
+        );
+        let signers = self.signer_indices();
+        for (index, key) in signers.iter().zip(&self.signer_public_keys) {
+            let name = committee.authority_by_index(*index).unwrap();
+            fp_ensure!(
+                name.as_ref() == key.as_slice(),
+                SuiError::from(
+                    format!("Exported key of signer {index} is not the key of the committee")
+                        .as_str()
+                )
+            );
+        }
+        Ok(AuthorityQuorumSignInfo {
+            epoch: self.epoch,
+            signature: AggregateAuthoritySignature::from_bytes(&self.signature).map_err(|_| {
+                SuiError::InvalidSignature {
+                    error: "Invalid aggregate signature in quorum signature export".to_string(),
+                }
+            })?,
+            signers_map: signers.into_iter().collect(),
+        })
+    }
+}
+
+fn bitmap_len(committee_size: u16) -> usize {
+    (usize::from(committee_size) + 7) / 8
+}
+
+struct ExportReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ExportReader<'a> {
+    fn take(&mut self, len: usize) -> SuiResult<&'a [u8]> {
+        fp_ensure!(
+            self.bytes.len() >= len,
+            SuiError::from("Truncated quorum signature export")
+        );
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+}
@@ -0,0 +1,50 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The canonical notification of new certified checkpoints, shared by the components
+//! forming, storing, executing and serving them.
+
+use tokio::sync::broadcast;
+
+use crate::messages_checkpoint::VerifiedCertifiedCheckpointSummary;
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_notifier_tests.rs"]
+mod checkpoint_notifier_tests;
+
+/// The number of checkpoints a subscriber can lag behind before missing notifications.
+pub const CHECKPOINT_NOTIFIER_CAPACITY: usize = 16;
+
+/// Broadcasts every new certified checkpoint to its subscribers. Clones share the same
+/// channel, so the notifier can be handed to every producer and consumer.
+#[derive(Clone)]
+pub struct CertifiedCheckpointNotifier {
+    sender: broadcast::Sender<VerifiedCertifiedCheckpointSummary>,
+}
+
+impl Default for CertifiedCheckpointNotifier {
+    fn default() -> Self {
+        Self::new(CHECKPOINT_NOTIFIER_CAPACITY)
+    }
+}
+
+impl CertifiedCheckpointNotifier {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Notify the subscribers of a new certified checkpoint, returning how many there are.
+    pub fn notify(&self, checkpoint: VerifiedCertifiedCheckpointSummary) -> usize {
+        self.sender.send(checkpoint).unwrap_or(0)
+    }
+
+    /// Subscribe to the checkpoints notified from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<VerifiedCertifiedCheckpointSummary> {
+        self.sender.subscribe()
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
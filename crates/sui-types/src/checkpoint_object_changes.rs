@@ -0,0 +1,112 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Commitments to the object versions a checkpoint finalized, so that light clients can
+//! prove an object version against a certified checkpoint without its contents nor effects.
+//! Summaries from version 3 may carry the root of a Merkle tree over the references of the
+//! objects written by the transactions of the checkpoint, sorted by object ID and version.
+//!
+//! Checkpoint stores fill the commitment of the version 4 summaries they build from the effects
+//! of their transactions, see `ProvisionalCheckpointSummary::set_object_changes`. Summaries
+//! built from the contents alone, such as the ones cut by `ConsensusCommitCheckpointBuilder`,
+//! carry none.
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::{ObjectID, ObjectRef};
+use crate::checkpoint_commitment::{hash_leaf, merkle_root, MerkleProof};
+use crate::error::{SuiError, SuiResult};
+use crate::messages::TransactionEffects;
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_object_changes_tests.rs"]
+mod checkpoint_object_changes_tests;
+
+pub type ObjectChangesRoot = [u8; 32];
+
+/// The references of the objects written by the transactions of a checkpoint: created,
+/// mutated and unwrapped objects, and deleted and wrapped ones with the digest marking them
+/// as such.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectChanges {
+    /// Sorted by object ID then version, without duplicates.
+    changes: Vec<ObjectRef>,
+}
+
+fn object_leaf(object_ref: &ObjectRef) -> [u8; 32] {
+    hash_leaf(&bcs::to_bytes(object_ref).expect("Serialization should not fail"))
+}
+
+impl ObjectChanges {
+    /// The object changes of the effects of the transactions of a checkpoint.
+    pub fn from_effects<'a>(effects: impl IntoIterator<Item = &'a TransactionEffects>) -> Self {
+        let mut changes: Vec<_> = effects
+            .into_iter()
+            .flat_map(|effects| {
+                effects
+                    .all_mutated()
+                    .map(|(object_ref, _, _)| *object_ref)
+                    .chain(effects.deleted.iter().copied())
+                    .chain(effects.wrapped.iter().copied())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        changes.sort_unstable();
+        changes.dedup();
+        Self { changes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ObjectRef> {
+        self.changes.iter()
+    }
+
+    /// The last version of `id` written in the checkpoint.
+    pub fn latest(&self, id: &ObjectID) -> Option<&ObjectRef> {
+        let end = self
+            .changes
+            .partition_point(|(object_id, _, _)| object_id <= id);
+        self.changes[..end]
+            .last()
+            .filter(|(object_id, _, _)| object_id == id)
+    }
+
+    pub fn root(&self) -> ObjectChangesRoot {
+        merkle_root(self.changes.iter().map(object_leaf).collect())
+    }
+
+    /// A proof that the checkpoint wrote `object_ref`, against `root`. None if it did not.
+    pub fn prove(&self, object_ref: &ObjectRef) -> Option<MerkleProof> {
+        let index = self.changes.binary_search(object_ref).ok()?;
+        Some(MerkleProof::new(
+            self.changes.iter().map(object_leaf).collect(),
+            index,
+        ))
+    }
+}
+
+/// Check that a checkpoint whose object changes have root `root` wrote `object_ref`.
+pub fn verify_object_change(
+    root: &ObjectChangesRoot,
+    object_ref: &ObjectRef,
+    proof: &MerkleProof,
+) -> SuiResult {
+    fp_ensure!(
+        proof.root_of_leaf(object_leaf(object_ref))? == *root,
+        SuiError::from(
+            format!(
+                "Merkle proof does not prove the change of object {} to version {}",
+                object_ref.0, object_ref.1
+            )
+            .as_str()
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,378 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reconciliation of two checkpoint proposals by exchanging a sketch of their contents, an
+//! invertible Bloom lookup table (IBLT), whose size is proportional to the number of
+//! transactions the proposals differ by instead of the number of transactions they hold.
+//!
+//! The requester sends the sketch of its proposal, sized for the difference it expects. The
+//! responder subtracts the sketch of its own proposal and decodes the transactions each side
+//! is missing. If the difference is larger than the sketch can hold, decoding fails and the
+//! responder sends its full contents instead, from which the fragment is built as before.
+//! Either way the requester checks the diff against the waypoints of both proposals, so that
+//! a wrongly decoded or forged diff is never turned into a fragment.
+//!
+//! Diff requests are sent as `CheckpointRequestType::CheckpointProposalDiff`. The sketches and
+//! messages are available to all builds, answering requests and building fragments from the
+//! responses needs the `checkpoint-validator` feature.
+
+use std::collections::{BTreeSet, VecDeque};
+
+use fastcrypto::hash::{HashFunction, Sha3_256};
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::{ExecutionDigests, TransactionDigest, TransactionEffectsDigest};
+use crate::error::{SuiError, SuiResult};
+#[cfg(feature = "checkpoint-validator")]
+use crate::messages_checkpoint::{CheckpointFragment, CheckpointFragmentData, CheckpointProposal};
+use crate::messages_checkpoint::{
+    CheckpointProposalContents, CheckpointSequenceNumber, SignedCheckpointProposalSummary,
+};
+#[cfg(feature = "checkpoint-validator")]
+use crate::waypoint::WaypointDiff;
+
+#[cfg(all(test, feature = "checkpoint-validator"))]
+#[path = "unit_tests/checkpoint_proposal_diff_tests.rs"]
+mod checkpoint_proposal_diff_tests;
+
+/// Each transaction is added to one cell of each of as many equal partitions of the sketch.
+const SKETCH_HASHES: usize = 3;
+
+/// The largest number of cells of a sketch, to bound the memory a request can make the
+/// responder allocate.
+pub const MAX_SKETCH_CELLS: usize = 30_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct SketchCell {
+    /// Transactions added minus transactions removed.
+    count: i64,
+    transaction_xor: [u8; 32],
+    effects_xor: [u8; 32],
+    /// Xor of the check hashes of the transactions, telling pure cells from mixed ones.
+    check_xor: u64,
+}
+
+impl SketchCell {
+    fn toggle(&mut self, digests: &ExecutionDigests, check: u64, count: i64) {
+        // Counts of a sketch received from the network are arbitrary.
+        self.count = self.count.wrapping_add(count);
+        xor_into(&mut self.transaction_xor, digests.transaction.as_ref());
+        xor_into(&mut self.effects_xor, &digests.effects.0);
+        self.check_xor ^= check;
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The transaction of the cell, if it holds exactly one.
+    fn pure(&self) -> Option<ExecutionDigests> {
+        if self.count != 1 && self.count != -1 {
+            return None;
+        }
+        let digests = ExecutionDigests::new(
+            TransactionDigest::new(self.transaction_xor),
+            TransactionEffectsDigest(self.effects_xor),
+        );
+        (sketch_hashes(&digests).0 == self.check_xor).then_some(digests)
+    }
+}
+
+fn xor_into(target: &mut [u8; 32], bytes: &[u8]) {
+    target
+        .iter_mut()
+        .zip(bytes)
+        .for_each(|(target, byte)| *target ^= byte);
+}
+
+/// The check hash of a transaction, and its cell in each partition.
+fn sketch_hashes(digests: &ExecutionDigests) -> (u64, [u64; SKETCH_HASHES]) {
+    let mut hasher = Sha3_256::default();
+    hasher.update(digests.transaction.as_ref());
+    hasher.update(digests.effects.0);
+    let hash: [u8; 32] = hasher.finalize().into();
+    let word = |i: usize| u64::from_le_bytes(hash[i * 8..(i + 1) * 8].try_into().unwrap());
+    (word(0), [word(1), word(2), word(3)])
+}
+
+/// An invertible Bloom lookup table of the transactions of a proposal.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalSketch {
+    cells: Vec<SketchCell>,
+}
+
+/// The transactions two sketched proposals differ by.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SketchDifference {
+    /// Transactions of the first proposal missing from the second.
+    pub only_in_first: BTreeSet<ExecutionDigests>,
+    /// Transactions of the second proposal missing from the first.
+    pub only_in_second: BTreeSet<ExecutionDigests>,
+}
+
+impl ProposalSketch {
+    /// An empty sketch able to decode a difference of about `expected_difference`
+    /// transactions.
+    pub fn new(expected_difference: usize) -> Self {
+        Self::with_cells(expected_difference.saturating_mul(2) + SKETCH_HASHES)
+    }
+
+    fn with_cells(cells: usize) -> Self {
+        let partition = (cells.min(MAX_SKETCH_CELLS) + SKETCH_HASHES - 1) / SKETCH_HASHES;
+        Self {
+            cells: vec![SketchCell::default(); partition * SKETCH_HASHES],
+        }
+    }
+
+    pub fn from_contents(
+        contents: &CheckpointProposalContents,
+        expected_difference: usize,
+    ) -> Self {
+        let mut sketch = Self::new(expected_difference);
+        contents
+            .transactions
+            .iter()
+            .for_each(|digests| sketch.insert(digests));
+        sketch
+    }
+
+    pub fn cells(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn insert(&mut self, digests: &ExecutionDigests) {
+        self.toggle(digests, 1);
+    }
+
+    /// Add `count` times `digests` to its cells, returning them.
+    fn toggle(&mut self, digests: &ExecutionDigests, count: i64) -> [usize; SKETCH_HASHES] {
+        let (check, indexes) = sketch_hashes(digests);
+        let partition = self.cells.len() / SKETCH_HASHES;
+        let mut cells = [0; SKETCH_HASHES];
+        for (i, index) in indexes.iter().enumerate() {
+            cells[i] = i * partition + (*index % partition as u64) as usize;
+            self.cells[cells[i]].toggle(digests, check, count);
+        }
+        cells
+    }
+
+    pub(crate) fn check_shape(&self) -> SuiResult {
+        fp_ensure!(
+            !self.cells.is_empty()
+                && self.cells.len() <= MAX_SKETCH_CELLS
+                && self.cells.len() % SKETCH_HASHES == 0,
+            SuiError::from(format!("Invalid sketch of {} cells", self.cells.len()).as_str())
+        );
+        Ok(())
+    }
+
+    /// Decode the transactions this sketch and `other` differ by. Fails if the sketches have
+    /// different sizes, or if the difference is too large for their size.
+    pub fn difference(&self, other: &ProposalSketch) -> SuiResult<SketchDifference> {
+        self.check_shape()?;
+        fp_ensure!(
+            self.cells.len() == other.cells.len(),
+            SuiError::from(
+                format!(
+                    "Sketches of {} and {} cells cannot be subtracted",
+                    self.cells.len(),
+                    other.cells.len()
+                )
+                .as_str()
+            )
+        );
+        let mut diff = self.clone();
+        for (cell, other) in diff.cells.iter_mut().zip(&other.cells) {
+            cell.count = cell.count.wrapping_sub(other.count);
+            xor_into(&mut cell.transaction_xor, &other.transaction_xor);
+            xor_into(&mut cell.effects_xor, &other.effects_xor);
+            cell.check_xor ^= other.check_xor;
+        }
+
+        let undecodable = || {
+            SuiError::from(
+                format!(
+                    "Sketch of {} cells cannot decode the difference of the proposals",
+                    self.cells.len()
+                )
+                .as_str(),
+            )
+        };
+        let mut difference = SketchDifference::default();
+        // Peel pure cells until none is left. Removing a transaction only changes its own
+        // cells, so only those are checked again. A difference the sketch can decode has
+        // fewer transactions than cells, which bounds the peeling of a forged sketch.
+        let mut pure: VecDeque<usize> = (0..diff.cells.len())
+            .filter(|i| diff.cells[*i].pure().is_some())
+            .collect();
+        let mut peeled = 0;
+        while let Some(i) = pure.pop_front() {
+            // The cell may have changed since it was queued.
+            let Some(digests) = diff.cells[i].pure() else {
+                continue;
+            };
+            peeled += 1;
+            fp_ensure!(peeled <= diff.cells.len(), undecodable());
+            let count = diff.cells[i].count;
+            let side = if count > 0 {
+                &mut difference.only_in_first
+            } else {
+                &mut difference.only_in_second
+            };
+            fp_ensure!(
+                side.insert(digests),
+                SuiError::from("Sketch difference decodes a transaction twice")
+            );
+            for cell in diff.toggle(&digests, -count) {
+                if diff.cells[cell].pure().is_some() {
+                    pure.push_back(cell);
+                }
+            }
+        }
+        fp_ensure!(diff.cells.iter().all(SketchCell::is_empty), undecodable());
+        Ok(difference)
+    }
+}
+
+/// Asks an authority for the difference between its proposal and the proposal of the
+/// requester, given as a sketch.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointProposalDiffRequest {
+    pub sequence_number: CheckpointSequenceNumber,
+    pub sketch: ProposalSketch,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum CheckpointProposalDiffResponse {
+    /// The difference decoded from the sketch of the requester.
+    Diff {
+        proposal: SignedCheckpointProposalSummary,
+        /// Transactions of the responder missing from the requester.
+        missing_from_requester: Vec<ExecutionDigests>,
+        /// Transactions of the requester missing from the responder.
+        missing_from_responder: Vec<ExecutionDigests>,
+    },
+    /// The sketch could not be decoded: the full contents of the responder.
+    Contents {
+        proposal: SignedCheckpointProposalSummary,
+        contents: CheckpointProposalContents,
+    },
+}
+
+impl CheckpointProposalDiffResponse {
+    /// The proposal of the responder.
+    pub fn proposal(&self) -> &SignedCheckpointProposalSummary {
+        match self {
+            Self::Diff { proposal, .. } | Self::Contents { proposal, .. } => proposal,
+        }
+    }
+}
+
+#[cfg(feature = "checkpoint-validator")]
+impl CheckpointProposal {
+    pub fn sketch(&self, expected_difference: usize) -> CheckpointProposalDiffRequest {
+        CheckpointProposalDiffRequest {
+            sequence_number: *self.sequence_number(),
+            sketch: ProposalSketch::from_contents(&self.transactions, expected_difference),
+        }
+    }
+
+    /// Answer a diff request with the difference to this proposal, or its contents if the
+    /// sketch of the requester cannot be decoded.
+    pub fn answer_diff_request(
+        &self,
+        request: &CheckpointProposalDiffRequest,
+    ) -> SuiResult<CheckpointProposalDiffResponse> {
+        fp_ensure!(
+            request.sequence_number == *self.sequence_number(),
+            SuiError::from(
+                format!(
+                    "Diff requested for proposal {}, current proposal is {}",
+                    request.sequence_number,
+                    self.sequence_number()
+                )
+                .as_str()
+            )
+        );
+        request.sketch.check_shape()?;
+        let mut own = ProposalSketch::with_cells(request.sketch.cells());
+        self.transactions
+            .transactions
+            .iter()
+            .for_each(|digests| own.insert(digests));
+        Ok(match own.difference(&request.sketch) {
+            Ok(difference) => CheckpointProposalDiffResponse::Diff {
+                proposal: self.signed_summary.clone(),
+                missing_from_requester: difference.only_in_first.into_iter().collect(),
+                missing_from_responder: difference.only_in_second.into_iter().collect(),
+            },
+            Err(_) => CheckpointProposalDiffResponse::Contents {
+                proposal: self.signed_summary.clone(),
+                contents: self.transactions.clone(),
+            },
+        })
+    }
+
+    /// Build the fragment between this proposal and the one of the responder to a diff
+    /// request. As with `fragment_with`, the certs of the diff are left to the caller. A diff
+    /// not matching the waypoints of both proposals is refused, and the caller may then fall
+    /// back to fetching the full proposal of the responder.
+    pub fn fragment_from_diff_response(
+        &self,
+        response: &CheckpointProposalDiffResponse,
+    ) -> SuiResult<CheckpointFragment> {
+        fp_ensure!(
+            response.proposal().authority() != self.name(),
+            SuiError::from("Own proposal received through the peer path")
+        );
+        let (other, missing_from_me, missing_from_other) = match response {
+            CheckpointProposalDiffResponse::Diff {
+                proposal,
+                missing_from_requester,
+                missing_from_responder,
+            } => (proposal, missing_from_requester, missing_from_responder),
+            CheckpointProposalDiffResponse::Contents { proposal, contents } => {
+                fp_ensure!(
+                    proposal.data().content_digest == contents.digest(),
+                    SuiError::from("Proposal contents do not match their summary")
+                );
+                return Ok(self.fragment_with(
+                    &CheckpointProposal::new_from_signed_proposal_summary(
+                        proposal.clone(),
+                        contents.clone(),
+                    ),
+                ));
+            }
+        };
+        fp_ensure!(
+            other.data().sequence_number == *self.sequence_number(),
+            SuiError::from("Diff response is for another proposal")
+        );
+        if let Ok(fragment) =
+            CheckpointFragment::new_trivial(self.signed_summary.clone(), other.clone())
+        {
+            return Ok(fragment);
+        }
+        let diff = WaypointDiff::new(
+            *self.name(),
+            *self.signed_summary.data().waypoint.clone(),
+            missing_from_me.iter().copied(),
+            *other.authority(),
+            *other.data().waypoint.clone(),
+            missing_from_other.iter().copied(),
+        );
+        fp_ensure!(
+            diff.check(),
+            SuiError::from("Diff response does not match the waypoints of the proposals")
+        );
+        Ok(CheckpointFragment {
+            proposer: self.signed_summary.clone(),
+            other: other.clone(),
+            data: CheckpointFragmentData {
+                diff,
+                certs: Default::default(),
+            },
+        })
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Post-incident replay of checkpoint proposals: the proposals retained in the logs of each
+//! authority are merged with the certified checkpoints, to tell for each proposal which of its
+//! transactions made it into the checkpoint proposed for, which only landed in a later one,
+//! and which landed in none of the replayed checkpoints. The result is best-effort: logs may
+//! miss proposals, or only hold their summaries, and transactions reported as dropped may
+//! land in a checkpoint after the last one replayed.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::{AuthorityName, ExecutionDigests};
+use crate::checkpoint_construction::{ProposalConflict, ProposalSlotMap};
+use crate::committee::Committee;
+use crate::error::SuiResult;
+use crate::messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointContents, CheckpointContentsDigest,
+    CheckpointProposalContents, CheckpointSequenceNumber, SignedCheckpointProposalSummary,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_replay_tests.rs"]
+mod checkpoint_replay_tests;
+
+/// A proposal as retained in the logs of an authority, with its contents if they were kept.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoggedProposal {
+    pub summary: SignedCheckpointProposalSummary,
+    pub contents: Option<CheckpointProposalContents>,
+}
+
+/// A proposed transaction that landed in a later checkpoint than the one proposed for.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LateTransaction {
+    pub transaction: ExecutionDigests,
+    pub landed_in: CheckpointSequenceNumber,
+}
+
+/// What became of a proposal.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalOutcome {
+    pub sequence_number: CheckpointSequenceNumber,
+    pub content_digest: CheckpointContentsDigest,
+    /// Whether the checkpoint proposed for is among the replayed checkpoints.
+    pub certified: bool,
+    /// The number of transactions proposed, None if the contents of the proposal were not
+    /// retained, in which case no transaction is reported.
+    pub proposed: Option<usize>,
+    /// The number of proposed transactions in the checkpoint proposed for.
+    pub included: usize,
+    /// The number of proposed transactions already in an earlier checkpoint.
+    pub already_checkpointed: usize,
+    pub late: Vec<LateTransaction>,
+    /// Proposed transactions in none of the replayed checkpoints.
+    pub dropped: Vec<ExecutionDigests>,
+}
+
+/// The outcomes of the proposals of every authority, by increasing sequence number.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub timelines: BTreeMap<AuthorityName, Vec<ProposalOutcome>>,
+    /// The sequence numbers of the replayed checkpoints.
+    pub checkpoints: Vec<CheckpointSequenceNumber>,
+}
+
+impl ReplayReport {
+    /// The transactions proposed by any authority but in none of the replayed checkpoints,
+    /// with the authorities that proposed them.
+    pub fn dropped_transactions(&self) -> BTreeMap<ExecutionDigests, Vec<AuthorityName>> {
+        let mut dropped: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (authority, outcomes) in &self.timelines {
+            for transaction in outcomes.iter().flat_map(|outcome| &outcome.dropped) {
+                let proposers = dropped.entry(*transaction).or_default();
+                if proposers.last() != Some(authority) {
+                    proposers.push(*authority);
+                }
+            }
+        }
+        dropped
+    }
+}
+
+/// Collects certified checkpoints and logged proposals, then merges them into a
+/// `ReplayReport`.
+#[derive(Default)]
+pub struct ProposalReplay {
+    /// The checkpoint each transaction landed in.
+    landed: HashMap<ExecutionDigests, CheckpointSequenceNumber>,
+    checkpoints: Vec<CheckpointSequenceNumber>,
+    proposals: ProposalSlotMap<SignedCheckpointProposalSummary>,
+    contents: HashMap<(CheckpointSequenceNumber, AuthorityName), CheckpointProposalContents>,
+}
+
+impl ProposalReplay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a certified checkpoint and its contents, both verified against `committee`.
+    pub fn add_checkpoint(
+        &mut self,
+        checkpoint: &CertifiedCheckpointSummary,
+        contents: &CheckpointContents,
+        committee: &Committee,
+    ) -> SuiResult {
+        checkpoint.verify(committee, Some(contents))?;
+        let seq = checkpoint.data().sequence_number;
+        self.landed
+            .extend(contents.iter().map(|digests| (*digests, seq)));
+        self.checkpoints.push(seq);
+        Ok(())
+    }
+
+    /// Add a proposal from the logs of an authority, verified against `committee`. Copies of
+    /// a proposal are ignored, and a proposal conflicting with one already added is refused.
+    pub fn add_proposal(&mut self, proposal: LoggedProposal, committee: &Committee) -> SuiResult {
+        proposal
+            .summary
+            .verify_with_contents(committee, proposal.contents.as_ref())?;
+        let key = (
+            proposal.summary.data().sequence_number,
+            *proposal.summary.authority(),
+        );
+        self.proposals.insert(proposal.summary)?;
+        // A copy of the proposal may hold the contents the first one lacked.
+        if let Some(contents) = proposal.contents {
+            self.contents.entry(key).or_insert(contents);
+        }
+        Ok(())
+    }
+
+    /// Evidence of the conflicting proposals found in the logs.
+    pub fn conflicts(&self) -> &[ProposalConflict] {
+        self.proposals.conflicts()
+    }
+
+    pub fn report(&self) -> ReplayReport {
+        let mut timelines: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (seq, authority, proposal) in self.proposals.iter() {
+            timelines
+                .entry(*authority)
+                .or_default()
+                .push(self.outcome(seq, proposal));
+        }
+        let mut checkpoints = self.checkpoints.clone();
+        checkpoints.sort_unstable();
+        checkpoints.dedup();
+        ReplayReport {
+            timelines,
+            checkpoints,
+        }
+    }
+
+    fn outcome(
+        &self,
+        seq: CheckpointSequenceNumber,
+        proposal: &SignedCheckpointProposalSummary,
+    ) -> ProposalOutcome {
+        let mut outcome = ProposalOutcome {
+            sequence_number: seq,
+            content_digest: proposal.data().content_digest,
+            certified: self.checkpoints.contains(&seq),
+            proposed: None,
+            included: 0,
+            already_checkpointed: 0,
+            late: Vec::new(),
+            dropped: Vec::new(),
+        };
+        let contents = match self.contents.get(&(seq, *proposal.authority())) {
+            Some(contents) => contents,
+            None => return outcome,
+        };
+        outcome.proposed = Some(contents.transactions.len());
+        for transaction in contents.transactions.iter() {
+            match self.landed.get(transaction) {
+                Some(landed_in) if *landed_in == seq => outcome.included += 1,
+                Some(landed_in) if *landed_in < seq => outcome.already_checkpointed += 1,
+                Some(landed_in) => outcome.late.push(LateTransaction {
+                    transaction: *transaction,
+                    landed_in: *landed_in,
+                }),
+                None => outcome.dropped.push(*transaction),
+            }
+        }
+        outcome
+    }
+}
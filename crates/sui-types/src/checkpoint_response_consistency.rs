@@ -0,0 +1,217 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detection of authorities answering the same checkpoint request twice, within a session,
+//! with different checkpoints.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::AuthorityName;
+use crate::committee::Committee;
+use crate::error::{SuiError, SuiResult};
+use crate::messages_checkpoint::{
+    AuthenticatedCheckpoint, CheckpointDigest, CheckpointRequest, CheckpointResponse,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_response_consistency_tests.rs"]
+mod checkpoint_response_consistency_tests;
+
+/// The default number of responses, and of conflicts, a session keeps.
+pub const MAX_SESSION_RESPONSES: usize = 10_000;
+
+/// Two different checkpoints with the same epoch and sequence number, served by `authority`
+/// in answer to the same request. A signed checkpoint must be signed by the authority, so
+/// that two of them prove it signed both. A certified checkpoint only shows what the
+/// authority served.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResponseConflict {
+    pub authority: AuthorityName,
+    pub request: CheckpointRequest,
+    pub first: AuthenticatedCheckpoint,
+    pub second: AuthenticatedCheckpoint,
+}
+
+impl ResponseConflict {
+    /// Check that this is evidence of contradicting responses, against the committee of
+    /// the epoch of the checkpoints.
+    pub fn verify(&self, committee: &Committee) -> SuiResult {
+        fp_ensure!(
+            self.first.epoch() == self.second.epoch()
+                && self.first.sequence_number() == self.second.sequence_number(),
+            SuiError::from("Conflicting responses are about different checkpoints")
+        );
+        fp_ensure!(
+            self.first.summary().digest() != self.second.summary().digest(),
+            SuiError::from("Conflicting responses are identical")
+        );
+        for checkpoint in [&self.first, &self.second] {
+            if let AuthenticatedCheckpoint::Signed(signed) = checkpoint {
+                fp_ensure!(
+                    signed.authority() == &self.authority,
+                    SuiError::from("Conflicting response is signed by another authority")
+                );
+            }
+            checkpoint.verify(committee, None)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<ResponseConflict> for SuiError {
+    fn from(conflict: ResponseConflict) -> Self {
+        SuiError::from(
+            format!(
+                "Authority {:?} served conflicting responses for checkpoint {}",
+                conflict.authority,
+                conflict.first.sequence_number()
+            )
+            .as_str(),
+        )
+    }
+}
+
+/// The checkpoint in a response, for the responses that hold one.
+fn response_checkpoint(response: &CheckpointResponse) -> Option<&AuthenticatedCheckpoint> {
+    match response {
+        CheckpointResponse::AuthenticatedCheckpoint { checkpoint, .. }
+        | CheckpointResponse::AuthenticatedCheckpointBucket { checkpoint, .. }
+        | CheckpointResponse::AuthenticatedCheckpointMetadata { checkpoint, .. } => {
+            checkpoint.as_ref()
+        }
+        _ => None,
+    }
+}
+
+struct RecordedResponse {
+    request: CheckpointRequest,
+    digest: CheckpointDigest,
+    checkpoint: AuthenticatedCheckpoint,
+}
+
+// Requests are keyed by their serialization.
+type ResponseKey = (AuthorityName, Vec<u8>);
+
+#[derive(Default)]
+struct RecordedResponses {
+    by_request: HashMap<ResponseKey, VecDeque<RecordedResponse>>,
+    // The key of every recorded response, oldest first.
+    order: VecDeque<ResponseKey>,
+}
+
+/// The checkpoints served by each authority during a session, by request, to catch an
+/// authority answering a request again with another checkpoint. Responses to requests for
+/// the latest checkpoint change as checkpoints are made, so only responses about the same
+/// epoch and sequence number are compared. Responses must be verified before they are
+/// recorded.
+///
+/// A session keeps at most `max_responses` responses, forgetting the oldest ones first, and
+/// as many conflicts, dropping the later ones until they are taken.
+pub struct ResponseConsistencyChecker {
+    max_responses: usize,
+    responses: Mutex<RecordedResponses>,
+    conflicts: Mutex<Vec<ResponseConflict>>,
+}
+
+impl Default for ResponseConsistencyChecker {
+    fn default() -> Self {
+        Self::with_capacity(MAX_SESSION_RESPONSES)
+    }
+}
+
+impl ResponseConsistencyChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(max_responses: usize) -> Self {
+        Self {
+            max_responses: max_responses.max(1),
+            responses: Mutex::new(RecordedResponses::default()),
+            conflicts: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Record the response of `authority` to `request`. Returns the evidence if it
+    /// contradicts an earlier response to the same request, which is also kept until taken
+    /// with `take_conflicts`.
+    pub fn record(
+        &self,
+        authority: AuthorityName,
+        request: &CheckpointRequest,
+        response: &CheckpointResponse,
+    ) -> Option<ResponseConflict> {
+        let checkpoint = response_checkpoint(response)?;
+        let digest = checkpoint.summary().digest();
+        let key = (
+            authority,
+            bcs::to_bytes(request).expect("Serialization should not fail"),
+        );
+        let mut responses = self.responses.lock().unwrap();
+        let recorded = responses.by_request.entry(key.clone()).or_default();
+        let earlier = recorded.iter().find(|earlier| {
+            earlier.checkpoint.epoch() == checkpoint.epoch()
+                && earlier.checkpoint.sequence_number() == checkpoint.sequence_number()
+        });
+        match earlier {
+            Some(earlier) if earlier.digest == digest => None,
+            Some(earlier) => {
+                let conflict = ResponseConflict {
+                    authority,
+                    request: earlier.request.clone(),
+                    first: earlier.checkpoint.clone(),
+                    second: checkpoint.clone(),
+                };
+                let mut conflicts = self.conflicts.lock().unwrap();
+                if conflicts.len() < self.max_responses {
+                    conflicts.push(conflict.clone());
+                }
+                Some(conflict)
+            }
+            None => {
+                recorded.push_back(RecordedResponse {
+                    request: request.clone(),
+                    digest,
+                    checkpoint: checkpoint.clone(),
+                });
+                responses.order.push_back(key);
+                if responses.order.len() > self.max_responses {
+                    responses.forget_oldest();
+                }
+                None
+            }
+        }
+    }
+
+    /// The number of responses kept.
+    pub fn len(&self) -> usize {
+        self.responses.lock().unwrap().order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn take_conflicts(&self) -> Vec<ResponseConflict> {
+        std::mem::take(&mut *self.conflicts.lock().unwrap())
+    }
+}
+
+impl RecordedResponses {
+    fn forget_oldest(&mut self) {
+        let key = match self.order.pop_front() {
+            Some(key) => key,
+            None => return,
+        };
+        // Responses to a request are recorded in order, so its oldest is the first one.
+        if let Some(recorded) = self.by_request.get_mut(&key) {
+            recorded.pop_front();
+            if recorded.is_empty() {
+                self.by_request.remove(&key);
+            }
+        }
+    }
+}
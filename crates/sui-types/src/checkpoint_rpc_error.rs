@@ -0,0 +1,144 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Classification of checkpoint failures for JSON-RPC servers, so that every server reports a
+//! given failure with the same error code and data, whatever SDK the client uses.
+
+use serde::{Deserialize, Serialize};
+
+use crate::committee::EpochId;
+use crate::error::SuiError;
+use crate::messages_checkpoint::CheckpointSequenceNumber;
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_rpc_error_tests.rs"]
+mod checkpoint_rpc_error_tests;
+
+/// The JSON-RPC code of internal errors, used for failures of no other class.
+pub const INTERNAL_ERROR_CODE: i32 = -32603;
+
+/// The class of a checkpoint failure. Codes are taken from the range the JSON-RPC
+/// specification reserves for server errors, and must never change once released.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CheckpointError {
+    /// The checkpoint was not available in time.
+    Timeout,
+    /// The server or the client is in another epoch, or misses the committee of the epoch.
+    EpochMismatch,
+    /// A checkpoint or signature failed verification.
+    VerificationFailed,
+    /// A validator refused to sign a checkpoint, e.g. since it would equivocate.
+    SigningRefused,
+    /// Checkpoints could not be read from or written to storage.
+    Storage,
+    Internal,
+}
+
+impl CheckpointError {
+    pub fn classify(error: &SuiError) -> Self {
+        match error {
+            SuiError::CheckpointWaitTimeout { .. } | SuiError::TimeoutError => Self::Timeout,
+            SuiError::WrongEpoch { .. }
+            | SuiError::MissingCommitteeAtEpoch(_)
+            | SuiError::ValidatorHaltedAtEpochEnd => Self::EpochMismatch,
+            SuiError::InvalidSignature { .. }
+            | SuiError::IncorrectSigner { .. }
+            | SuiError::UnknownSigner
+            | SuiError::CertificateRequiresQuorum
+            | SuiError::CheckpointGasMismatch { .. }
+            | SuiError::InvalidNextEpochCommitteeSize { .. }
+            | SuiError::DuplicateNextEpochCommitteeMember { .. }
+            | SuiError::UnsortedNextEpochCommittee
+            | SuiError::InvalidNextEpochCommitteeStake
+            | SuiError::ChunkedDecodeError { .. } => Self::VerificationFailed,
+            SuiError::CheckpointSignatureRegression { .. }
+            | SuiError::ConflictingCheckpointSignature { .. }
+            | SuiError::CheckpointSigningRefused { .. } => Self::SigningRefused,
+            SuiError::StorageError(_)
+            | SuiError::GenericStorageError(_)
+            | SuiError::StorageMissingFieldError(_)
+            | SuiError::StorageCorruptedFieldError(_) => Self::Storage,
+            _ => Self::Internal,
+        }
+    }
+
+    pub fn rpc_code(&self) -> i32 {
+        match self {
+            Self::Timeout => -32050,
+            Self::EpochMismatch => -32051,
+            Self::VerificationFailed => -32052,
+            Self::SigningRefused => -32053,
+            Self::Storage => -32054,
+            Self::Internal => INTERNAL_ERROR_CODE,
+        }
+    }
+
+    /// Whether the same request may succeed later: once the checkpoint is made, the epoch
+    /// change is processed, or storage recovers. Failed verifications are only worth
+    /// retrying against another server.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Timeout | Self::EpochMismatch | Self::Storage)
+    }
+}
+
+impl From<&SuiError> for CheckpointError {
+    fn from(error: &SuiError) -> Self {
+        Self::classify(error)
+    }
+}
+
+/// The `data` of a JSON-RPC error reporting a checkpoint failure.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointErrorData {
+    pub kind: CheckpointError,
+    pub retryable: bool,
+    /// The epoch the failure is about, if known.
+    pub epoch: Option<EpochId>,
+    /// The checkpoint the failure is about, if known.
+    pub sequence_number: Option<CheckpointSequenceNumber>,
+}
+
+impl CheckpointErrorData {
+    pub fn new(error: &SuiError) -> Self {
+        let kind = CheckpointError::classify(error);
+        let (epoch, sequence_number) = match error {
+            SuiError::CheckpointWaitTimeout {
+                sequence_number, ..
+            }
+            | SuiError::CheckpointGasMismatch {
+                sequence_number, ..
+            }
+            | SuiError::CheckpointSigningRefused {
+                sequence_number, ..
+            } => (None, Some(*sequence_number)),
+            SuiError::CheckpointSignatureRegression {
+                epoch,
+                sequence_number,
+                ..
+            }
+            | SuiError::ConflictingCheckpointSignature {
+                epoch,
+                sequence_number,
+                ..
+            } => (Some(*epoch), Some(*sequence_number)),
+            SuiError::WrongEpoch { actual_epoch, .. } => (Some(*actual_epoch), None),
+            SuiError::MissingCommitteeAtEpoch(epoch) => (Some(*epoch), None),
+            _ => (None, None),
+        };
+        Self {
+            kind,
+            retryable: kind.is_retryable(),
+            epoch,
+            sequence_number,
+        }
+    }
+
+    pub fn rpc_code(&self) -> i32 {
+        self.kind.rpc_code()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("Serialization should not fail")
+    }
+}
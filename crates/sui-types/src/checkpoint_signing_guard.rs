@@ -0,0 +1,187 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A local record of the highest checkpoint messages signed by a validator, refusing to sign
+//! anything that would make it equivocate, even after its database is restored from a backup
+//! that predates some of its signatures.
+
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::committee::EpochId;
+use crate::error::{SuiError, SuiResult};
+use crate::messages_checkpoint::{
+    CheckpointProposalSummary, CheckpointSequenceNumber, CheckpointSummary, SigningPolicy,
+    SigningRefusal,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_signing_guard_tests.rs"]
+mod checkpoint_signing_guard_tests;
+
+/// The highest message of one kind signed by a validator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedHighWaterMark {
+    pub epoch: EpochId,
+    pub sequence_number: CheckpointSequenceNumber,
+    pub digest: [u8; 32],
+}
+
+/// The highest checkpoint summary and proposal signed by a validator. A message below the
+/// high-water mark of its kind, or for the same checkpoint with another digest, is refused.
+/// Signing the very message of the mark again is allowed, e.g. to resend it.
+///
+/// The guard must be persisted before any signature it allowed is released, and kept apart
+/// from the database of the validator, so that restoring the database does not restore an
+/// older guard.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningGuard {
+    pub summary: Option<SignedHighWaterMark>,
+    pub proposal: Option<SignedHighWaterMark>,
+}
+
+impl SigningGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check that `summary` can be signed, without recording it.
+    pub fn check_summary(&self, summary: &CheckpointSummary) -> SuiResult {
+        check("summary", self.summary, &summary_mark(summary))
+    }
+
+    /// Check that `summary` can be signed, and raise the high-water mark to it.
+    pub fn record_summary(&mut self, summary: &CheckpointSummary) -> SuiResult {
+        let mark = summary_mark(summary);
+        check("summary", self.summary, &mark)?;
+        self.summary = Some(mark);
+        Ok(())
+    }
+
+    /// Check that a proposal of `epoch` with `summary` can be signed, without recording it.
+    pub fn check_proposal(&self, epoch: EpochId, summary: &CheckpointProposalSummary) -> SuiResult {
+        check("proposal", self.proposal, &proposal_mark(epoch, summary))
+    }
+
+    /// Check that a proposal of `epoch` with `summary` can be signed, and raise the
+    /// high-water mark to it.
+    pub fn record_proposal(
+        &mut self,
+        epoch: EpochId,
+        summary: &CheckpointProposalSummary,
+    ) -> SuiResult {
+        let mark = proposal_mark(epoch, summary);
+        check("proposal", self.proposal, &mark)?;
+        self.proposal = Some(mark);
+        Ok(())
+    }
+}
+
+fn summary_mark(summary: &CheckpointSummary) -> SignedHighWaterMark {
+    SignedHighWaterMark {
+        epoch: summary.epoch,
+        sequence_number: summary.sequence_number,
+        digest: summary.digest(),
+    }
+}
+
+fn proposal_mark(epoch: EpochId, summary: &CheckpointProposalSummary) -> SignedHighWaterMark {
+    SignedHighWaterMark {
+        epoch,
+        sequence_number: summary.sequence_number,
+        digest: summary.digest(),
+    }
+}
+
+fn check(kind: &str, signed: Option<SignedHighWaterMark>, mark: &SignedHighWaterMark) -> SuiResult {
+    let signed = match signed {
+        Some(signed) => signed,
+        None => return Ok(()),
+    };
+    let position = (mark.epoch, mark.sequence_number);
+    let signed_position = (signed.epoch, signed.sequence_number);
+    fp_ensure!(
+        position >= signed_position,
+        SuiError::CheckpointSignatureRegression {
+            kind: kind.to_string(),
+            epoch: mark.epoch,
+            sequence_number: mark.sequence_number,
+            signed_epoch: signed.epoch,
+            signed_sequence_number: signed.sequence_number,
+        }
+    );
+    fp_ensure!(
+        position > signed_position || mark.digest == signed.digest,
+        SuiError::ConflictingCheckpointSignature {
+            kind: kind.to_string(),
+            epoch: mark.epoch,
+            sequence_number: mark.sequence_number,
+        }
+    );
+    Ok(())
+}
+
+/// Stores a `SigningGuard` durably.
+pub trait SigningGuardPersistence: Send + Sync {
+    fn persist(&self, guard: &SigningGuard) -> SuiResult;
+}
+
+/// A `SigningGuard` persisted on every change, before the signature it allows is released.
+/// As a `SigningPolicy`, it guards the summaries signed by a checkpoint store.
+pub struct PersistedSigningGuard {
+    guard: Mutex<SigningGuard>,
+    persistence: Box<dyn SigningGuardPersistence>,
+}
+
+impl PersistedSigningGuard {
+    /// Resume from `guard`, as last persisted.
+    pub fn new(guard: SigningGuard, persistence: Box<dyn SigningGuardPersistence>) -> Self {
+        Self {
+            guard: Mutex::new(guard),
+            persistence,
+        }
+    }
+
+    pub fn guard(&self) -> SigningGuard {
+        self.guard.lock().unwrap().clone()
+    }
+
+    /// Record `summary`, see `SigningGuard::record_summary`. The guard is left unchanged if
+    /// it cannot be persisted.
+    pub fn record_summary(&self, summary: &CheckpointSummary) -> SuiResult {
+        self.update(|guard| guard.record_summary(summary))
+    }
+
+    /// Record a proposal, see `SigningGuard::record_proposal`. The guard is left unchanged
+    /// if it cannot be persisted.
+    pub fn record_proposal(
+        &self,
+        epoch: EpochId,
+        summary: &CheckpointProposalSummary,
+    ) -> SuiResult {
+        self.update(|guard| guard.record_proposal(epoch, summary))
+    }
+
+    fn update(&self, f: impl FnOnce(&mut SigningGuard) -> SuiResult) -> SuiResult {
+        let mut guard = self.guard.lock().unwrap();
+        let mut updated = guard.clone();
+        f(&mut updated)?;
+        if updated != *guard {
+            self.persistence.persist(&updated)?;
+            *guard = updated;
+        }
+        Ok(())
+    }
+}
+
+impl SigningPolicy for PersistedSigningGuard {
+    fn review(&self, summary: &CheckpointSummary) -> Result<(), SigningRefusal> {
+        self.record_summary(summary)
+            .map_err(|error| SigningRefusal {
+                policy: "PersistedSigningGuard".to_string(),
+                sequence_number: summary.sequence_number,
+                reason: error.to_string(),
+            })
+    }
+}
@@ -0,0 +1,623 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A deterministic, single process simulation of the checkpoint protocol, so that changes to
+//! the protocol can be tested end to end within this crate. Authorities propose, exchange
+//! fragments through a simulated consensus, reconstruct the contents of the checkpoint, and
+//! sign and certify it, across sequence numbers and epoch changes, while a `FaultInjector`
+//! drops or delays fragment messages and makes authorities equivocate.
+//!
+//! The simulation cuts corners where the real protocol depends on execution: transactions
+//! are certified transfers that are never executed, effects are random digests, and
+//! checkpoint contents are ordered by digest rather than causally.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::base_types::{
+    AuthorityName, ExecutionDigests, ObjectDigest, ObjectID, SequenceNumber, TransactionDigest,
+    TransactionEffectsDigest,
+};
+use crate::committee::{Committee, EpochId};
+use crate::crypto::{get_key_pair_from_rng, AccountKeyPair, AuthorityKeyPair, Signature};
+use crate::error::{SuiError, SuiResult};
+use crate::gas::GasCostSummary;
+use crate::messages::{CertifiedTransaction, SenderSignedData, SignedTransaction, TransactionData};
+use crate::messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointContents, CheckpointDigest, CheckpointFragment,
+    CheckpointFragmentMessage, CheckpointFragmentMessageChunk, CheckpointProposal,
+    CheckpointProposalContents, CheckpointSequenceNumber, FragmentHeaderDigest,
+    PartialCheckpointFragment, SignedCheckpointFragmentMessage, SignedCheckpointProposalSummary,
+    SignedCheckpointSummary,
+};
+use crate::utils::make_committee_key_num;
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_sim_tests.rs"]
+mod checkpoint_sim_tests;
+
+/// What happens to a fragment message on its way through consensus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Delivery {
+    Deliver,
+    Drop,
+    /// Deliver the message at the next attempt to build the checkpoint.
+    Delay,
+}
+
+/// Decides which faults happen during a simulation. By default, nothing goes wrong.
+pub trait FaultInjector {
+    fn fragment_message(
+        &mut self,
+        _sequence_number: CheckpointSequenceNumber,
+        _message: &CheckpointFragmentMessage,
+    ) -> Delivery {
+        Delivery::Deliver
+    }
+
+    /// Whether `authority` signs a summary conflicting with the agreed one.
+    fn equivocates(
+        &mut self,
+        _sequence_number: CheckpointSequenceNumber,
+        _authority: &AuthorityName,
+    ) -> bool {
+        false
+    }
+}
+
+pub struct NoFaults;
+
+impl FaultInjector for NoFaults {}
+
+/// Drops and delays fragment chunks at random, and has a fixed set of authorities
+/// equivocate on every checkpoint. Headers always go through, so that faults only hit chunks.
+pub struct RandomFaults {
+    rng: StdRng,
+    pub drop_probability: f64,
+    pub delay_probability: f64,
+    pub equivocators: BTreeSet<AuthorityName>,
+}
+
+impl RandomFaults {
+    pub fn new(seed: [u8; 32], drop_probability: f64, delay_probability: f64) -> Self {
+        Self {
+            rng: StdRng::from_seed(seed),
+            drop_probability,
+            delay_probability,
+            equivocators: BTreeSet::new(),
+        }
+    }
+}
+
+impl FaultInjector for RandomFaults {
+    fn fragment_message(
+        &mut self,
+        _sequence_number: CheckpointSequenceNumber,
+        message: &CheckpointFragmentMessage,
+    ) -> Delivery {
+        if let CheckpointFragmentMessage::Header(_) = message {
+            return Delivery::Deliver;
+        }
+        let draw: f64 = self.rng.gen();
+        if draw < self.drop_probability {
+            Delivery::Drop
+        } else if draw < self.drop_probability + self.delay_probability {
+            Delivery::Delay
+        } else {
+            Delivery::Deliver
+        }
+    }
+
+    fn equivocates(
+        &mut self,
+        _sequence_number: CheckpointSequenceNumber,
+        authority: &AuthorityName,
+    ) -> bool {
+        self.equivocators.contains(authority)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum SimOutcome {
+    Certified(CertifiedCheckpointSummary),
+    /// The verified fragments do not connect the proposals of a quorum.
+    NoQuorumOfProposals,
+    /// No summary gathered the signatures of a quorum.
+    NoQuorumOfSignatures,
+}
+
+/// What happened during one attempt to build a checkpoint.
+#[derive(Clone, Debug)]
+pub struct SimAttempt {
+    pub sequence_number: CheckpointSequenceNumber,
+    pub delivered: usize,
+    pub dropped: usize,
+    pub delayed: usize,
+    /// The fragments fully received and verified so far for this checkpoint.
+    pub verified_fragments: usize,
+    pub outcome: SimOutcome,
+}
+
+struct SimAuthority {
+    name: AuthorityName,
+    key: AuthorityKeyPair,
+    /// Executed transactions not yet in a checkpoint.
+    pending: BTreeSet<ExecutionDigests>,
+}
+
+pub struct CheckpointSim<F> {
+    rng: StdRng,
+    faults: F,
+    genesis_committee: Committee,
+    committee: Committee,
+    authorities: Vec<SimAuthority>,
+    next_epoch: Option<(Committee, Vec<SimAuthority>)>,
+    next_sequence_number: CheckpointSequenceNumber,
+    previous_digest: Option<CheckpointDigest>,
+    checkpoints: Vec<(CertifiedCheckpointSummary, CheckpointContents)>,
+    /// The certificates of the executed transactions, to include in fragments.
+    certs: HashMap<ExecutionDigests, CertifiedTransaction>,
+    // The state of consensus for the checkpoint being built.
+    delayed: Vec<SignedCheckpointFragmentMessage>,
+    partial: HashMap<FragmentHeaderDigest, PartialCheckpointFragment>,
+    orphan_chunks: HashMap<FragmentHeaderDigest, Vec<CheckpointFragmentMessageChunk>>,
+    fragments: BTreeMap<(AuthorityName, AuthorityName), CheckpointFragment>,
+}
+
+impl<F: FaultInjector> CheckpointSim<F> {
+    /// A simulation of `committee_size` authorities of equal stake, fully determined by `seed`.
+    pub fn new(seed: [u8; 32], committee_size: usize, faults: F) -> Self {
+        let mut rng = StdRng::from_seed(seed);
+        let (committee, authorities) = make_authorities(&mut rng, 0, committee_size);
+        Self {
+            rng,
+            faults,
+            genesis_committee: committee.clone(),
+            committee,
+            authorities,
+            next_epoch: None,
+            next_sequence_number: 0,
+            previous_digest: None,
+            checkpoints: Vec::new(),
+            certs: HashMap::new(),
+            delayed: Vec::new(),
+            partial: HashMap::new(),
+            orphan_chunks: HashMap::new(),
+            fragments: BTreeMap::new(),
+        }
+    }
+
+    pub fn committee(&self) -> &Committee {
+        &self.committee
+    }
+
+    pub fn genesis_committee(&self) -> &Committee {
+        &self.genesis_committee
+    }
+
+    pub fn faults_mut(&mut self) -> &mut F {
+        &mut self.faults
+    }
+
+    pub fn authority_names(&self) -> Vec<AuthorityName> {
+        self.authorities.iter().map(|a| a.name).collect()
+    }
+
+    pub fn next_sequence_number(&self) -> CheckpointSequenceNumber {
+        self.next_sequence_number
+    }
+
+    /// The certified checkpoints so far, with their contents.
+    pub fn checkpoints(&self) -> &[(CertifiedCheckpointSummary, CheckpointContents)] {
+        &self.checkpoints
+    }
+
+    /// Create and certify `count` transactions, each executed by a random non-empty subset of
+    /// the authorities.
+    pub fn execute_transactions(&mut self, count: usize) -> Vec<ExecutionDigests> {
+        (0..count)
+            .map(|_| {
+                let (sender, sender_key): (_, AccountKeyPair) =
+                    get_key_pair_from_rng(&mut self.rng);
+                let mut object_ref = || {
+                    (
+                        ObjectID::random_from_rng(&mut self.rng),
+                        SequenceNumber::new(),
+                        ObjectDigest::new([0; 32]),
+                    )
+                };
+                let data = TransactionData::new_transfer(
+                    sender,
+                    object_ref(),
+                    sender,
+                    object_ref(),
+                    10000,
+                );
+                let signature = Signature::new(&data, &sender_key);
+                let cert = certify_transaction(
+                    SenderSignedData::new(data, signature),
+                    &self.authorities,
+                    &self.committee,
+                );
+                let digests =
+                    ExecutionDigests::new(*cert.digest(), TransactionEffectsDigest(self.rng.gen()));
+                self.certs.insert(digests, cert);
+                let forced = self.rng.gen_range(0..self.authorities.len());
+                for (index, authority) in self.authorities.iter_mut().enumerate() {
+                    if index == forced || self.rng.gen_bool(0.75) {
+                        authority.pending.insert(digests);
+                    }
+                }
+                digests
+            })
+            .collect()
+    }
+
+    /// Have the next certified checkpoint close the epoch, handing over to a new committee of
+    /// `committee_size` authorities.
+    pub fn schedule_epoch_change(&mut self, committee_size: usize) {
+        let next = make_authorities(&mut self.rng, self.committee.epoch + 1, committee_size);
+        self.next_epoch = Some(next);
+    }
+
+    /// Make one attempt at building the next checkpoint.
+    pub fn run_attempt(&mut self) -> SimAttempt {
+        let sequence_number = self.next_sequence_number;
+        let epoch = self.committee.epoch;
+        let proposals: Vec<_> = self
+            .authorities
+            .iter()
+            .map(|authority| {
+                CheckpointProposal::new(
+                    epoch,
+                    sequence_number,
+                    authority.name,
+                    &authority.key,
+                    CheckpointProposalContents::new(authority.pending.iter().cloned()),
+                )
+            })
+            .collect();
+
+        // Every authority fragments with the next one, and sends the fragment through
+        // consensus, after the messages delayed during the previous attempt.
+        let mut arrived = std::mem::take(&mut self.delayed);
+        let (mut dropped, mut delayed) = (0, 0);
+        for (index, proposal) in proposals.iter().enumerate() {
+            let other = &proposals[(index + 1) % proposals.len()];
+            if other.name() == proposal.name() {
+                continue;
+            }
+            let mut fragment = proposal.fragment_with(other);
+            let diff = &fragment.data.diff;
+            let certs = diff
+                .first
+                .items
+                .iter()
+                .chain(diff.second.items.iter())
+                .map(|digests| (*digests, self.certs[digests].clone()))
+                .collect();
+            fragment.data.certs = certs;
+            let messages = fragment.to_signed_message_chunks(&self.authorities[index].key);
+            for message in messages {
+                match self
+                    .faults
+                    .fragment_message(sequence_number, &message.message)
+                {
+                    Delivery::Deliver => arrived.push(message),
+                    Delivery::Drop => dropped += 1,
+                    Delivery::Delay => {
+                        self.delayed.push(message);
+                        delayed += 1;
+                    }
+                }
+            }
+        }
+        let delivered = arrived.len();
+        for message in arrived {
+            self.receive(message);
+        }
+
+        let outcome = match self.reconstruct(&proposals) {
+            Some(contents) => self.certify(contents),
+            None => SimOutcome::NoQuorumOfProposals,
+        };
+        SimAttempt {
+            sequence_number,
+            delivered,
+            dropped,
+            delayed,
+            verified_fragments: self.fragments.len(),
+            outcome,
+        }
+    }
+
+    /// Make attempts at building the next checkpoint until it is certified.
+    pub fn run_until_certified(
+        &mut self,
+        max_attempts: usize,
+    ) -> SuiResult<CertifiedCheckpointSummary> {
+        for _ in 0..max_attempts {
+            if let SimOutcome::Certified(checkpoint) = self.run_attempt().outcome {
+                return Ok(checkpoint);
+            }
+        }
+        Err(SuiError::from(
+            format!(
+                "Checkpoint {} not certified after {} attempts",
+                self.next_sequence_number, max_attempts
+            )
+            .as_str(),
+        ))
+    }
+
+    /// Check the whole chain of certified checkpoints from the genesis committee, as a
+    /// client following it would.
+    pub fn verify_chain(&self) -> SuiResult {
+        CertifiedCheckpointSummary::verify_chain(
+            self.genesis_committee.clone(),
+            0,
+            None,
+            self.checkpoints
+                .iter()
+                .map(|(checkpoint, contents)| (checkpoint, Some(contents))),
+        )?;
+        Ok(())
+    }
+
+    /// Feed a message sequenced by consensus to the fragment reassembly shared by all
+    /// authorities. Invalid messages and fragments are ignored.
+    fn receive(&mut self, message: SignedCheckpointFragmentMessage) {
+        if message.verify(self.committee.epoch).is_err()
+            || message.message.proposer_sequence_number() != self.next_sequence_number
+        {
+            return;
+        }
+        let header_digest = match message.message {
+            CheckpointFragmentMessage::Header(header) => {
+                let header_digest = header.digest();
+                let partial = self
+                    .partial
+                    .entry(header_digest)
+                    .or_insert_with(|| PartialCheckpointFragment::new(*header));
+                for chunk in self
+                    .orphan_chunks
+                    .remove(&header_digest)
+                    .unwrap_or_default()
+                {
+                    let _ = partial.add_chunk(chunk);
+                }
+                header_digest
+            }
+            CheckpointFragmentMessage::Chunk(chunk) => {
+                let header_digest = chunk.header_digest;
+                match self.partial.get_mut(&header_digest) {
+                    Some(partial) => {
+                        let _ = partial.add_chunk(*chunk);
+                    }
+                    None => self
+                        .orphan_chunks
+                        .entry(header_digest)
+                        .or_default()
+                        .push(*chunk),
+                }
+                header_digest
+            }
+        };
+
+        if self
+            .partial
+            .get(&header_digest)
+            .map_or(false, |partial| partial.is_complete())
+        {
+            let partial = self.partial.remove(&header_digest).unwrap();
+            if let Ok(fragment) = partial.to_fragment() {
+                if fragment.verify(&self.committee).is_ok() {
+                    self.fragments.insert(
+                        (*fragment.proposer.authority(), *fragment.other.authority()),
+                        fragment,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Reconstruct the contents of the checkpoint from the verified fragments between the
+    /// current proposals, if they connect the proposals of a quorum. The first authority of
+    /// the quorum starts from its own proposal and derives the others through the diffs.
+    fn reconstruct(&self, proposals: &[CheckpointProposal]) -> Option<CheckpointContents> {
+        let content_digests: HashMap<_, _> = proposals
+            .iter()
+            .map(|p| (*p.name(), p.signed_summary.data().content_digest))
+            .collect();
+        let mut edges: HashMap<AuthorityName, Vec<&CheckpointFragment>> = HashMap::new();
+        for fragment in self.fragments.values() {
+            let current = |side: &SignedCheckpointProposalSummary| {
+                content_digests.get(side.authority()) == Some(&side.data().content_digest)
+            };
+            // Fragments of earlier attempts may be between proposals since replaced.
+            if current(&fragment.proposer) && current(&fragment.other) {
+                edges
+                    .entry(*fragment.proposer.authority())
+                    .or_default()
+                    .push(fragment);
+                edges
+                    .entry(*fragment.other.authority())
+                    .or_default()
+                    .push(fragment);
+            }
+        }
+
+        for root in proposals {
+            let mut known = BTreeMap::new();
+            known.insert(
+                *root.name(),
+                root.transactions().cloned().collect::<BTreeSet<_>>(),
+            );
+            let mut queue = VecDeque::from([*root.name()]);
+            while let Some(name) = queue.pop_front() {
+                for fragment in edges.get(&name).into_iter().flatten() {
+                    let (other, missing, extra) = if fragment.proposer.authority() == &name {
+                        (
+                            *fragment.other.authority(),
+                            &fragment.data.diff.first.items,
+                            &fragment.data.diff.second.items,
+                        )
+                    } else {
+                        (
+                            *fragment.proposer.authority(),
+                            &fragment.data.diff.second.items,
+                            &fragment.data.diff.first.items,
+                        )
+                    };
+                    if known.contains_key(&other) {
+                        continue;
+                    }
+                    let mut contents = known[&name].clone();
+                    contents.extend(missing.iter().cloned());
+                    extra.iter().for_each(|digests| {
+                        contents.remove(digests);
+                    });
+                    if CheckpointProposalContents::new(contents.iter().cloned()).digest()
+                        == content_digests[&other]
+                    {
+                        known.insert(other, contents);
+                        queue.push_back(other);
+                    }
+                }
+            }
+
+            let stake: u64 = known.keys().map(|name| self.committee.weight(name)).sum();
+            if stake >= self.committee.quorum_threshold() {
+                let transactions: BTreeSet<_> = known.into_values().flatten().collect();
+                return Some(CheckpointContents::new_with_causally_ordered_transactions(
+                    transactions.into_iter(),
+                ));
+            }
+        }
+        None
+    }
+
+    /// Have every authority sign the summary of `contents`, and certify it if a quorum
+    /// agrees. Equivocating authorities sign contents with an extra transaction.
+    fn certify(&mut self, contents: CheckpointContents) -> SimOutcome {
+        let sequence_number = self.next_sequence_number;
+        let next_committee = self.next_epoch.as_ref().map(|(c, _)| c.clone());
+        let mut signed: BTreeMap<CheckpointDigest, Vec<SignedCheckpointSummary>> = BTreeMap::new();
+        for authority in &self.authorities {
+            let signed_contents = if self.faults.equivocates(sequence_number, &authority.name) {
+                CheckpointContents::new_with_causally_ordered_transactions(
+                    contents.iter().cloned().chain([ExecutionDigests::new(
+                        TransactionDigest::new(self.rng.gen()),
+                        TransactionEffectsDigest(self.rng.gen()),
+                    )]),
+                )
+            } else {
+                contents.clone()
+            };
+            let summary = SignedCheckpointSummary::new_from_contents(
+                self.committee.epoch,
+                sequence_number,
+                authority.name,
+                &authority.key,
+                &signed_contents,
+                self.previous_digest,
+                GasCostSummary::default(),
+                next_committee.clone(),
+            );
+            signed
+                .entry(summary.data().digest())
+                .or_default()
+                .push(summary);
+        }
+
+        let quorum = signed.into_values().find(|group| {
+            group
+                .iter()
+                .map(|s| self.committee.weight(s.authority()))
+                .sum::<u64>()
+                >= self.committee.quorum_threshold()
+        });
+        let checkpoint = match quorum
+            .and_then(|group| CertifiedCheckpointSummary::aggregate(group, &self.committee).ok())
+        {
+            Some(checkpoint) => checkpoint,
+            None => return SimOutcome::NoQuorumOfSignatures,
+        };
+
+        for digests in contents.iter() {
+            self.certs.remove(digests);
+            for authority in &mut self.authorities {
+                authority.pending.remove(digests);
+            }
+        }
+        self.previous_digest = Some(checkpoint.data().digest());
+        self.next_sequence_number += 1;
+        self.checkpoints.push((checkpoint.clone(), contents));
+        self.delayed.clear();
+        self.partial.clear();
+        self.orphan_chunks.clear();
+        self.fragments.clear();
+
+        if let Some((committee, mut authorities)) = self.next_epoch.take() {
+            // The new committee executes whatever the old one left out of checkpoints.
+            let pending: BTreeSet<_> = self
+                .authorities
+                .iter()
+                .flat_map(|a| a.pending.iter().cloned())
+                .collect();
+            for authority in &mut authorities {
+                authority.pending = pending.clone();
+            }
+            // Their certificates must be signed by the new committee to be included.
+            for digests in &pending {
+                let cert = self.certs.get_mut(digests).unwrap();
+                *cert = certify_transaction(cert.data().clone(), &authorities, &committee);
+            }
+            self.committee = committee;
+            self.authorities = authorities;
+        }
+        SimOutcome::Certified(checkpoint)
+    }
+}
+
+fn certify_transaction(
+    data: SenderSignedData,
+    authorities: &[SimAuthority],
+    committee: &Committee,
+) -> CertifiedTransaction {
+    let signatures = authorities
+        .iter()
+        .map(|authority| {
+            SignedTransaction::new(
+                committee.epoch,
+                data.clone(),
+                &authority.key,
+                authority.name,
+            )
+            .auth_sig()
+            .clone()
+        })
+        .collect();
+    CertifiedTransaction::new(data, signatures, committee).unwrap()
+}
+
+fn make_authorities(
+    rng: &mut StdRng,
+    epoch: EpochId,
+    committee_size: usize,
+) -> (Committee, Vec<SimAuthority>) {
+    let (keys, committee) = make_committee_key_num(committee_size, rng);
+    let committee = Committee::new(epoch, committee.voting_rights.into_iter().collect()).unwrap();
+    let authorities = keys
+        .into_iter()
+        .map(|key| SimAuthority {
+            name: key.public().into(),
+            key,
+            pending: BTreeSet::new(),
+        })
+        .collect();
+    (committee, authorities)
+}
@@ -0,0 +1,592 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Versions of the checkpoint summary, so that fields can be added to summaries without
+//! breaking the ones already signed.
+//!
+//! Compatibility rules:
+//! - Version 1 is the layout of `CheckpointSummary` when versions were introduced, frozen in
+//!   `CheckpointSummaryV1`. It is signed under the name `CheckpointSummary`, so that its
+//!   encoding, digest and signing message are those of the summaries signed before.
+//! - Every other version is its own struct, whose name prefixes its signing message, so that
+//!   the signature of a summary of one version can never be valid for another version.
+//! - A `VersionedCheckpointSummary` is digested and signed as the summary it holds: the
+//!   version is implied by the name prefix, not by the enum tag.
+//! - Versions are only ever appended to the enum, whose tag is the version minus one.
+//!
+//! Checkpoints are built, signed and certified as version 4, which is `CheckpointSummary`
+//! itself: version 1 with the Merkle root of the contents, so that `verify_inclusion` works on
+//! every certified checkpoint, and the commitment to its object changes when it was built from
+//! the effects of its transactions, for `verify_object_change`. Nothing builds versions 2 and 3
+//! yet, as they also carry the time of the checkpoint, which validators have no agreed value
+//! for.
+
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::{AuthorityName, ExecutionDigests, ObjectRef};
+use crate::checkpoint_commitment::{ContentsMerkleRoot, MerkleProof};
+use crate::checkpoint_object_changes::{verify_object_change, ObjectChangesRoot};
+use crate::committee::{Committee, EpochId, EpochIdExt, StakeUnit};
+use crate::crypto::{
+    sha3_hash, AuthoritySignInfo, AuthoritySignInfoTrait, AuthorityWeakQuorumSignInfo, Signable,
+};
+use crate::error::{SuiError, SuiResult};
+use crate::gas::GasCostSummary;
+use crate::messages_checkpoint::{
+    add_gas_costs, CheckpointContentsDigest, CheckpointDigest, CheckpointSequenceNumber,
+    CheckpointSummary, EndOfEpochData, GasFeeAttribution,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_summary_versions_tests.rs"]
+mod checkpoint_summary_versions_tests;
+
+/// Version 1, which checkpoints were built with before version 4, i.e. before
+/// `CheckpointSummary` carried a contents Merkle root.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename = "CheckpointSummary")]
+pub struct CheckpointSummaryV1 {
+    pub epoch: EpochId,
+    pub sequence_number: CheckpointSequenceNumber,
+    pub content_digest: CheckpointContentsDigest,
+    pub previous_digest: Option<CheckpointDigest>,
+    pub gas_cost_summary: GasCostSummary,
+    pub next_epoch_committee: Option<Vec<(AuthorityName, StakeUnit)>>,
+}
+
+/// Version 2 adds the time at which the checkpoint was built and a Merkle root over its
+/// contents, see `ContentsCommitments`. Not produced yet, see the module documentation.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CheckpointSummaryV2 {
+    pub epoch: EpochId,
+    pub sequence_number: CheckpointSequenceNumber,
+    pub content_digest: CheckpointContentsDigest,
+    pub contents_merkle_root: ContentsMerkleRoot,
+    pub previous_digest: Option<CheckpointDigest>,
+    pub gas_cost_summary: GasCostSummary,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub next_epoch_committee: Option<Vec<(AuthorityName, StakeUnit)>>,
+    pub end_of_epoch_data: Option<EndOfEpochData>,
+}
+
+/// Version 3 may add a commitment to the object versions written by the checkpoint, see
+/// `ObjectChanges`. Not produced yet, see the module documentation.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CheckpointSummaryV3 {
+    pub epoch: EpochId,
+    pub sequence_number: CheckpointSequenceNumber,
+    pub content_digest: CheckpointContentsDigest,
+    pub contents_merkle_root: ContentsMerkleRoot,
+    pub object_changes_commitment: Option<ObjectChangesRoot>,
+    pub previous_digest: Option<CheckpointDigest>,
+    pub gas_cost_summary: GasCostSummary,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub next_epoch_committee: Option<Vec<(AuthorityName, StakeUnit)>>,
+    pub end_of_epoch_data: Option<EndOfEpochData>,
+}
+
+/// The fields of a checkpoint summary, whatever its version. Fields missing from a version
+/// are None.
+pub trait CheckpointSummaryAccess {
+    fn version(&self) -> u8;
+    fn epoch(&self) -> EpochId;
+    fn sequence_number(&self) -> CheckpointSequenceNumber;
+    fn content_digest(&self) -> &CheckpointContentsDigest;
+    fn previous_digest(&self) -> Option<&CheckpointDigest>;
+    fn gas_cost_summary(&self) -> &GasCostSummary;
+    fn next_epoch_committee(&self) -> Option<&[(AuthorityName, StakeUnit)]>;
+    fn end_of_epoch_data(&self) -> Option<&EndOfEpochData>;
+    fn contents_merkle_root(&self) -> Option<&ContentsMerkleRoot>;
+    fn object_changes_commitment(&self) -> Option<&ObjectChangesRoot>;
+    fn timestamp_ms(&self) -> Option<u64>;
+    /// The digest signed by authorities.
+    fn digest(&self) -> CheckpointDigest;
+
+    /// Check that `digests` is in the contents of the checkpoint, without the contents. Only
+    /// summaries with a contents Merkle root, from version 2, can be proven against.
+    fn verify_inclusion(&self, digests: &ExecutionDigests, proof: &MerkleProof) -> SuiResult {
+        let root = self.contents_merkle_root().ok_or_else(|| {
+            SuiError::from(
+                format!(
+                    "Version {} checkpoint summaries have no contents Merkle root",
+                    self.version()
+                )
+                .as_str(),
+            )
+        })?;
+        proof.verify(root, digests)
+    }
+
+    /// Check that end-of-epoch data is only set on the last checkpoint of an epoch, and is
+    /// well formed.
+    fn verify_end_of_epoch_data(&self) -> SuiResult {
+        let data = match self.end_of_epoch_data() {
+            Some(data) => data,
+            None => return Ok(()),
+        };
+        fp_ensure!(
+            self.next_epoch_committee().is_some(),
+            SuiError::from(
+                format!(
+                    "Checkpoint {} has end-of-epoch data but is not the last of its epoch",
+                    self.sequence_number()
+                )
+                .as_str()
+            )
+        );
+        match data {
+            EndOfEpochData::V1 {
+                gas_fee_attribution,
+            } => gas_fee_attribution.check(),
+        }
+    }
+
+    /// Check that the checkpoint wrote `object_ref`, see `ObjectChanges::prove`.
+    fn verify_object_change(&self, object_ref: &ObjectRef, proof: &MerkleProof) -> SuiResult {
+        let root = self
+            .object_changes_commitment()
+            .ok_or_else(|| SuiError::from("Checkpoint summary has no object changes commitment"))?;
+        verify_object_change(root, object_ref, proof)
+    }
+}
+
+impl CheckpointSummaryAccess for CheckpointSummaryV1 {
+    fn version(&self) -> u8 {
+        1
+    }
+
+    fn epoch(&self) -> EpochId {
+        self.epoch
+    }
+
+    fn sequence_number(&self) -> CheckpointSequenceNumber {
+        self.sequence_number
+    }
+
+    fn content_digest(&self) -> &CheckpointContentsDigest {
+        &self.content_digest
+    }
+
+    fn previous_digest(&self) -> Option<&CheckpointDigest> {
+        self.previous_digest.as_ref()
+    }
+
+    fn gas_cost_summary(&self) -> &GasCostSummary {
+        &self.gas_cost_summary
+    }
+
+    fn next_epoch_committee(&self) -> Option<&[(AuthorityName, StakeUnit)]> {
+        self.next_epoch_committee.as_deref()
+    }
+
+    fn end_of_epoch_data(&self) -> Option<&EndOfEpochData> {
+        None
+    }
+
+    fn contents_merkle_root(&self) -> Option<&ContentsMerkleRoot> {
+        None
+    }
+
+    fn object_changes_commitment(&self) -> Option<&ObjectChangesRoot> {
+        None
+    }
+
+    fn timestamp_ms(&self) -> Option<u64> {
+        None
+    }
+
+    fn digest(&self) -> CheckpointDigest {
+        sha3_hash(self)
+    }
+}
+
+impl CheckpointSummaryAccess for CheckpointSummaryV2 {
+    fn version(&self) -> u8 {
+        2
+    }
+
+    fn epoch(&self) -> EpochId {
+        self.epoch
+    }
+
+    fn sequence_number(&self) -> CheckpointSequenceNumber {
+        self.sequence_number
+    }
+
+    fn content_digest(&self) -> &CheckpointContentsDigest {
+        &self.content_digest
+    }
+
+    fn previous_digest(&self) -> Option<&CheckpointDigest> {
+        self.previous_digest.as_ref()
+    }
+
+    fn gas_cost_summary(&self) -> &GasCostSummary {
+        &self.gas_cost_summary
+    }
+
+    fn next_epoch_committee(&self) -> Option<&[(AuthorityName, StakeUnit)]> {
+        self.next_epoch_committee.as_deref()
+    }
+
+    fn end_of_epoch_data(&self) -> Option<&EndOfEpochData> {
+        self.end_of_epoch_data.as_ref()
+    }
+
+    fn contents_merkle_root(&self) -> Option<&ContentsMerkleRoot> {
+        Some(&self.contents_merkle_root)
+    }
+
+    fn object_changes_commitment(&self) -> Option<&ObjectChangesRoot> {
+        None
+    }
+
+    fn timestamp_ms(&self) -> Option<u64> {
+        Some(self.timestamp_ms)
+    }
+
+    fn digest(&self) -> CheckpointDigest {
+        sha3_hash(self)
+    }
+}
+
+impl CheckpointSummaryAccess for CheckpointSummaryV3 {
+    fn version(&self) -> u8 {
+        3
+    }
+
+    fn epoch(&self) -> EpochId {
+        self.epoch
+    }
+
+    fn sequence_number(&self) -> CheckpointSequenceNumber {
+        self.sequence_number
+    }
+
+    fn content_digest(&self) -> &CheckpointContentsDigest {
+        &self.content_digest
+    }
+
+    fn previous_digest(&self) -> Option<&CheckpointDigest> {
+        self.previous_digest.as_ref()
+    }
+
+    fn gas_cost_summary(&self) -> &GasCostSummary {
+        &self.gas_cost_summary
+    }
+
+    fn next_epoch_committee(&self) -> Option<&[(AuthorityName, StakeUnit)]> {
+        self.next_epoch_committee.as_deref()
+    }
+
+    fn end_of_epoch_data(&self) -> Option<&EndOfEpochData> {
+        self.end_of_epoch_data.as_ref()
+    }
+
+    fn contents_merkle_root(&self) -> Option<&ContentsMerkleRoot> {
+        Some(&self.contents_merkle_root)
+    }
+
+    fn object_changes_commitment(&self) -> Option<&ObjectChangesRoot> {
+        self.object_changes_commitment.as_ref()
+    }
+
+    fn timestamp_ms(&self) -> Option<u64> {
+        Some(self.timestamp_ms)
+    }
+
+    fn digest(&self) -> CheckpointDigest {
+        sha3_hash(self)
+    }
+}
+
+/// Version 4 is `CheckpointSummary`, see the module documentation.
+impl CheckpointSummaryAccess for CheckpointSummary {
+    fn version(&self) -> u8 {
+        4
+    }
+
+    fn epoch(&self) -> EpochId {
+        self.epoch
+    }
+
+    fn sequence_number(&self) -> CheckpointSequenceNumber {
+        self.sequence_number
+    }
+
+    fn content_digest(&self) -> &CheckpointContentsDigest {
+        &self.content_digest
+    }
+
+    fn previous_digest(&self) -> Option<&CheckpointDigest> {
+        self.previous_digest.as_ref()
+    }
+
+    fn gas_cost_summary(&self) -> &GasCostSummary {
+        &self.gas_cost_summary
+    }
+
+    fn next_epoch_committee(&self) -> Option<&[(AuthorityName, StakeUnit)]> {
+        self.next_epoch_committee.as_deref()
+    }
+
+    fn end_of_epoch_data(&self) -> Option<&EndOfEpochData> {
+        None
+    }
+
+    fn contents_merkle_root(&self) -> Option<&ContentsMerkleRoot> {
+        Some(&self.contents_merkle_root)
+    }
+
+    fn object_changes_commitment(&self) -> Option<&ObjectChangesRoot> {
+        self.object_changes_commitment.as_ref()
+    }
+
+    fn timestamp_ms(&self) -> Option<u64> {
+        None
+    }
+
+    fn digest(&self) -> CheckpointDigest {
+        sha3_hash(self)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum VersionedCheckpointSummary {
+    V1(CheckpointSummaryV1),
+    V2(CheckpointSummaryV2),
+    V3(CheckpointSummaryV3),
+    V4(CheckpointSummary),
+}
+
+impl VersionedCheckpointSummary {
+    fn inner(&self) -> &dyn CheckpointSummaryAccess {
+        match self {
+            Self::V1(summary) => summary,
+            Self::V2(summary) => summary,
+            Self::V3(summary) => summary,
+            Self::V4(summary) => summary,
+        }
+    }
+
+    pub fn as_v1(&self) -> Option<&CheckpointSummaryV1> {
+        match self {
+            Self::V1(summary) => Some(summary),
+            _ => None,
+        }
+    }
+
+    pub fn as_v2(&self) -> Option<&CheckpointSummaryV2> {
+        match self {
+            Self::V2(summary) => Some(summary),
+            _ => None,
+        }
+    }
+
+    pub fn as_v3(&self) -> Option<&CheckpointSummaryV3> {
+        match self {
+            Self::V3(summary) => Some(summary),
+            _ => None,
+        }
+    }
+
+    pub fn as_v4(&self) -> Option<&CheckpointSummary> {
+        match self {
+            Self::V4(summary) => Some(summary),
+            _ => None,
+        }
+    }
+}
+
+impl From<CheckpointSummaryV1> for VersionedCheckpointSummary {
+    fn from(summary: CheckpointSummaryV1) -> Self {
+        Self::V1(summary)
+    }
+}
+
+impl From<CheckpointSummary> for VersionedCheckpointSummary {
+    fn from(summary: CheckpointSummary) -> Self {
+        Self::V4(summary)
+    }
+}
+
+impl From<CheckpointSummaryV2> for VersionedCheckpointSummary {
+    fn from(summary: CheckpointSummaryV2) -> Self {
+        Self::V2(summary)
+    }
+}
+
+impl From<CheckpointSummaryV3> for VersionedCheckpointSummary {
+    fn from(summary: CheckpointSummaryV3) -> Self {
+        Self::V3(summary)
+    }
+}
+
+impl CheckpointSummaryAccess for VersionedCheckpointSummary {
+    fn version(&self) -> u8 {
+        self.inner().version()
+    }
+
+    fn epoch(&self) -> EpochId {
+        self.inner().epoch()
+    }
+
+    fn sequence_number(&self) -> CheckpointSequenceNumber {
+        self.inner().sequence_number()
+    }
+
+    fn content_digest(&self) -> &CheckpointContentsDigest {
+        self.inner().content_digest()
+    }
+
+    fn previous_digest(&self) -> Option<&CheckpointDigest> {
+        self.inner().previous_digest()
+    }
+
+    fn gas_cost_summary(&self) -> &GasCostSummary {
+        self.inner().gas_cost_summary()
+    }
+
+    fn next_epoch_committee(&self) -> Option<&[(AuthorityName, StakeUnit)]> {
+        self.inner().next_epoch_committee()
+    }
+
+    fn end_of_epoch_data(&self) -> Option<&EndOfEpochData> {
+        self.inner().end_of_epoch_data()
+    }
+
+    fn contents_merkle_root(&self) -> Option<&ContentsMerkleRoot> {
+        self.inner().contents_merkle_root()
+    }
+
+    fn object_changes_commitment(&self) -> Option<&ObjectChangesRoot> {
+        self.inner().object_changes_commitment()
+    }
+
+    fn timestamp_ms(&self) -> Option<u64> {
+        self.inner().timestamp_ms()
+    }
+
+    fn digest(&self) -> CheckpointDigest {
+        self.inner().digest()
+    }
+}
+
+/// Signed as the summary it holds, see the compatibility rules above.
+impl<W: Write> Signable<W> for VersionedCheckpointSummary {
+    fn write(&self, writer: &mut W) {
+        match self {
+            Self::V1(summary) => summary.write(writer),
+            Self::V2(summary) => summary.write(writer),
+            Self::V3(summary) => summary.write(writer),
+            Self::V4(summary) => summary.write(writer),
+        }
+    }
+}
+
+/// A versioned summary with the signature of a quorum of the committee of its epoch.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CertifiedVersionedCheckpointSummary {
+    pub summary: VersionedCheckpointSummary,
+    pub auth_signature: AuthorityWeakQuorumSignInfo,
+}
+
+impl CertifiedVersionedCheckpointSummary {
+    pub fn new(
+        summary: VersionedCheckpointSummary,
+        signatures: Vec<AuthoritySignInfo>,
+        committee: &Committee,
+    ) -> SuiResult<Self> {
+        Ok(Self {
+            summary,
+            auth_signature: AuthorityWeakQuorumSignInfo::new_from_auth_sign_infos(
+                signatures, committee,
+            )?,
+        })
+    }
+
+    /// Check that the summary is well formed and certified by `committee`.
+    pub fn verify(&self, committee: &Committee) -> SuiResult {
+        fp_ensure!(
+            self.summary.epoch() == committee.epoch,
+            SuiError::from("Epoch in the summary doesn't match with the committee")
+        );
+        self.summary.verify_end_of_epoch_data()?;
+        self.auth_signature.verify(&self.summary, committee)
+    }
+
+    /// Authenticate the gas fee attribution of an epoch from its certified checkpoints alone.
+    /// `epoch_checkpoints` are all the checkpoints of the epoch of `committee`, in order, and
+    /// `previous_epoch_last` the last checkpoint of the previous epoch, None for epoch 0.
+    /// The last checkpoint must carry an attribution to exactly the members of the committee,
+    /// adding up to the gas costs of the checkpoints.
+    pub fn verify_gas_fee_attribution<'a>(
+        committee: &Committee,
+        previous_epoch_last: Option<&CertifiedVersionedCheckpointSummary>,
+        epoch_checkpoints: &'a [CertifiedVersionedCheckpointSummary],
+    ) -> SuiResult<&'a GasFeeAttribution> {
+        let (mut previous_digest, mut next_sequence_number) = match previous_epoch_last {
+            Some(previous) => {
+                let previous = &previous.summary;
+                fp_ensure!(
+                    committee.epoch.is_successor_of(previous.epoch())
+                        && previous.next_epoch_committee() == Some(&committee.voting_rights[..]),
+                    SuiError::from("Previous epoch did not hand over to the committee")
+                );
+                (Some(previous.digest()), previous.sequence_number() + 1)
+            }
+            None => (None, 0),
+        };
+        for checkpoint in epoch_checkpoints {
+            let summary = &checkpoint.summary;
+            fp_ensure!(
+                summary.sequence_number() == next_sequence_number
+                    && summary.previous_digest() == previous_digest.as_ref(),
+                SuiError::from(
+                    format!(
+                        "Checkpoint {} does not follow checkpoint {} of the epoch",
+                        summary.sequence_number(),
+                        next_sequence_number.saturating_sub(1)
+                    )
+                    .as_str()
+                )
+            );
+            checkpoint.verify(committee)?;
+            previous_digest = Some(summary.digest());
+            next_sequence_number += 1;
+        }
+
+        let last = epoch_checkpoints
+            .last()
+            .filter(|last| last.summary.next_epoch_committee().is_some())
+            .ok_or_else(|| SuiError::from("Missing the last checkpoint of the epoch"))?;
+        let attribution = last
+            .summary
+            .end_of_epoch_data()
+            .and_then(|data| data.gas_fee_attribution())
+            .ok_or_else(|| {
+                SuiError::from("Last checkpoint of the epoch has no gas fee attribution")
+            })?;
+        fp_ensure!(
+            attribution.len() == committee.num_members()
+                && attribution
+                    .iter()
+                    .all(|(name, _)| committee.authority_exists(name)),
+            SuiError::from("Gas fee attribution does not match the committee")
+        );
+        let total = add_gas_costs(
+            epoch_checkpoints
+                .iter()
+                .map(|c| c.summary.gas_cost_summary()),
+        );
+        fp_ensure!(
+            total.is_some() && attribution.total() == total,
+            SuiError::from("Gas fee attribution does not add up to the gas costs of the epoch")
+        );
+        Ok(attribution)
+    }
+}
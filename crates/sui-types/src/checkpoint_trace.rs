@@ -0,0 +1,213 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Opt-in tracing of selected transactions through checkpoint formation: when they entered a
+//! proposal, which fragments carried them, and which checkpoint finalized them, to diagnose
+//! complaints about the time transactions take to be checkpointed. Nothing is recorded for
+//! transactions that were not selected.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::{AuthorityName, TransactionDigest};
+use crate::messages_checkpoint::{
+    CheckpointContents, CheckpointFragment, CheckpointProposal, CheckpointSequenceNumber,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_trace_tests.rs"]
+mod checkpoint_trace_tests;
+
+/// A proposal that included the transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TracedProposal {
+    pub sequence_number: CheckpointSequenceNumber,
+    pub authority: AuthorityName,
+    /// Milliseconds since the Unix epoch.
+    pub at_ms: u64,
+}
+
+/// A fragment whose diff carried the transaction, i.e. between a proposal that included it
+/// and one that did not.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TracedFragment {
+    pub sequence_number: CheckpointSequenceNumber,
+    pub proposer: AuthorityName,
+    pub other: AuthorityName,
+    pub at_ms: u64,
+}
+
+/// The checkpoint that finalized the transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TracedCheckpoint {
+    pub sequence_number: CheckpointSequenceNumber,
+    pub at_ms: u64,
+}
+
+/// What happened to a traced transaction so far, in the order it was recorded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionTimeline {
+    pub transaction: TransactionDigest,
+    /// When the transaction was selected for tracing.
+    pub selected_at_ms: u64,
+    pub proposals: Vec<TracedProposal>,
+    pub fragments: Vec<TracedFragment>,
+    pub checkpoint: Option<TracedCheckpoint>,
+}
+
+impl TransactionTimeline {
+    fn new(transaction: TransactionDigest, now_ms: u64) -> Self {
+        Self {
+            transaction,
+            selected_at_ms: now_ms,
+            proposals: Vec::new(),
+            fragments: Vec::new(),
+            checkpoint: None,
+        }
+    }
+
+    /// The time from the first proposal including the transaction to its checkpoint.
+    pub fn inclusion_latency_ms(&self) -> Option<u64> {
+        let checkpoint = self.checkpoint.as_ref()?;
+        let proposed = self.proposals.iter().map(|p| p.at_ms).min()?;
+        Some(checkpoint.at_ms.saturating_sub(proposed))
+    }
+}
+
+/// Records the timelines of up to `max_traced` selected transactions. The recording methods
+/// take a shared reference, so that a single collector can be shared by the components
+/// proposing, reconstructing and storing checkpoints.
+pub struct TxCheckpointTrace {
+    max_traced: usize,
+    timelines: Mutex<HashMap<TransactionDigest, TransactionTimeline>>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+impl TxCheckpointTrace {
+    pub fn new(max_traced: usize) -> Self {
+        Self {
+            max_traced,
+            timelines: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start tracing a transaction. Returns false if `max_traced` transactions are already
+    /// traced.
+    pub fn select(&self, transaction: TransactionDigest) -> bool {
+        self.select_at(transaction, now_ms())
+    }
+
+    pub fn select_at(&self, transaction: TransactionDigest, now_ms: u64) -> bool {
+        let mut timelines = self.timelines.lock().unwrap();
+        if timelines.contains_key(&transaction) {
+            return true;
+        }
+        if timelines.len() >= self.max_traced {
+            return false;
+        }
+        timelines.insert(transaction, TransactionTimeline::new(transaction, now_ms));
+        true
+    }
+
+    /// Stop tracing a transaction, returning its timeline.
+    pub fn unselect(&self, transaction: &TransactionDigest) -> Option<TransactionTimeline> {
+        self.timelines.lock().unwrap().remove(transaction)
+    }
+
+    pub fn timeline(&self, transaction: &TransactionDigest) -> Option<TransactionTimeline> {
+        self.timelines.lock().unwrap().get(transaction).cloned()
+    }
+
+    /// All the timelines, by increasing transaction digest.
+    pub fn timelines(&self) -> Vec<TransactionTimeline> {
+        let mut timelines: Vec<_> = self.timelines.lock().unwrap().values().cloned().collect();
+        timelines.sort_by_key(|timeline| timeline.transaction);
+        timelines
+    }
+
+    pub fn record_proposal(&self, proposal: &CheckpointProposal) {
+        self.record_proposal_at(proposal, now_ms());
+    }
+
+    pub fn record_proposal_at(&self, proposal: &CheckpointProposal, now_ms: u64) {
+        self.record(
+            proposal.transactions().map(|digests| &digests.transaction),
+            |timeline| {
+                timeline.proposals.push(TracedProposal {
+                    sequence_number: *proposal.sequence_number(),
+                    authority: *proposal.name(),
+                    at_ms: now_ms,
+                })
+            },
+        );
+    }
+
+    pub fn record_fragment(&self, fragment: &CheckpointFragment) {
+        self.record_fragment_at(fragment, now_ms());
+    }
+
+    pub fn record_fragment_at(&self, fragment: &CheckpointFragment, now_ms: u64) {
+        self.record(
+            fragment
+                .data
+                .required_digests()
+                .map(|digests| &digests.transaction),
+            |timeline| {
+                timeline.fragments.push(TracedFragment {
+                    sequence_number: fragment.proposer.data().sequence_number,
+                    proposer: *fragment.proposer.authority(),
+                    other: *fragment.other.authority(),
+                    at_ms: now_ms,
+                })
+            },
+        );
+    }
+
+    pub fn record_checkpoint(&self, seq: CheckpointSequenceNumber, contents: &CheckpointContents) {
+        self.record_checkpoint_at(seq, contents, now_ms());
+    }
+
+    /// Record the checkpoint of the transactions of `contents`. Only the first checkpoint of
+    /// a transaction is kept.
+    pub fn record_checkpoint_at(
+        &self,
+        seq: CheckpointSequenceNumber,
+        contents: &CheckpointContents,
+        now_ms: u64,
+    ) {
+        self.record(
+            contents.iter().map(|digests| &digests.transaction),
+            |timeline| {
+                timeline.checkpoint.get_or_insert(TracedCheckpoint {
+                    sequence_number: seq,
+                    at_ms: now_ms,
+                });
+            },
+        );
+    }
+
+    fn record<'a>(
+        &self,
+        transactions: impl Iterator<Item = &'a TransactionDigest>,
+        mut update: impl FnMut(&mut TransactionTimeline),
+    ) {
+        let mut timelines = self.timelines.lock().unwrap();
+        if timelines.is_empty() {
+            return;
+        }
+        for transaction in transactions {
+            if let Some(timeline) = timelines.get_mut(transaction) {
+                update(timeline);
+            }
+        }
+    }
+}
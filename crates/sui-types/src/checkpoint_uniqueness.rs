@@ -0,0 +1,149 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detection of transactions included in more than one checkpoint.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::ExecutionDigests;
+use crate::messages_checkpoint::{CheckpointContents, CheckpointSequenceNumber};
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_uniqueness_tests.rs"]
+mod checkpoint_uniqueness_tests;
+
+const BLOOM_HASHES: u64 = 7;
+/// About 1% false positives with 7 hashes.
+const BLOOM_BITS_PER_ITEM: usize = 10;
+
+/// A transaction found in a checkpoint that was already seen in an earlier one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicateTransaction {
+    pub digests: ExecutionDigests,
+    pub checkpoint: CheckpointSequenceNumber,
+    /// The checkpoint that first included the transaction. None if the transaction is only
+    /// known to the bloom filters: it is then a probable duplicate of an older checkpoint.
+    pub previous: Option<CheckpointSequenceNumber>,
+}
+
+struct BloomFilter {
+    bits: Vec<u64>,
+    items: usize,
+}
+
+impl BloomFilter {
+    fn new(expected_items: usize) -> Self {
+        let words = (expected_items * BLOOM_BITS_PER_ITEM + 63) / 64;
+        Self {
+            bits: vec![0; words.max(1)],
+            items: 0,
+        }
+    }
+
+    /// Transaction digests are hashes already, so their bytes are used directly to derive
+    /// the bit positions (by double hashing).
+    fn positions(&self, digests: &ExecutionDigests) -> impl Iterator<Item = usize> {
+        let bytes = digests.transaction.as_ref();
+        let h1 = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) | 1;
+        let len = (self.bits.len() * 64) as u64;
+        (0..BLOOM_HASHES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+
+    fn insert(&mut self, digests: &ExecutionDigests) {
+        for position in self.positions(digests) {
+            self.bits[position / 64] |= 1 << (position % 64);
+        }
+        self.items += 1;
+    }
+
+    fn contains(&self, digests: &ExecutionDigests) -> bool {
+        self.positions(digests)
+            .all(|position| self.bits[position / 64] & (1 << (position % 64)) != 0)
+    }
+}
+
+/// Keeps the exact set of transactions of the `window` most recent checkpoints, and bloom
+/// filters of the transactions of older checkpoints. The filters are rotated once they hold
+/// `bloom_capacity` transactions, so the memory used stays bounded: transactions evicted
+/// from the window are remembered until two rotations happened.
+pub struct UniquenessTracker {
+    window: usize,
+    bloom_capacity: usize,
+    recent: BTreeMap<CheckpointSequenceNumber, Vec<ExecutionDigests>>,
+    exact: HashMap<ExecutionDigests, CheckpointSequenceNumber>,
+    current_bloom: BloomFilter,
+    previous_bloom: BloomFilter,
+}
+
+impl UniquenessTracker {
+    pub fn new(window: usize, bloom_capacity: usize) -> Self {
+        Self {
+            window,
+            bloom_capacity,
+            recent: BTreeMap::new(),
+            exact: HashMap::new(),
+            current_bloom: BloomFilter::new(bloom_capacity),
+            previous_bloom: BloomFilter::new(bloom_capacity),
+        }
+    }
+
+    /// Record the transactions of a checkpoint, returning the ones seen before. Checking a
+    /// checkpoint still in the window again is a no-op.
+    pub fn check_and_insert(
+        &mut self,
+        seq: CheckpointSequenceNumber,
+        contents: &CheckpointContents,
+    ) -> Vec<DuplicateTransaction> {
+        if self.recent.contains_key(&seq) {
+            return vec![];
+        }
+        let mut duplicates = Vec::new();
+        let mut transactions = Vec::with_capacity(contents.size());
+        for digests in contents.iter() {
+            let previous = match self.exact.get(digests) {
+                Some(previous) => Some(Some(*previous)),
+                None if self.current_bloom.contains(digests)
+                    || self.previous_bloom.contains(digests) =>
+                {
+                    Some(None)
+                }
+                None => None,
+            };
+            match previous {
+                Some(previous) => duplicates.push(DuplicateTransaction {
+                    digests: *digests,
+                    checkpoint: seq,
+                    previous,
+                }),
+                None => {
+                    self.exact.insert(*digests, seq);
+                    transactions.push(*digests);
+                }
+            }
+        }
+        self.recent.insert(seq, transactions);
+        self.evict();
+        duplicates
+    }
+
+    /// Move the transactions of checkpoints out of the window into the bloom filters.
+    fn evict(&mut self) {
+        while self.recent.len() > self.window {
+            let oldest = *self.recent.keys().next().unwrap();
+            let transactions = self.recent.remove(&oldest).unwrap();
+            for digests in transactions {
+                self.exact.remove(&digests);
+                if self.current_bloom.items >= self.bloom_capacity {
+                    self.previous_bloom = std::mem::replace(
+                        &mut self.current_bloom,
+                        BloomFilter::new(self.bloom_capacity),
+                    );
+                }
+                self.current_bloom.insert(&digests);
+            }
+        }
+    }
+}
@@ -0,0 +1,255 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detects checkpoints whose formation stalls, and diagnoses at which stage: proposals,
+//! fragments or certificate signatures.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::AuthorityName;
+use crate::committee::{Committee, StakeUnit};
+use crate::messages_checkpoint::CheckpointSequenceNumber;
+
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_watchdog_tests.rs"]
+mod checkpoint_watchdog_tests;
+
+/// How long each stage of checkpoint formation may take before it is reported as stalled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchdogTimeouts {
+    /// From the first event on a checkpoint to proposals from a quorum.
+    pub proposals: Duration,
+    /// From proposals from a quorum to the contents being constructed from fragments.
+    pub fragments: Duration,
+    /// From the contents being constructed to the checkpoint being certified.
+    pub certificate: Duration,
+}
+
+impl Default for WatchdogTimeouts {
+    fn default() -> Self {
+        Self {
+            proposals: Duration::from_secs(10),
+            fragments: Duration::from_secs(30),
+            certificate: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StallDiagnosis {
+    /// The proposals received so far are not from a quorum.
+    NoQuorumProposals { received_stake: StakeUnit },
+    /// A quorum proposed, but the completed fragments do not connect enough of them to
+    /// construct the contents.
+    FragmentGraphDisconnected { fragments: usize },
+    /// The contents are constructed, but signatures worth `stake` are still missing to
+    /// certify the checkpoint.
+    CertSignaturesMissing { stake: StakeUnit },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointStall {
+    pub sequence_number: CheckpointSequenceNumber,
+    /// Time spent in the stalled stage.
+    pub stalled_for_ms: u64,
+    pub diagnosis: StallDiagnosis,
+}
+
+struct CheckpointProgress {
+    started: Instant,
+    proposals: BTreeSet<AuthorityName>,
+    quorum_proposals_at: Option<Instant>,
+    fragments: BTreeSet<(AuthorityName, AuthorityName)>,
+    constructed_at: Option<Instant>,
+    signatures: BTreeSet<AuthorityName>,
+}
+
+impl CheckpointProgress {
+    fn new(started: Instant) -> Self {
+        Self {
+            started,
+            proposals: BTreeSet::new(),
+            quorum_proposals_at: None,
+            fragments: BTreeSet::new(),
+            constructed_at: None,
+            signatures: BTreeSet::new(),
+        }
+    }
+}
+
+/// Observes the formation of checkpoints, from proposals to certificates, for the checkpoints
+/// not certified yet.
+pub struct CheckpointLivenessWatchdog {
+    committee: Committee,
+    timeouts: WatchdogTimeouts,
+    progress: BTreeMap<CheckpointSequenceNumber, CheckpointProgress>,
+    /// Events on checkpoints up to this one are ignored.
+    last_certified: Option<CheckpointSequenceNumber>,
+}
+
+impl CheckpointLivenessWatchdog {
+    pub fn new(committee: Committee, timeouts: WatchdogTimeouts) -> Self {
+        Self {
+            committee,
+            timeouts,
+            progress: BTreeMap::new(),
+            last_certified: None,
+        }
+    }
+
+    pub fn record_proposal_at(
+        &mut self,
+        seq: CheckpointSequenceNumber,
+        authority: AuthorityName,
+        now: Instant,
+    ) {
+        let committee = &self.committee;
+        let progress = match Self::progress_mut(&mut self.progress, self.last_certified, seq, now) {
+            Some(progress) => progress,
+            None => return,
+        };
+        if progress.proposals.insert(authority) && progress.quorum_proposals_at.is_none() {
+            let stake: StakeUnit = progress
+                .proposals
+                .iter()
+                .map(|authority| committee.weight(authority))
+                .sum();
+            if stake >= committee.quorum_threshold() {
+                progress.quorum_proposals_at = Some(now);
+            }
+        }
+    }
+
+    pub fn record_fragment_at(
+        &mut self,
+        seq: CheckpointSequenceNumber,
+        proposer: AuthorityName,
+        other: AuthorityName,
+        now: Instant,
+    ) {
+        if let Some(progress) =
+            Self::progress_mut(&mut self.progress, self.last_certified, seq, now)
+        {
+            progress.fragments.insert((proposer, other));
+        }
+    }
+
+    /// Record that the contents of the checkpoint were constructed from the fragments.
+    pub fn record_contents_constructed_at(&mut self, seq: CheckpointSequenceNumber, now: Instant) {
+        if let Some(progress) =
+            Self::progress_mut(&mut self.progress, self.last_certified, seq, now)
+        {
+            progress.constructed_at.get_or_insert(now);
+        }
+    }
+
+    pub fn record_signature_at(
+        &mut self,
+        seq: CheckpointSequenceNumber,
+        authority: AuthorityName,
+        now: Instant,
+    ) {
+        if let Some(progress) =
+            Self::progress_mut(&mut self.progress, self.last_certified, seq, now)
+        {
+            progress.signatures.insert(authority);
+        }
+    }
+
+    /// Stop watching the checkpoint and all the previous ones.
+    pub fn record_certified(&mut self, seq: CheckpointSequenceNumber) {
+        self.progress = self.progress.split_off(&(seq + 1));
+        self.last_certified = self.last_certified.max(Some(seq));
+    }
+
+    pub fn record_proposal(&mut self, seq: CheckpointSequenceNumber, authority: AuthorityName) {
+        self.record_proposal_at(seq, authority, Instant::now());
+    }
+
+    pub fn record_fragment(
+        &mut self,
+        seq: CheckpointSequenceNumber,
+        proposer: AuthorityName,
+        other: AuthorityName,
+    ) {
+        self.record_fragment_at(seq, proposer, other, Instant::now());
+    }
+
+    pub fn record_contents_constructed(&mut self, seq: CheckpointSequenceNumber) {
+        self.record_contents_constructed_at(seq, Instant::now());
+    }
+
+    pub fn record_signature(&mut self, seq: CheckpointSequenceNumber, authority: AuthorityName) {
+        self.record_signature_at(seq, authority, Instant::now());
+    }
+
+    /// The checkpoints whose current stage has lasted longer than its timeout.
+    pub fn stalls_at(&self, now: Instant) -> Vec<CheckpointStall> {
+        let quorum = self.committee.quorum_threshold();
+        self.progress
+            .iter()
+            .filter_map(|(seq, progress)| {
+                let (since, timeout, diagnosis) =
+                    match (progress.quorum_proposals_at, progress.constructed_at) {
+                        (_, Some(constructed_at)) => (
+                            constructed_at,
+                            self.timeouts.certificate,
+                            StallDiagnosis::CertSignaturesMissing {
+                                stake: quorum.saturating_sub(self.stake_of(&progress.signatures)),
+                            },
+                        ),
+                        (Some(quorum_proposals_at), None) => (
+                            quorum_proposals_at,
+                            self.timeouts.fragments,
+                            StallDiagnosis::FragmentGraphDisconnected {
+                                fragments: progress.fragments.len(),
+                            },
+                        ),
+                        (None, None) => (
+                            progress.started,
+                            self.timeouts.proposals,
+                            StallDiagnosis::NoQuorumProposals {
+                                received_stake: self.stake_of(&progress.proposals),
+                            },
+                        ),
+                    };
+                let stalled_for = now.saturating_duration_since(since);
+                (stalled_for >= timeout).then(|| CheckpointStall {
+                    sequence_number: *seq,
+                    stalled_for_ms: stalled_for.as_millis() as u64,
+                    diagnosis,
+                })
+            })
+            .collect()
+    }
+
+    pub fn stalls(&self) -> Vec<CheckpointStall> {
+        self.stalls_at(Instant::now())
+    }
+
+    fn stake_of(&self, authorities: &BTreeSet<AuthorityName>) -> StakeUnit {
+        authorities
+            .iter()
+            .map(|authority| self.committee.weight(authority))
+            .sum()
+    }
+
+    fn progress_mut(
+        progress: &mut BTreeMap<CheckpointSequenceNumber, CheckpointProgress>,
+        last_certified: Option<CheckpointSequenceNumber>,
+        seq: CheckpointSequenceNumber,
+        now: Instant,
+    ) -> Option<&mut CheckpointProgress> {
+        if last_certified.map_or(false, |last| seq <= last) {
+            return None;
+        }
+        Some(
+            progress
+                .entry(seq)
+                .or_insert_with(|| CheckpointProgress::new(now)),
+        )
+    }
+}
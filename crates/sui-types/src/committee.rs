@@ -3,7 +3,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::base_types::*;
-use crate::crypto::{sha3_hash, AuthorityPublicKey};
+use crate::crypto::{
+    sha3_hash, AggregateAuthenticator, AuthorityPublicKey, AuthorityQuorumSignInfo, Signable,
+};
 use crate::error::{SuiError, SuiResult};
 use crate::messages::CommitteeInfo;
 use itertools::Itertools;
@@ -13,9 +15,49 @@ use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
 
 pub type EpochId = u64;
 
+/// Checked arithmetic on epochs, for verification paths where an epoch comes from a message
+/// and cannot be trusted not to be 0 or the last representable epoch.
+pub trait EpochIdExt {
+    /// The epoch after this one.
+    fn next(self) -> SuiResult<EpochId>;
+    /// The epoch before this one, None for the genesis epoch.
+    fn checked_prev(self) -> Option<EpochId>;
+    /// Whether this epoch comes right after `previous`.
+    fn is_successor_of(self, previous: EpochId) -> bool;
+    /// Fail unless this epoch comes right after `previous`.
+    fn ensure_successor_of(self, previous: EpochId) -> SuiResult;
+}
+
+impl EpochIdExt for EpochId {
+    fn next(self) -> SuiResult<EpochId> {
+        self.checked_add(1)
+            .ok_or(SuiError::EpochOverflow { epoch: self })
+    }
+
+    fn checked_prev(self) -> Option<EpochId> {
+        self.checked_sub(1)
+    }
+
+    fn is_successor_of(self, previous: EpochId) -> bool {
+        previous.checked_add(1) == Some(self)
+    }
+
+    fn ensure_successor_of(self, previous: EpochId) -> SuiResult {
+        fp_ensure!(
+            self.is_successor_of(previous),
+            SuiError::WrongEpoch {
+                expected_epoch: previous.next()?,
+                actual_epoch: self,
+            }
+        );
+        Ok(())
+    }
+}
+
 pub type StakeUnit = u64;
 
 pub type CommitteeDigest = [u8; 32];
@@ -295,6 +337,149 @@ impl Display for Committee {
     }
 }
 
+/// Maximum number of distinct signer sets remembered by a PreparedCommittee.
+const MAX_PREPARED_SIGNER_SETS: usize = 256;
+
+/// The public keys and total stake of a set of signers of a quorum signature.
+struct PreparedSignerSet {
+    stake: StakeUnit,
+    public_keys: Vec<AuthorityPublicKey>,
+}
+
+/// A committee with its public keys deserialized once, meant to be built once per epoch
+/// and shared by all verification paths of that epoch. The public keys and stake of each
+/// signer set seen in quorum signatures are remembered, so verifying the next certificate
+/// signed by the same authorities (the common case) skips the per-signer lookups.
+pub struct PreparedCommittee {
+    committee: Committee,
+    public_keys: Vec<AuthorityPublicKey>,
+    signer_sets: Mutex<HashMap<Vec<u32>, Arc<PreparedSignerSet>>>,
+}
+
+impl PreparedCommittee {
+    pub fn new(committee: Committee) -> SuiResult<Self> {
+        let public_keys = committee
+            .names()
+            .map(|name| committee.public_key(name))
+            .collect::<SuiResult<_>>()?;
+        Ok(Self {
+            committee,
+            public_keys,
+            signer_sets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn committee(&self) -> &Committee {
+        &self.committee
+    }
+
+    pub fn epoch(&self) -> EpochId {
+        self.committee.epoch
+    }
+
+    /// Number of signer sets currently remembered.
+    pub fn cached_signer_sets(&self) -> usize {
+        self.signer_sets.lock().unwrap().len()
+    }
+
+    fn signer_set(&self, signers: Vec<u32>) -> SuiResult<Arc<PreparedSignerSet>> {
+        if let Some(set) = self.signer_sets.lock().unwrap().get(&signers) {
+            return Ok(set.clone());
+        }
+
+        let mut stake = 0;
+        let mut public_keys = Vec::with_capacity(signers.len());
+        for index in &signers {
+            let (_, weight) = self
+                .committee
+                .voting_rights
+                .get(*index as usize)
+                .ok_or(SuiError::UnknownSigner)?;
+            fp_ensure!(*weight > 0, SuiError::UnknownSigner);
+            stake += weight;
+            public_keys.push(self.public_keys[*index as usize].clone());
+        }
+        let set = Arc::new(PreparedSignerSet { stake, public_keys });
+
+        let mut signer_sets = self.signer_sets.lock().unwrap();
+        if signer_sets.len() >= MAX_PREPARED_SIGNER_SETS {
+            signer_sets.clear();
+        }
+        signer_sets.insert(signers, set.clone());
+        Ok(set)
+    }
+
+    /// Verify a quorum signature on `data`, equivalent to `AuthoritySignInfoTrait::verify`
+    /// on the quorum signature with the underlying committee.
+    pub fn verify_quorum<T, const STRONG_THRESHOLD: bool>(
+        &self,
+        data: &T,
+        signature: &AuthorityQuorumSignInfo<STRONG_THRESHOLD>,
+    ) -> SuiResult
+    where
+        T: Signable<Vec<u8>>,
+    {
+        fp_ensure!(
+            signature.epoch == self.epoch(),
+            SuiError::WrongEpoch {
+                expected_epoch: self.epoch(),
+                actual_epoch: signature.epoch,
+            }
+        );
+
+        let signers = self.signer_set(signature.signers_map.iter().collect())?;
+        fp_ensure!(
+            signers.stake
+                >= AuthorityQuorumSignInfo::<STRONG_THRESHOLD>::quorum_threshold(&self.committee),
+            SuiError::CertificateRequiresQuorum
+        );
+
+        let mut message = Vec::new();
+        data.write(&mut message);
+        signature.epoch.write(&mut message);
+        signature
+            .signature
+            .verify(&signers.public_keys[..], &message[..])
+            .map_err(|error| SuiError::InvalidSignature {
+                error: format!("{error}"),
+            })
+    }
+
+    /// The signer sets remembered, to be persisted across restarts and prepared again with
+    /// `prepare_signer_sets`.
+    pub fn dump_signer_sets(&self) -> SignerSetsIndex {
+        let mut signer_sets: Vec<_> = self.signer_sets.lock().unwrap().keys().cloned().collect();
+        signer_sets.sort();
+        SignerSetsIndex {
+            epoch: self.epoch(),
+            signer_sets,
+        }
+    }
+
+    /// Prepare the signer sets of `index`, returning how many were prepared. An index of
+    /// another epoch is ignored, since its signers are positions in another committee.
+    pub fn prepare_signer_sets(&self, index: &SignerSetsIndex) -> SuiResult<usize> {
+        if index.epoch != self.epoch() {
+            return Ok(0);
+        }
+        let signer_sets =
+            &index.signer_sets[..index.signer_sets.len().min(MAX_PREPARED_SIGNER_SETS)];
+        for signers in signer_sets {
+            self.signer_set(signers.clone())?;
+        }
+        Ok(signer_sets.len())
+    }
+}
+
+/// The signer sets remembered by a `PreparedCommittee`, see
+/// `PreparedCommittee::dump_signer_sets`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignerSetsIndex {
+    pub epoch: EpochId,
+    /// The positions in the committee of the signers of each set.
+    pub signer_sets: Vec<Vec<u32>>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommitteeWithNetAddresses {
     pub committee: Committee,
@@ -391,4 +576,34 @@ mod test {
             (a3, "c")
         );
     }
+
+    #[test]
+    fn test_epoch_arithmetic() {
+        assert_eq!(3u64.next().unwrap(), 4);
+        assert!(matches!(
+            EpochId::MAX.next(),
+            Err(SuiError::EpochOverflow {
+                epoch: EpochId::MAX
+            })
+        ));
+        assert_eq!(3u64.checked_prev(), Some(2));
+        assert_eq!(0u64.checked_prev(), None);
+
+        assert!(4u64.is_successor_of(3));
+        assert!(!3u64.is_successor_of(3));
+        assert!(!2u64.is_successor_of(3));
+        assert!(!0u64.is_successor_of(EpochId::MAX));
+        assert!(4u64.ensure_successor_of(3).is_ok());
+        assert!(matches!(
+            2u64.ensure_successor_of(3),
+            Err(SuiError::WrongEpoch {
+                expected_epoch: 4,
+                actual_epoch: 2
+            })
+        ));
+        assert!(matches!(
+            0u64.ensure_successor_of(EpochId::MAX),
+            Err(SuiError::EpochOverflow { .. })
+        ));
+    }
 }
@@ -1355,11 +1355,31 @@ mod bcs_signable {
     impl BcsSignable for crate::committee::CommitteeWithNetAddresses {}
     impl BcsSignable for crate::messages_checkpoint::CheckpointSummary {}
     impl BcsSignable for crate::messages_checkpoint::CheckpointContents {}
+    impl BcsSignable for crate::messages_checkpoint::CheckpointContentsV2 {}
+    #[cfg(feature = "checkpoint-client")]
+    impl BcsSignable for crate::checkpoint_summary_versions::CheckpointSummaryV1 {}
+    #[cfg(feature = "checkpoint-client")]
+    impl BcsSignable for crate::checkpoint_summary_versions::CheckpointSummaryV2 {}
+    #[cfg(feature = "checkpoint-client")]
+    impl BcsSignable for crate::checkpoint_summary_versions::CheckpointSummaryV3 {}
+    #[cfg(feature = "checkpoint-validator")]
+    impl BcsSignable for crate::checkpoint_availability::AvailabilityAttestation {}
     impl BcsSignable for crate::messages_checkpoint::CheckpointProposalContents {}
     impl BcsSignable for crate::messages_checkpoint::CheckpointProposalSummary {}
+    #[cfg(feature = "checkpoint-validator")]
+    impl BcsSignable for crate::messages_checkpoint::CheckpointProposalApproval {}
+    impl BcsSignable for crate::messages_checkpoint::CheckpointSequenceInfo {}
+    impl BcsSignable for crate::messages_checkpoint::NotAvailableAttestation {}
+    impl BcsSignable for crate::messages_checkpoint::ContentsBucket {}
+    impl BcsSignable for crate::messages_checkpoint::StateSnapshotManifest {}
+    #[cfg(feature = "checkpoint-validator")]
     impl BcsSignable for crate::messages_checkpoint::CheckpointFragmentMessageHeader {}
+    #[cfg(feature = "checkpoint-validator")]
     impl BcsSignable for crate::messages_checkpoint::CheckpointFragmentMessageChunk {}
+    #[cfg(feature = "checkpoint-validator")]
     impl BcsSignable for crate::messages_checkpoint::CheckpointFragmentMessage {}
+    #[cfg(feature = "checkpoint-validator")]
+    impl BcsSignable for crate::messages_checkpoint::CheckpointSignatureBatchMessage {}
 
     impl BcsSignable for crate::messages::CommitteeInfoResponse {}
     impl BcsSignable for crate::messages::TransactionEffects {}
@@ -1453,6 +1473,9 @@ pub struct VerificationObligation {
     pub messages: Vec<Vec<u8>>,
     pub signatures: Vec<AggregateAuthoritySignature>,
     pub public_keys: Vec<Vec<AuthorityPublicKey>>,
+    /// Buffers of cleared messages and public keys, reused by `add_message`.
+    spare_messages: Vec<Vec<u8>>,
+    spare_public_keys: Vec<Vec<AuthorityPublicKey>>,
 }
 
 impl VerificationObligation {
@@ -1468,12 +1491,13 @@ impl VerificationObligation {
     where
         T: Signable<Vec<u8>>,
     {
-        let mut message = Vec::new();
+        let mut message = self.spare_messages.pop().unwrap_or_default();
         message_value.write(&mut message);
         epoch.write(&mut message);
 
         self.signatures.push(AggregateAuthoritySignature::default());
-        self.public_keys.push(Vec::new());
+        self.public_keys
+            .push(self.spare_public_keys.pop().unwrap_or_default());
         self.messages.push(message);
         self.messages.len() - 1
     }
@@ -1507,7 +1531,36 @@ impl VerificationObligation {
         }
     }
 
+    /// Remove all messages, keeping the allocated buffers for the next ones. This lets a
+    /// single obligation verify many messages in turn, e.g. thousands of checkpoints during
+    /// sync, without allocating for each of them.
+    pub fn clear(&mut self) {
+        self.signatures.clear();
+        self.spare_messages
+            .extend(self.messages.drain(..).map(|mut message| {
+                message.clear();
+                message
+            }));
+        self.spare_public_keys
+            .extend(self.public_keys.drain(..).map(|mut keys| {
+                keys.clear();
+                keys
+            }));
+    }
+
+    /// Same as `verify_all`, then clears the obligation for reuse, whether or not the
+    /// verification succeeded.
+    pub fn verify_and_clear(&mut self) -> SuiResult<()> {
+        let result = self.verify();
+        self.clear();
+        result
+    }
+
     pub fn verify_all(self) -> SuiResult<()> {
+        self.verify()
+    }
+
+    fn verify(&self) -> SuiResult<()> {
         AggregateAuthoritySignature::batch_verify(
             &self.signatures.iter().collect::<Vec<_>>()[..],
             self.public_keys
@@ -10,11 +10,14 @@ use crate::{
 };
 use move_binary_format::errors::{Location, PartialVMError, VMError};
 use move_core_types::vm_status::{StatusCode, StatusType};
+#[cfg(feature = "checkpoint-validator")]
 use narwhal_executor::SubscriberError;
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, fmt::Debug};
 use thiserror::Error;
+#[cfg(feature = "checkpoint-validator")]
 use tonic::Status;
+#[cfg(feature = "checkpoint-validator")]
 use typed_store::rocks::TypedStoreError;
 
 pub const TRANSACTION_NOT_FOUND_MSG_PREFIX: &str = "Could not find the referenced transaction";
@@ -372,7 +375,7 @@ pub enum SuiError {
         error: Box<SuiError>,
     },
     #[error("Storage error")]
-    StorageError(#[from] TypedStoreError),
+    StorageError(#[from] StorageErrorKind),
     #[error("Non-RocksDB Storage error: {0}")]
     GenericStorageError(String),
     #[error(
@@ -500,6 +503,78 @@ pub enum SuiError {
 
     #[error("SUI payment transactions use first input coin for gas payment, but found a different gas object.")]
     UnexpectedGasPaymentObject,
+
+    // Checkpoint errors. The bincode variant index is part of the wire format, so new
+    // variants are appended at the end of the enum.
+    #[error(
+        "Failed to decode {expected_type} at byte {offset} of {total_size} (chunks {first_chunk}..={last_chunk}): {error}"
+    )]
+    ChunkedDecodeError {
+        expected_type: String,
+        offset: u64,
+        total_size: u64,
+        first_chunk: u32,
+        last_chunk: u32,
+        error: String,
+    },
+
+    #[error("Next epoch committee has {size} members, expected between 1 and {max}")]
+    InvalidNextEpochCommitteeSize { size: usize, max: usize },
+    #[error("Next epoch committee lists {authority:?} more than once")]
+    DuplicateNextEpochCommitteeMember { authority: AuthorityName },
+    #[error("Next epoch committee is not sorted by authority name")]
+    UnsortedNextEpochCommittee,
+    #[error("Next epoch committee has no stake, or more than the total stake can hold")]
+    InvalidNextEpochCommitteeStake,
+
+    #[error("{policy} refused to sign checkpoint {sequence_number}: {reason}")]
+    CheckpointSigningRefused {
+        policy: String,
+        sequence_number: u64,
+        reason: String,
+    },
+
+    #[error(
+        "Timed out waiting for checkpoint {sequence_number}, latest certified checkpoint is {watermark:?}"
+    )]
+    CheckpointWaitTimeout {
+        sequence_number: u64,
+        watermark: Option<u64>,
+    },
+
+    #[error(
+        "Gas cost summary of checkpoint {sequence_number} is {declared}, but its effects add up to {recomputed}"
+    )]
+    CheckpointGasMismatch {
+        sequence_number: u64,
+        declared: String,
+        recomputed: String,
+    },
+
+    #[error(
+        "Refusing to sign a checkpoint {kind} for checkpoint {sequence_number} of epoch {epoch}, below checkpoint {signed_sequence_number} of epoch {signed_epoch} already signed"
+    )]
+    CheckpointSignatureRegression {
+        kind: String,
+        epoch: EpochId,
+        sequence_number: u64,
+        signed_epoch: EpochId,
+        signed_sequence_number: u64,
+    },
+    #[error(
+        "Refusing to sign a checkpoint {kind} for checkpoint {sequence_number} of epoch {epoch} conflicting with the one already signed"
+    )]
+    ConflictingCheckpointSignature {
+        kind: String,
+        epoch: EpochId,
+        sequence_number: u64,
+    },
+
+    #[error("Checkpoint request needs {required} access, client is granted {granted}")]
+    CheckpointAccessDenied { required: String, granted: String },
+
+    #[error("Epoch {epoch} has no successor")]
+    EpochOverflow { epoch: EpochId },
 }
 
 pub type SuiResult<T = ()> = Result<T, SuiError>;
@@ -527,12 +602,51 @@ impl From<VMError> for SuiError {
     }
 }
 
+/// The errors of `typed_store`, in the same order, so that `SuiError` encodes the same whether
+/// or not the crate is built with the storage of the `checkpoint-validator` feature.
+#[derive(Error, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub enum StorageErrorKind {
+    #[error("rocksdb error: {0}")]
+    RocksDBError(String),
+    #[error("(de)serialization error: {0}")]
+    SerializationError(String),
+    #[error("the column family {0} was not registered with the database")]
+    UnregisteredColumn(String),
+    #[error("a batch operation can't operate across databases")]
+    CrossDBBatch,
+    #[error("Metric reporting thread failed with error")]
+    MetricsReporting,
+}
+
+#[cfg(feature = "checkpoint-validator")]
+impl From<TypedStoreError> for StorageErrorKind {
+    fn from(error: TypedStoreError) -> Self {
+        match error {
+            TypedStoreError::RocksDBError(e) => Self::RocksDBError(e),
+            TypedStoreError::SerializationError(e) => Self::SerializationError(e),
+            TypedStoreError::UnregisteredColumn(e) => Self::UnregisteredColumn(e),
+            TypedStoreError::CrossDBBatch => Self::CrossDBBatch,
+            TypedStoreError::MetricsReporting => Self::MetricsReporting,
+            e => Self::RocksDBError(e.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "checkpoint-validator")]
+impl From<TypedStoreError> for SuiError {
+    fn from(error: TypedStoreError) -> Self {
+        SuiError::StorageError(error.into())
+    }
+}
+
+#[cfg(feature = "checkpoint-validator")]
 impl From<SubscriberError> for SuiError {
     fn from(error: SubscriberError) -> Self {
         SuiError::HandleConsensusTransactionFailure(error.to_string())
     }
 }
 
+#[cfg(feature = "checkpoint-validator")]
 impl From<Status> for SuiError {
     fn from(status: Status) -> Self {
         let result = bincode::deserialize::<SuiError>(status.details());
@@ -547,6 +661,7 @@ impl From<Status> for SuiError {
     }
 }
 
+#[cfg(feature = "checkpoint-validator")]
 impl From<SuiError> for Status {
     fn from(error: SuiError) -> Self {
         let bytes = bincode::serialize(&error).unwrap();
@@ -31,6 +31,7 @@ pub enum IntentScope {
     AuthorityBatch = 2,
     CheckpointSummary = 3,
     PersonalMessage = 4,
+    FragmentTransportChallenge = 5,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
@@ -22,6 +22,57 @@ pub mod error;
 pub mod balance;
 pub mod base_types;
 pub mod batch;
+#[cfg(feature = "checkpoint-validator")]
+pub mod checkpoint_access;
+#[cfg(feature = "checkpoint-archive")]
+pub mod checkpoint_archive;
+#[cfg(feature = "archive-compression")]
+pub mod checkpoint_archive_compression;
+#[cfg(feature = "checkpoint-client")]
+pub mod checkpoint_audit;
+#[cfg(feature = "checkpoint-validator")]
+pub mod checkpoint_availability;
+#[cfg(feature = "checkpoint-client")]
+pub mod checkpoint_batching;
+#[cfg(feature = "checkpoint-validator")]
+pub mod checkpoint_cache;
+#[cfg(feature = "checkpoint-archive")]
+pub mod checkpoint_chunk_store;
+pub mod checkpoint_commitment;
+#[cfg(feature = "checkpoint-validator")]
+pub mod checkpoint_construction;
+#[cfg(feature = "checkpoint-validator")]
+pub mod checkpoint_execution;
+#[cfg(feature = "checkpoint-client")]
+pub mod checkpoint_finality;
+#[cfg(feature = "checkpoint-validator")]
+pub mod checkpoint_indexer;
+#[cfg(feature = "checkpoint-validator")]
+pub mod checkpoint_latency;
+#[cfg(feature = "checkpoint-client")]
+pub mod checkpoint_move_bcs;
+#[cfg(feature = "checkpoint-validator")]
+pub mod checkpoint_notifier;
+pub mod checkpoint_object_changes;
+pub mod checkpoint_proposal_diff;
+#[cfg(feature = "checkpoint-validator")]
+pub mod checkpoint_replay;
+#[cfg(feature = "checkpoint-client")]
+pub mod checkpoint_response_consistency;
+#[cfg(feature = "checkpoint-client")]
+pub mod checkpoint_rpc_error;
+#[cfg(feature = "checkpoint-validator")]
+pub mod checkpoint_signing_guard;
+#[cfg(all(feature = "checkpoint-validator", any(test, feature = "test-utils")))]
+pub mod checkpoint_sim;
+#[cfg(feature = "checkpoint-client")]
+pub mod checkpoint_summary_versions;
+#[cfg(feature = "checkpoint-validator")]
+pub mod checkpoint_trace;
+#[cfg(feature = "checkpoint-client")]
+pub mod checkpoint_uniqueness;
+#[cfg(feature = "checkpoint-validator")]
+pub mod checkpoint_watchdog;
 pub mod coin;
 pub mod collection_types;
 pub mod committee;
@@ -32,12 +83,15 @@ pub mod gas_coin;
 pub mod id;
 pub mod in_memory_storage;
 pub mod intent;
+#[cfg(feature = "checkpoint-client")]
+pub mod light_client;
 pub mod message_envelope;
 pub mod messages;
 pub mod messages_checkpoint;
 pub mod move_package;
 pub mod object;
 pub mod query;
+pub mod shuffle;
 pub mod signature_seed;
 pub mod storage;
 pub mod sui_serde;
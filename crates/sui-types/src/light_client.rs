@@ -0,0 +1,116 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The verification-only subset of the checkpoint types, for bridges and light clients that
+//! follow the checkpoint chain without running a node: certified summaries, committees,
+//! quorum signatures, transaction finality proofs and proofs of inclusion in the contents of a
+//! checkpoint against its Merkle root.
+//!
+//! The items used here do not depend on the storage, consensus and RPC parts of this crate, and
+//! CI builds them with only the `checkpoint-client` feature, which leaves out `typed-store`,
+//! `narwhal-executor` and `tonic`, for the host and for `wasm32-unknown-unknown`, see
+//! `examples/light_client.rs`.
+
+pub use crate::base_types::ExecutionDigests;
+pub use crate::checkpoint_commitment::MerkleProof;
+pub use crate::checkpoint_finality::{FinalityTrustRoot, TransactionFinalityProof};
+pub use crate::checkpoint_move_bcs::{
+    CHECKPOINT_SUMMARY_MOVE_LAYOUT, QUORUM_SIGNATURE_MOVE_LAYOUT,
+};
+pub use crate::checkpoint_summary_versions::CheckpointSummaryAccess;
+pub use crate::committee::{Committee, EpochId, StakeUnit};
+pub use crate::messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointContents, CheckpointDigest, CheckpointSequenceNumber,
+    CheckpointSummary,
+};
+
+use crate::error::{SuiError, SuiResult};
+
+#[cfg(test)]
+#[path = "unit_tests/light_client_tests.rs"]
+mod light_client_tests;
+
+/// Follows the certified checkpoints of the chain from a trusted committee, moving to the
+/// committee of the next epoch when the last checkpoint of an epoch is verified.
+pub struct LightClient {
+    committee: Committee,
+    latest: Option<CheckpointSummary>,
+}
+
+impl LightClient {
+    pub fn new(committee: Committee) -> Self {
+        Self {
+            committee,
+            latest: None,
+        }
+    }
+
+    /// The committee of the current epoch.
+    pub fn committee(&self) -> &Committee {
+        &self.committee
+    }
+
+    /// The latest checkpoint verified.
+    pub fn latest(&self) -> Option<&CheckpointSummary> {
+        self.latest.as_ref()
+    }
+
+    /// Verify a certified checkpoint of the current epoch, newer than the latest one. It must
+    /// link to the latest checkpoint if it directly follows it.
+    pub fn verify_checkpoint(&mut self, checkpoint: &CertifiedCheckpointSummary) -> SuiResult {
+        let summary = checkpoint.data();
+        if let Some(latest) = &self.latest {
+            fp_ensure!(
+                summary.sequence_number > latest.sequence_number,
+                SuiError::from(
+                    format!(
+                        "Checkpoint {} is not newer than checkpoint {}",
+                        summary.sequence_number, latest.sequence_number
+                    )
+                    .as_str()
+                )
+            );
+            fp_ensure!(
+                summary.sequence_number != latest.sequence_number + 1
+                    || summary.previous_digest == Some(latest.digest()),
+                SuiError::from(
+                    format!(
+                        "Checkpoint {} does not link to checkpoint {}",
+                        summary.sequence_number, latest.sequence_number
+                    )
+                    .as_str()
+                )
+            );
+        }
+        checkpoint.verify(&self.committee, None)?;
+        if summary.next_epoch_committee.is_some() {
+            self.committee = summary.next_committee()?;
+        }
+        self.latest = Some(summary.clone());
+        Ok(())
+    }
+
+    /// Move to a later epoch, following the last checkpoints of the epochs in between.
+    pub fn verify_epoch_changes(&mut self, proofs: &[CertifiedCheckpointSummary]) -> SuiResult {
+        proofs
+            .iter()
+            .try_for_each(|proof| self.verify_checkpoint(proof))
+    }
+
+    /// Verify that a transaction is final, in a checkpoint of the current epoch.
+    pub fn verify_finality(&self, proof: &TransactionFinalityProof) -> SuiResult {
+        proof.verify(FinalityTrustRoot::Committee(&self.committee))
+    }
+
+    /// Verify that a transaction and its effects are in the contents of a certified checkpoint
+    /// of the current epoch, without the contents.
+    pub fn verify_inclusion(
+        &self,
+        checkpoint: &CertifiedCheckpointSummary,
+        digests: &ExecutionDigests,
+        proof: &MerkleProof,
+    ) -> SuiResult {
+        checkpoint.verify(&self.committee, None)?;
+        checkpoint.data().verify_inclusion(digests, proof)
+    }
+}
@@ -34,6 +34,15 @@ pub struct Envelope<T: Message, S> {
 }
 
 impl<T: Message, S> Envelope<T, S> {
+    /// An envelope of data and a signature on it, which is not checked.
+    pub fn new_from_data_and_sig(data: T, auth_signature: S) -> Self {
+        Self {
+            digest: OnceCell::new(),
+            data,
+            auth_signature,
+        }
+    }
+
     pub fn data(&self) -> &T {
         &self.data
     }
@@ -62,6 +71,10 @@ impl<T: Message, S> Envelope<T, S> {
     pub fn data_mut_for_testing(&mut self) -> &mut T {
         &mut self.data
     }
+
+    pub fn auth_sig_mut_for_testing(&mut self) -> &mut S {
+        &mut self.auth_signature
+    }
 }
 
 impl<T: Message + PartialEq, S: PartialEq> PartialEq for Envelope<T, S> {
@@ -113,6 +126,10 @@ where
         self.auth_signature.epoch
     }
 
+    pub fn authority(&self) -> &AuthorityName {
+        &self.auth_signature.authority
+    }
+
     pub fn into_unsigned(self) -> Envelope<T, EmptySignInfo> {
         Envelope::<T, EmptySignInfo>::new(self.into_data())
     }
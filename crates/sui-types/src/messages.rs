@@ -10,13 +10,15 @@ use crate::crypto::{
 };
 use crate::gas::GasCostSummary;
 use crate::message_envelope::{Envelope, Message, TrustedEnvelope, VerifiedEnvelope};
+use crate::messages_checkpoint::{AuthenticatedCheckpoint, CheckpointSequenceNumber};
+#[cfg(feature = "checkpoint-validator")]
 use crate::messages_checkpoint::{
-    AuthenticatedCheckpoint, CheckpointSequenceNumber, CheckpointSignatureMessage,
-    SignedCheckpointFragmentMessage,
+    CheckpointSignatureBatch, CheckpointSignatureMessage, SignedCheckpointFragmentMessage,
 };
 use crate::object::{Object, ObjectFormatOptions, Owner, OBJECT_START_VERSION};
 use crate::storage::{DeleteKind, WriteKind};
 use crate::{SUI_SYSTEM_STATE_OBJECT_ID, SUI_SYSTEM_STATE_OBJECT_SHARED_VERSION};
+#[cfg(feature = "checkpoint-validator")]
 use byteorder::{BigEndian, ReadBytesExt};
 use fastcrypto::encoding::{Base64, Encoding};
 use itertools::Either;
@@ -33,13 +35,13 @@ use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use serde_with::Bytes;
+#[cfg(feature = "checkpoint-validator")]
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt::Write;
 use std::fmt::{Debug, Display, Formatter};
-use std::{
-    collections::{BTreeMap, BTreeSet, HashSet},
-    hash::{Hash, Hasher},
-};
+#[cfg(feature = "checkpoint-validator")]
+use std::hash::{Hash, Hasher};
 use tracing::debug;
 
 #[cfg(test)]
@@ -1987,6 +1989,7 @@ pub struct ConsensusSync {
     pub sequence_number: SequenceNumber,
 }
 
+#[cfg(feature = "checkpoint-validator")]
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ConsensusTransaction {
     /// Encodes an u64 unique tracking id to allow us trace a message between Sui and Narwhal.
@@ -1995,13 +1998,16 @@ pub struct ConsensusTransaction {
     pub kind: ConsensusTransactionKind,
 }
 
+#[cfg(feature = "checkpoint-validator")]
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum ConsensusTransactionKind {
     UserTransaction(Box<CertifiedTransaction>),
     Checkpoint(Box<SignedCheckpointFragmentMessage>),
     CheckpointSignature(Box<CheckpointSignatureMessage>),
+    CheckpointSignatureBatch(Box<CheckpointSignatureBatch>),
 }
 
+#[cfg(feature = "checkpoint-validator")]
 impl ConsensusTransaction {
     pub fn new_certificate_message(
         authority: &AuthorityName,
@@ -2030,7 +2036,7 @@ impl ConsensusTransaction {
 
     pub fn new_checkpoint_signature_message(data: CheckpointSignatureMessage) -> Self {
         let mut hasher = DefaultHasher::new();
-        data.summary.auth_signature.signature.hash(&mut hasher);
+        data.summary.auth_sig().signature.hash(&mut hasher);
         let tracking_id = hasher.finish().to_be_bytes();
         Self {
             tracking_id,
@@ -2038,6 +2044,16 @@ impl ConsensusTransaction {
         }
     }
 
+    pub fn new_checkpoint_signature_batch(batch: CheckpointSignatureBatch) -> Self {
+        let mut hasher = DefaultHasher::new();
+        batch.signature.hash(&mut hasher);
+        let tracking_id = hasher.finish().to_be_bytes();
+        Self {
+            tracking_id,
+            kind: ConsensusTransactionKind::CheckpointSignatureBatch(Box::new(batch)),
+        }
+    }
+
     pub fn get_tracking_id(&self) -> u64 {
         (&self.tracking_id[..])
             .read_u64::<BigEndian>()
@@ -2051,6 +2067,7 @@ impl ConsensusTransaction {
             }
             ConsensusTransactionKind::Checkpoint(fragment) => fragment.verify(committee.epoch),
             ConsensusTransactionKind::CheckpointSignature(data) => data.verify(committee),
+            ConsensusTransactionKind::CheckpointSignatureBatch(batch) => batch.verify(committee),
         }
     }
 }
@@ -0,0 +1,1386 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The checkpoint messages only validators exchange: proposals, the fragments built from them
+//! and their chunks, and the signatures published to consensus. Re-exported by the parent
+//! module, so that their paths do not depend on the features the crate is built with.
+
+use std::hash::Hasher;
+use std::io::Cursor;
+
+use serde::de::DeserializeOwned;
+
+use super::*;
+use crate::checkpoint_signing_guard::PersistedSigningGuard;
+use crate::waypoint::WaypointDiff;
+
+/// This is a message validators publish to consensus in order to sign checkpoint
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointSignatureMessage {
+    pub summary: SignedCheckpointSummary,
+}
+
+impl CheckpointSignatureMessage {
+    pub fn verify(&self, committee: &Committee) -> SuiResult {
+        self.summary.verify_with_contents(committee, None)
+    }
+}
+
+/// The largest number of signatures in a `CheckpointSignatureBatch`.
+pub const MAX_CHECKPOINT_SIGNATURE_BATCH: usize = 100;
+
+/// Signatures of one authority on consecutive checkpoints.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointSignatureBatchMessage {
+    pub authority: AuthorityName,
+    pub summaries: Vec<SignedCheckpointSummary>,
+}
+
+/// Bundles the signatures an authority publishes to consensus while catching up, so that
+/// they go through consensus as one message instead of one message per checkpoint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointSignatureBatch {
+    pub message: CheckpointSignatureBatchMessage,
+    pub signature: AuthoritySignature,
+}
+
+impl CheckpointSignatureBatch {
+    pub fn new(
+        summaries: Vec<SignedCheckpointSummary>,
+        authority: AuthorityName,
+        epoch: EpochId,
+        signer: &dyn signature::Signer<AuthoritySignature>,
+    ) -> SuiResult<Self> {
+        let message = CheckpointSignatureBatchMessage {
+            authority,
+            summaries,
+        };
+        Self::check_message(&message, epoch)?;
+        let signature = AuthoritySignature::new(&message, epoch, signer);
+        Ok(Self { message, signature })
+    }
+
+    fn check_message(message: &CheckpointSignatureBatchMessage, epoch: EpochId) -> SuiResult {
+        let summaries = &message.summaries;
+        fp_ensure!(
+            !summaries.is_empty() && summaries.len() <= MAX_CHECKPOINT_SIGNATURE_BATCH,
+            SuiError::from(
+                format!(
+                    "A checkpoint signature batch must hold between 1 and {} signatures, not {}",
+                    MAX_CHECKPOINT_SIGNATURE_BATCH,
+                    summaries.len()
+                )
+                .as_str()
+            )
+        );
+        fp_ensure!(
+            summaries
+                .iter()
+                .all(|s| *s.authority() == message.authority && s.data().epoch == epoch),
+            SuiError::from("Checkpoint signature batch mixes authorities or epochs")
+        );
+        fp_ensure!(
+            summaries
+                .windows(2)
+                .all(|w| w[1].data().sequence_number == w[0].data().sequence_number + 1),
+            SuiError::from("Checkpoint signature batch sequence numbers must be consecutive")
+        );
+        Ok(())
+    }
+
+    pub fn authority(&self) -> &AuthorityName {
+        &self.message.authority
+    }
+
+    /// Check the outer signature, then every inner signature.
+    pub fn verify(&self, committee: &Committee) -> SuiResult {
+        Self::check_message(&self.message, committee.epoch)?;
+        self.signature
+            .verify(&self.message, committee.epoch, self.message.authority)?;
+        self.message
+            .summaries
+            .iter()
+            .try_for_each(|summary| summary.verify_with_contents(committee, None))
+    }
+
+    /// Unpack the batch into the messages it replaces.
+    pub fn into_messages(self) -> impl Iterator<Item = CheckpointSignatureMessage> {
+        self.message
+            .summaries
+            .into_iter()
+            .map(|summary| CheckpointSignatureMessage { summary })
+    }
+}
+
+impl CheckpointFragment {
+    /// Same as `verify`, against the committee of the epoch the proposals were signed in,
+    /// which may be the previous one as allowed by `policy`. Both proposals must be of the
+    /// same epoch.
+    pub fn verify_with_epoch_tolerance(
+        &self,
+        committees: &[Committee; 2],
+        policy: &EpochTolerancePolicy,
+    ) -> SuiResult {
+        fp_ensure!(
+            self.proposer.epoch() == self.other.epoch(),
+            SuiError::from("Fragment proposals are signed in different epochs")
+        );
+        let committee = policy.committee_for(
+            committees,
+            self.proposer.epoch(),
+            self.proposer.data().sequence_number,
+            policy.proposals,
+        )?;
+        self.verify(committee)
+    }
+}
+
+/// The message signed by an operator key to approve a proposal summary before the
+/// authority signs it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointProposalApproval {
+    pub authority: AuthorityName,
+    pub summary_digest: [u8; 32],
+}
+
+/// An M-of-N policy over the operator keys of a validator. Validators running replicated
+/// (e.g. active-passive) setups may require several operators to approve a proposal before
+/// the authority key signs it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalApprovalPolicy {
+    pub operators: BTreeSet<AuthorityName>,
+    pub threshold: usize,
+}
+
+impl ProposalApprovalPolicy {
+    pub fn new(operators: BTreeSet<AuthorityName>, threshold: usize) -> SuiResult<Self> {
+        fp_ensure!(
+            threshold > 0 && threshold <= operators.len(),
+            SuiError::from("Approval threshold must be between 1 and the number of operators")
+        );
+        Ok(Self {
+            operators,
+            threshold,
+        })
+    }
+}
+
+/// A proposal summary collecting operator approvals. Once enough operators approved it
+/// according to a ProposalApprovalPolicy, it can be turned into a signed proposal summary.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApprovedCheckpointProposalSummary {
+    pub epoch: EpochId,
+    pub authority: AuthorityName,
+    pub summary: CheckpointProposalSummary,
+    pub approvals: BTreeMap<AuthorityName, AuthoritySignature>,
+}
+
+impl ApprovedCheckpointProposalSummary {
+    pub fn new(
+        epoch: EpochId,
+        authority: AuthorityName,
+        summary: CheckpointProposalSummary,
+    ) -> Self {
+        Self {
+            epoch,
+            authority,
+            summary,
+            approvals: BTreeMap::new(),
+        }
+    }
+
+    fn approval_message(&self) -> CheckpointProposalApproval {
+        CheckpointProposalApproval {
+            authority: self.authority,
+            summary_digest: self.summary.digest(),
+        }
+    }
+
+    /// Add the approval of an operator.
+    pub fn approve(
+        &mut self,
+        operator: AuthorityName,
+        signer: &dyn signature::Signer<AuthoritySignature>,
+    ) {
+        let signature = AuthoritySignature::new(&self.approval_message(), self.epoch, signer);
+        self.approvals.insert(operator, signature);
+    }
+
+    /// Check that at least `policy.threshold` operators of the policy approved the summary.
+    /// Approvals from keys outside the policy are ignored.
+    pub fn verify(&self, policy: &ProposalApprovalPolicy) -> SuiResult {
+        let message = self.approval_message();
+        let mut approved = 0;
+        for (operator, signature) in &self.approvals {
+            if policy.operators.contains(operator) {
+                signature.verify(&message, self.epoch, *operator)?;
+                approved += 1;
+            }
+        }
+        fp_ensure!(
+            approved >= policy.threshold,
+            SuiError::from(
+                format!(
+                    "Proposal approved by {approved} operators, {} required",
+                    policy.threshold
+                )
+                .as_str()
+            )
+        );
+        Ok(())
+    }
+
+    /// Verify the approvals against the policy, and only then sign the summary with the
+    /// authority key.
+    pub fn into_signed_summary(
+        self,
+        policy: &ProposalApprovalPolicy,
+        signer: &dyn signature::Signer<AuthoritySignature>,
+    ) -> SuiResult<SignedCheckpointProposalSummary> {
+        self.verify(policy)?;
+        Ok(SignedCheckpointProposalSummary::new(
+            self.epoch,
+            self.summary,
+            signer,
+            self.authority,
+        ))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointProposal {
+    /// Summary of the checkpoint proposal.
+    pub signed_summary: SignedCheckpointProposalSummary,
+    /// The transactions included in the proposal.
+    /// TODO: only include a commitment by default.
+    pub transactions: CheckpointProposalContents,
+}
+
+impl CheckpointProposal {
+    pub fn new_from_signed_proposal_summary(
+        signed_summary: SignedCheckpointProposalSummary,
+        transactions: CheckpointProposalContents,
+    ) -> Self {
+        debug_assert!(signed_summary.data().content_digest == transactions.digest());
+        Self {
+            signed_summary,
+            transactions,
+        }
+    }
+
+    /// Create a proposal for a checkpoint at a particular height
+    /// This contains a signed proposal summary and the list of transactions
+    /// in the proposal. Outside of this crate, proposals are signed by `LocalProposal::new`,
+    /// which checks them against the signing guard of the authority.
+    pub(crate) fn new(
+        epoch: EpochId,
+        sequence_number: CheckpointSequenceNumber,
+        authority: AuthorityName,
+        signer: &dyn signature::Signer<AuthoritySignature>,
+        transactions: CheckpointProposalContents,
+    ) -> Self {
+        let proposal_summary = CheckpointProposalSummary::new(sequence_number, &transactions);
+        Self {
+            signed_summary: SignedCheckpointProposalSummary::new(
+                epoch,
+                proposal_summary,
+                signer,
+                authority,
+            ),
+            transactions,
+        }
+    }
+
+    /// Returns the sequence number of this proposal
+    pub fn sequence_number(&self) -> &CheckpointSequenceNumber {
+        &self.signed_summary.data().sequence_number
+    }
+
+    // Iterate over all transaction/effects
+    pub fn transactions(&self) -> impl Iterator<Item = &ExecutionDigests> {
+        self.transactions.transactions.iter()
+    }
+
+    // Get the authority name
+    pub fn name(&self) -> &AuthorityName {
+        &self.signed_summary.auth_sig().authority
+    }
+
+    /// Construct a Diff structure between this proposal and another
+    /// proposal. A diff structure has to contain keys. The diff represents
+    /// the elements that each proposal need to be augmented by to
+    /// contain the same elements.
+    ///
+    /// This requires the contents of both proposals, see `checkpoint_proposal_diff` to build
+    /// the fragment from a sketch of the contents of the other proposal instead. Outside of
+    /// this crate, fragments are built with `LocalProposal::fragment_with_remote`.
+    pub(crate) fn fragment_with(&self, other_proposal: &CheckpointProposal) -> CheckpointFragment {
+        if let Ok(fragment) = CheckpointFragment::new_trivial(
+            self.signed_summary.clone(),
+            other_proposal.signed_summary.clone(),
+        ) {
+            return fragment;
+        }
+
+        let my_transactions = &self.transactions.transactions;
+        let other_transactions = &other_proposal.transactions.transactions;
+        let iter_missing_me = other_transactions.difference(my_transactions).copied();
+        let iter_missing_other = my_transactions.difference(other_transactions).copied();
+
+        let diff = WaypointDiff::new(
+            *self.name(),
+            *self.signed_summary.data().waypoint.clone(),
+            iter_missing_me,
+            *other_proposal.name(),
+            *other_proposal.signed_summary.data().waypoint.clone(),
+            iter_missing_other,
+        );
+
+        CheckpointFragment {
+            proposer: self.signed_summary.clone(),
+            other: other_proposal.signed_summary.clone(),
+            data: CheckpointFragmentData {
+                diff,
+                certs: BTreeMap::new(),
+            },
+        }
+    }
+}
+
+/// A proposal made by this validator. Only a local proposal can be fragmented with a
+/// `RemoteProposal`, so that a validator never fragments with itself.
+#[derive(Clone, Debug)]
+pub struct LocalProposal(CheckpointProposal);
+
+/// A proposal of another validator, as received from the network.
+#[derive(Clone, Debug)]
+pub struct RemoteProposal(CheckpointProposal);
+
+impl LocalProposal {
+    /// Sign a proposal of this authority, once `guard` allows it. Nothing is refused without
+    /// a guard.
+    pub fn new(
+        epoch: EpochId,
+        sequence_number: CheckpointSequenceNumber,
+        authority: AuthorityName,
+        signer: &dyn signature::Signer<AuthoritySignature>,
+        transactions: CheckpointProposalContents,
+        guard: Option<&PersistedSigningGuard>,
+    ) -> SuiResult<Self> {
+        let summary = CheckpointProposalSummary::new(sequence_number, &transactions);
+        if let Some(guard) = guard {
+            guard.record_proposal(epoch, &summary)?;
+        }
+        Ok(Self(CheckpointProposal::new_from_signed_proposal_summary(
+            SignedCheckpointProposalSummary::new(epoch, summary, signer, authority),
+            transactions,
+        )))
+    }
+
+    /// Wrap a proposal that must have been made by `local_name`.
+    pub fn from_proposal(
+        proposal: CheckpointProposal,
+        local_name: &AuthorityName,
+    ) -> SuiResult<Self> {
+        fp_ensure!(
+            proposal.name() == local_name,
+            SuiError::from("Proposal of another validator used as the local proposal")
+        );
+        Ok(Self(proposal))
+    }
+
+    pub fn into_inner(self) -> CheckpointProposal {
+        self.0
+    }
+
+    pub fn fragment_with_remote(&self, other: &RemoteProposal) -> CheckpointFragment {
+        self.0.fragment_with(&other.0)
+    }
+}
+
+impl RemoteProposal {
+    /// Wrap a proposal received from the network, rejecting the own proposal of `local_name`.
+    pub fn new(proposal: CheckpointProposal, local_name: &AuthorityName) -> SuiResult<Self> {
+        fp_ensure!(
+            proposal.name() != local_name,
+            SuiError::from("Own proposal received through the peer path")
+        );
+        Ok(Self(proposal))
+    }
+
+    pub fn into_inner(self) -> CheckpointProposal {
+        self.0
+    }
+}
+
+impl Deref for LocalProposal {
+    type Target = CheckpointProposal;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Deref for RemoteProposal {
+    type Target = CheckpointProposal;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointFragmentData {
+    pub diff: WaypointDiff<AuthorityName, ExecutionDigests>,
+    pub certs: BTreeMap<ExecutionDigests, CertifiedTransaction>,
+}
+
+impl CheckpointFragmentData {
+    /// The transactions in the diff, whose certs are needed by whoever holds the contents
+    /// of only one of the proposals.
+    pub fn required_digests(&self) -> impl Iterator<Item = &ExecutionDigests> {
+        self.diff
+            .first
+            .items
+            .iter()
+            .chain(self.diff.second.items.iter())
+    }
+}
+
+/// Certificates known locally, e.g. those of the transactions a fullnode already executed.
+/// Certificates returned must have been verified when they were stored.
+pub trait CertResolver {
+    fn resolve(&self, digests: &ExecutionDigests) -> SuiResult<Option<CertifiedTransaction>>;
+}
+
+/// Contents reconstructed from a fragment, with the certs of the transactions that were
+/// missing from the base contents.
+#[derive(Clone, Debug)]
+pub struct ReconstructedContents {
+    pub contents: CheckpointProposalContents,
+    pub certs: BTreeMap<ExecutionDigests, CertifiedTransaction>,
+    /// How many certs were resolved locally, and how many were taken from the fragment.
+    pub resolved_locally: usize,
+    pub from_fragment: usize,
+}
+
+// The construction of checkpoints is based on the aggregation of fragments.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointFragment {
+    pub proposer: SignedCheckpointProposalSummary,
+    pub other: SignedCheckpointProposalSummary,
+    pub data: CheckpointFragmentData,
+}
+
+impl CheckpointFragment {
+    /// Create the fragment between two proposals with the same contents, which has an
+    /// empty diff and no certs.
+    pub fn new_trivial(
+        proposer: SignedCheckpointProposalSummary,
+        other: SignedCheckpointProposalSummary,
+    ) -> SuiResult<Self> {
+        fp_ensure!(
+            proposer.data().content_digest == other.data().content_digest,
+            SuiError::from("Trivial fragment requires proposals with the same contents")
+        );
+        let diff = WaypointDiff::new(
+            *proposer.authority(),
+            *proposer.data().waypoint.clone(),
+            std::iter::empty(),
+            *other.authority(),
+            *other.data().waypoint.clone(),
+            std::iter::empty(),
+        );
+        Ok(CheckpointFragment {
+            proposer,
+            other,
+            data: CheckpointFragmentData {
+                diff,
+                certs: BTreeMap::new(),
+            },
+        })
+    }
+
+    /// Whether both proposals have the same contents, so that the diff is empty.
+    pub fn is_trivial(&self) -> bool {
+        self.proposer.data().content_digest == self.other.data().content_digest
+            && self.data.diff.first.items.is_empty()
+            && self.data.diff.second.items.is_empty()
+    }
+
+    pub fn verify(&self, committee: &Committee) -> SuiResult {
+        checkpoint_span!(
+            "checkpoint_fragment_verify",
+            seq = self.proposer.data().sequence_number,
+            proposer = %self.proposer.authority(),
+            other = %self.other.authority(),
+            certs = self.data.certs.len()
+        );
+        self.verify_summaries(committee)?;
+
+        // Check that the fragment contains all missing certs indicated in diff.
+        for digest in self.diff_items() {
+            self.cert(digest)?.verify_signature(committee)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs all the checks of `verify`, then additionally cross-checks the attached
+    /// certificates against the fragment: both proposals must be signed in the same epoch,
+    /// every certificate must come from an epoch no later than that epoch, and every
+    /// certificate must be keyed by its own transaction digest in the diff.
+    pub fn verify_strict(&self, committee: &Committee) -> SuiResult {
+        self.verify(committee)?;
+        self.check_strict()
+    }
+
+    /// Same as `verify`, or `verify_strict` if `strict` is set, but the signatures of both
+    /// proposals and the quorum signatures of all certs are checked in a single batch, which
+    /// is much faster for fragments carrying many certs. A failure doesn't tell which signature
+    /// is invalid: callers needing to know can fall back to `verify`.
+    pub fn verify_batched(&self, committee: &Committee, strict: bool) -> SuiResult {
+        checkpoint_span!(
+            "checkpoint_fragment_verify_batched",
+            seq = self.proposer.data().sequence_number,
+            proposer = %self.proposer.authority(),
+            other = %self.other.authority(),
+            certs = self.data.certs.len()
+        );
+        self.check_summaries()?;
+        if strict {
+            self.check_strict()?;
+        }
+
+        let mut obligation = VerificationObligation::default();
+        for proposal in [&self.proposer, &self.other] {
+            let idx = obligation.add_message(proposal.data(), proposal.auth_sig().epoch);
+            proposal
+                .auth_sig()
+                .add_to_verification_obligation(committee, &mut obligation, idx)?;
+        }
+        for digest in self.diff_items() {
+            let cert = self.cert(digest)?;
+            // Sender signatures cannot be batched.
+            cert.data().verify()?;
+            let idx = obligation.add_message(cert.data(), cert.auth_sig().epoch);
+            cert.auth_sig()
+                .add_to_verification_obligation(committee, &mut obligation, idx)?;
+        }
+        obligation.verify_all()
+    }
+
+    fn diff_items(&self) -> impl Iterator<Item = &ExecutionDigests> {
+        self.data.required_digests()
+    }
+
+    fn cert(&self, digest: &ExecutionDigests) -> SuiResult<&CertifiedTransaction> {
+        self.data
+            .certs
+            .get(digest)
+            .ok_or_else(|| SuiError::from(format!("Missing cert with digest {digest:?}").as_str()))
+    }
+
+    /// Checks everything but the certs: signatures of both proposals and the diff.
+    fn verify_summaries(&self, committee: &Committee) -> SuiResult {
+        // Check the signatures of proposer and other
+        self.proposer.verify_with_contents(committee, None)?;
+        self.other.verify_with_contents(committee, None)?;
+
+        self.check_summaries()
+    }
+
+    /// Same as `verify_summaries`, without the signatures.
+    fn check_summaries(&self) -> SuiResult {
+        fp_ensure!(
+            self.proposer.data().sequence_number == self.other.data().sequence_number,
+            SuiError::from("Proposer and other have inconsistent sequence number")
+        );
+
+        // Check consistency between checkpoint summary and waypoints.
+        fp_ensure!(
+            self.data.diff.first.waypoint == *self.proposer.data().waypoint
+                && self.data.diff.second.waypoint == *self.other.data().waypoint
+                && &self.data.diff.first.key == self.proposer.authority()
+                && &self.data.diff.second.key == self.other.authority(),
+            SuiError::from("Waypoint diff and checkpoint summary inconsistent")
+        );
+
+        // Check consistency of waypoint diff. With identical contents, there is nothing to
+        // accumulate: the waypoints must simply be equal.
+        if self.is_trivial() {
+            fp_ensure!(
+                self.data.diff.first.waypoint == self.data.diff.second.waypoint
+                    && self.data.certs.is_empty(),
+                SuiError::from("Trivial fragment is not valid")
+            );
+        } else {
+            fp_ensure!(
+                self.data.diff.check(),
+                SuiError::from("Waypoint diff is not valid")
+            );
+        }
+        Ok(())
+    }
+
+    /// The checks `verify_strict` adds to `verify`, without any signature.
+    fn check_strict(&self) -> SuiResult {
+        let checkpoint_epoch = self.proposer.auth_sig().epoch;
+        fp_ensure!(
+            self.other.auth_sig().epoch == checkpoint_epoch,
+            SuiError::WrongEpoch {
+                expected_epoch: checkpoint_epoch,
+                actual_epoch: self.other.auth_sig().epoch,
+            }
+        );
+        for digest in self.diff_items() {
+            let cert = self.cert(digest)?;
+            fp_ensure!(
+                cert.epoch() <= checkpoint_epoch,
+                SuiError::from(
+                    format!(
+                        "Cert {:?} is from epoch {}, after checkpoint epoch {}",
+                        digest.transaction,
+                        cert.epoch(),
+                        checkpoint_epoch
+                    )
+                    .as_str()
+                )
+            );
+            fp_ensure!(
+                *cert.digest() == digest.transaction,
+                SuiError::from(
+                    format!(
+                        "Cert digest {:?} doesn't match diff entry {:?}",
+                        cert.digest(),
+                        digest.transaction
+                    )
+                    .as_str()
+                )
+            );
+        }
+        Ok(())
+    }
+
+    /// Split the certs of this fragment into parts serializing to at most `max_bytes` each.
+    /// Every part carries the full diff and can be verified on its own with
+    /// `CheckpointFragmentPart::verify`; the fragment is rebuilt with `from_parts`.
+    /// Fails if the diff alone, or the diff with a single cert, exceeds the budget.
+    pub fn split_by_budget(&self, max_bytes: u64) -> SuiResult<Vec<CheckpointFragmentPart>> {
+        let empty_part = |part_index| CheckpointFragmentPart {
+            fragment: CheckpointFragment {
+                proposer: self.proposer.clone(),
+                other: self.other.clone(),
+                data: CheckpointFragmentData {
+                    diff: self.data.diff.clone(),
+                    certs: BTreeMap::new(),
+                },
+            },
+            part_index,
+            has_more: true,
+        };
+        let base_bytes = bincode::serialized_size(&empty_part(0)).unwrap();
+        fp_ensure!(
+            base_bytes <= max_bytes,
+            SuiError::from(
+                format!("Fragment diff needs {base_bytes} bytes, over the {max_bytes} budget")
+                    .as_str()
+            )
+        );
+
+        let mut parts = vec![empty_part(0)];
+        let mut part_bytes = base_bytes;
+        for (digest, cert) in &self.data.certs {
+            let cert_bytes = bincode::serialized_size(&(digest, cert)).unwrap();
+            fp_ensure!(
+                base_bytes + cert_bytes <= max_bytes,
+                SuiError::from(
+                    format!("Cert {digest:?} does not fit in the {max_bytes} bytes budget")
+                        .as_str()
+                )
+            );
+            if part_bytes + cert_bytes > max_bytes {
+                parts.push(empty_part(parts.len() as u32));
+                part_bytes = base_bytes;
+            }
+            let part = parts.last_mut().unwrap();
+            part.fragment.data.certs.insert(*digest, cert.clone());
+            part_bytes += cert_bytes;
+        }
+        parts.last_mut().unwrap().has_more = false;
+        Ok(parts)
+    }
+
+    /// Rebuild a fragment from all the parts produced by `split_by_budget`, in any order.
+    /// The result should still be checked with `verify`.
+    pub fn from_parts(mut parts: Vec<CheckpointFragmentPart>) -> SuiResult<CheckpointFragment> {
+        parts.sort_by_key(|part| part.part_index);
+        fp_ensure!(
+            !parts.is_empty()
+                && parts
+                    .iter()
+                    .enumerate()
+                    .all(|(idx, part)| part.part_index == idx as u32)
+                && parts
+                    .iter()
+                    .enumerate()
+                    .all(|(idx, part)| part.has_more == (idx + 1 < parts.len())),
+            SuiError::from("Fragment parts are missing or inconsistent")
+        );
+        let mut parts = parts.into_iter();
+        let mut fragment = parts.next().unwrap().fragment;
+        for part in parts {
+            fp_ensure!(
+                part.fragment.same_diff_as(&fragment),
+                SuiError::from("Fragment parts belong to different fragments")
+            );
+            fragment.data.certs.extend(part.fragment.data.certs);
+        }
+        Ok(fragment)
+    }
+
+    fn same_diff_as(&self, other: &CheckpointFragment) -> bool {
+        let same_proposal = |a: &SignedCheckpointProposalSummary,
+                             b: &SignedCheckpointProposalSummary| {
+            a.authority() == b.authority() && a.data() == b.data()
+        };
+        let (diff, other_diff) = (&self.data.diff, &other.data.diff);
+        same_proposal(&self.proposer, &other.proposer)
+            && same_proposal(&self.other, &other.other)
+            && diff.first.key == other_diff.first.key
+            && diff.first.items == other_diff.first.items
+            && diff.second.key == other_diff.second.key
+            && diff.second.items == other_diff.second.items
+    }
+
+    pub fn proposer_sequence_number(&self) -> &CheckpointSequenceNumber {
+        &self.proposer.data().sequence_number
+    }
+
+    /// Derive the union of the contents of both proposals, given the contents of either
+    /// the proposer or the other proposal. The result is checked against the waypoints of
+    /// both proposals; the fragment itself should already be verified.
+    pub fn reconstruct_union(
+        &self,
+        base: &CheckpointProposalContents,
+    ) -> SuiResult<CheckpointProposalContents> {
+        let base_digest = base.digest();
+        let missing = if base_digest == self.proposer.data().content_digest {
+            &self.data.diff.first.items
+        } else if base_digest == self.other.data().content_digest {
+            &self.data.diff.second.items
+        } else {
+            return Err(SuiError::from(
+                "Contents match neither proposal of the fragment",
+            ));
+        };
+        let union = CheckpointProposalContents::new(
+            base.transactions.iter().chain(missing.iter()).cloned(),
+        );
+
+        let mut union_waypoint = Waypoint::default();
+        union_waypoint.insert_all(union.transactions.iter());
+        for side in [&self.data.diff.first, &self.data.diff.second] {
+            let mut expected = side.waypoint.clone();
+            expected.insert_all(side.items.iter());
+            fp_ensure!(
+                expected == union_waypoint,
+                SuiError::from("Reconstructed contents inconsistent with waypoints")
+            );
+        }
+        Ok(union)
+    }
+
+    /// Same as `reconstruct_union`, also gathering the certs of the transactions missing
+    /// from `base`. Certs known to `resolver` are used first, and the ones attached to the
+    /// fragment only as a fallback. The certs attached to the fragment are dropped with it,
+    /// whether they are used or not.
+    pub fn reconstruct_with_certs(
+        mut self,
+        base: &CheckpointProposalContents,
+        resolver: &dyn CertResolver,
+    ) -> SuiResult<ReconstructedContents> {
+        let contents = self.reconstruct_union(base)?;
+        let mut reconstructed = ReconstructedContents {
+            contents,
+            certs: BTreeMap::new(),
+            resolved_locally: 0,
+            from_fragment: 0,
+        };
+        for digests in &reconstructed.contents.transactions {
+            if base.transactions.contains(digests) {
+                continue;
+            }
+            let cert = match resolver.resolve(digests)? {
+                Some(cert) => {
+                    reconstructed.resolved_locally += 1;
+                    cert
+                }
+                None => {
+                    let cert = self.data.certs.remove(digests).ok_or_else(|| {
+                        SuiError::from(
+                            format!("Missing cert of transaction {:?}", digests.transaction)
+                                .as_str(),
+                        )
+                    })?;
+                    reconstructed.from_fragment += 1;
+                    cert
+                }
+            };
+            fp_ensure!(
+                *cert.digest() == digests.transaction,
+                SuiError::from(
+                    format!(
+                        "Cert {:?} resolved for transaction {:?}",
+                        cert.digest(),
+                        digests.transaction
+                    )
+                    .as_str()
+                )
+            );
+            reconstructed.certs.insert(*digests, cert);
+        }
+        Ok(reconstructed)
+    }
+
+    pub fn to_signed_message_chunks(
+        &self,
+        signer: &dyn signature::Signer<AuthoritySignature>,
+    ) -> Vec<SignedCheckpointFragmentMessage> {
+        self.to_signed_message_chunks_with(signer, &DecodeLimits::default())
+    }
+
+    pub fn to_signed_message_chunks_with(
+        &self,
+        signer: &dyn signature::Signer<AuthoritySignature>,
+        limits: &DecodeLimits,
+    ) -> Vec<SignedCheckpointFragmentMessage> {
+        self.to_message_chunks_with(limits)
+            .into_iter()
+            .map(|message| {
+                SignedCheckpointFragmentMessage::new(
+                    message,
+                    self.proposer.auth_sig().epoch,
+                    signer,
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the size statistics of this fragment, as it would be sent in chunks.
+    pub fn stats(&self) -> FragmentStats {
+        self.stats_with(&DecodeLimits::default())
+    }
+
+    /// Same as `stats`, for chunks of `limits.max_chunk_size` bytes.
+    pub fn stats_with(&self, limits: &DecodeLimits) -> FragmentStats {
+        let total_bytes = bincode::serialized_size(&self.data).unwrap();
+        let chunk_size = limits.max_chunk_size as u64;
+        self.stats_for(
+            total_bytes,
+            ((total_bytes + chunk_size - 1) / chunk_size) as u32,
+        )
+    }
+
+    fn stats_for(&self, total_bytes: u64, chunk_count: u32) -> FragmentStats {
+        FragmentStats {
+            sequence_number: self.proposer.data().sequence_number,
+            proposer: *self.proposer.authority(),
+            other: *self.other.authority(),
+            total_bytes,
+            chunk_count,
+            cert_count: self.data.certs.len() as u64,
+            diff_sizes: (
+                self.data.diff.first.items.len() as u64,
+                self.data.diff.second.items.len() as u64,
+            ),
+        }
+    }
+
+    pub fn to_message_chunks(&self) -> Vec<CheckpointFragmentMessage> {
+        self.to_message_chunks_with(&DecodeLimits::default())
+    }
+
+    /// Split the fragment in chunks of `limits.max_chunk_size` bytes, so that a network with
+    /// smaller chunks than the default accepts them.
+    pub fn to_message_chunks_with(&self, limits: &DecodeLimits) -> Vec<CheckpointFragmentMessage> {
+        self.to_message_chunks_with_stats(limits).0
+    }
+
+    pub fn to_message_chunks_with_stats(
+        &self,
+        limits: &DecodeLimits,
+    ) -> (Vec<CheckpointFragmentMessage>, FragmentStats) {
+        let proposer_name = *self.proposer.authority();
+        let other_name = *self.other.authority();
+        let sequence_number = self.proposer.data().sequence_number;
+        checkpoint_span!(
+            "checkpoint_fragment_serialize",
+            seq = sequence_number,
+            proposer = %proposer_name,
+            other = %other_name
+        );
+        let bytes = serialize(&self.data).unwrap();
+        checkpoint_event!(bytes = bytes.len(), "Serialized checkpoint fragment");
+        let chunks = bytes.chunks(limits.max_chunk_size);
+        let stats = self.stats_for(bytes.len() as u64, chunks.len() as u32);
+        let header = CheckpointFragmentMessageHeader {
+            proposer: self.proposer.clone(),
+            other: self.other.clone(),
+            chunk_count: chunks.len() as u32,
+            content_digest: sha3_bytes(&bytes),
+        };
+        let header_digest = header.digest();
+        let mut results = vec![CheckpointFragmentMessage::Header(Box::new(header))];
+        for (idx, chunk) in chunks.enumerate() {
+            results.push(CheckpointFragmentMessage::Chunk(Box::new(
+                CheckpointFragmentMessageChunk {
+                    sequence_number,
+                    proposer: proposer_name,
+                    other: other_name,
+                    header_digest,
+                    chunk_id: idx as u32,
+                    content: chunk.to_vec(),
+                },
+            )))
+        }
+        (results, stats)
+    }
+}
+
+/// A fragment holding a subset of the certs of a larger fragment, produced by
+/// `CheckpointFragment::split_by_budget`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointFragmentPart {
+    pub fragment: CheckpointFragment,
+    pub part_index: u32,
+    /// Whether more parts follow this one.
+    pub has_more: bool,
+}
+
+impl CheckpointFragmentPart {
+    /// Checks the proposals and the diff as `CheckpointFragment::verify` does, and that
+    /// every cert of this part is part of the diff.
+    pub fn verify(&self, committee: &Committee) -> SuiResult {
+        let fragment = &self.fragment;
+        fragment.verify_summaries(committee)?;
+        let diff = &fragment.data.diff;
+        for (digest, cert) in &fragment.data.certs {
+            fp_ensure!(
+                diff.first.items.contains(digest) || diff.second.items.contains(digest),
+                SuiError::from(format!("Cert {digest:?} is not part of the diff").as_str())
+            );
+            cert.verify_signature(committee)?;
+        }
+        Ok(())
+    }
+}
+
+/// Size statistics of a fragment, addressed by the same key as the fragment messages.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FragmentStats {
+    pub sequence_number: CheckpointSequenceNumber,
+    pub proposer: AuthorityName,
+    pub other: AuthorityName,
+    /// Size of the serialized fragment data, split into chunks.
+    pub total_bytes: u64,
+    pub chunk_count: u32,
+    pub cert_count: u64,
+    /// Number of transactions missing from the proposer and from the other proposal.
+    pub diff_sizes: (u64, u64),
+}
+
+impl FragmentStats {
+    /// Returns the checkpoint sequence number, proposer's name an other's name.
+    pub fn message_key(&self) -> (CheckpointSequenceNumber, AuthorityName, AuthorityName) {
+        (self.sequence_number, self.proposer, self.other)
+    }
+}
+
+/// Reassembly progress of a fragment, for operators debugging stuck checkpoints.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FragmentProgress {
+    pub sequence_number: CheckpointSequenceNumber,
+    pub proposer: AuthorityName,
+    pub other: AuthorityName,
+    pub received: u32,
+    pub total: u32,
+    pub missing_ids: Vec<u32>,
+    /// Bytes received so far.
+    pub bytes: u64,
+}
+
+impl FragmentProgress {
+    pub fn is_complete(&self) -> bool {
+        self.missing_ids.is_empty()
+    }
+}
+
+impl Display for FragmentProgress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Fragment seq={} proposer={:?} other={:?}: {}/{} chunks, {} bytes",
+            self.sequence_number,
+            self.proposer.concise(),
+            self.other.concise(),
+            self.received,
+            self.total,
+            self.bytes
+        )?;
+        if !self.missing_ids.is_empty() {
+            write!(f, ", missing {:?}", self.missing_ids)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckpointFragmentMessageHeader {
+    pub proposer: SignedCheckpointProposalSummary,
+    pub other: SignedCheckpointProposalSummary,
+    pub chunk_count: u32,
+    /// Digest of the serialized fragment data split in the chunks. It makes the header digest
+    /// differ between two versions of a fragment for the same proposals.
+    pub content_digest: [u8; 32],
+}
+
+pub type FragmentHeaderDigest = [u8; 32];
+
+/// The fields of a `CheckpointFragmentMessageHeader`, before validation.
+#[derive(Deserialize)]
+struct UncheckedCheckpointFragmentMessageHeader {
+    proposer: SignedCheckpointProposalSummary,
+    other: SignedCheckpointProposalSummary,
+    chunk_count: u32,
+    content_digest: [u8; 32],
+}
+
+impl<'de> Deserialize<'de> for CheckpointFragmentMessageHeader {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let unchecked = UncheckedCheckpointFragmentMessageHeader::deserialize(deserializer)?;
+        let header = CheckpointFragmentMessageHeader {
+            proposer: unchecked.proposer,
+            other: unchecked.other,
+            chunk_count: unchecked.chunk_count,
+            content_digest: unchecked.content_digest,
+        };
+        header.validate().map_err(serde::de::Error::custom)?;
+        Ok(header)
+    }
+}
+
+impl CheckpointFragmentMessageHeader {
+    pub fn digest(&self) -> FragmentHeaderDigest {
+        sha3_hash(self)
+    }
+
+    /// Structural checks, done when decoding headers. Signatures are not checked.
+    pub fn validate(&self) -> SuiResult {
+        self.validate_with(&DecodeLimits::default())
+    }
+
+    pub fn validate_with(&self, limits: &DecodeLimits) -> SuiResult {
+        fp_ensure!(
+            self.chunk_count > 0 && self.chunk_count <= limits.max_chunk_count(),
+            SuiError::from(format!("Invalid fragment chunk count {}", self.chunk_count).as_str())
+        );
+        fp_ensure!(
+            self.proposer.data().sequence_number == self.other.data().sequence_number,
+            SuiError::from("Proposer and other have inconsistent sequence number")
+        );
+        Ok(())
+    }
+}
+
+impl Hash for CheckpointFragmentMessageHeader {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.proposer.authority().hash(state);
+        self.other.authority().hash(state);
+        self.proposer.data().sequence_number.hash(state);
+        self.content_digest.hash(state);
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckpointFragmentMessageChunk {
+    pub sequence_number: CheckpointSequenceNumber,
+    pub proposer: AuthorityName,
+    pub other: AuthorityName,
+    /// Digest of the header of the fragment this chunk belongs to.
+    pub header_digest: FragmentHeaderDigest,
+    /// Index of the chunk within the fragment.
+    pub chunk_id: u32,
+    pub content: Vec<u8>,
+}
+
+/// The fields of a `CheckpointFragmentMessageChunk`, before validation.
+#[derive(Deserialize)]
+struct UncheckedCheckpointFragmentMessageChunk {
+    sequence_number: CheckpointSequenceNumber,
+    proposer: AuthorityName,
+    other: AuthorityName,
+    header_digest: FragmentHeaderDigest,
+    chunk_id: u32,
+    content: Vec<u8>,
+}
+
+impl<'de> Deserialize<'de> for CheckpointFragmentMessageChunk {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let unchecked = UncheckedCheckpointFragmentMessageChunk::deserialize(deserializer)?;
+        let chunk = CheckpointFragmentMessageChunk {
+            sequence_number: unchecked.sequence_number,
+            proposer: unchecked.proposer,
+            other: unchecked.other,
+            header_digest: unchecked.header_digest,
+            chunk_id: unchecked.chunk_id,
+            content: unchecked.content,
+        };
+        chunk.validate().map_err(serde::de::Error::custom)?;
+        Ok(chunk)
+    }
+}
+
+impl CheckpointFragmentMessageChunk {
+    /// Structural checks, done when decoding chunks: the chunk holds between one byte and
+    /// the size of a chunk, and its index fits in a valid header.
+    pub fn validate(&self) -> SuiResult {
+        self.validate_with(&DecodeLimits::default())
+    }
+
+    pub fn validate_with(&self, limits: &DecodeLimits) -> SuiResult {
+        fp_ensure!(
+            self.chunk_id < limits.max_chunk_count(),
+            SuiError::from(format!("Invalid fragment chunk id {}", self.chunk_id).as_str())
+        );
+        fp_ensure!(
+            !self.content.is_empty() && self.content.len() <= limits.max_chunk_size,
+            SuiError::from(format!("Invalid fragment chunk size {}", self.content.len()).as_str())
+        );
+        Ok(())
+    }
+
+    /// Identifies the chunk across fragments, including different versions of a fragment
+    /// for the same proposals.
+    pub fn chunk_key(&self) -> (FragmentHeaderDigest, u32) {
+        (self.header_digest, self.chunk_id)
+    }
+}
+
+impl Hash for CheckpointFragmentMessageChunk {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.proposer.hash(state);
+        self.other.hash(state);
+        self.sequence_number.hash(state);
+        self.header_digest.hash(state);
+        self.chunk_id.hash(state);
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Hash)]
+pub enum CheckpointFragmentMessage {
+    Header(Box<CheckpointFragmentMessageHeader>),
+    Chunk(Box<CheckpointFragmentMessageChunk>),
+}
+
+impl CheckpointFragmentMessage {
+    pub fn proposer_sequence_number(&self) -> CheckpointSequenceNumber {
+        match self {
+            CheckpointFragmentMessage::Header(header) => header.proposer.data().sequence_number,
+            CheckpointFragmentMessage::Chunk(chunk) => chunk.sequence_number,
+        }
+    }
+
+    /// Returns the checkpoint sequence number, proposer's name an other's name.
+    pub fn message_key(&self) -> (CheckpointSequenceNumber, AuthorityName, AuthorityName) {
+        match self {
+            CheckpointFragmentMessage::Header(header) => (
+                header.proposer.data().sequence_number,
+                *header.proposer.authority(),
+                *header.other.authority(),
+            ),
+            CheckpointFragmentMessage::Chunk(chunk) => {
+                (chunk.sequence_number, chunk.proposer, chunk.other)
+            }
+        }
+    }
+
+    /// The structural checks of the header or chunk, against the limits of the network.
+    pub fn validate_with(&self, limits: &DecodeLimits) -> SuiResult {
+        match self {
+            CheckpointFragmentMessage::Header(header) => header.validate_with(limits),
+            CheckpointFragmentMessage::Chunk(chunk) => chunk.validate_with(limits),
+        }
+    }
+}
+
+/// Not an `Envelope`: the signer of a fragment message is its proposer and the
+/// epoch is that of the proposals, so the signature carries neither.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedCheckpointFragmentMessage {
+    pub message: CheckpointFragmentMessage,
+    pub signature: AuthoritySignature,
+}
+
+impl Hash for SignedCheckpointFragmentMessage {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.message.hash(state);
+    }
+}
+
+impl SignedCheckpointFragmentMessage {
+    pub fn new(
+        message: CheckpointFragmentMessage,
+        epoch: EpochId,
+        signer: &dyn signature::Signer<AuthoritySignature>,
+    ) -> Self {
+        let signature = AuthoritySignature::new(&message, epoch, signer);
+        Self { message, signature }
+    }
+
+    pub fn verify(&self, epoch: EpochId) -> SuiResult {
+        let proposer = match &self.message {
+            CheckpointFragmentMessage::Header(header) => *header.proposer.authority(),
+            CheckpointFragmentMessage::Chunk(chunk) => chunk.proposer,
+        };
+        self.signature.verify(&self.message, epoch, proposer)
+    }
+}
+
+/// Decode a value from bytes reassembled from chunks, where `chunk_ends` holds the id of each
+/// chunk and the offset just past its last byte. On failure, the error locates where decoding
+/// stopped, both in the bytes and in the chunks: a failure in the middle of the data points to
+/// corrupted chunks, while unknown variants or trailing bytes point to a version mismatch.
+pub(super) fn decode_chunked<T: DeserializeOwned>(
+    content: &[u8],
+    chunk_ends: &[(u32, usize)],
+) -> SuiResult<T> {
+    let mut cursor = Cursor::new(content);
+    let result = bincode::deserialize_from::<_, T>(&mut cursor);
+    let offset = cursor.position() as usize;
+    let error = match result {
+        Ok(value) if offset == content.len() => return Ok(value),
+        Ok(_) => "trailing bytes after the value".to_string(),
+        Err(err) => err.to_string(),
+    };
+    // The read that failed may span the chunk boundary at the offset.
+    let chunk_of = |byte: usize| {
+        let index = chunk_ends.partition_point(|(_, end)| *end <= byte);
+        chunk_ends
+            .get(index)
+            .or_else(|| chunk_ends.last())
+            .map_or(0, |(chunk_id, _)| *chunk_id)
+    };
+    Err(SuiError::ChunkedDecodeError {
+        expected_type: std::any::type_name::<T>().to_string(),
+        offset: offset as u64,
+        total_size: content.len() as u64,
+        first_chunk: chunk_of(offset.saturating_sub(1)),
+        last_chunk: chunk_of(offset),
+        error,
+    })
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartialCheckpointFragment {
+    pub proposer: SignedCheckpointProposalSummary,
+    pub other: SignedCheckpointProposalSummary,
+    pub chunk_count: u32,
+    pub header_digest: FragmentHeaderDigest,
+    pub content_digest: [u8; 32],
+    pub chunks: BTreeMap<u32, Vec<u8>>,
+}
+
+impl PartialCheckpointFragment {
+    pub fn new(header: CheckpointFragmentMessageHeader) -> Self {
+        Self {
+            header_digest: header.digest(),
+            proposer: header.proposer,
+            other: header.other,
+            chunk_count: header.chunk_count,
+            content_digest: header.content_digest,
+            chunks: Default::default(),
+        }
+    }
+
+    pub fn add_chunk(&mut self, chunk: CheckpointFragmentMessageChunk) -> SuiResult {
+        fp_ensure!(
+            chunk.header_digest == self.header_digest,
+            SuiError::from("Chunk belongs to another version of the fragment")
+        );
+        fp_ensure!(
+            chunk.chunk_id < self.chunk_count,
+            SuiError::from(
+                format!(
+                    "chunk_id ({:?}) out of bound ({:?})",
+                    chunk.chunk_id, self.chunk_count
+                )
+                .as_str()
+            )
+        );
+        self.chunks.insert(chunk.chunk_id, chunk.content);
+        Ok(())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.chunks.len() as u32 == self.chunk_count
+    }
+
+    pub fn progress(&self) -> FragmentProgress {
+        FragmentProgress {
+            sequence_number: self.proposer.data().sequence_number,
+            proposer: *self.proposer.authority(),
+            other: *self.other.authority(),
+            received: self.chunks.len() as u32,
+            total: self.chunk_count,
+            missing_ids: (0..self.chunk_count)
+                .filter(|id| !self.chunks.contains_key(id))
+                .collect(),
+            bytes: self.chunks.values().map(|chunk| chunk.len() as u64).sum(),
+        }
+    }
+
+    pub fn to_fragment(self) -> SuiResult<CheckpointFragment> {
+        self.to_fragment_with_stats().map(|(fragment, _)| fragment)
+    }
+
+    pub fn to_fragment_with_stats(self) -> SuiResult<(CheckpointFragment, FragmentStats)> {
+        fp_ensure!(
+            self.is_complete(),
+            SuiError::from("Fragment is missing chunks")
+        );
+        let Self {
+            proposer,
+            other,
+            chunk_count,
+            header_digest: _,
+            content_digest,
+            chunks,
+        } = self;
+        checkpoint_span!(
+            "checkpoint_fragment_deserialize",
+            seq = proposer.data().sequence_number,
+            proposer = %proposer.authority(),
+            other = %other.authority()
+        );
+        let mut chunk_ends = Vec::with_capacity(chunks.len());
+        let mut content = Vec::new();
+        for (chunk_id, chunk) in chunks {
+            content.extend(chunk);
+            chunk_ends.push((chunk_id, content.len()));
+        }
+        checkpoint_event!(bytes = content.len(), "Deserializing checkpoint fragment");
+        fp_ensure!(
+            sha3_bytes(&content) == content_digest,
+            SuiError::from("Fragment chunks don't match the header content digest")
+        );
+        let data: CheckpointFragmentData = decode_chunked(&content, &chunk_ends)?;
+        let fragment = CheckpointFragment {
+            proposer,
+            other,
+            data,
+        };
+        let stats = fragment.stats_for(content.len() as u64, chunk_count);
+        Ok((fragment, stats))
+    }
+}
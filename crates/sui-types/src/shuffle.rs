@@ -0,0 +1,52 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Deterministic permutations seeded by checkpoint digests.
+//!
+//! The algorithm is fixed and must never change, since different nodes (and different
+//! versions of the code) must derive the same permutation from the same seed:
+//! a Fisher-Yates shuffle, iterating from the last index down to 1, where the index to
+//! swap with is drawn uniformly from `0..=i` by rejection sampling over `u64` values taken
+//! from a ChaCha20 stream keyed with the seed. We intentionally do not rely on
+//! `rand::seq::SliceRandom`, whose algorithm is not guaranteed to be stable across releases.
+
+use rand::RngCore;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use crate::messages_checkpoint::CheckpointDigest;
+
+#[cfg(test)]
+#[path = "unit_tests/shuffle_tests.rs"]
+mod shuffle_tests;
+
+/// Shuffle `items` in place with a permutation derived from `seed`.
+pub fn deterministic_shuffle<T>(seed: CheckpointDigest, items: &mut [T]) {
+    let mut rng = ChaCha20Rng::from_seed(seed);
+    for i in (1..items.len()).rev() {
+        let j = uniform_index(&mut rng, i as u64 + 1);
+        items.swap(i, j as usize);
+    }
+}
+
+/// Returns the items in the order given by the permutation derived from `seed`.
+pub fn deterministic_shuffled<T>(
+    seed: CheckpointDigest,
+    items: impl IntoIterator<Item = T>,
+) -> Vec<T> {
+    let mut items: Vec<T> = items.into_iter().collect();
+    deterministic_shuffle(seed, &mut items);
+    items
+}
+
+/// Draw a value uniformly from `0..bound`. Values below `2^64 mod bound` are rejected so
+/// that the remaining range is a multiple of `bound`.
+fn uniform_index(rng: &mut ChaCha20Rng, bound: u64) -> u64 {
+    let threshold = bound.wrapping_neg() % bound;
+    loop {
+        let value = rng.next_u64();
+        if value >= threshold {
+            return value % bound;
+        }
+    }
+}
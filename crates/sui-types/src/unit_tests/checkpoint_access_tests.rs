@@ -0,0 +1,63 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+
+#[test]
+fn test_access_class_of_requests() {
+    assert_eq!(
+        AccessClass::of(&CheckpointRequest::authenticated(Some(1), false)),
+        AccessClass::PublicSummary
+    );
+    assert_eq!(
+        AccessClass::of(&CheckpointRequest::latest_sequence_number()),
+        AccessClass::PublicSummary
+    );
+    assert_eq!(
+        AccessClass::of(&CheckpointRequest::metadata(Some(1))),
+        AccessClass::PublicSummary
+    );
+    assert_eq!(
+        AccessClass::of(&CheckpointRequest::authenticated(Some(1), true)),
+        AccessClass::PeerContents
+    );
+    assert_eq!(
+        AccessClass::of(&CheckpointRequest::proposal(false)),
+        AccessClass::PeerContents
+    );
+    assert_eq!(
+        AccessClass::of(
+            &CheckpointRequest::authenticated(Some(1), true)
+                .with_detail(CheckpointDetail::GAS_STATS)
+        ),
+        AccessClass::AdminDebug
+    );
+}
+
+#[test]
+fn test_peer_access_policy() {
+    let peer: SocketAddr = "10.0.0.1:8080".parse().unwrap();
+    let admin: SocketAddr = "10.0.0.2:8080".parse().unwrap();
+    let policy = PeerAccessPolicy::new(AccessClass::PublicSummary)
+        .with_peer(peer.ip(), AccessClass::PeerContents)
+        .with_peer(admin.ip(), AccessClass::AdminDebug);
+
+    let summary = CheckpointRequest::authenticated(Some(1), false);
+    let contents = CheckpointRequest::authenticated(Some(1), true);
+    let evaluate = |request: &CheckpointRequest, client: Option<SocketAddr>| {
+        policy.evaluate(request, AccessClass::of(request), client)
+    };
+
+    assert!(evaluate(&summary, None).is_ok());
+    assert!(matches!(
+        evaluate(&contents, None),
+        Err(SuiError::CheckpointAccessDenied { .. })
+    ));
+    // Only the address is checked, not the port.
+    assert!(evaluate(&contents, Some("10.0.0.1:9000".parse().unwrap())).is_ok());
+    assert!(evaluate(&contents, Some("10.0.0.3:8080".parse().unwrap())).is_err());
+    assert_eq!(policy.granted(Some(admin)), AccessClass::AdminDebug);
+    assert!(AccessClass::AdminDebug
+        .check_granted(policy.granted(Some(peer)))
+        .is_err());
+}
@@ -0,0 +1,117 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::*;
+use crate::base_types::ExecutionDigests;
+use crate::checkpoint_archive::{verify_archive, write_archive_contents};
+use crate::gas::GasCostSummary;
+
+/// Contents drawing their transactions from a small pool, so that they repeat across
+/// checkpoints as structures do in real archives.
+fn sample_contents(rng: &mut StdRng, count: usize) -> Vec<CheckpointContents> {
+    let pool: Vec<_> = (0..64).map(|_| ExecutionDigests::random()).collect();
+    (0..count)
+        .map(|_| {
+            let size = rng.gen_range(1..20);
+            CheckpointContents::new_with_causally_ordered_transactions(
+                (0..size).map(|_| pool[rng.gen_range(0..pool.len())]),
+            )
+        })
+        .collect()
+}
+
+fn summaries(contents: &[CheckpointContents]) -> Vec<CheckpointSummary> {
+    contents
+        .iter()
+        .enumerate()
+        .map(|(seq, contents)| {
+            CheckpointSummary::new(
+                0,
+                seq as u64,
+                contents,
+                None,
+                GasCostSummary::default(),
+                None,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_compressed_archive() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let training = sample_contents(&mut rng, 2000);
+    let dictionary = ArchiveDictionary::train(&training, &[], 4096).unwrap();
+    assert_ne!(dictionary.id(), 0);
+    assert_eq!(
+        ArchiveDictionary::from_bytes(dictionary.as_bytes().to_vec()).unwrap(),
+        dictionary
+    );
+    assert!(ArchiveDictionary::from_bytes(vec![0; 16]).is_err());
+
+    let contents = &training[..200];
+    let summaries = summaries(contents);
+    let mut plain = Vec::new();
+    for contents in contents {
+        write_archive_contents(&mut plain, contents).unwrap();
+    }
+    verify_archive(&plain[..], &summaries).unwrap();
+
+    let mut writer = CompressedArchiveWriter::new(Vec::new(), 3, Some(&dictionary)).unwrap();
+    for contents in contents {
+        writer.append(contents).unwrap();
+    }
+    let compressed = writer.into_inner();
+    assert!(compressed.len() < plain.len());
+    assert_eq!(
+        read_archive_dictionary_id(&compressed[..]).unwrap(),
+        dictionary.id()
+    );
+    let limits = DecodeLimits::default();
+    assert_eq!(
+        verify_compressed_archive(&compressed[..], Some(&dictionary), &summaries, &limits).unwrap(),
+        compressed.len() as u64
+    );
+
+    // The dictionary must be the one of the header.
+    assert!(verify_compressed_archive(&compressed[..], None, &summaries, &limits).is_err());
+    assert!(verify_archive(&compressed[..], &summaries).is_err());
+    // Contents are still checked against their summaries.
+    assert!(verify_compressed_archive(
+        &compressed[..],
+        Some(&dictionary),
+        &summaries[1..],
+        &limits
+    )
+    .is_err());
+}
+
+#[test]
+fn test_compressed_archive_without_dictionary() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let contents = sample_contents(&mut rng, 10);
+    let summaries = summaries(&contents);
+    let mut writer = CompressedArchiveWriter::new(Vec::new(), 3, None).unwrap();
+    for contents in &contents {
+        writer.append(contents).unwrap();
+    }
+    let mut compressed = writer.into_inner();
+    assert_eq!(read_archive_dictionary_id(&compressed[..]).unwrap(), 0);
+    let limits = DecodeLimits::default();
+    verify_compressed_archive(&compressed[..], None, &summaries, &limits).unwrap();
+
+    // Records may not decompress beyond the fragment size of the limits.
+    let small = DecodeLimits {
+        max_fragment_bytes: 8,
+        ..DecodeLimits::default()
+    };
+    assert!(verify_compressed_archive(&compressed[..], None, &summaries, &small).is_err());
+
+    // A corrupted frame is detected.
+    let last = compressed.len() - 1;
+    compressed[last] ^= 0xff;
+    assert!(verify_compressed_archive(&compressed[..], None, &summaries, &limits).is_err());
+}
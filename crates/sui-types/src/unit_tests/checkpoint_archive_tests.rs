@@ -0,0 +1,108 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::base_types::ExecutionDigests;
+use crate::gas::GasCostSummary;
+use crate::utils::{make_committee_key, CheckpointChainBuilder};
+
+#[test]
+fn test_archive_usage_tracker() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee)
+        .epochs(2)
+        .checkpoints_per_epoch(2)
+        .transactions_per_checkpoint(10)
+        .build(&mut rng);
+
+    let mut tracker = ArchiveUsageTracker::new();
+    assert_eq!(tracker.report().total_bytes, 0);
+
+    let (first, first_contents) = &chain.checkpoints[0];
+    assert!(tracker.record(first, Some(first_contents)));
+    // Checkpoints already accounted for are ignored.
+    assert!(!tracker.record(first, Some(first_contents)));
+    let (second, second_contents) = &chain.checkpoints[1];
+    assert!(tracker.record(second, Some(second_contents)));
+
+    let epoch0 = tracker.epoch(0).unwrap().clone();
+    assert_eq!(epoch0.checkpoints, 2);
+    assert_eq!(epoch0.transactions, 20);
+    assert_eq!(
+        epoch0.contents_bytes,
+        bincode::serialized_size(first_contents).unwrap()
+            + bincode::serialized_size(second_contents).unwrap()
+    );
+    assert!(epoch0.summary_bytes > 0 && epoch0.signature_bytes > 0);
+
+    // While the first epoch is the only one, it is the basis of the projection.
+    assert_eq!(
+        tracker.report().projected_bytes_per_epoch,
+        epoch0.total_bytes()
+    );
+
+    // Contents that are not stored are not accounted for.
+    let (third, _) = &chain.checkpoints[2];
+    assert!(tracker.record(third, None));
+    let report = tracker.report();
+    assert_eq!(report.epochs.len(), 2);
+    assert_eq!(report.epochs[1].contents_bytes, 0);
+    assert_eq!(report.epochs[1].transactions, 0);
+    assert_eq!(
+        report.total_bytes,
+        epoch0.total_bytes() + report.epochs[1].total_bytes()
+    );
+    assert_eq!(report.mean_bytes_per_checkpoint, report.total_bytes / 3);
+    // The epoch in progress is left out of the projection.
+    assert_eq!(report.projected_bytes_per_epoch, epoch0.total_bytes());
+    assert_eq!(
+        report.projected_total_bytes(2),
+        report.total_bytes + 2 * epoch0.total_bytes()
+    );
+}
+
+#[test]
+fn test_verify_archive() {
+    let contents: Vec<_> = (1..=3)
+        .map(|size| {
+            CheckpointContents::new_with_causally_ordered_transactions(
+                (0..size).map(|_| ExecutionDigests::random()),
+            )
+        })
+        .collect();
+    let summaries: Vec<_> = contents
+        .iter()
+        .enumerate()
+        .map(|(seq, c)| {
+            CheckpointSummary::new(0, seq as u64, c, None, GasCostSummary::default(), None)
+        })
+        .collect();
+    let mut archive = Vec::new();
+    for c in &contents {
+        write_archive_contents(&mut archive, c).unwrap();
+    }
+
+    assert_eq!(
+        verify_archive(&archive[..], &summaries).unwrap(),
+        archive.len() as u64
+    );
+    // A prefix of the archive matches a prefix of the summaries only.
+    let first_len = 8 + bincode::serialized_size(&contents[0]).unwrap() as usize;
+    verify_archive(&archive[..first_len], &summaries[..1]).unwrap();
+    assert!(verify_archive(&archive[..first_len], &summaries).is_err());
+    assert!(verify_archive(&archive[..], &summaries[..2]).is_err());
+    assert!(verify_archive(&archive[..archive.len() - 1], &summaries).is_err());
+
+    // Contents out of order do not match their digests.
+    let mut swapped = summaries.clone();
+    swapped.swap(1, 2);
+    assert!(verify_archive(&archive[..], &swapped).is_err());
+    let mut corrupted = archive.clone();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 1;
+    assert!(verify_archive(&corrupted[..], &summaries).is_err());
+}
@@ -0,0 +1,84 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::base_types::ExecutionDigests;
+use crate::messages_checkpoint::{CheckpointContents, CheckpointSummary, SignedCheckpointSummary};
+use crate::utils::{make_committee_key, CheckpointChainBuilder};
+
+#[test]
+fn test_response_auditor() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone())
+        .checkpoints_per_epoch(2)
+        .build(&mut rng);
+    let keys = chain.keys(committee.epoch).unwrap();
+    let names: Vec<AuthorityName> = keys.iter().map(|k| k.public().into()).collect();
+    let agreed = chain.checkpoints[1].0.data().clone();
+    let forked = CheckpointSummary {
+        content_digest: CheckpointContents::new_with_causally_ordered_transactions(
+            [ExecutionDigests::random()].into_iter(),
+        )
+        .digest(),
+        ..agreed.clone()
+    };
+    let sign = |index: usize, summary: &CheckpointSummary| {
+        SignedCheckpointSummary::new_from_summary(summary.clone(), names[index], &keys[index])
+    };
+    let certified = chain.certify_by(agreed.clone(), 0..3);
+
+    let mut auditor = AuthorityResponseAuditor::new();
+    // Responses received before the certified checkpoint are audited once it is known.
+    assert!(auditor
+        .record_response(names[0], AuthenticatedCheckpoint::Signed(sign(0, &agreed)),)
+        .is_none());
+    assert!(auditor
+        .record_response(names[3], AuthenticatedCheckpoint::Signed(sign(3, &forked)),)
+        .is_none());
+    let reports = auditor.record_certified(certified.clone());
+    assert_eq!(reports.len(), 1);
+    let report = reports[0].clone();
+    assert_eq!(report.served_by, names[3]);
+    report.verify(&committee).unwrap();
+    assert_eq!(report.accused(&committee).unwrap(), vec![names[3]]);
+
+    // Responses matching the certified checkpoint are fine, conflicting ones are flagged
+    // immediately.
+    assert!(auditor
+        .record_response(
+            names[1],
+            AuthenticatedCheckpoint::Certified(certified.clone()),
+        )
+        .is_none());
+    let forked_cert = chain.certify_by(forked.clone(), 1..4);
+    let report = auditor
+        .record_response(names[2], AuthenticatedCheckpoint::Certified(forked_cert))
+        .unwrap()
+        .clone();
+    report.verify(&committee).unwrap();
+    // Only the authorities that signed both checkpoints are accountable.
+    let mut accused = report.accused(&committee).unwrap();
+    accused.sort();
+    let mut expected = names[1..3].to_vec();
+    expected.sort();
+    assert_eq!(accused, expected);
+    assert_eq!(auditor.reports().len(), 2);
+    assert_eq!(auditor.reports_for(&names[2]).count(), 1);
+
+    // A report with agreeing checkpoints is not valid evidence.
+    let mut invalid = report;
+    invalid.served = AuthenticatedCheckpoint::Certified(certified);
+    assert!(invalid.verify(&committee).is_err());
+
+    // Pruning forgets the certified checkpoint, but keeps the reports.
+    auditor.prune_below(2);
+    assert!(auditor
+        .record_response(names[3], AuthenticatedCheckpoint::Signed(sign(3, &forked)),)
+        .is_none());
+    assert_eq!(auditor.reports().len(), 2);
+}
@@ -0,0 +1,96 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::base_types::ExecutionDigests;
+use crate::crypto::{get_key_pair, AccountKeyPair};
+use crate::gas::GasCostSummary;
+use crate::messages_checkpoint::CheckpointContents;
+
+fn availability(chunks: &[u32]) -> ContentsAvailability {
+    let mut availability = ContentsAvailability::new([1; 32]);
+    chunks.iter().for_each(|chunk| availability.set(*chunk));
+    availability
+}
+
+#[test]
+fn test_set_and_merge() {
+    let mut ours = availability(&[0, 2]);
+    assert!(ours.has(2) && !ours.has(1));
+    ours.merge(&availability(&[1, 2, 5])).unwrap();
+    assert_eq!(
+        ours.chunk_bitmap.iter().collect::<Vec<_>>(),
+        vec![0, 1, 2, 5]
+    );
+    assert_eq!(ours.chunk_count(), 4);
+
+    let other = ContentsAvailability::complete([2; 32], 3);
+    assert_eq!(other.chunk_count(), 3);
+    assert!(ours.merge(&other).is_err());
+    assert_eq!(ours.chunk_count(), 4);
+
+    let bytes = bincode::serialize(&ours).unwrap();
+    assert_eq!(
+        bincode::deserialize::<ContentsAvailability>(&bytes).unwrap(),
+        ours
+    );
+}
+
+#[test]
+fn test_select_missing_rarest_first() {
+    let ours = availability(&[0]);
+    let mut stale = ContentsAvailability::complete([2; 32], 10);
+    stale.set(10);
+    let peers = vec![
+        availability(&[0, 1, 2, 3]),
+        stale,
+        availability(&[1, 2, 4]),
+        availability(&[1, 3]),
+    ];
+
+    let selected = ours.select_missing(&peers, usize::MAX);
+    // Chunk 4 is only held by peer 2, chunks 2 and 3 by two peers and chunk 1 by three.
+    assert_eq!(
+        selected,
+        vec![
+            ChunkAssignment { chunk: 4, peer: 2 },
+            ChunkAssignment { chunk: 2, peer: 0 },
+            ChunkAssignment { chunk: 3, peer: 3 },
+            ChunkAssignment { chunk: 1, peer: 0 },
+        ]
+    );
+    assert!(selected.iter().all(|a| peers[a.peer].has(a.chunk)));
+
+    assert_eq!(ours.select_missing(&peers, 1).len(), 1);
+    assert!(availability(&[0, 1, 2, 3, 4])
+        .select_missing(&peers, usize::MAX)
+        .is_empty());
+}
+
+#[test]
+fn test_availability_attestation() {
+    let contents = |n| {
+        CheckpointContents::new_with_causally_ordered_transactions(
+            (0..n).map(|_| ExecutionDigests::random()),
+        )
+    };
+    let summary = CheckpointSummary::new(0, 1, &contents(2), None, GasCostSummary::default(), None);
+    let other = CheckpointSummary::new(0, 1, &contents(1), None, GasCostSummary::default(), None);
+    let (address, key): (_, AccountKeyPair) = get_key_pair();
+    let (other_address, other_key): (_, AccountKeyPair) = get_key_pair();
+
+    let signed = AvailabilityAttestation::new(&summary, 1_000, address).sign(&key);
+    signed.verify().unwrap();
+    signed.verify_for(&summary).unwrap();
+    assert!(signed.verify_for(&other).is_err());
+
+    // The signature covers every field, and must be by the signer.
+    let mut later = signed.clone();
+    later.attestation.timestamp_ms += 1;
+    assert!(later.verify().is_err());
+    let mut impersonated = signed;
+    impersonated.attestation.signer = other_address;
+    assert!(impersonated.verify().is_err());
+    let forged = AvailabilityAttestation::new(&summary, 1_000, address).sign(&other_key);
+    assert!(forged.verify().is_err());
+}
@@ -0,0 +1,71 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+
+fn config() -> AdaptiveBatcherConfig {
+    AdaptiveBatcherConfig {
+        min_batch: 2,
+        max_batch: 100,
+        initial_batch: 10,
+        increase: 5,
+        decrease_percent: 50,
+        target_latency: Duration::from_secs(1),
+        max_response_bytes: 10_000,
+    }
+}
+
+#[test]
+fn test_additive_increase_multiplicative_decrease() {
+    let fast = Duration::from_millis(100);
+    let mut batcher = AdaptiveBatcher::new(config());
+    assert_eq!(batcher.next_batch_size(), 10);
+    assert_eq!(batcher.next_range(20, 100), 20..30);
+    assert_eq!(batcher.next_range(95, 100), 95..100);
+
+    // Full batches within targets grow the batch, up to the maximum.
+    batcher.record_response(10, 1_000, fast);
+    assert_eq!(batcher.next_batch_size(), 15);
+    for _ in 0..100 {
+        let size = batcher.next_batch_size();
+        batcher.record_response(size, size, fast);
+    }
+    assert_eq!(batcher.next_batch_size(), 100);
+
+    // Partial batches do not.
+    let mut batcher = AdaptiveBatcher::new(config());
+    batcher.record_response(3, 300, fast);
+    assert_eq!(batcher.next_batch_size(), 10);
+
+    // Slow responses and failures halve it, down to the minimum.
+    batcher.record_response(10, 1_000, Duration::from_secs(2));
+    assert_eq!(batcher.next_batch_size(), 5);
+    batcher.record_failure();
+    assert_eq!(batcher.next_batch_size(), 2);
+    batcher.record_failure();
+    assert_eq!(batcher.next_batch_size(), 2);
+}
+
+#[test]
+fn test_batch_sized_by_response_bytes() {
+    let fast = Duration::from_millis(100);
+    let mut batcher = AdaptiveBatcher::new(config());
+
+    // Large checkpoints cap the batch to what fits in a response, at once.
+    batcher.record_response(10, 8_000, fast);
+    assert_eq!(batcher.next_batch_size(), 12);
+    batcher.record_response(12, 24_000, fast);
+    // The response was too large: the batch is halved.
+    assert_eq!(batcher.next_batch_size(), 7);
+
+    // Even huge checkpoints are requested at least the minimum at a time.
+    batcher.record_response(7, 1_000_000, fast);
+    assert_eq!(batcher.next_batch_size(), 2);
+
+    // Once checkpoints shrink again, the batch recovers.
+    for _ in 0..20 {
+        let size = batcher.next_batch_size();
+        batcher.record_response(size, 0, fast);
+    }
+    assert!(batcher.next_batch_size() > 50);
+}
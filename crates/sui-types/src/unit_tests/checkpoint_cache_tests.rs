@@ -0,0 +1,191 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::base_types::ExecutionDigests;
+use crate::utils::make_committee_key;
+
+fn contents(size: usize) -> Arc<CheckpointContents> {
+    Arc::new(CheckpointContents::new_with_causally_ordered_transactions(
+        (0..size).map(|_| ExecutionDigests::random()),
+    ))
+}
+
+fn size_of(contents: &CheckpointContents) -> usize {
+    bcs::serialized_size(contents).unwrap()
+}
+
+#[test]
+fn test_lru_eviction_within_budget() {
+    let c = contents(2);
+    let cache = ContentsCache::new(2 * size_of(&c));
+
+    assert!(cache.insert(1, c.clone()));
+    assert!(cache.insert(2, contents(2)));
+    // Touch 1 so that 2 becomes the least recently used entry.
+    assert!(cache.get(1).is_some());
+    assert!(cache.insert(3, contents(2)));
+
+    assert!(cache.contains(1));
+    assert!(!cache.contains(2));
+    assert!(cache.contains(3));
+
+    let stats = cache.stats();
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.evictions, 1);
+    assert_eq!(stats.entries, 2);
+    assert!(stats.bytes <= cache.budget_bytes());
+}
+
+#[test]
+fn test_pinned_entries_are_not_evicted() {
+    let c = contents(2);
+    let cache = ContentsCache::new(2 * size_of(&c));
+
+    cache.pin(1);
+    assert!(cache.insert(1, c));
+    assert!(cache.insert(2, contents(2)));
+    assert!(cache.insert(3, contents(2)));
+    assert!(cache.contains(1));
+    assert!(!cache.contains(2));
+
+    // Contents that cannot fit next to the pinned entry are served but not cached.
+    assert!(!cache.insert(4, contents(4)));
+    assert!(!cache.contains(4));
+    assert_eq!(cache.stats().rejected, 1);
+
+    cache.unpin(1);
+    assert!(cache.insert(5, contents(2)));
+    assert!(!cache.contains(1));
+    assert!(cache.get(6).is_none());
+    assert_eq!(cache.stats().misses, 1);
+}
+
+#[test]
+fn test_replace_entry() {
+    let c = contents(2);
+    let cache = ContentsCache::new(3 * size_of(&c));
+
+    assert!(cache.insert(1, c.clone()));
+    assert!(cache.insert(2, contents(2)));
+    // Replacing an entry by contents of the same size evicts nothing.
+    assert!(cache.insert(1, contents(2)));
+    assert!(cache.contains(1));
+    assert!(cache.contains(2));
+    assert_eq!(cache.stats().evictions, 0);
+    assert_eq!(cache.stats().bytes, 2 * size_of(&c));
+
+    // Larger contents make room by evicting other entries only.
+    let large = contents(5);
+    assert!(cache.insert(1, large.clone()));
+    assert!(!cache.contains(2));
+    assert_eq!(cache.stats().evictions, 1);
+    assert_eq!(cache.stats().entries, 1);
+    assert_eq!(cache.stats().bytes, size_of(&large));
+
+    // Contents too large for the budget leave the current entry in place.
+    assert!(!cache.insert(1, contents(8)));
+    assert!(Arc::ptr_eq(&cache.get(1).unwrap(), &large));
+    assert_eq!(cache.stats().bytes, size_of(&large));
+}
+
+#[test]
+fn test_dump_index_and_warm() {
+    let stored: HashMap<_, _> = (0..11)
+        .filter(|seq| *seq != 9)
+        .map(|seq| (seq, contents(2)))
+        .collect();
+    let entry_size = size_of(&stored[&0]);
+    let cache = ContentsCache::new(4 * entry_size);
+    for seq in [1, 2, 3] {
+        cache.insert(seq, stored[&seq].clone());
+    }
+    cache.pin(2);
+    // 1 is the most recently used, and 9 is no longer stored.
+    cache.get(1);
+    cache.insert(9, contents(2));
+
+    let index = cache.dump_index();
+    assert_eq!(index.recency, vec![2, 3, 1, 9]);
+    assert_eq!(index.pinned, vec![2]);
+    let index: ContentsCacheIndex = bcs::from_bytes(&bcs::to_bytes(&index).unwrap()).unwrap();
+
+    // After a restart, the cache is warmed from the store within the budget: the pinned
+    // entry, then the index from the most recently used, then the latest checkpoints.
+    let cache = ContentsCache::new(4 * entry_size);
+    let loader = |seq: CheckpointSequenceNumber| -> SuiResult<Option<CheckpointContents>> {
+        Ok(stored.get(&seq).map(|contents| (**contents).clone()))
+    };
+    let warmer = CacheWarmer::new(&cache, loader, index.clone());
+    let stats = warmer.warm(10, 4 * entry_size).unwrap();
+    assert_eq!(
+        stats,
+        WarmupStats {
+            loaded: 4,
+            bytes: 4 * entry_size,
+            missing: 1,
+            signer_sets: 0,
+        }
+    );
+    for seq in [1, 2, 3, 10] {
+        assert!(cache.contains(seq));
+    }
+    assert_eq!(cache.dump_index().recency, vec![10, 3, 1, 2]);
+    assert_eq!(cache.dump_index().pinned, vec![2]);
+
+    // A smaller budget only loads the most important entries, and checkpoints beyond the
+    // latest one are skipped.
+    let cache = ContentsCache::new(4 * entry_size);
+    let warmer = CacheWarmer::new(&cache, loader, index);
+    let stats = warmer.warm(2, 2 * entry_size).unwrap();
+    assert_eq!(stats.loaded, 2);
+    assert!(cache.contains(2) && cache.contains(1));
+    assert!(!cache.contains(3));
+
+    // Without an index, the latest checkpoints are loaded down to the first pruned one.
+    let cache = ContentsCache::new(10 * entry_size);
+    let loader = |seq: CheckpointSequenceNumber| -> SuiResult<Option<CheckpointContents>> {
+        Ok((seq >= 4).then(|| (*stored[&seq]).clone()))
+    };
+    let stats = CacheWarmer::new(&cache, loader, ContentsCacheIndex::default())
+        .warm(5, usize::MAX)
+        .unwrap();
+    assert_eq!(stats.loaded, 2);
+    assert!(cache.contains(4) && cache.contains(5));
+}
+
+#[test]
+fn test_warm_signer_sets() {
+    let (_, committee) = make_committee_key(&mut StdRng::from_seed([0; 32]));
+    let prepared = PreparedCommittee::new(committee.clone()).unwrap();
+    let index = SignerSetsIndex {
+        epoch: committee.epoch,
+        signer_sets: vec![vec![0, 1, 2], vec![1, 2, 3]],
+    };
+    assert_eq!(prepared.prepare_signer_sets(&index).unwrap(), 2);
+    let index: SignerSetsIndex =
+        bcs::from_bytes(&bcs::to_bytes(&prepared.dump_signer_sets()).unwrap()).unwrap();
+
+    // After a restart, the signer sets are prepared along with the contents.
+    let cache = ContentsCache::new(1024);
+    let prepared = PreparedCommittee::new(committee.clone()).unwrap();
+    let loader = |_| -> SuiResult<Option<CheckpointContents>> { Ok(None) };
+    let stats = CacheWarmer::new(&cache, loader, ContentsCacheIndex::default())
+        .with_signer_sets(&prepared, index.clone())
+        .warm(0, usize::MAX)
+        .unwrap();
+    assert_eq!(stats.signer_sets, 2);
+    assert_eq!(prepared.dump_signer_sets(), index);
+
+    // Signer sets of another epoch are not prepared.
+    let index = SignerSetsIndex {
+        epoch: committee.epoch + 1,
+        ..index
+    };
+    let prepared = PreparedCommittee::new(committee).unwrap();
+    assert_eq!(prepared.prepare_signer_sets(&index).unwrap(), 0);
+    assert_eq!(prepared.cached_signer_sets(), 0);
+}
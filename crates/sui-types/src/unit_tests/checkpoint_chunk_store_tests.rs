@@ -0,0 +1,151 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::base_types::{AuthorityName, ExecutionDigests};
+use crate::checkpoint_archive::write_archive_contents;
+use crate::gas::GasCostSummary;
+use crate::messages_checkpoint::{
+    CheckpointFragmentMessage, CheckpointProposal, CheckpointProposalContents,
+};
+use crate::utils::make_committee_key;
+
+fn check_store(store: &dyn ChunkStore) {
+    let bytes = b"chunk".to_vec();
+    let digest = put_chunk(store, bytes.clone()).unwrap();
+    assert!(store.contains(&digest).unwrap());
+    assert_eq!(store.get(&digest).unwrap(), Some(bytes.clone()));
+    // Storing a chunk twice is a no-op.
+    store.put(digest, bytes).unwrap();
+
+    // Bytes must match their digest.
+    assert!(store.put([0; 32], b"other".to_vec()).is_err());
+    assert!(!store.contains(&[0; 32]).unwrap());
+
+    assert!(store.evict(&digest).unwrap());
+    assert!(!store.evict(&digest).unwrap());
+    assert_eq!(store.get(&digest).unwrap(), None);
+}
+
+#[test]
+fn test_in_memory_chunk_store() {
+    let store = InMemoryChunkStore::new(10);
+    check_store(&store);
+    assert!(store.is_empty());
+
+    let first = put_chunk(&store, vec![1; 4]).unwrap();
+    let second = put_chunk(&store, vec![2; 4]).unwrap();
+    assert_eq!(store.bytes(), 8);
+    // The oldest chunk makes room for the next one.
+    let third = put_chunk(&store, vec![3; 4]).unwrap();
+    assert!(!store.contains(&first).unwrap());
+    assert!(store.contains(&second).unwrap() && store.contains(&third).unwrap());
+    assert_eq!(store.bytes(), 8);
+    // Chunks over the budget are not stored.
+    let large = put_chunk(&store, vec![4; 11]).unwrap();
+    assert!(!store.contains(&large).unwrap());
+    assert_eq!(store.len(), 2);
+}
+
+#[test]
+fn test_disk_chunk_store() {
+    let dir = std::env::temp_dir().join(format!(
+        "chunk_store_{}",
+        hex::encode(ExecutionDigests::random().effects.0)
+    ));
+    let store = DiskChunkStore::open(&dir).unwrap();
+    check_store(&store);
+
+    // Corrupted chunks are detected when read.
+    let digest = put_chunk(&store, b"chunk".to_vec()).unwrap();
+    std::fs::write(dir.join(hex::encode(digest)), b"corrupted").unwrap();
+    assert!(store.get(&digest).is_err());
+
+    // Chunks survive reopening the store.
+    let digest = put_chunk(&store, b"kept".to_vec()).unwrap();
+    let store = DiskChunkStore::open(&dir).unwrap();
+    assert_eq!(store.get(&digest).unwrap(), Some(b"kept".to_vec()));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_contents_and_archive_chunks() {
+    let store = InMemoryChunkStore::new(1 << 20);
+    let contents = CheckpointContents::new_with_causally_ordered_transactions(
+        (0..10).map(|_| ExecutionDigests::random()),
+    );
+    let digests = put_contents_chunks(&store, &contents, 3, 4).unwrap();
+    assert_eq!(digests.len(), 3);
+    let rebuilt = get_contents_chunks(&store, &digests).unwrap();
+    assert_eq!(rebuilt.digest(), contents.digest());
+    store.evict(&digests[1]).unwrap();
+    assert!(get_contents_chunks(&store, &digests).is_err());
+
+    let summary = CheckpointSummary::new(0, 3, &contents, None, GasCostSummary::default(), None);
+    let mut archive = Vec::new();
+    write_archive_contents(&mut archive, &contents).unwrap();
+    let records = verify_archive_into_store(&archive[..], &[summary.clone()], &store).unwrap();
+    assert_eq!(
+        store.get(&records[0]).unwrap().unwrap(),
+        bincode::serialize(&contents).unwrap()
+    );
+
+    // Nothing is stored from an archive failing verification.
+    let store = InMemoryChunkStore::new(1 << 20);
+    let other = CheckpointContents::new_with_causally_ordered_transactions(
+        [ExecutionDigests::random()].into_iter(),
+    );
+    write_archive_contents(&mut archive, &other).unwrap();
+    assert!(verify_archive_into_store(&archive[..], &[summary.clone(), summary], &store).is_err());
+    assert!(store.is_empty());
+}
+
+#[test]
+fn test_fragment_chunks() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let name1: AuthorityName = keys[0].public().into();
+    let name2: AuthorityName = keys[1].public().into();
+    let proposal1 = CheckpointProposal::new(
+        committee.epoch,
+        1,
+        name1,
+        &keys[0],
+        CheckpointProposalContents::new([ExecutionDigests::random()].into_iter()),
+    );
+    let proposal2 = CheckpointProposal::new(
+        committee.epoch,
+        1,
+        name2,
+        &keys[1],
+        CheckpointProposalContents::new([ExecutionDigests::random()].into_iter()),
+    );
+    let fragment = proposal1.fragment_with(&proposal2);
+
+    let mut messages = fragment.to_message_chunks().into_iter();
+    let header = match messages.next() {
+        Some(CheckpointFragmentMessage::Header(header)) => *header,
+        _ => panic!("First message must be the header"),
+    };
+    let mut partial = PartialCheckpointFragment::new(header.clone());
+    for message in messages {
+        if let CheckpointFragmentMessage::Chunk(chunk) = message {
+            partial.add_chunk(*chunk).unwrap();
+        }
+    }
+
+    let store = InMemoryChunkStore::new(1 << 20);
+    let digests = put_fragment_chunks(&store, &partial).unwrap();
+    let mut restored = PartialCheckpointFragment::new(header);
+    get_fragment_chunks(&store, &mut restored, &digests).unwrap();
+    assert!(restored.is_complete());
+    let restored = restored.to_fragment().unwrap();
+    assert_eq!(
+        bcs::to_bytes(&restored.data).unwrap(),
+        bcs::to_bytes(&fragment.data).unwrap()
+    );
+}
@@ -0,0 +1,203 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+
+fn leaf(digests: &ExecutionDigests) -> [u8; 32] {
+    let mut hasher = Sha3_256::default();
+    hasher.update([LEAF_TAG]);
+    hasher.update(bcs::to_bytes(digests).unwrap());
+    hasher.finalize().into()
+}
+
+/// Level by level construction, promoting the last node of odd levels, then hashing the
+/// number of leaves with the top node.
+fn naive_merkle_root(transactions: &[ExecutionDigests]) -> [u8; 32] {
+    let mut level: Vec<_> = transactions.iter().map(leaf).collect();
+    if level.is_empty() {
+        return [0; 32];
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => hash_node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    let mut hasher = Sha3_256::default();
+    hasher.update([ROOT_TAG]);
+    hasher.update((transactions.len() as u64).to_le_bytes());
+    hasher.update(level[0]);
+    hasher.finalize().into()
+}
+
+#[test]
+fn test_dual_commitments() {
+    // 200 needs a two bytes length prefix in the flat digest.
+    for len in [0, 1, 2, 3, 5, 7, 8, 13, 200] {
+        let transactions: Vec<_> = (0..len).map(|_| ExecutionDigests::random()).collect();
+        let contents = CheckpointContents::new_with_causally_ordered_transactions(
+            transactions.iter().cloned(),
+        );
+        let commitments = contents.commitments();
+        assert_eq!(commitments.flat, contents.digest());
+        assert_eq!(commitments.merkle_root, naive_merkle_root(&transactions));
+    }
+}
+
+#[test]
+fn test_verify_stream() {
+    let transactions: Vec<_> = (0..4).map(|_| ExecutionDigests::random()).collect();
+    let contents =
+        CheckpointContents::new_with_causally_ordered_transactions(transactions.iter().cloned());
+    let commitments = contents.commitments();
+
+    for expected in [
+        ExpectedContentsCommitment::Flat(commitments.flat),
+        ExpectedContentsCommitment::MerkleRoot(commitments.merkle_root),
+        ExpectedContentsCommitment::Both {
+            flat: commitments.flat,
+            merkle_root: commitments.merkle_root,
+        },
+    ] {
+        DualCommitmentHasher::verify_stream(&expected, 4, &transactions).unwrap();
+        assert!(DualCommitmentHasher::verify_stream(&expected, 3, &transactions[..3]).is_err());
+    }
+    let wrong_root = ExpectedContentsCommitment::Both {
+        flat: commitments.flat,
+        merkle_root: [0; 32],
+    };
+    assert!(DualCommitmentHasher::verify_stream(&wrong_root, 4, &transactions).is_err());
+    // The stream must have the announced length.
+    let flat = ExpectedContentsCommitment::Flat(commitments.flat);
+    assert!(DualCommitmentHasher::verify_stream(&flat, 5, &transactions).is_err());
+}
+
+#[test]
+fn test_contents_accumulator() {
+    let transactions: Vec<_> = (0..200).map(|_| ExecutionDigests::random()).collect();
+    let mut accumulator = ContentsAccumulator::new();
+    assert!(accumulator.is_empty());
+    assert_eq!(
+        accumulator.digest(),
+        CheckpointContents::new_with_causally_ordered_transactions(std::iter::empty()).digest()
+    );
+    // Batches of uneven sizes, crossing the two bytes length prefix at 128.
+    let mut appended = 0;
+    for batch in transactions.chunks(37) {
+        accumulator.append(batch.iter().cloned());
+        appended += batch.len();
+        let batch_digest = CheckpointContents::new_with_causally_ordered_transactions(
+            transactions[..appended].iter().cloned(),
+        )
+        .digest();
+        assert_eq!(accumulator.digest(), batch_digest);
+    }
+    assert_eq!(accumulator.len(), 200);
+    assert!(accumulator
+        .append_consensus_commits([ConsensusCommitRef {
+            round: 1,
+            digest: [1; 32],
+        }])
+        .is_err());
+    let digest = accumulator.digest();
+    let contents = accumulator.finalize();
+    assert_eq!(contents.digest(), digest);
+    assert_eq!(contents.version(), 1);
+    assert_eq!(contents.iter().cloned().collect::<Vec<_>>(), transactions);
+}
+
+#[test]
+fn test_contents_accumulator_consensus_commits() {
+    let transactions: Vec<_> = (0..5).map(|_| ExecutionDigests::random()).collect();
+    let commits: Vec<_> = (0..3)
+        .map(|round| ConsensusCommitRef {
+            round,
+            digest: [round as u8; 32],
+        })
+        .collect();
+    let mut accumulator = ContentsAccumulator::new_with_consensus_commits();
+    accumulator.append(transactions[..2].iter().cloned());
+    accumulator
+        .append_consensus_commits(commits[..1].iter().cloned())
+        .unwrap();
+    // The digest read between appends is the one of the contents so far.
+    let partial = CheckpointContentsV2 {
+        contents: CheckpointContents::new_with_causally_ordered_transactions(
+            transactions[..2].iter().cloned(),
+        ),
+        consensus_commits: commits[..1].to_vec(),
+    };
+    assert_eq!(accumulator.digest(), partial.digest());
+    accumulator.append(transactions[2..].iter().cloned());
+    accumulator
+        .append_consensus_commits(commits[1..].iter().cloned())
+        .unwrap();
+
+    let batch = CheckpointContentsV2 {
+        contents: CheckpointContents::new_with_causally_ordered_transactions(
+            transactions.iter().cloned(),
+        ),
+        consensus_commits: commits.clone(),
+    };
+    assert_eq!(accumulator.digest(), batch.digest());
+    let contents = accumulator.finalize();
+    assert_eq!(contents.version(), 2);
+    assert_eq!(contents.digest(), batch.digest());
+    assert_eq!(contents.consensus_commits(), Some(&commits[..]));
+}
+
+#[test]
+fn test_inclusion_proofs() {
+    for len in [1, 2, 3, 5, 7, 8, 13] {
+        let transactions: Vec<_> = (0..len).map(|_| ExecutionDigests::random()).collect();
+        let contents = CheckpointContents::new_with_causally_ordered_transactions(
+            transactions.iter().cloned(),
+        );
+        let root = naive_merkle_root(&transactions);
+        for (index, digests) in transactions.iter().enumerate() {
+            let proof = contents.prove_inclusion(digests).unwrap();
+            assert_eq!(proof.index, index as u64);
+            proof.verify(&root, digests).unwrap();
+            // A proof only holds for its own transaction.
+            let other = &transactions[(index + 1) % transactions.len()];
+            if other != digests {
+                assert!(proof.verify(&root, other).is_err());
+            }
+        }
+    }
+    let transactions: Vec<_> = (0..5).map(|_| ExecutionDigests::random()).collect();
+    let contents =
+        CheckpointContents::new_with_causally_ordered_transactions(transactions.iter().cloned());
+    let root = contents.commitments().merkle_root;
+    assert!(contents
+        .prove_inclusion(&ExecutionDigests::random())
+        .is_none());
+
+    // Proofs with a tampered sibling, index or sibling count are rejected.
+    let proof = contents.prove_inclusion(&transactions[2]).unwrap();
+    let mut tampered = proof.clone();
+    tampered.siblings[0] = [0; 32];
+    assert!(tampered.verify(&root, &transactions[2]).is_err());
+    let mut tampered = proof.clone();
+    tampered.index = 3;
+    assert!(tampered.verify(&root, &transactions[2]).is_err());
+    let mut tampered = proof.clone();
+    tampered.index = 5;
+    assert!(tampered.root(&transactions[2]).is_err());
+    let mut tampered = proof;
+    tampered.siblings.push([0; 32]);
+    assert!(tampered.root(&transactions[2]).is_err());
+
+    // The last of 5 leaves is promoted twice and then hashed on the right of the first four,
+    // as the second of 2 leaves would be: the number of leaves tells the two apart.
+    let proof = contents.prove_inclusion(&transactions[4]).unwrap();
+    assert_eq!(proof.siblings.len(), 1);
+    let mut tampered = proof;
+    tampered.index = 1;
+    tampered.leaves = 2;
+    assert!(tampered.verify(&root, &transactions[4]).is_err());
+}
@@ -0,0 +1,689 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{Arc, Mutex};
+
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::error::SuiError;
+use crate::utils::make_committee_key;
+
+#[derive(Default)]
+struct RecordingPersistence {
+    stored: Mutex<BTreeMap<TxSequenceNumber, ExecutionDigests>>,
+    fail_removals: Mutex<bool>,
+}
+
+impl PendingExecutionPersistence for Arc<RecordingPersistence> {
+    fn persist_inserted(&self, entries: &[(TxSequenceNumber, ExecutionDigests)]) -> SuiResult {
+        self.stored.lock().unwrap().extend(entries.iter().cloned());
+        Ok(())
+    }
+
+    fn persist_removed(&self, entries: &[(TxSequenceNumber, ExecutionDigests)]) -> SuiResult {
+        if *self.fail_removals.lock().unwrap() {
+            return Err(SuiError::GenericStorageError("removal failed".to_string()));
+        }
+        let mut stored = self.stored.lock().unwrap();
+        for (seq, _) in entries {
+            stored.remove(seq);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_pending_pool_drain_and_reinsert() {
+    let persistence = Arc::new(RecordingPersistence::default());
+    let mut pool = PendingExecutionPool::new_with_persistence(Box::new(persistence.clone()));
+
+    let digests: Vec<_> = (0..5).map(|_| ExecutionDigests::random()).collect();
+    let added = pool
+        .insert(digests.iter().enumerate().map(|(i, d)| (i as u64, *d)))
+        .unwrap();
+    assert_eq!(added, 5);
+    // Duplicates are ignored.
+    assert_eq!(pool.insert([(10, digests[0])]).unwrap(), 0);
+    // Another transaction at a taken sequence number is refused, and nothing is added.
+    let other = ExecutionDigests::random();
+    assert!(pool.insert([(4, other)]).is_err());
+    assert!(pool
+        .insert([(11, other), (11, ExecutionDigests::random())])
+        .is_err());
+    assert!(!pool.contains(&other));
+    assert_eq!(persistence.stored.lock().unwrap().len(), 5);
+
+    let (contents, drained) = pool.drain_proposal(3).unwrap();
+    assert_eq!(contents.transactions.len(), 3);
+    assert_eq!(
+        drained.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+    assert_eq!(pool.len(), 2);
+    assert_eq!(persistence.stored.lock().unwrap().len(), 2);
+
+    // The proposal is abandoned: its transactions come back first.
+    pool.reinsert(drained).unwrap();
+    assert_eq!(pool.len(), 5);
+    assert_eq!(pool.drain_up_to(1).unwrap()[0].1, digests[0]);
+
+    let checkpoint =
+        CheckpointContents::new_with_causally_ordered_transactions(digests[1..3].iter().cloned());
+    assert_eq!(pool.remove_checkpointed(&checkpoint).unwrap().len(), 2);
+    assert_eq!(pool.len(), 2);
+
+    // A failing hook leaves the pool untouched.
+    *persistence.fail_removals.lock().unwrap() = true;
+    assert!(pool.drain_up_to(2).is_err());
+    assert_eq!(pool.len(), 2);
+}
+
+#[test]
+fn test_pending_pool_restore() {
+    let entries: Vec<_> = (0..3)
+        .map(|i| (i as u64, ExecutionDigests::random()))
+        .collect();
+    let pool = PendingExecutionPool::restore(entries.clone(), None).unwrap();
+    assert_eq!(pool.len(), 3);
+    assert!(entries.iter().all(|(_, d)| pool.contains(d)));
+
+    let colliding = [entries[0], (entries[0].0, ExecutionDigests::random())];
+    assert!(PendingExecutionPool::restore(colliding, None).is_err());
+}
+
+#[test]
+fn test_proposal_validator() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let transactions: Vec<_> = (0..3).map(|_| ExecutionDigests::random()).collect();
+    let proposal = |epoch, seq, transactions: &[ExecutionDigests]| {
+        CheckpointProposal::new(
+            epoch,
+            seq,
+            keys[0].public().into(),
+            &keys[0],
+            CheckpointProposalContents::new(transactions.iter().cloned()),
+        )
+    };
+
+    let mut known_invalid = HashSet::new();
+    let validator = ProposalValidator::new(ProposalValidationPolicy {
+        max_transactions: 2,
+        ..Default::default()
+    });
+    let local = ProposalLocalState {
+        committee: &committee,
+        next_sequence_number: 5,
+        known_invalid: &known_invalid,
+    };
+
+    validator
+        .validate(&proposal(committee.epoch, 5, &transactions[..2]), &local)
+        .unwrap();
+    assert!(matches!(
+        validator.validate(
+            &proposal(committee.epoch + 1, 5, &transactions[..2]),
+            &local
+        ),
+        Err(SuiError::WrongEpoch { .. })
+    ));
+    assert!(validator
+        .validate(&proposal(committee.epoch, 4, &transactions[..2]), &local)
+        .is_err());
+    assert!(validator
+        .validate(&proposal(committee.epoch, 5, &transactions), &local)
+        .is_err());
+
+    // Proposed transactions must match the signed summary.
+    let mut tampered = proposal(committee.epoch, 5, &transactions[..2]);
+    tampered.transactions = CheckpointProposalContents::new(transactions[1..].iter().cloned());
+    assert!(validator.validate(&tampered, &local).is_err());
+
+    known_invalid.insert(transactions[1]);
+    let local = ProposalLocalState {
+        committee: &committee,
+        next_sequence_number: 5,
+        known_invalid: &known_invalid,
+    };
+    assert!(validator
+        .validate(&proposal(committee.epoch, 5, &transactions[..2]), &local)
+        .is_err());
+    let lenient = ProposalValidator::new(ProposalValidationPolicy {
+        max_transactions: 2,
+        reject_known_invalid: false,
+    });
+    lenient
+        .validate(&proposal(committee.epoch, 5, &transactions[..2]), &local)
+        .unwrap();
+}
+
+#[test]
+fn test_consensus_commit_checkpoint_builder() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (_, committee) = make_committee_key(&mut rng);
+    let transactions: Vec<_> = (0..5).map(|_| ExecutionDigests::random()).collect();
+    let commit = |round: u64, transactions: &[ExecutionDigests]| OrderedConsensusCommit {
+        commit: ConsensusCommitRef {
+            round,
+            digest: [round as u8; 32],
+        },
+        transactions: transactions.to_vec(),
+        gas_cost_summary: GasCostSummary {
+            computation_cost: 1,
+            storage_cost: 2,
+            storage_rebate: 0,
+        },
+    };
+
+    let mut builder = ConsensusCommitCheckpointBuilder::new(0, 0, None, 3);
+    assert!(builder
+        .push_commit(commit(1, &transactions[..2]))
+        .unwrap()
+        .is_none());
+    // Transactions sequenced twice are only included once.
+    let (summary, contents) = builder
+        .push_commit(commit(2, &transactions[1..4]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(summary.sequence_number, 0);
+    assert_eq!(summary.content_digest, contents.digest());
+    assert_eq!(summary.gas_cost_summary.computation_cost, 2);
+    assert_eq!(
+        contents.iter().cloned().collect::<Vec<_>>(),
+        transactions[..4].to_vec()
+    );
+    assert_eq!(
+        contents
+            .consensus_commits()
+            .unwrap()
+            .iter()
+            .map(|c| c.round)
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+
+    // Commits must arrive in round order.
+    assert!(builder.push_commit(commit(2, &transactions[4..])).is_err());
+
+    // Every validator fed the same commits cuts the same checkpoints.
+    let mut other = ConsensusCommitCheckpointBuilder::new(0, 0, None, 3);
+    other.push_commit(commit(1, &transactions[..2])).unwrap();
+    let (other_summary, _) = other
+        .push_commit(commit(2, &transactions[1..4]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(other_summary.digest(), summary.digest());
+
+    assert!(builder
+        .push_commit(commit(3, &transactions[4..]))
+        .unwrap()
+        .is_none());
+    assert_eq!(builder.pending_transactions(), 1);
+    let pending_digest = builder.pending_digest();
+    let (last, last_contents) = builder.finish_epoch(committee.clone());
+    assert_eq!(last_contents.digest(), pending_digest);
+    assert_eq!(last.sequence_number, 1);
+    assert_eq!(last.previous_digest, Some(summary.digest()));
+    assert_eq!(last_contents.size(), 1);
+    assert_eq!(
+        last.next_committee().unwrap().voting_rights,
+        committee.voting_rights
+    );
+
+    let (empty, empty_contents) = builder.finish_epoch(committee);
+    assert_eq!(empty.epoch, 1);
+    assert_eq!(empty_contents.size(), 0);
+
+    // Rounds start over in the next epoch, where transactions are sequenced anew.
+    let (next, next_contents) = builder
+        .push_commit(commit(1, &transactions[..3]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(next.epoch, 2);
+    assert_eq!(next.sequence_number, 3);
+    assert_eq!(next.previous_digest, Some(empty.digest()));
+    assert_eq!(next_contents.size(), 3);
+}
+
+#[test]
+fn test_proposal_history() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, _) = make_committee_key(&mut rng);
+    let names: Vec<AuthorityName> = keys.iter().map(|k| k.public().into()).collect();
+    let transactions: Vec<_> = (0..4).map(|_| ExecutionDigests::random()).collect();
+    let proposal = |index: usize, seq, transactions: &[ExecutionDigests]| {
+        CheckpointProposal::new(
+            0,
+            seq,
+            names[index],
+            &keys[index],
+            CheckpointProposalContents::new(transactions.iter().cloned()),
+        )
+    };
+
+    let mut history = ProposalHistory::new(2);
+    assert!(history.is_empty());
+    history.record(proposal(0, 1, &transactions[..2])).unwrap();
+    history.record(proposal(1, 1, &transactions[1..3])).unwrap();
+    history.record(proposal(0, 2, &transactions[2..])).unwrap();
+    assert_eq!(history.retained_range(), Some((1, 2)));
+    assert_eq!(history.by_sequence_number(1).count(), 2);
+    assert_eq!(
+        history
+            .by_authority(&names[0])
+            .map(|(seq, _)| seq)
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+
+    let contents = CheckpointContents::new_with_causally_ordered_transactions(
+        transactions[1..2].iter().cloned(),
+    );
+    assert_eq!(
+        history.dropped_transactions(1, &names[0], &contents),
+        Some(vec![transactions[0]])
+    );
+    assert_eq!(history.dropped_transactions(1, &names[2], &contents), None);
+
+    // Older sequence numbers fall out of the window, and are not recorded again.
+    history.record(proposal(1, 3, &transactions)).unwrap();
+    assert_eq!(history.retained_range(), Some((2, 3)));
+    assert!(history.get(1, &names[0]).is_none());
+    history.record(proposal(2, 1, &transactions)).unwrap();
+    assert_eq!(history.by_sequence_number(1).count(), 0);
+
+    // The history survives a round trip through storage.
+    let restored: ProposalHistory =
+        bincode::deserialize(&bincode::serialize(&history).unwrap()).unwrap();
+    assert_eq!(restored.retained_range(), Some((2, 3)));
+    assert_eq!(
+        restored
+            .get(3, &names[1])
+            .unwrap()
+            .signed_summary
+            .data()
+            .digest(),
+        history
+            .get(3, &names[1])
+            .unwrap()
+            .signed_summary
+            .data()
+            .digest()
+    );
+
+    // Proposals inconsistent with their summary are rejected.
+    let mut forged = proposal(3, 3, &transactions[..1]);
+    forged.transactions = CheckpointProposalContents::new(transactions.iter().cloned());
+    assert!(history.record(forged).is_err());
+}
+
+#[test]
+fn test_construction_state_pruning() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, _) = make_committee_key(&mut rng);
+    let names: Vec<AuthorityName> = keys.iter().map(|k| k.public().into()).collect();
+    let proposal = |index: usize, seq| {
+        CheckpointProposal::new(
+            0,
+            seq,
+            names[index],
+            &keys[index],
+            CheckpointProposalContents::new((0..3).map(|_| ExecutionDigests::random())),
+        )
+    };
+    let contents = CheckpointContents::new_with_causally_ordered_transactions(
+        (0..3).map(|_| ExecutionDigests::random()),
+    );
+
+    let mut state = CheckpointConstructionState::new();
+    for seq in 1..=3 {
+        let first = proposal(0, seq);
+        let second = proposal(1, seq);
+        let mut messages = first.fragment_with(&second).to_message_chunks();
+        state.add_proposal(first).unwrap();
+        state.add_proposal(second).unwrap();
+        state.add_signature(SignedCheckpointSummary::new_from_contents(
+            0,
+            seq,
+            names[0],
+            &keys[0],
+            &contents,
+            None,
+            GasCostSummary::default(),
+            None,
+        ));
+        if seq == 3 {
+            // Leave the last fragment incomplete.
+            messages.pop();
+        }
+        let completed: Vec<_> = messages
+            .into_iter()
+            .filter_map(|m| state.add_fragment_message(m).unwrap())
+            .collect();
+        assert_eq!(completed.len(), usize::from(seq != 3));
+    }
+    assert_eq!(state.fragments(1).count(), 1);
+
+    let pruned = state.prune_below(2);
+    assert!(pruned.proposals > 0 && pruned.fragments > 0 && pruned.signatures > 0);
+    assert_eq!(pruned.partial_fragments, 0);
+    assert_eq!(
+        pruned.total(),
+        pruned.proposals + pruned.fragments + pruned.signatures
+    );
+    for seq in 1..=2 {
+        assert_eq!(state.proposals(seq).count(), 0);
+        assert_eq!(state.fragments(seq).count(), 0);
+        assert!(state.signatures(seq).is_empty());
+    }
+    assert_eq!(state.proposals(3).count(), 2);
+    assert_eq!(state.signatures(3).len(), 1);
+
+    // The partial fragment goes with its sequence number.
+    let pruned = state.prune_below(3);
+    assert!(pruned.partial_fragments > 0);
+    assert_eq!(state.prune_below(3), PrunedBytes::default());
+
+    // The last sequence number prunes everything.
+    state.add_proposal(proposal(0, 4)).unwrap();
+    assert!(state.prune_below(CheckpointSequenceNumber::MAX).proposals > 0);
+    assert_eq!(state.proposals(4).count(), 0);
+}
+
+#[test]
+fn test_construction_state_bounds() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, _) = make_committee_key(&mut rng);
+    let names: Vec<AuthorityName> = keys.iter().map(|k| k.public().into()).collect();
+    let proposal = |index: usize| {
+        CheckpointProposal::new(
+            0,
+            1,
+            names[index],
+            &keys[index],
+            CheckpointProposalContents::new((0..3).map(|_| ExecutionDigests::random())),
+        )
+    };
+    let mut state = CheckpointConstructionState::new();
+
+    // A signature is kept once per authority.
+    let contents = CheckpointContents::new_with_causally_ordered_transactions(
+        (0..3).map(|_| ExecutionDigests::random()),
+    );
+    let signed = |index: usize| {
+        SignedCheckpointSummary::new_from_contents(
+            0,
+            1,
+            names[index],
+            &keys[index],
+            &contents,
+            None,
+            GasCostSummary::default(),
+            None,
+        )
+    };
+    assert!(state.add_signature(signed(0)));
+    assert!(!state.add_signature(signed(0)));
+    assert!(state.add_signature(signed(1)));
+    assert_eq!(state.signatures(1).len(), 2);
+
+    // Only a few versions of the fragment between two proposals are reassembled at once.
+    let first = proposal(0);
+    let header =
+        |other: &CheckpointProposal| first.fragment_with(other).to_message_chunks().remove(0);
+    for _ in 0..MAX_PARTIAL_FRAGMENT_VERSIONS {
+        let message = header(&proposal(1));
+        assert!(state
+            .add_fragment_message(message.clone())
+            .unwrap()
+            .is_none());
+        // Headers already received are not counted again.
+        assert!(state.add_fragment_message(message).unwrap().is_none());
+    }
+    assert!(state.add_fragment_message(header(&proposal(1))).is_err());
+    assert!(state
+        .add_fragment_message(header(&proposal(2)))
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_proposal_slot_map() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let names: Vec<AuthorityName> = keys.iter().map(|k| k.public().into()).collect();
+    let proposal = |index: usize, seq| {
+        CheckpointProposal::new(
+            0,
+            seq,
+            names[index],
+            &keys[index],
+            CheckpointProposalContents::new((0..2).map(|_| ExecutionDigests::random())),
+        )
+    };
+
+    let mut slots = ProposalSlotMap::new();
+    let first = proposal(0, 1);
+    assert!(slots.insert(first.clone()).unwrap());
+    assert!(!slots.insert(first.clone()).unwrap());
+    assert!(slots.insert(proposal(1, 1)).unwrap());
+    assert!(slots.insert(proposal(0, 2)).unwrap());
+
+    // A second proposal of the same authority at the same height is refused with evidence.
+    let conflict = slots.insert(proposal(0, 1)).unwrap_err();
+    assert_eq!(conflict.authority(), &names[0]);
+    assert_eq!(conflict.sequence_number(), 1);
+    conflict.verify(&committee).unwrap();
+    assert_eq!(
+        slots
+            .get(1, &names[0])
+            .unwrap()
+            .signed_summary
+            .data()
+            .digest(),
+        first.signed_summary.data().digest()
+    );
+    assert_eq!(slots.conflicts().len(), 1);
+
+    // Further conflicting proposals for the slot are refused, but one piece of evidence is
+    // kept per slot.
+    assert!(slots.insert(proposal(0, 1)).is_err());
+    assert!(slots.insert(proposal(0, 1)).is_err());
+    assert_eq!(slots.conflicts().len(), 1);
+    assert_eq!(
+        slots.conflicts()[0].second.data().digest(),
+        conflict.second.data().digest()
+    );
+    assert!(slots.insert(proposal(0, 2)).is_err());
+    assert_eq!(slots.conflicts().len(), 2);
+    assert_eq!(slots.take_conflicts().len(), 2);
+    assert!(slots.conflicts().is_empty());
+
+    // Evidence is kept until taken, or until its slot is pruned.
+    assert!(slots.insert(proposal(0, 1)).is_err());
+    assert!(slots.insert(proposal(0, 2)).is_err());
+    assert_eq!(slots.remove_below(2).len(), 2);
+    assert_eq!(slots.sequence_range(), Some((2, 2)));
+    assert_eq!(slots.conflicts().len(), 1);
+    assert_eq!(slots.conflicts()[0].sequence_number(), 2);
+
+    // Evidence must be about a single slot, with different proposals.
+    let mut forged = conflict.clone();
+    forged.second = forged.first.clone();
+    assert!(forged.verify(&committee).is_err());
+    forged.second = proposal(1, 1).signed_summary;
+    assert!(forged.verify(&committee).is_err());
+
+    // The proposal history refuses conflicting proposals too.
+    let mut history = ProposalHistory::new(2);
+    history.record(first).unwrap();
+    assert!(history.record(proposal(0, 1)).is_err());
+    assert_eq!(history.conflicts().len(), 1);
+}
+
+#[test]
+fn test_construction_state_dump() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, _) = make_committee_key(&mut rng);
+    let names: Vec<AuthorityName> = keys.iter().map(|k| k.public().into()).collect();
+    let proposal = |index: usize, seq| {
+        CheckpointProposal::new(
+            0,
+            seq,
+            names[index],
+            &keys[index],
+            CheckpointProposalContents::new((0..3).map(|_| ExecutionDigests::random())),
+        )
+    };
+    let contents = CheckpointContents::new_with_causally_ordered_transactions(
+        (0..3).map(|_| ExecutionDigests::random()),
+    );
+
+    let mut state = CheckpointConstructionState::new();
+    assert!(state.export().sequence_number.is_none());
+    let mut last_chunk = None;
+    for seq in 1..=2 {
+        let first = proposal(0, seq);
+        let second = proposal(1, seq);
+        let mut messages = first.fragment_with(&second).to_message_chunks();
+        state.add_proposal(first).unwrap();
+        state.add_proposal(second).unwrap();
+        state.add_signature(SignedCheckpointSummary::new_from_contents(
+            0,
+            seq,
+            names[0],
+            &keys[0],
+            &contents,
+            None,
+            GasCostSummary::default(),
+            None,
+        ));
+        if seq == 2 {
+            last_chunk = messages.pop();
+        }
+        for message in messages {
+            state.add_fragment_message(message).unwrap();
+        }
+    }
+    assert!(state.add_proposal(proposal(0, 2)).is_err());
+
+    // Only the state of the active sequence number is exported.
+    assert_eq!(state.active_sequence_number(), Some(1));
+    let dump = state.export();
+    assert_eq!(dump.sequence_number, Some(1));
+    assert_eq!(dump.proposals.len(), 2);
+    assert_eq!(dump.fragments.len(), 1);
+    assert!(dump.partial_fragments.is_empty() && dump.proposal_conflicts.is_empty());
+    assert_eq!(dump.signatures.len(), 1);
+
+    state.prune_below(1);
+    let dump = state.export();
+    assert_eq!(dump.sequence_number, Some(2));
+    assert_eq!(dump.partial_fragments.len(), 1);
+    assert_eq!(dump.proposal_conflicts.len(), 1);
+    let bytes = bincode::serialize(&dump).unwrap();
+
+    // The imported state picks up where the exported one stopped.
+    let mut imported =
+        CheckpointConstructionState::import(bincode::deserialize(&bytes).unwrap()).unwrap();
+    assert_eq!(imported.proposals(2).count(), 2);
+    assert_eq!(imported.signatures(2).len(), 1);
+    assert_eq!(imported.take_proposal_conflicts().len(), 1);
+    let fragment = imported
+        .add_fragment_message(last_chunk.unwrap())
+        .unwrap()
+        .unwrap();
+    assert_eq!(fragment.proposer.data().sequence_number, 2);
+    assert_eq!(imported.fragments(2).count(), 1);
+
+    let mut mismatched = dump.clone();
+    mismatched.sequence_number = Some(1);
+    assert!(CheckpointConstructionState::import(mismatched).is_err());
+    let mut unsupported = dump;
+    unsupported.version += 1;
+    assert!(CheckpointConstructionState::import(unsupported).is_err());
+}
+
+#[test]
+fn test_proposal_timeout_schedule() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let names: Vec<AuthorityName> = keys.iter().map(|k| k.public().into()).collect();
+    let ms = Duration::from_millis;
+    let schedule = ProposalTimeoutSchedule {
+        base_delay: ms(500),
+        coverage_thresholds: vec![(6_667, ms(100)), (10_000, Duration::ZERO)],
+        max_wait: ms(2_000),
+    };
+
+    // Without proposals, the wait is the longest.
+    let timeout = schedule.evaluate(&committee, &[]);
+    assert_eq!(timeout.deadline, ms(2_000));
+    assert_eq!(timeout.trigger, ProposalTimeoutTrigger::MaxWait);
+    assert!(timeout.included.is_empty());
+
+    // A single proposal starts the base delay.
+    let timeout = schedule.evaluate(&committee, &[(names[0], ms(300))]);
+    assert_eq!(timeout.deadline, ms(800));
+    assert_eq!(timeout.trigger, ProposalTimeoutTrigger::BaseDelay);
+    assert!(!schedule.is_expired(&committee, &[(names[0], ms(300))], ms(799)));
+    assert!(schedule.is_expired(&committee, &[(names[0], ms(300))], ms(800)));
+
+    // Unless it comes so late that the longest wait ends first.
+    let timeout = schedule.evaluate(&committee, &[(names[0], ms(1_800))]);
+    assert_eq!(timeout.deadline, ms(2_000));
+    assert_eq!(timeout.trigger, ProposalTimeoutTrigger::MaxWait);
+
+    // Two thirds of the stake shorten the wait, whatever the order of the arrivals.
+    let arrivals = vec![
+        (names[2], ms(250)),
+        (names[0], ms(100)),
+        (names[1], ms(200)),
+        (names[3], ms(400)),
+    ];
+    let timeout = schedule.evaluate(&committee, &arrivals);
+    assert_eq!(timeout.deadline, ms(350));
+    assert_eq!(
+        timeout.trigger,
+        ProposalTimeoutTrigger::Coverage {
+            basis_points: 6_667
+        }
+    );
+    assert_eq!(timeout.included, vec![names[0], names[1], names[2]]);
+    assert_eq!(timeout.included_stake, 3);
+    let mut reversed = arrivals.clone();
+    reversed.reverse();
+    assert_eq!(schedule.evaluate(&committee, &reversed), timeout);
+
+    // All of the stake ends the wait at once.
+    let mut all = arrivals;
+    all[3].1 = ms(300);
+    let timeout = schedule.evaluate(&committee, &all);
+    assert_eq!(timeout.deadline, ms(300));
+    assert_eq!(
+        timeout.trigger,
+        ProposalTimeoutTrigger::Coverage {
+            basis_points: 10_000
+        }
+    );
+    assert_eq!(timeout.included_stake, 4);
+
+    // Repeated proposals and proposals of non-members do not count, and ties are broken by
+    // authority.
+    let outsider: AuthorityName = make_committee_key(&mut rng).0[0].public().into();
+    let arrivals = vec![
+        (names[1], ms(100)),
+        (names[1], ms(110)),
+        (outsider, ms(120)),
+        (names[0], ms(100)),
+    ];
+    let timeout = schedule.evaluate(&committee, &arrivals);
+    assert_eq!(timeout.deadline, ms(600));
+    assert_eq!(timeout.included_stake, 2);
+    let mut first = vec![names[0], names[1]];
+    first.sort();
+    assert_eq!(timeout.included, first);
+}
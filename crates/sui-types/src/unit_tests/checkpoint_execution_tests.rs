@@ -0,0 +1,62 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::messages_checkpoint::CheckpointSummary;
+use crate::utils::{make_committee_key, CheckpointChainBuilder};
+
+#[test]
+fn test_execution_contract() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone())
+        .checkpoints_per_epoch(5)
+        .transactions_per_checkpoint(3)
+        .build(&mut rng);
+    let (certified, contents) = &chain.checkpoints[4];
+    let transactions: Vec<_> = contents.iter().cloned().collect();
+    let gas_used = GasCostSummary {
+        computation_cost: 10,
+        storage_cost: 20,
+        storage_rebate: 5,
+    };
+    let summary = chain.certify(CheckpointSummary {
+        gas_cost_summary: gas_used.clone(),
+        ..certified.data().clone()
+    });
+    let contents = contents.clone();
+
+    let other_contents = CheckpointContents::new_with_causally_ordered_transactions(
+        transactions[1..].iter().cloned(),
+    );
+    assert!(CheckpointExecutionRequest::new(summary.clone(), other_contents, &committee).is_err());
+    let request = CheckpointExecutionRequest::new(summary, contents, &committee).unwrap();
+    assert_eq!(request.sequence_number(), 4);
+
+    let result = CheckpointExecutionResult {
+        sequence_number: 4,
+        effects_digests: transactions.iter().map(|d| d.effects).collect(),
+        gas_used,
+        duration: Duration::from_millis(3),
+    };
+    request.check_result(&result).unwrap();
+
+    let mut wrong_gas = result.clone();
+    wrong_gas.gas_used.storage_rebate += 1;
+    assert!(request.check_result(&wrong_gas).is_err());
+
+    let mut wrong_order = result.clone();
+    wrong_order.effects_digests.swap(0, 1);
+    assert!(request.check_result(&wrong_order).is_err());
+
+    let mut missing = result.clone();
+    missing.effects_digests.pop();
+    assert!(request.check_result(&missing).is_err());
+
+    let mut wrong_checkpoint = result;
+    wrong_checkpoint.sequence_number = 5;
+    assert!(request.check_result(&wrong_checkpoint).is_err());
+}
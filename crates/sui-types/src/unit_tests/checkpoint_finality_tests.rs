@@ -0,0 +1,126 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::base_types::{random_object_ref, ExecutionDigests};
+use crate::crypto::{get_key_pair, AccountKeyPair};
+use crate::messages::{SignedTransaction, Transaction, TransactionData, TransactionEffects};
+use crate::messages_checkpoint::CheckpointSummary;
+use crate::utils::{make_committee_key, CheckpointChainBuilder};
+
+#[test]
+fn test_transaction_finality_proof() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee0) = make_committee_key(&mut rng);
+    // The committee changes with the epoch, so that the proof must carry the epoch change.
+    let chain = CheckpointChainBuilder::new(keys, committee0.clone())
+        .epochs(2)
+        .rotate_committee(4)
+        .build(&mut rng);
+    let epoch_change = chain.checkpoints[0].0.clone();
+    let committee1 = chain.committee(1).unwrap().clone();
+    let keys1 = chain.keys(1).unwrap();
+
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let transaction = Transaction::from_data_and_signer(
+        TransactionData::new_transfer(
+            sender,
+            random_object_ref(),
+            sender,
+            random_object_ref(),
+            10000,
+        ),
+        &sender_key,
+    )
+    .verify()
+    .unwrap()
+    .into_message();
+    let certificate = CertifiedTransaction::new(
+        transaction.clone(),
+        keys1
+            .iter()
+            .map(|k| {
+                SignedTransaction::new(1, transaction.clone(), k, k.public().into())
+                    .auth_sig()
+                    .clone()
+            })
+            .collect(),
+        &committee1,
+    )
+    .unwrap();
+    let effects = TransactionEffects {
+        transaction_digest: *certificate.digest(),
+        ..Default::default()
+    };
+    let effects = CertifiedTransactionEffects::new(
+        effects.clone(),
+        keys1
+            .iter()
+            .map(|k| {
+                effects
+                    .clone()
+                    .to_sign_effects(1, &k.public().into(), k)
+                    .auth_signature
+            })
+            .collect(),
+        &committee1,
+    )
+    .unwrap();
+
+    let contents = CheckpointContents::new_with_causally_ordered_transactions(
+        [
+            ExecutionDigests::random(),
+            effects.effects.execution_digests(),
+        ]
+        .into_iter(),
+    );
+    let proof = TransactionFinalityProof {
+        certificate,
+        effects,
+        checkpoint: chain.certify(CheckpointSummary {
+            content_digest: contents.digest(),
+            contents_merkle_root: contents.commitments().merkle_root,
+            ..chain.checkpoints[1].0.data().clone()
+        }),
+        contents,
+        epoch_change_proofs: vec![epoch_change.clone()],
+    };
+
+    proof
+        .verify(FinalityTrustRoot::Committee(&committee0))
+        .unwrap();
+    // Starting from the epoch change checkpoint, no epoch change proof is needed.
+    assert!(proof
+        .verify(FinalityTrustRoot::Checkpoint(epoch_change.data()))
+        .is_err());
+    let mut short = proof.clone();
+    short.epoch_change_proofs.clear();
+    short
+        .verify(FinalityTrustRoot::Checkpoint(epoch_change.data()))
+        .unwrap();
+    short
+        .verify(FinalityTrustRoot::Committee(&committee1))
+        .unwrap();
+    assert!(short
+        .verify(FinalityTrustRoot::Committee(&committee0))
+        .is_err());
+
+    // The transaction must be in the checkpoint.
+    let mut missing = proof.clone();
+    missing.contents =
+        CheckpointContents::new_with_causally_ordered_transactions(std::iter::empty());
+    assert!(missing
+        .verify(FinalityTrustRoot::Committee(&committee0))
+        .is_err());
+
+    // The effects must be the ones of the transaction.
+    let mut other_effects = proof;
+    other_effects.effects.effects.transaction_digest = ExecutionDigests::random().transaction;
+    assert!(other_effects
+        .verify(FinalityTrustRoot::Committee(&committee0))
+        .is_err());
+}
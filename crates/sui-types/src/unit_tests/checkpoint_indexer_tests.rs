@@ -0,0 +1,119 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::utils::{make_committee_key, CheckpointChainBuilder};
+
+/// A sink committing to an in-memory index, which can be made to fail at a transaction.
+#[derive(Default)]
+struct MemorySink {
+    committed: Vec<(CheckpointSequenceNumber, Vec<(usize, ExecutionDigests)>)>,
+    cursor: IndexerCursor,
+    pending: Option<(CheckpointSequenceNumber, Vec<(usize, ExecutionDigests)>)>,
+    aborted: usize,
+    fail_at: Option<(CheckpointSequenceNumber, usize)>,
+}
+
+impl IndexerSink for MemorySink {
+    fn begin_checkpoint(&mut self, summary: &CertifiedCheckpointSummary) -> SuiResult {
+        assert!(self.pending.is_none());
+        self.pending = Some((summary.data().sequence_number, Vec::new()));
+        Ok(())
+    }
+
+    fn transaction(&mut self, digests: &ExecutionDigests, position: usize) -> SuiResult {
+        let (seq, transactions) = self.pending.as_mut().unwrap();
+        if self.fail_at == Some((*seq, position)) {
+            return Err(SuiError::from("Index unavailable"));
+        }
+        transactions.push((position, *digests));
+        Ok(())
+    }
+
+    fn end_checkpoint(
+        &mut self,
+        summary: &CertifiedCheckpointSummary,
+        cursor: IndexerCursor,
+    ) -> SuiResult {
+        let pending = self.pending.take().unwrap();
+        assert_eq!(pending.0, summary.data().sequence_number);
+        self.committed.push(pending);
+        self.cursor = cursor;
+        Ok(())
+    }
+
+    fn abort_checkpoint(&mut self) {
+        self.pending = None;
+        self.aborted += 1;
+    }
+}
+
+#[test]
+fn test_indexer_driver() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone())
+        .checkpoints_per_epoch(4)
+        .transactions_per_checkpoint(3)
+        .build(&mut rng);
+    let checkpoints: Vec<_> = chain
+        .checkpoints
+        .iter()
+        .map(|(summary, contents)| {
+            CheckpointExecutionRequest::new(summary.clone(), contents.clone(), &committee).unwrap()
+        })
+        .collect();
+
+    let mut driver = IndexerDriver::new(
+        MemorySink {
+            fail_at: Some((2, 1)),
+            ..Default::default()
+        },
+        IndexerCursor::default(),
+    );
+    assert!(driver.ingest(&checkpoints[0]).unwrap());
+    // Checkpoints are ingested once, in order.
+    assert!(!driver.ingest(&checkpoints[0]).unwrap());
+    assert!(driver.ingest(&checkpoints[2]).is_err());
+    assert!(driver.ingest(&checkpoints[1]).unwrap());
+
+    // A failing sink aborts the checkpoint, which is ingested again once the sink recovers.
+    assert!(driver.ingest(&checkpoints[2]).is_err());
+    assert_eq!(driver.sink().aborted, 1);
+    assert_eq!(driver.cursor().next_sequence_number, 2);
+    let mut sink = driver.into_sink();
+    sink.fail_at = None;
+
+    // Ingestion resumes from the cursor committed by the sink.
+    let cursor = sink.cursor;
+    let mut driver = IndexerDriver::new(sink, cursor);
+    assert!(driver.ingest(&checkpoints[2]).unwrap());
+    assert!(driver.ingest(&checkpoints[3]).unwrap());
+    let sink = driver.into_sink();
+    assert_eq!(
+        sink.committed
+            .iter()
+            .map(|(seq, _)| *seq)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 2, 3]
+    );
+    for ((_, transactions), (_, contents)) in sink.committed.iter().zip(&chain.checkpoints) {
+        let expected: Vec<_> = contents.iter().cloned().enumerate().collect();
+        assert_eq!(transactions, &expected);
+    }
+
+    // A checkpoint of another chain does not link to the last one ingested.
+    let (keys, committee) = make_committee_key(&mut rng);
+    let other = CheckpointChainBuilder::new(keys, committee.clone())
+        .checkpoints_per_epoch(5)
+        .build(&mut rng);
+    let (summary, contents) = other.checkpoints[4].clone();
+    let forked = CheckpointExecutionRequest::new(summary, contents, &committee).unwrap();
+    let mut driver = IndexerDriver::new(sink, cursor);
+    assert!(driver.ingest(&checkpoints[2]).unwrap());
+    assert!(driver.ingest(&checkpoints[3]).unwrap());
+    assert!(driver.ingest(&forked).is_err());
+}
@@ -0,0 +1,63 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::utils::make_committee_key;
+
+fn names() -> Vec<AuthorityName> {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, _) = make_committee_key(&mut rng);
+    keys.iter().map(|k| k.public().into()).collect()
+}
+
+#[test]
+fn test_latency_report() {
+    let names = names();
+    let start = Instant::now();
+    let mut tracker = SignatureLatencyTracker::new(10);
+
+    for (i, name) in names.iter().enumerate() {
+        tracker.record_at(1, *name, start + Duration::from_millis(100 * i as u64));
+    }
+    // A duplicate signature does not change the delay of the authority.
+    tracker.record_at(1, names[0], start + Duration::from_millis(1000));
+
+    let report = tracker.report(1).unwrap();
+    assert_eq!(report.signers, 4);
+    assert_eq!(report.p50_ms, 100);
+    assert_eq!(report.p90_ms, 300);
+    assert_eq!(report.max_ms, 300);
+    assert_eq!(report.slowest, names[3]);
+    assert!(tracker.report(2).is_none());
+
+    let json = serde_json::to_string(&tracker.reports()).unwrap();
+    let reports: Vec<SignatureLatencyReport> = serde_json::from_str(&json).unwrap();
+    assert_eq!(reports, vec![report]);
+}
+
+#[test]
+fn test_slow_authorities_and_pruning() {
+    let names = names();
+    let start = Instant::now();
+    let mut tracker = SignatureLatencyTracker::new(2);
+
+    for seq in 0..3 {
+        tracker.record_at(seq, names[0], start);
+        tracker.record_at(seq, names[1], start + Duration::from_millis(50));
+    }
+    // Only the two highest sequence numbers are kept.
+    assert_eq!(tracker.reports().len(), 2);
+    assert!(tracker.report(0).is_none());
+    tracker.record_at(0, names[2], start);
+    assert!(tracker.report(0).is_none());
+
+    let summaries = tracker.authority_summaries();
+    assert_eq!(summaries[0].authority, names[1]);
+    assert_eq!(summaries[0].samples, 2);
+    assert_eq!(summaries[0].mean_ms, 50);
+    assert_eq!(summaries[1].mean_ms, 0);
+}
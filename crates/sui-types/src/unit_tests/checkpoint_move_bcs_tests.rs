@@ -0,0 +1,190 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::hash::{HashFunction, Sha3_256};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::crypto::AuthorityWeakQuorumSignInfo;
+use crate::gas::GasCostSummary;
+use crate::messages_checkpoint::CertifiedCheckpointSummary;
+use crate::utils::{make_committee_key, CheckpointChainBuilder};
+
+/// Any change to this encoding must be mirrored by the Move verifier.
+const GOLDEN_SUMMARY: &str = "\
+    01000000000000000200000000000000\
+    0303030303030303030303030303030303030303030303030303030303030303\
+    0808080808080808080808080808080808080808080808080808080808080808\
+    00\
+    01\
+    0404040404040404040404040404040404040404040404040404040404040404\
+    050000000000000006000000000000000700000000000000\
+    00";
+
+fn golden_summary() -> CheckpointSummary {
+    CheckpointSummary {
+        epoch: 1,
+        sequence_number: 2,
+        content_digest: [3; 32],
+        contents_merkle_root: [8; 32],
+        object_changes_commitment: None,
+        previous_digest: Some([4; 32]),
+        gas_cost_summary: GasCostSummary {
+            computation_cost: 5,
+            storage_cost: 6,
+            storage_rebate: 7,
+        },
+        next_epoch_committee: None,
+    }
+}
+
+#[test]
+fn test_summary_golden_encoding() {
+    let summary = golden_summary();
+    let bytes = summary.to_move_bcs();
+    assert_eq!(hex::encode(&bytes), GOLDEN_SUMMARY);
+    assert_eq!(CheckpointSummary::from_move_bcs(&bytes).unwrap(), summary);
+
+    // Move recomputes the digest from the encoding.
+    let message = summary.move_signing_message();
+    assert!(message.starts_with(CHECKPOINT_SUMMARY_SIGNING_PREFIX));
+    assert_eq!(
+        &message[CHECKPOINT_SUMMARY_SIGNING_PREFIX.len()..],
+        &bytes[..]
+    );
+    let mut hasher = Sha3_256::default();
+    hasher.update(&message);
+    let digest: [u8; 32] = hasher.finalize().into();
+    assert_eq!(digest, summary.digest());
+
+    // Trailing bytes are rejected.
+    let mut extended = bytes;
+    extended.push(0);
+    assert!(CheckpointSummary::from_move_bcs(&extended).is_err());
+}
+
+#[test]
+fn test_quorum_signature_round_trip() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone()).build(&mut rng);
+    let summary = CheckpointSummary {
+        next_epoch_committee: Some(committee.voting_rights.clone()),
+        ..chain.checkpoints[0].0.data().clone()
+    };
+    // Leave the first authority out, so that the signers are not all of the committee.
+    let keys = chain.keys(committee.epoch).unwrap();
+    let cert = chain.certify_by(summary, 1..keys.len());
+
+    let summary = CheckpointSummary::from_move_bcs(&cert.data().to_move_bcs()).unwrap();
+    assert_eq!(&summary, cert.data());
+    assert_eq!(summary.digest(), cert.data().digest());
+
+    let bytes = cert.auth_sig().to_move_bcs();
+    let signature = AuthorityWeakQuorumSignInfo::from_move_bcs(&bytes).unwrap();
+    assert_eq!(signature.epoch, cert.auth_sig().epoch);
+    assert_eq!(signature.signers_map, cert.auth_sig().signers_map);
+    assert_eq!(signature.to_move_bcs(), bytes);
+    let decoded = CertifiedCheckpointSummary::new_from_data_and_sig(summary, signature);
+    decoded.verify(&committee, None).unwrap();
+
+    // Signers out of order are not canonical.
+    let mut encoded: MoveQuorumSignature = bcs::from_bytes(&bytes).unwrap();
+    assert_eq!(encoded.signers.len(), keys.len() - 1);
+    encoded.signers.reverse();
+    assert!(AuthorityWeakQuorumSignInfo::from_move_bcs(&bcs::to_bytes(&encoded).unwrap()).is_err());
+}
+
+#[test]
+fn test_quorum_signature_export_golden_encoding() {
+    // Signers 0, 2 and 9 of a committee of 10.
+    let export = QuorumSignatureExport {
+        epoch: 1,
+        committee_size: 10,
+        signer_bitmap: vec![0xa0, 0x40],
+        signature: vec![0xaa; 4],
+        signer_public_keys: vec![
+            [0x11; AuthorityPublicKey::LENGTH],
+            [0x22; AuthorityPublicKey::LENGTH],
+            [0x33; AuthorityPublicKey::LENGTH],
+        ],
+    };
+    // Any change to this encoding must be mirrored by the verifiers.
+    assert_eq!(AuthorityPublicKey::LENGTH, 96);
+    let golden = format!(
+        "01\
+         0000000000000001\
+         000a\
+         a040\
+         0004\
+         aaaaaaaa\
+         {}{}{}",
+        "11".repeat(96),
+        "22".repeat(96),
+        "33".repeat(96)
+    );
+    let bytes = export.encode();
+    assert_eq!(hex::encode(&bytes), golden);
+    assert_eq!(QuorumSignatureExport::decode(&bytes).unwrap(), export);
+    assert_eq!(export.signer_indices(), vec![0, 2, 9]);
+
+    // Truncated and extended encodings are rejected.
+    assert!(QuorumSignatureExport::decode(&bytes[..bytes.len() - 1]).is_err());
+    let mut extended = bytes.clone();
+    extended.push(0);
+    assert!(QuorumSignatureExport::decode(&extended).is_err());
+
+    // So are other versions.
+    let mut other_version = bytes.clone();
+    other_version[0] = 2;
+    assert!(QuorumSignatureExport::decode(&other_version).is_err());
+
+    // And signers past the committee size, even with a key for them.
+    let mut past_committee = bytes[..13].to_vec();
+    past_committee[12] |= 0x01;
+    past_committee.extend_from_slice(&bytes[13..]);
+    past_committee.extend_from_slice(&[0x44; 96]);
+    assert!(QuorumSignatureExport::decode(&past_committee).is_err());
+}
+
+#[test]
+fn test_quorum_signature_export_round_trip() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone()).build(&mut rng);
+    // Leave the first authority out, so that the bitmap is not all ones.
+    let cert = chain.certify_by(
+        chain.checkpoints[0].0.data().clone(),
+        1..committee.num_members(),
+    );
+
+    let export = QuorumSignatureExport::new(cert.auth_sig(), &committee).unwrap();
+    assert_eq!(export.committee_size() as usize, committee.num_members());
+    let signers: Vec<u32> = cert.auth_sig().signers_map.iter().collect();
+    assert_eq!(export.signer_indices(), signers);
+    for (index, key) in signers.iter().zip(export.signer_public_keys()) {
+        assert_eq!(
+            committee.authority_by_index(*index).unwrap().as_ref(),
+            &key[..]
+        );
+    }
+
+    let decoded = QuorumSignatureExport::decode(&export.encode()).unwrap();
+    assert_eq!(decoded, export);
+    let signature: AuthorityWeakQuorumSignInfo = decoded.to_quorum_signature(&committee).unwrap();
+    assert_eq!(signature.signers_map, cert.auth_sig().signers_map);
+    CertifiedCheckpointSummary::new_from_data_and_sig(cert.data().clone(), signature)
+        .verify(&committee, None)
+        .unwrap();
+
+    // Keys swapped between signers do not match the committee.
+    let mut swapped = decoded;
+    swapped.signer_public_keys.swap(0, 1);
+    assert!(swapped.to_quorum_signature::<false>(&committee).is_err());
+
+    // Nor does a committee of another epoch.
+    let mut next_committee = committee.clone();
+    next_committee.epoch += 1;
+    assert!(QuorumSignatureExport::new(cert.auth_sig(), &next_committee).is_err());
+}
@@ -0,0 +1,51 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::utils::{make_committee_key, CheckpointChainBuilder};
+
+#[test]
+fn test_notifier() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone())
+        .checkpoints_per_epoch(3)
+        .build(&mut rng);
+    let mut checkpoints = chain.checkpoints.into_iter().map(|(c, _)| c);
+
+    let notifier = CertifiedCheckpointNotifier::new(2);
+    let first = checkpoints.next().unwrap();
+    let verified = VerifiedCertifiedCheckpointSummary::new(first.clone(), &committee).unwrap();
+    assert_eq!(notifier.notify(verified.clone()), 0);
+
+    // Clones share the channel.
+    let mut subscriber = notifier.clone().subscribe();
+    assert_eq!(notifier.subscriber_count(), 1);
+    assert_eq!(notifier.notify(verified), 1);
+    assert_eq!(
+        subscriber.try_recv().unwrap().data().digest(),
+        first.data().digest()
+    );
+
+    // Subscribers lagging behind more than the capacity miss notifications.
+    for checkpoint in checkpoints {
+        notifier.notify(VerifiedCertifiedCheckpointSummary::new_unchecked(
+            checkpoint,
+        ));
+    }
+    notifier.notify(VerifiedCertifiedCheckpointSummary::new_unchecked(
+        first.clone(),
+    ));
+    assert!(matches!(
+        subscriber.try_recv(),
+        Err(broadcast::error::TryRecvError::Lagged(1))
+    ));
+
+    // Only verified certificates can be notified.
+    let mut forged = first;
+    forged.data_mut_for_testing().sequence_number += 1;
+    assert!(VerifiedCertifiedCheckpointSummary::new(forged, &committee).is_err());
+}
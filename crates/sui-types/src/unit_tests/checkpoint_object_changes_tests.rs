@@ -0,0 +1,54 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::base_types::{ObjectDigest, SequenceNumber, SuiAddress};
+use crate::object::Owner;
+
+fn object_ref(id: ObjectID, version: u64) -> ObjectRef {
+    (
+        id,
+        SequenceNumber::from_u64(version),
+        ObjectDigest::random(),
+    )
+}
+
+#[test]
+fn test_object_changes_proofs() {
+    let owner = Owner::AddressOwner(SuiAddress::default());
+    let shared = ObjectID::random();
+    let deleted = (
+        ObjectID::random(),
+        SequenceNumber::from_u64(3),
+        ObjectDigest::OBJECT_DIGEST_DELETED,
+    );
+    let first = TransactionEffects {
+        created: vec![(object_ref(ObjectID::random(), 1), owner)],
+        mutated: vec![(object_ref(shared, 1), owner)],
+        deleted: vec![deleted],
+        ..Default::default()
+    };
+    let second = TransactionEffects {
+        mutated: vec![(object_ref(shared, 2), owner)],
+        wrapped: vec![object_ref(ObjectID::random(), 5)],
+        ..Default::default()
+    };
+    let changes = ObjectChanges::from_effects([&first, &second]);
+    assert_eq!(changes.len(), 5);
+    assert_eq!(changes.latest(&shared), Some(&second.mutated[0].0));
+    assert_eq!(changes.latest(&ObjectID::random()), None);
+
+    let root = changes.root();
+    for object_ref in changes.iter() {
+        let proof = changes.prove(object_ref).unwrap();
+        verify_object_change(&root, object_ref, &proof).unwrap();
+    }
+    // A proof only holds for the reference it was made for.
+    let proof = changes.prove(&first.mutated[0].0).unwrap();
+    assert!(verify_object_change(&root, &second.mutated[0].0, &proof).is_err());
+    assert!(changes.prove(&object_ref(shared, 3)).is_none());
+
+    // The commitment does not depend on the order of the effects.
+    assert_eq!(ObjectChanges::from_effects([&second, &first]).root(), root);
+    assert_eq!(ObjectChanges::default().root(), [0; 32]);
+}
@@ -0,0 +1,160 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::base_types::AuthorityName;
+use crate::messages_checkpoint::{
+    validate_response, CheckpointRequest, CheckpointRequestType, CheckpointResponse,
+};
+use crate::utils::make_committee_key;
+
+fn digests(count: usize) -> Vec<ExecutionDigests> {
+    (0..count).map(|_| ExecutionDigests::random()).collect()
+}
+
+fn contents(digests: &[ExecutionDigests]) -> CheckpointProposalContents {
+    CheckpointProposalContents::new(digests.iter().copied())
+}
+
+#[test]
+fn test_sketch_difference() {
+    let shared = digests(500);
+    let first_only = digests(10);
+    let second_only = digests(7);
+    let first = contents(&[&shared[..], &first_only[..]].concat());
+    let second = contents(&[&shared[..], &second_only[..]].concat());
+
+    let difference = ProposalSketch::from_contents(&first, 40)
+        .difference(&ProposalSketch::from_contents(&second, 40))
+        .unwrap();
+    assert_eq!(difference.only_in_first, first_only.into_iter().collect());
+    assert_eq!(difference.only_in_second, second_only.into_iter().collect());
+
+    // The size of a sketch depends on the difference it is made for, not on the contents.
+    assert_eq!(
+        ProposalSketch::from_contents(&first, 40).cells(),
+        ProposalSketch::new(40).cells()
+    );
+
+    // Sketches too small for the difference, or of different sizes, cannot be decoded.
+    assert!(ProposalSketch::from_contents(&first, 1)
+        .difference(&ProposalSketch::from_contents(&second, 1))
+        .is_err());
+    assert!(ProposalSketch::from_contents(&first, 40)
+        .difference(&ProposalSketch::from_contents(&second, 60))
+        .is_err());
+
+    // Identical proposals have no difference.
+    let sketch = ProposalSketch::from_contents(&first, 5);
+    assert_eq!(
+        sketch.difference(&sketch).unwrap(),
+        SketchDifference::default()
+    );
+}
+
+#[test]
+fn test_proposal_diff_exchange() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let names: Vec<AuthorityName> = keys.iter().map(|k| k.public().into()).collect();
+    let shared = digests(200);
+    let proposal = |index: usize, own: &[ExecutionDigests]| {
+        CheckpointProposal::new(
+            committee.epoch,
+            1,
+            names[index],
+            &keys[index],
+            contents(&[&shared[..], own].concat()),
+        )
+    };
+    let requester = proposal(0, &digests(3));
+    let responder = proposal(1, &digests(4));
+    let expected = requester.fragment_with(&responder);
+
+    // A small difference is decoded from the sketch.
+    let response = responder
+        .answer_diff_request(&requester.sketch(20))
+        .unwrap();
+    assert!(matches!(
+        &response,
+        CheckpointProposalDiffResponse::Diff {
+            missing_from_requester,
+            missing_from_responder,
+            ..
+        } if missing_from_requester.len() == 4 && missing_from_responder.len() == 3
+    ));
+    let fragment = requester.fragment_from_diff_response(&response).unwrap();
+    assert_eq!(
+        bcs::to_bytes(&fragment.data).unwrap(),
+        bcs::to_bytes(&expected.data).unwrap()
+    );
+    assert!(fragment.data.diff.check());
+
+    // A difference too large for the sketch falls back to the contents of the responder.
+    let response = responder.answer_diff_request(&requester.sketch(0)).unwrap();
+    assert!(matches!(
+        response,
+        CheckpointProposalDiffResponse::Contents { .. }
+    ));
+    let fragment = requester.fragment_from_diff_response(&response).unwrap();
+    assert_eq!(
+        bcs::to_bytes(&fragment.data).unwrap(),
+        bcs::to_bytes(&expected.data).unwrap()
+    );
+
+    // A diff not matching the waypoints of the proposals is refused.
+    let forged = CheckpointProposalDiffResponse::Diff {
+        proposal: responder.signed_summary.clone(),
+        missing_from_requester: digests(4),
+        missing_from_responder: Vec::new(),
+    };
+    assert!(requester.fragment_from_diff_response(&forged).is_err());
+
+    // Requests for another proposal are refused.
+    let mut request = requester.sketch(20);
+    request.sequence_number = 2;
+    assert!(responder.answer_diff_request(&request).is_err());
+
+    // The own proposal of the requester is not fragmented with.
+    let own = requester
+        .answer_diff_request(&requester.sketch(20))
+        .unwrap();
+    assert!(requester.fragment_from_diff_response(&own).is_err());
+}
+
+#[test]
+fn test_proposal_diff_request() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let name: AuthorityName = keys[0].public().into();
+    let proposal =
+        CheckpointProposal::new(committee.epoch, 1, name, &keys[0], contents(&digests(5)));
+
+    let request = CheckpointRequest::proposal_diff(proposal.sketch(20));
+    let decoded: CheckpointRequest = bcs::from_bytes(&bcs::to_bytes(&request).unwrap()).unwrap();
+    assert!(matches!(
+        decoded.request_type,
+        CheckpointRequestType::CheckpointProposalDiff(_)
+    ));
+
+    // Malformed sketches are refused when decoding the request.
+    let mut malformed = proposal.sketch(20);
+    malformed.sketch.cells.pop();
+    let malformed = CheckpointRequest::proposal_diff(malformed);
+    assert!(malformed.validate().is_err());
+    assert!(bcs::from_bytes::<CheckpointRequest>(&bcs::to_bytes(&malformed).unwrap()).is_err());
+
+    // Responses must be about the requested proposal.
+    let response = CheckpointResponse::CheckpointProposalDiff {
+        response: Some(proposal.answer_diff_request(&proposal.sketch(20)).unwrap()),
+    };
+    validate_response(&request, &response).unwrap();
+    let mut other = proposal.sketch(20);
+    other.sequence_number = 2;
+    assert!(validate_response(&CheckpointRequest::proposal_diff(other), &response).is_err());
+    assert!(validate_response(&CheckpointRequest::proposal(true), &response).is_err());
+}
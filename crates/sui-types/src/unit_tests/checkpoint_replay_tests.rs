@@ -0,0 +1,109 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::messages_checkpoint::CheckpointProposal;
+use crate::utils::{make_committee_key, CheckpointChainBuilder};
+
+#[test]
+fn test_proposal_replay() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let names: Vec<AuthorityName> = keys.iter().map(|k| k.public().into()).collect();
+    let chain = CheckpointChainBuilder::new(keys.iter().map(|k| k.copy()).collect(), committee)
+        .checkpoints_per_epoch(3)
+        .transactions_per_checkpoint(3)
+        .build(&mut rng);
+    let committee = chain.committee(0).unwrap();
+    let landed = |seq: usize, index: usize| *chain.checkpoints[seq].1.iter().nth(index).unwrap();
+    let never_landed = ExecutionDigests::random();
+
+    let proposal = |index: usize, seq, transactions: Vec<ExecutionDigests>| {
+        let proposal = CheckpointProposal::new(
+            committee.epoch,
+            seq,
+            names[index],
+            &keys[index],
+            CheckpointProposalContents::new(transactions.into_iter()),
+        );
+        LoggedProposal {
+            summary: proposal.signed_summary,
+            contents: Some(proposal.transactions),
+        }
+    };
+
+    let mut replay = ProposalReplay::new();
+    for (checkpoint, contents) in &chain.checkpoints[..2] {
+        replay
+            .add_checkpoint(checkpoint, contents, committee)
+            .unwrap();
+    }
+    // Contents must match the checkpoint.
+    let (checkpoint, _) = &chain.checkpoints[2];
+    assert!(replay
+        .add_checkpoint(checkpoint, &chain.checkpoints[0].1, committee)
+        .is_err());
+
+    let first = proposal(
+        0,
+        0,
+        vec![landed(0, 0), landed(0, 1), landed(1, 0), never_landed],
+    );
+    replay.add_proposal(first.clone(), committee).unwrap();
+    replay
+        .add_proposal(proposal(1, 1, vec![landed(0, 2), landed(1, 1)]), committee)
+        .unwrap();
+
+    // A proposal logged without its contents, then with them.
+    let with_contents = proposal(2, 1, vec![landed(1, 2)]);
+    let summary_only = LoggedProposal {
+        contents: None,
+        ..with_contents.clone()
+    };
+    replay
+        .add_proposal(summary_only.clone(), committee)
+        .unwrap();
+    assert_eq!(replay.report().timelines[&names[2]][0].proposed, None);
+    replay.add_proposal(with_contents, committee).unwrap();
+    replay.add_proposal(summary_only, committee).unwrap();
+
+    // Proposals must match their contents, and conflicting proposals are refused.
+    let mut forged = proposal(3, 1, vec![landed(1, 0)]);
+    forged.contents = Some(CheckpointProposalContents::new([never_landed].into_iter()));
+    assert!(replay.add_proposal(forged, committee).is_err());
+    assert!(replay
+        .add_proposal(proposal(0, 0, vec![landed(0, 0)]), committee)
+        .is_err());
+    assert_eq!(replay.conflicts().len(), 1);
+
+    let report = replay.report();
+    assert_eq!(report.checkpoints, vec![0, 1]);
+    assert_eq!(report.timelines.len(), 3);
+    assert_eq!(
+        report.timelines[&names[0]],
+        vec![ProposalOutcome {
+            sequence_number: 0,
+            content_digest: first.summary.data().content_digest,
+            certified: true,
+            proposed: Some(4),
+            included: 2,
+            already_checkpointed: 0,
+            late: vec![LateTransaction {
+                transaction: landed(1, 0),
+                landed_in: 1,
+            }],
+            dropped: vec![never_landed],
+        }]
+    );
+    let outcome = &report.timelines[&names[1]][0];
+    assert_eq!((outcome.included, outcome.already_checkpointed), (1, 1));
+    assert_eq!(report.timelines[&names[2]][0].included, 1);
+    assert_eq!(
+        report.dropped_transactions(),
+        [(never_landed, vec![names[0]])].into_iter().collect()
+    );
+}
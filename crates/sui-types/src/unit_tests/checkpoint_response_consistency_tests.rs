@@ -0,0 +1,140 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::base_types::ExecutionDigests;
+use crate::crypto::{AuthorityKeyPair, KeypairTraits};
+use crate::gas::GasCostSummary;
+use crate::messages_checkpoint::{CheckpointContents, SignedCheckpointSummary};
+use crate::utils::make_committee_key;
+
+fn signed(key: &AuthorityKeyPair, seq: u64) -> AuthenticatedCheckpoint {
+    let contents = CheckpointContents::new_with_causally_ordered_transactions(
+        [ExecutionDigests::random()].into_iter(),
+    );
+    AuthenticatedCheckpoint::Signed(SignedCheckpointSummary::new_from_contents(
+        0,
+        seq,
+        key.public().into(),
+        key,
+        &contents,
+        None,
+        GasCostSummary::default(),
+        None,
+    ))
+}
+
+fn response(checkpoint: &AuthenticatedCheckpoint) -> CheckpointResponse {
+    CheckpointResponse::AuthenticatedCheckpoint {
+        checkpoint: Some(checkpoint.clone()),
+        contents: None,
+    }
+}
+
+#[test]
+fn test_response_consistency_checker() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let authority: AuthorityName = keys[0].public().into();
+    let checker = ResponseConsistencyChecker::new();
+    let request = CheckpointRequest::authenticated(Some(3), false);
+
+    let first = signed(&keys[0], 3);
+    assert!(checker
+        .record(authority, &request, &response(&first))
+        .is_none());
+    // The same response again is consistent.
+    assert!(checker
+        .record(authority, &request, &response(&first))
+        .is_none());
+
+    // Another authority may serve another checkpoint, e.g. on a fork: it is not compared.
+    let other = signed(&keys[1], 3);
+    assert!(checker
+        .record(keys[1].public().into(), &request, &response(&other))
+        .is_none());
+
+    let second = signed(&keys[0], 3);
+    let conflict = checker
+        .record(authority, &request, &response(&second))
+        .unwrap();
+    assert_eq!(conflict.authority, authority);
+    assert_eq!(conflict.first.summary(), first.summary());
+    assert_eq!(conflict.second.summary(), second.summary());
+    conflict.verify(&committee).unwrap();
+    assert_eq!(checker.take_conflicts().len(), 1);
+    assert!(checker.take_conflicts().is_empty());
+
+    // The latest checkpoint changes over the session.
+    let latest = CheckpointRequest::authenticated(None, false);
+    for seq in [3, 4] {
+        assert!(checker
+            .record(authority, &latest, &response(&signed(&keys[0], seq)))
+            .is_none());
+    }
+    assert!(checker
+        .record(authority, &latest, &response(&signed(&keys[0], 4)))
+        .is_some());
+}
+
+#[test]
+fn test_response_conflict_verify() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let authority: AuthorityName = keys[0].public().into();
+    let request = CheckpointRequest::authenticated(Some(3), false);
+    let conflict = ResponseConflict {
+        authority,
+        request,
+        first: signed(&keys[0], 3),
+        second: signed(&keys[0], 3),
+    };
+    conflict.verify(&committee).unwrap();
+
+    let mut identical = conflict.clone();
+    identical.second = identical.first.clone();
+    assert!(identical.verify(&committee).is_err());
+
+    let mut other_checkpoint = conflict.clone();
+    other_checkpoint.second = signed(&keys[0], 4);
+    assert!(other_checkpoint.verify(&committee).is_err());
+
+    let mut other_signer = conflict.clone();
+    other_signer.second = signed(&keys[1], 3);
+    assert!(other_signer.verify(&committee).is_err());
+
+    let mut forged = conflict;
+    if let AuthenticatedCheckpoint::Signed(signed) = &mut forged.second {
+        signed
+            .data_mut_for_testing()
+            .gas_cost_summary
+            .computation_cost += 1;
+    }
+    assert!(forged.verify(&committee).is_err());
+}
+
+#[test]
+fn test_response_consistency_checker_capacity() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, _) = make_committee_key(&mut rng);
+    let authority: AuthorityName = keys[0].public().into();
+    let checker = ResponseConsistencyChecker::with_capacity(2);
+
+    let request = |seq| CheckpointRequest::authenticated(Some(seq), false);
+    for seq in 1..=3 {
+        assert!(checker
+            .record(authority, &request(seq), &response(&signed(&keys[0], seq)))
+            .is_none());
+    }
+    assert_eq!(checker.len(), 2);
+
+    // The oldest response is forgotten, so that another answer to it is no longer caught.
+    assert!(checker
+        .record(authority, &request(1), &response(&signed(&keys[0], 1)))
+        .is_none());
+    assert_eq!(checker.len(), 2);
+    assert!(checker.take_conflicts().is_empty());
+}
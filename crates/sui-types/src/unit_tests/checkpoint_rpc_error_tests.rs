@@ -0,0 +1,101 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+
+use serde_json::json;
+
+use super::*;
+
+#[test]
+fn test_checkpoint_error_classification() {
+    let timeout = SuiError::CheckpointWaitTimeout {
+        sequence_number: 7,
+        watermark: Some(5),
+    };
+    assert_eq!(CheckpointError::from(&timeout), CheckpointError::Timeout);
+    assert!(CheckpointError::from(&timeout).is_retryable());
+
+    let wrong_epoch = SuiError::WrongEpoch {
+        expected_epoch: 1,
+        actual_epoch: 2,
+    };
+    assert_eq!(
+        CheckpointError::classify(&wrong_epoch),
+        CheckpointError::EpochMismatch
+    );
+
+    let conflicting = SuiError::ConflictingCheckpointSignature {
+        kind: "summary".to_string(),
+        epoch: 3,
+        sequence_number: 9,
+    };
+    assert_eq!(
+        CheckpointError::classify(&conflicting),
+        CheckpointError::SigningRefused
+    );
+    assert!(!CheckpointError::classify(&conflicting).is_retryable());
+
+    assert_eq!(
+        CheckpointError::classify(&SuiError::UnknownSigner),
+        CheckpointError::VerificationFailed
+    );
+    assert_eq!(
+        CheckpointError::classify(&SuiError::GenericStorageError("disk".to_string())),
+        CheckpointError::Storage
+    );
+    let internal = CheckpointError::classify(&SuiError::from("anything else"));
+    assert_eq!(internal, CheckpointError::Internal);
+    assert_eq!(internal.rpc_code(), INTERNAL_ERROR_CODE);
+
+    // Codes identify classes.
+    let kinds = [
+        CheckpointError::Timeout,
+        CheckpointError::EpochMismatch,
+        CheckpointError::VerificationFailed,
+        CheckpointError::SigningRefused,
+        CheckpointError::Storage,
+        CheckpointError::Internal,
+    ];
+    let codes: HashSet<_> = kinds.iter().map(|kind| kind.rpc_code()).collect();
+    assert_eq!(codes.len(), kinds.len());
+    assert!(kinds
+        .iter()
+        .filter(|kind| **kind != CheckpointError::Internal)
+        .all(|kind| (-32099..=-32000).contains(&kind.rpc_code())));
+}
+
+#[test]
+fn test_checkpoint_error_data() {
+    let data = CheckpointErrorData::new(&SuiError::ConflictingCheckpointSignature {
+        kind: "summary".to_string(),
+        epoch: 3,
+        sequence_number: 9,
+    });
+    assert_eq!(data.rpc_code(), -32053);
+    assert_eq!(
+        data.to_json(),
+        json!({
+            "kind": "SigningRefused",
+            "retryable": false,
+            "epoch": 3,
+            "sequenceNumber": 9,
+        })
+    );
+    let decoded: CheckpointErrorData = serde_json::from_value(data.to_json()).unwrap();
+    assert_eq!(decoded, data);
+
+    let data = CheckpointErrorData::new(&SuiError::CheckpointWaitTimeout {
+        sequence_number: 7,
+        watermark: None,
+    });
+    assert_eq!(
+        data.to_json(),
+        json!({
+            "kind": "Timeout",
+            "retryable": true,
+            "epoch": null,
+            "sequenceNumber": 7,
+        })
+    );
+}
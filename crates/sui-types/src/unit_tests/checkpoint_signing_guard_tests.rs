@@ -0,0 +1,131 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use super::*;
+use crate::base_types::ExecutionDigests;
+use crate::gas::GasCostSummary;
+use crate::messages_checkpoint::{CheckpointContents, CheckpointProposalContents};
+
+#[derive(Default)]
+struct RecordingPersistence {
+    stored: Mutex<Option<SigningGuard>>,
+    fail: Mutex<bool>,
+}
+
+impl SigningGuardPersistence for Arc<RecordingPersistence> {
+    fn persist(&self, guard: &SigningGuard) -> SuiResult {
+        if *self.fail.lock().unwrap() {
+            return Err(SuiError::GenericStorageError("persist failed".to_string()));
+        }
+        *self.stored.lock().unwrap() = Some(guard.clone());
+        Ok(())
+    }
+}
+
+fn summary(epoch: EpochId, seq: CheckpointSequenceNumber) -> CheckpointSummary {
+    let contents = CheckpointContents::new_with_causally_ordered_transactions(
+        [ExecutionDigests::random()].into_iter(),
+    );
+    CheckpointSummary::new(epoch, seq, &contents, None, GasCostSummary::default(), None)
+}
+
+#[test]
+fn test_signing_guard() {
+    let mut guard = SigningGuard::new();
+    let first = summary(0, 5);
+    guard.record_summary(&first).unwrap();
+    // Signing the same summary again is harmless.
+    guard.record_summary(&first).unwrap();
+
+    assert!(matches!(
+        guard.check_summary(&summary(0, 4)),
+        Err(SuiError::CheckpointSignatureRegression {
+            sequence_number: 4,
+            signed_sequence_number: 5,
+            ..
+        })
+    ));
+    assert!(matches!(
+        guard.check_summary(&summary(0, 5)),
+        Err(SuiError::ConflictingCheckpointSignature {
+            sequence_number: 5,
+            ..
+        })
+    ));
+    // Checkpoints of a later epoch are above the mark.
+    guard.record_summary(&summary(1, 5)).unwrap();
+    assert!(guard.check_summary(&summary(0, 6)).is_err());
+    assert_eq!(guard.summary.unwrap().epoch, 1);
+
+    // Proposals have their own mark.
+    assert!(guard.proposal.is_none());
+    let proposal = |seq| {
+        CheckpointProposalSummary::new(
+            seq,
+            &CheckpointProposalContents::new([ExecutionDigests::random()].into_iter()),
+        )
+    };
+    let first_proposal = proposal(3);
+    guard.record_proposal(1, &first_proposal).unwrap();
+    guard.check_proposal(1, &first_proposal).unwrap();
+    assert!(guard.check_proposal(1, &proposal(3)).is_err());
+    assert!(guard.check_proposal(1, &proposal(2)).is_err());
+    guard.check_proposal(1, &proposal(4)).unwrap();
+
+    // The guard survives a round trip through storage.
+    let bytes = bincode::serialize(&guard).unwrap();
+    assert_eq!(bincode::deserialize::<SigningGuard>(&bytes).unwrap(), guard);
+}
+
+#[test]
+fn test_persisted_signing_guard() {
+    let persistence = Arc::new(RecordingPersistence::default());
+    let guarded = PersistedSigningGuard::new(SigningGuard::new(), Box::new(persistence.clone()));
+    let first = summary(0, 1);
+    guarded.review(&first).unwrap();
+    assert_eq!(
+        persistence.stored.lock().unwrap().as_ref(),
+        Some(&guarded.guard())
+    );
+
+    let refusal = guarded.review(&summary(0, 1)).unwrap_err();
+    assert_eq!(refusal.policy, "PersistedSigningGuard");
+    assert_eq!(refusal.sequence_number, 1);
+
+    // A guard that cannot be persisted refuses to sign, and stays unchanged.
+    *persistence.fail.lock().unwrap() = true;
+    assert!(guarded.review(&summary(0, 2)).is_err());
+    assert_eq!(guarded.guard().summary.unwrap().sequence_number, 1);
+    // Resending the summary already signed needs no new persistence.
+    guarded.review(&first).unwrap();
+    *persistence.fail.lock().unwrap() = false;
+
+    // After a restart, the persisted guard carries on.
+    let restored = persistence.stored.lock().unwrap().clone().unwrap();
+    let guarded = PersistedSigningGuard::new(restored, Box::new(persistence.clone()));
+    assert!(guarded.review(&summary(0, 0)).is_err());
+    guarded
+        .record_proposal(
+            0,
+            &CheckpointProposalSummary::new(
+                2,
+                &CheckpointProposalContents::new(std::iter::empty()),
+            ),
+        )
+        .unwrap();
+    guarded.review(&summary(0, 2)).unwrap();
+    assert_eq!(
+        persistence
+            .stored
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .proposal
+            .unwrap()
+            .sequence_number,
+        2
+    );
+}
@@ -0,0 +1,110 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+
+/// Drops every chunk, so that no fragment is ever complete.
+struct DropAllChunks;
+
+impl FaultInjector for DropAllChunks {
+    fn fragment_message(
+        &mut self,
+        _sequence_number: CheckpointSequenceNumber,
+        message: &CheckpointFragmentMessage,
+    ) -> Delivery {
+        match message {
+            CheckpointFragmentMessage::Header(_) => Delivery::Deliver,
+            CheckpointFragmentMessage::Chunk(_) => Delivery::Drop,
+        }
+    }
+}
+
+#[test]
+fn test_sim_without_faults() {
+    let mut sim = CheckpointSim::new([0; 32], 4, NoFaults);
+    for round in 0..4 {
+        let executed = sim.execute_transactions(10);
+        if round == 1 {
+            sim.schedule_epoch_change(5);
+        }
+        let attempt = sim.run_attempt();
+        assert_eq!(attempt.sequence_number, round);
+        assert_eq!(attempt.dropped + attempt.delayed, 0);
+        assert!(matches!(attempt.outcome, SimOutcome::Certified(_)));
+
+        // With every proposal connected, the checkpoint includes every transaction.
+        let (_, contents) = sim.checkpoints().last().unwrap();
+        assert_eq!(contents.size(), executed.len());
+        assert!(executed.iter().all(|d| contents.iter().any(|c| c == d)));
+    }
+    assert_eq!(sim.committee().epoch, 1);
+    assert_eq!(sim.committee().num_members(), 5);
+    assert!(sim.checkpoints()[1].0.data().next_epoch_committee.is_some());
+    sim.verify_chain().unwrap();
+}
+
+#[test]
+fn test_sim_is_deterministic() {
+    let run = || {
+        let mut sim = CheckpointSim::new([7; 32], 4, RandomFaults::new([1; 32], 0.2, 0.2));
+        for _ in 0..3 {
+            sim.execute_transactions(20);
+            sim.run_until_certified(50).unwrap();
+        }
+        sim.checkpoints()
+            .iter()
+            .map(|(c, _)| c.data().digest())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn test_sim_with_lossy_consensus_and_equivocation() {
+    let mut sim = CheckpointSim::new([0; 32], 4, RandomFaults::new([2; 32], 0.3, 0.3));
+    let equivocator = sim.authority_names()[0];
+    sim.faults_mut().equivocators.insert(equivocator);
+    for round in 0..5 {
+        sim.execute_transactions(30);
+        if round == 2 {
+            sim.schedule_epoch_change(4);
+            sim.faults_mut().equivocators.clear();
+        }
+        let checkpoint = sim.run_until_certified(100).unwrap();
+        if round < 2 {
+            // The conflicting signature is left out of the certificate.
+            let signers: Vec<_> = checkpoint
+                .signatory_authorities(sim.genesis_committee())
+                .collect::<SuiResult<_>>()
+                .unwrap();
+            assert!(!signers.contains(&&equivocator));
+        }
+    }
+    assert_eq!(sim.checkpoints().len(), 5);
+    sim.verify_chain().unwrap();
+}
+
+#[test]
+fn test_sim_stalls_without_quorum() {
+    // Without fragments, no proposals can be connected.
+    let mut sim = CheckpointSim::new([0; 32], 4, DropAllChunks);
+    sim.execute_transactions(10);
+    let attempt = sim.run_attempt();
+    assert!(attempt.dropped > 0);
+    assert_eq!(attempt.verified_fragments, 0);
+    assert!(matches!(attempt.outcome, SimOutcome::NoQuorumOfProposals));
+    assert!(sim.run_until_certified(3).is_err());
+    assert!(sim.checkpoints().is_empty());
+
+    // More than a third of equivocating authorities prevent certification.
+    let mut sim = CheckpointSim::new([0; 32], 4, RandomFaults::new([3; 32], 0.0, 0.0));
+    let names = sim.authority_names();
+    sim.faults_mut()
+        .equivocators
+        .extend(names[..2].iter().cloned());
+    sim.execute_transactions(10);
+    assert!(matches!(
+        sim.run_attempt().outcome,
+        SimOutcome::NoQuorumOfSignatures
+    ));
+}
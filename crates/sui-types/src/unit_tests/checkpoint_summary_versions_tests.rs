@@ -0,0 +1,347 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::hash::{HashFunction, Sha3_256};
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::base_types::{ObjectDigest, ObjectID, SequenceNumber};
+use crate::checkpoint_object_changes::ObjectChanges;
+use crate::crypto::{AuthoritySignInfo, AuthoritySignInfoTrait};
+use crate::messages::TransactionEffects;
+use crate::messages_checkpoint::{CheckpointContents, ProvisionalCheckpointSummary};
+use crate::utils::{make_committee_key, CheckpointChainBuilder};
+
+fn v1() -> CheckpointSummaryV1 {
+    CheckpointSummaryV1 {
+        epoch: 1,
+        sequence_number: 2,
+        content_digest: [3; 32],
+        previous_digest: Some([4; 32]),
+        gas_cost_summary: GasCostSummary {
+            computation_cost: 5,
+            storage_cost: 6,
+            storage_rebate: 7,
+        },
+        next_epoch_committee: None,
+    }
+}
+
+fn v2() -> CheckpointSummaryV2 {
+    let v1 = v1();
+    CheckpointSummaryV2 {
+        epoch: v1.epoch,
+        sequence_number: v1.sequence_number,
+        content_digest: v1.content_digest,
+        contents_merkle_root: [8; 32],
+        previous_digest: v1.previous_digest,
+        gas_cost_summary: v1.gas_cost_summary,
+        timestamp_ms: 9,
+        next_epoch_committee: None,
+        end_of_epoch_data: None,
+    }
+}
+
+fn name_prefixed_digest(name: &str, bytes: &[u8]) -> CheckpointDigest {
+    let mut hasher = Sha3_256::default();
+    hasher.update(format!("{name}::").as_bytes());
+    hasher.update(bytes);
+    hasher.finalize().into()
+}
+
+#[test]
+fn test_versioned_summary_decoding() {
+    // Version 1 keeps the encoding and digest of the summaries signed so far.
+    let v1_bytes = bcs::to_bytes(&v1()).unwrap();
+    let versioned = VersionedCheckpointSummary::from(v1());
+    let bytes = bcs::to_bytes(&versioned).unwrap();
+    assert_eq!(bytes[0], 0);
+    assert_eq!(&bytes[1..], &v1_bytes[..]);
+    let decoded: VersionedCheckpointSummary = bcs::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded, versioned);
+    assert_eq!(decoded.version(), 1);
+    assert_eq!(decoded.as_v1(), Some(&v1()));
+    assert!(decoded.as_v2().is_none());
+    assert_eq!(decoded.digest(), v1().digest());
+    assert_eq!(
+        decoded.digest(),
+        name_prefixed_digest("CheckpointSummary", &v1_bytes)
+    );
+    // Checkpoints are built as version 4, encoded and digested as the summary itself.
+    let summary = CheckpointSummary::new(
+        1,
+        2,
+        &CheckpointContents::new_with_causally_ordered_transactions(std::iter::empty()),
+        Some([4; 32]),
+        GasCostSummary::default(),
+        None,
+    );
+    let summary_bytes = bcs::to_bytes(&summary).unwrap();
+    let versioned = VersionedCheckpointSummary::from(summary.clone());
+    let bytes = bcs::to_bytes(&versioned).unwrap();
+    assert_eq!(bytes[0], 3);
+    assert_eq!(&bytes[1..], &summary_bytes[..]);
+    assert_eq!(versioned.version(), 4);
+    assert_eq!(versioned.as_v4(), Some(&summary));
+    assert_eq!(versioned.digest(), summary.digest());
+    assert_eq!(
+        summary.digest(),
+        name_prefixed_digest("CheckpointSummaryV4", &summary_bytes)
+    );
+
+    let v2_bytes = bcs::to_bytes(&v2()).unwrap();
+    let versioned = VersionedCheckpointSummary::from(v2());
+    let bytes = bcs::to_bytes(&versioned).unwrap();
+    assert_eq!(bytes[0], 1);
+    assert_eq!(&bytes[1..], &v2_bytes[..]);
+    let decoded: VersionedCheckpointSummary = bcs::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded, versioned);
+    assert_eq!(decoded.version(), 2);
+    assert_eq!(decoded.as_v2(), Some(&v2()));
+    assert_eq!(
+        decoded.digest(),
+        name_prefixed_digest("CheckpointSummaryV2", &v2_bytes)
+    );
+
+    // Unknown versions, truncated and extended encodings are rejected.
+    let mut unknown = bytes.clone();
+    unknown[0] = 4;
+    assert!(bcs::from_bytes::<VersionedCheckpointSummary>(&unknown).is_err());
+    assert!(bcs::from_bytes::<VersionedCheckpointSummary>(&[]).is_err());
+    assert!(bcs::from_bytes::<VersionedCheckpointSummary>(&bytes[..bytes.len() - 1]).is_err());
+    let mut extended = bytes;
+    extended.push(0);
+    assert!(bcs::from_bytes::<VersionedCheckpointSummary>(&extended).is_err());
+    // A version 1 encoding is not a valid version 2 encoding.
+    let mut retagged = bcs::to_bytes(&VersionedCheckpointSummary::from(v1())).unwrap();
+    retagged[0] = 1;
+    assert!(bcs::from_bytes::<VersionedCheckpointSummary>(&retagged).is_err());
+}
+
+#[test]
+fn test_versioned_summary_accessors() {
+    let first = VersionedCheckpointSummary::from(v1());
+    let second = VersionedCheckpointSummary::from(v2());
+    for summary in [&first, &second] {
+        assert_eq!(summary.epoch(), 1);
+        assert_eq!(summary.sequence_number(), 2);
+        assert_eq!(summary.content_digest(), &[3; 32]);
+        assert_eq!(summary.previous_digest(), Some(&[4; 32]));
+        assert_eq!(summary.gas_cost_summary().storage_rebate, 7);
+        assert!(summary.next_epoch_committee().is_none());
+        assert!(summary.end_of_epoch_data().is_none());
+    }
+    assert!(first.timestamp_ms().is_none() && first.contents_merkle_root().is_none());
+    assert_eq!(second.timestamp_ms(), Some(9));
+    assert_eq!(second.contents_merkle_root(), Some(&[8; 32]));
+    assert_ne!(first.digest(), second.digest());
+}
+
+#[test]
+fn test_versioned_summary_signatures() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let name: AuthorityName = keys[0].public().into();
+
+    // A signature over a versioned summary is a signature over the summary it holds.
+    let versioned = VersionedCheckpointSummary::from(v1());
+    let signature = AuthoritySignInfo::new(committee.epoch, &versioned, name, &keys[0]);
+    signature.verify(&v1(), &committee).unwrap();
+    signature.verify(&versioned, &committee).unwrap();
+
+    // It is never valid for another version with the same fields.
+    let signature = AuthoritySignInfo::new(committee.epoch, &v2(), name, &keys[0]);
+    signature
+        .verify(&VersionedCheckpointSummary::from(v2()), &committee)
+        .unwrap();
+    assert!(signature.verify(&v1(), &committee).is_err());
+    assert!(signature.verify(&versioned, &committee).is_err());
+}
+
+#[test]
+fn test_verify_inclusion() {
+    let transactions: Vec<_> = (0..3).map(|_| ExecutionDigests::random()).collect();
+    let contents =
+        CheckpointContents::new_with_causally_ordered_transactions(transactions.iter().cloned());
+    let proof = contents.prove_inclusion(&transactions[1]).unwrap();
+    let summary = VersionedCheckpointSummary::from(CheckpointSummaryV2 {
+        contents_merkle_root: contents.commitments().merkle_root,
+        ..v2()
+    });
+    summary.verify_inclusion(&transactions[1], &proof).unwrap();
+    assert!(summary.verify_inclusion(&transactions[0], &proof).is_err());
+
+    // Version 1 summaries do not commit to a Merkle root.
+    let summary = VersionedCheckpointSummary::from(v1());
+    assert!(summary.verify_inclusion(&transactions[1], &proof).is_err());
+
+    // The summaries checkpoints are built with do, and a certified one is enough.
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone()).build(&mut rng);
+    let certified = chain.certify(CheckpointSummary::new(
+        committee.epoch,
+        1,
+        &contents,
+        Some(chain.checkpoints[0].0.data().digest()),
+        GasCostSummary::default(),
+        None,
+    ));
+    certified.verify(&committee, Some(&contents)).unwrap();
+    certified
+        .data()
+        .verify_inclusion(&transactions[1], &proof)
+        .unwrap();
+    assert!(certified
+        .data()
+        .verify_inclusion(&transactions[0], &proof)
+        .is_err());
+}
+
+#[test]
+fn test_verify_object_change() {
+    let written = (
+        ObjectID::random(),
+        SequenceNumber::from_u64(1),
+        ObjectDigest::random(),
+    );
+    let effects = TransactionEffects {
+        deleted: vec![written],
+        ..Default::default()
+    };
+    let changes = ObjectChanges::from_effects([&effects]);
+    let proof = changes.prove(&written).unwrap();
+
+    let v2 = v2();
+    let v3 = |object_changes_commitment| CheckpointSummaryV3 {
+        epoch: v2.epoch,
+        sequence_number: v2.sequence_number,
+        content_digest: v2.content_digest,
+        contents_merkle_root: v2.contents_merkle_root,
+        object_changes_commitment,
+        previous_digest: v2.previous_digest,
+        gas_cost_summary: v2.gas_cost_summary.clone(),
+        timestamp_ms: v2.timestamp_ms,
+        next_epoch_committee: None,
+        end_of_epoch_data: None,
+    };
+    let summary = VersionedCheckpointSummary::from(v3(Some(changes.root())));
+    assert_eq!(summary.version(), 3);
+    summary.verify_object_change(&written, &proof).unwrap();
+    let other = (written.0, SequenceNumber::from_u64(2), written.2);
+    assert!(summary.verify_object_change(&other, &proof).is_err());
+
+    // The commitment is optional, and absent from earlier versions.
+    let summary = VersionedCheckpointSummary::from(v3(None));
+    assert!(summary.verify_object_change(&written, &proof).is_err());
+    assert_ne!(
+        summary.digest(),
+        VersionedCheckpointSummary::from(v2.clone()).digest()
+    );
+    let summary = VersionedCheckpointSummary::from(v2);
+    assert!(summary.object_changes_commitment().is_none());
+    assert!(summary.verify_object_change(&written, &proof).is_err());
+
+    // The summaries checkpoints are built with carry it when built from the effects.
+    let mut provisional = ProvisionalCheckpointSummary::new(0, 1, None, None);
+    provisional.set_contents(
+        CheckpointContents::new_with_causally_ordered_transactions(
+            [effects.execution_digests()].into_iter(),
+        ),
+        GasCostSummary::default(),
+    );
+    assert!(provisional
+        .clone()
+        .finalize()
+        .summary()
+        .object_changes_commitment()
+        .is_none());
+    provisional.set_object_changes(&changes);
+    let summary = VersionedCheckpointSummary::from(provisional.finalize().summary().clone());
+    assert_eq!(summary.version(), 4);
+    summary.verify_object_change(&written, &proof).unwrap();
+    assert!(summary.verify_object_change(&other, &proof).is_err());
+}
+
+#[test]
+fn test_gas_fee_attribution() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone())
+        .epochs(2)
+        .checkpoints_per_epoch(2)
+        .build(&mut rng);
+    let names: Vec<AuthorityName> = committee.voting_rights.iter().map(|(a, _)| *a).collect();
+    let gas = |cost| GasCostSummary {
+        computation_cost: cost,
+        storage_cost: 2 * cost,
+        storage_rebate: cost,
+    };
+    let attribution = |costs: &[u64]| EndOfEpochData::V1 {
+        gas_fee_attribution: GasFeeAttribution::new(
+            names.iter().cloned().zip(costs.iter().map(|c| gas(*c))),
+        ),
+    };
+    let certify = |summary: VersionedCheckpointSummary| {
+        let signatures = chain.sign(committee.epoch, &summary);
+        CertifiedVersionedCheckpointSummary::new(summary, signatures, &committee).unwrap()
+    };
+
+    // The first checkpoint of the epoch is kept as is, and the last one moves to version 2
+    // to carry the attribution.
+    let first = certify(chain.checkpoints[0].0.data().clone().into());
+    let last = chain.checkpoints[1].0.data();
+    let with_data = |next_epoch_committee, data| CheckpointSummaryV2 {
+        epoch: last.epoch,
+        sequence_number: last.sequence_number,
+        content_digest: last.content_digest,
+        contents_merkle_root: [0; 32],
+        previous_digest: last.previous_digest,
+        gas_cost_summary: gas(40),
+        timestamp_ms: 0,
+        next_epoch_committee,
+        end_of_epoch_data: Some(data),
+    };
+    let build_last = |data| certify(with_data(last.next_epoch_committee.clone(), data).into());
+
+    let epoch = vec![first.clone(), build_last(attribution(&[10, 10, 10, 10]))];
+    let verified =
+        CertifiedVersionedCheckpointSummary::verify_gas_fee_attribution(&committee, None, &epoch)
+            .unwrap();
+    assert_eq!(verified.get(&names[2]), Some(&gas(10)));
+    assert_eq!(verified.total(), Some(gas(40)));
+
+    // The attributions must add up to the gas costs of the epoch.
+    let epoch = vec![first.clone(), build_last(attribution(&[10, 10, 10, 11]))];
+    assert!(
+        CertifiedVersionedCheckpointSummary::verify_gas_fee_attribution(&committee, None, &epoch)
+            .is_err()
+    );
+    // Every checkpoint of the epoch is needed.
+    let epoch = vec![build_last(attribution(&[10, 10, 10, 10]))];
+    assert!(
+        CertifiedVersionedCheckpointSummary::verify_gas_fee_attribution(&committee, None, &epoch)
+            .is_err()
+    );
+    // The attribution covers the committee exactly.
+    let epoch = vec![first, build_last(attribution(&[20, 10, 10]))];
+    assert!(
+        CertifiedVersionedCheckpointSummary::verify_gas_fee_attribution(&committee, None, &epoch)
+            .is_err()
+    );
+
+    // End-of-epoch data is only valid on the last checkpoint of the epoch.
+    let summary = with_data(None, attribution(&[10, 10, 10, 10]));
+    assert!(summary.verify_end_of_epoch_data().is_err());
+    assert!(certify(summary.into()).verify(&committee).is_err());
+    let duplicated = EndOfEpochData::V1 {
+        gas_fee_attribution: GasFeeAttribution::new([(names[1], gas(1)), (names[1], gas(1))]),
+    };
+    let summary = with_data(last.next_epoch_committee.clone(), duplicated);
+    assert!(summary.verify_end_of_epoch_data().is_err());
+
+    // Summaries as checkpoints are built carry no end-of-epoch data.
+    assert!(chain.checkpoints[1].0.data().end_of_epoch_data().is_none());
+}
@@ -0,0 +1,81 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::base_types::ExecutionDigests;
+use crate::messages_checkpoint::CheckpointProposalContents;
+use crate::utils::make_committee_key;
+
+#[test]
+fn test_transaction_timelines() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let names: Vec<AuthorityName> = keys.iter().map(|k| k.public().into()).collect();
+    let traced = ExecutionDigests::random();
+    let other = ExecutionDigests::random();
+    let proposal = |index: usize, transactions: Vec<ExecutionDigests>| {
+        CheckpointProposal::new(
+            committee.epoch,
+            1,
+            names[index],
+            &keys[index],
+            CheckpointProposalContents::new(transactions.into_iter()),
+        )
+    };
+    let first = proposal(0, vec![traced, other]);
+    let second = proposal(1, vec![other]);
+
+    let trace = TxCheckpointTrace::new(1);
+    // Nothing is recorded before transactions are selected.
+    trace.record_proposal_at(&first, 5);
+    assert!(trace.timelines().is_empty());
+
+    assert!(trace.select_at(traced.transaction, 10));
+    assert!(!trace.select_at(other.transaction, 10));
+    trace.record_proposal_at(&first, 20);
+    trace.record_proposal_at(&second, 25);
+    trace.record_fragment_at(&first.fragment_with(&second), 30);
+    // The transaction is in both proposals, so this fragment does not carry it.
+    trace.record_fragment_at(&first.fragment_with(&first), 35);
+    let contents =
+        CheckpointContents::new_with_causally_ordered_transactions([other, traced].into_iter());
+    trace.record_checkpoint_at(1, &contents, 50);
+    trace.record_checkpoint_at(2, &contents, 60);
+
+    let timeline = trace.timeline(&traced.transaction).unwrap();
+    assert_eq!(timeline.selected_at_ms, 10);
+    assert_eq!(
+        timeline.proposals,
+        vec![TracedProposal {
+            sequence_number: 1,
+            authority: names[0],
+            at_ms: 20,
+        }]
+    );
+    assert_eq!(
+        timeline.fragments,
+        vec![TracedFragment {
+            sequence_number: 1,
+            proposer: names[0],
+            other: names[1],
+            at_ms: 30,
+        }]
+    );
+    assert_eq!(
+        timeline.checkpoint,
+        Some(TracedCheckpoint {
+            sequence_number: 1,
+            at_ms: 50,
+        })
+    );
+    assert_eq!(timeline.inclusion_latency_ms(), Some(30));
+
+    // Unselecting a transaction makes room for another one.
+    assert_eq!(trace.unselect(&traced.transaction), Some(timeline));
+    assert!(trace.select(other.transaction));
+    assert_eq!(trace.timelines().len(), 1);
+}
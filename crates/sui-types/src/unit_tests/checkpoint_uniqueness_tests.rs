@@ -0,0 +1,63 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+
+fn contents(transactions: &[ExecutionDigests]) -> CheckpointContents {
+    CheckpointContents::new_with_causally_ordered_transactions(transactions.iter().cloned())
+}
+
+#[test]
+fn test_uniqueness_tracker() {
+    let mut tracker = UniquenessTracker::new(2, 1000);
+    let transactions: Vec<_> = (0..4).map(|_| ExecutionDigests::random()).collect();
+
+    assert!(tracker
+        .check_and_insert(0, &contents(&transactions[0..2]))
+        .is_empty());
+    // Checking the same checkpoint again does not report its own transactions.
+    assert!(tracker
+        .check_and_insert(0, &contents(&transactions[0..2]))
+        .is_empty());
+
+    // A duplicate within the window is attributed to its checkpoint.
+    let duplicates = tracker.check_and_insert(1, &contents(&transactions[1..3]));
+    assert_eq!(
+        duplicates,
+        vec![DuplicateTransaction {
+            digests: transactions[1],
+            checkpoint: 1,
+            previous: Some(0),
+        }]
+    );
+
+    // Checkpoint 0 leaves the window: its transactions are only in the bloom filter.
+    assert!(tracker
+        .check_and_insert(2, &contents(&transactions[3..4]))
+        .is_empty());
+    let duplicates = tracker.check_and_insert(3, &contents(&transactions[0..1]));
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].digests, transactions[0]);
+    assert_eq!(duplicates[0].previous, None);
+}
+
+#[test]
+fn test_uniqueness_tracker_bloom_rotation() {
+    let mut tracker = UniquenessTracker::new(1, 1);
+    let transactions: Vec<_> = (0..4).map(|_| ExecutionDigests::random()).collect();
+    for (seq, digests) in transactions.iter().enumerate() {
+        assert!(tracker
+            .check_and_insert(seq as u64, &contents(&[*digests]))
+            .is_empty());
+    }
+    // Only the transactions of the last two filters are still remembered.
+    assert_eq!(
+        tracker
+            .check_and_insert(4, &contents(&transactions[1..2]))
+            .len(),
+        1
+    );
+    assert!(tracker
+        .check_and_insert(5, &contents(&transactions[0..1]))
+        .is_empty());
+}
@@ -0,0 +1,70 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::traits::KeyPair;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::utils::make_committee_key;
+
+fn committee() -> (Vec<AuthorityName>, Committee) {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    (keys.iter().map(|k| k.public().into()).collect(), committee)
+}
+
+fn secs(start: Instant, secs: u64) -> Instant {
+    start + Duration::from_secs(secs)
+}
+
+#[test]
+fn test_watchdog_stages() {
+    let (names, committee) = committee();
+    let start = Instant::now();
+    let mut watchdog = CheckpointLivenessWatchdog::new(committee, WatchdogTimeouts::default());
+    assert!(watchdog.stalls_at(secs(start, 100)).is_empty());
+
+    // Each authority has stake 1, and the quorum is 3.
+    watchdog.record_proposal_at(1, names[0], start);
+    watchdog.record_proposal_at(1, names[1], secs(start, 1));
+    assert!(watchdog.stalls_at(secs(start, 9)).is_empty());
+    assert_eq!(
+        watchdog.stalls_at(secs(start, 10)),
+        vec![CheckpointStall {
+            sequence_number: 1,
+            stalled_for_ms: 10_000,
+            diagnosis: StallDiagnosis::NoQuorumProposals { received_stake: 2 },
+        }]
+    );
+
+    // The fragments stage is timed from the proposal completing the quorum.
+    watchdog.record_proposal_at(1, names[2], secs(start, 20));
+    watchdog.record_fragment_at(1, names[0], names[1], secs(start, 21));
+    assert!(watchdog.stalls_at(secs(start, 49)).is_empty());
+    assert_eq!(
+        watchdog.stalls_at(secs(start, 50))[0].diagnosis,
+        StallDiagnosis::FragmentGraphDisconnected { fragments: 1 }
+    );
+
+    watchdog.record_contents_constructed_at(1, secs(start, 60));
+    watchdog.record_signature_at(1, names[0], secs(start, 61));
+    assert!(watchdog.stalls_at(secs(start, 89)).is_empty());
+    assert_eq!(
+        watchdog.stalls_at(secs(start, 90))[0].diagnosis,
+        StallDiagnosis::CertSignaturesMissing { stake: 2 }
+    );
+
+    // Certified checkpoints are no longer watched, nor can they be recorded again.
+    watchdog.record_proposal_at(2, names[0], secs(start, 90));
+    watchdog.record_certified(1);
+    watchdog.record_proposal_at(1, names[3], secs(start, 90));
+    let stalls = watchdog.stalls_at(secs(start, 200));
+    assert_eq!(stalls.len(), 1);
+    assert_eq!(stalls[0].sequence_number, 2);
+    assert_eq!(
+        serde_json::from_str::<Vec<CheckpointStall>>(&serde_json::to_string(&stalls).unwrap())
+            .unwrap(),
+        stalls
+    );
+}
@@ -0,0 +1,78 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::utils::{make_committee_key, CheckpointChainBuilder};
+
+#[test]
+fn test_light_client_follows_chain() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone())
+        .epochs(3)
+        .checkpoints_per_epoch(3)
+        .rotate_committee(5)
+        .build(&mut rng);
+    let checkpoints: Vec<_> = chain.checkpoints.iter().map(|(c, _)| c.clone()).collect();
+
+    // Following every checkpoint.
+    let mut client = LightClient::new(committee.clone());
+    for checkpoint in &checkpoints {
+        client.verify_checkpoint(checkpoint).unwrap();
+    }
+    assert_eq!(client.committee().epoch, 2);
+    assert_eq!(client.latest().unwrap().sequence_number, 8);
+
+    // Older checkpoints are refused.
+    assert!(client.verify_checkpoint(&checkpoints[7]).is_err());
+
+    // Skipping to the last epoch through the epoch changes.
+    let mut client = LightClient::new(committee.clone());
+    client
+        .verify_epoch_changes(&chain.epoch_change_proofs())
+        .unwrap();
+    assert_eq!(client.committee(), chain.committee(2).unwrap());
+    client.verify_checkpoint(&checkpoints[7]).unwrap();
+
+    // The last checkpoint of an epoch cannot be skipped.
+    let mut client = LightClient::new(committee);
+    client.verify_checkpoint(&checkpoints[0]).unwrap();
+    assert!(client.verify_checkpoint(&checkpoints[3]).is_err());
+
+    // A directly following checkpoint must link to the latest one.
+    let mut unlinked = checkpoints[1].clone();
+    unlinked.data_mut_for_testing().previous_digest = None;
+    assert!(client.verify_checkpoint(&unlinked).is_err());
+    client.verify_checkpoint(&checkpoints[1]).unwrap();
+}
+
+#[test]
+fn test_light_client_verifies_inclusion() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (keys, committee) = make_committee_key(&mut rng);
+    let chain = CheckpointChainBuilder::new(keys, committee.clone())
+        .epochs(2)
+        .transactions_per_checkpoint(3)
+        .rotate_committee(5)
+        .build(&mut rng);
+    let (checkpoint, contents) = &chain.checkpoints[0];
+    let transactions: Vec<_> = contents.iter().cloned().collect();
+    let proof = contents.prove_inclusion(&transactions[1]).unwrap();
+
+    let client = LightClient::new(committee);
+    client
+        .verify_inclusion(checkpoint, &transactions[1], &proof)
+        .unwrap();
+    assert!(client
+        .verify_inclusion(checkpoint, &transactions[0], &proof)
+        .is_err());
+
+    // Checkpoints of another epoch are refused.
+    let (other, contents) = chain.checkpoints.last().unwrap();
+    let transaction = contents.iter().next().unwrap();
+    let proof = contents.prove_inclusion(transaction).unwrap();
+    assert!(client.verify_inclusion(other, transaction, &proof).is_err());
+}
@@ -0,0 +1,48 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+
+#[test]
+fn test_chacha_stream_is_stable() {
+    // The first output of ChaCha20 keyed with zeroes, as per the reference test vectors.
+    let mut rng = ChaCha20Rng::from_seed([0; 32]);
+    assert_eq!(rng.next_u64(), 0x903df1a0ade0b876);
+}
+
+#[test]
+fn test_shuffle_is_stable() {
+    // These values must never change: other nodes and other versions of the code rely on
+    // deriving the same permutation from the same seed.
+    assert_eq!(
+        deterministic_shuffled([0; 32], 0..10),
+        vec![9, 7, 3, 6, 1, 4, 8, 5, 2, 0]
+    );
+
+    let mut seed = [0u8; 32];
+    seed.iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+    assert_eq!(
+        deterministic_shuffled(seed, 0..10),
+        vec![6, 0, 8, 4, 1, 5, 9, 2, 7, 3]
+    );
+
+    assert_eq!(
+        deterministic_shuffled([7; 32], 0..20),
+        vec![19, 9, 18, 2, 4, 13, 14, 6, 17, 0, 1, 7, 11, 3, 10, 5, 15, 12, 16, 8]
+    );
+}
+
+#[test]
+fn test_shuffle_is_a_permutation() {
+    let mut items: Vec<u32> = (0..100).collect();
+    deterministic_shuffle([42; 32], &mut items);
+    assert_ne!(items, (0..100).collect::<Vec<_>>());
+    items.sort_unstable();
+    assert_eq!(items, (0..100).collect::<Vec<_>>());
+
+    // Empty and single element inputs are left untouched.
+    let mut empty: Vec<u32> = vec![];
+    deterministic_shuffle([42; 32], &mut empty);
+    assert!(empty.is_empty());
+    assert_eq!(deterministic_shuffled([42; 32], [1]), vec![1]);
+}
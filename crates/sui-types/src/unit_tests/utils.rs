@@ -4,9 +4,20 @@
 use fastcrypto::traits::KeyPair as KeypairTraits;
 
 use crate::{
-    committee::Committee,
-    crypto::{get_key_pair_from_rng, AuthorityKeyPair, AuthorityPublicKeyBytes},
+    base_types::{ExecutionDigests, TransactionDigest, TransactionEffectsDigest},
+    committee::{Committee, EpochId},
+    crypto::{
+        get_key_pair_from_rng, AuthorityKeyPair, AuthorityPublicKeyBytes, AuthoritySignInfo,
+        Signable,
+    },
+    error::SuiResult,
+    gas::GasCostSummary,
+    messages_checkpoint::{
+        CertifiedCheckpointSummary, CheckpointContents, CheckpointSequenceNumber,
+        CheckpointSummary, SignedCheckpointSummary,
+    },
 };
+use rand::Rng;
 use std::collections::BTreeMap;
 
 pub fn make_committee_key<R>(rand: &mut R) -> (Vec<AuthorityKeyPair>, Committee)
@@ -35,3 +46,215 @@ where
     let committee = Committee::new(0, authorities).unwrap();
     (keys, committee)
 }
+
+/// Builds a chain of certified checkpoints, linked by their digests, starting in the epoch of
+/// the given committee. Every epoch but the last ends with a checkpoint handing over to the
+/// committee of the next epoch.
+pub struct CheckpointChainBuilder {
+    keys: Vec<AuthorityKeyPair>,
+    committee: Committee,
+    epochs: u64,
+    checkpoints_per_epoch: u64,
+    transactions_per_checkpoint: usize,
+    next_committee_size: Option<usize>,
+}
+
+impl CheckpointChainBuilder {
+    /// A single epoch of one checkpoint with one transaction, signed by `keys`, the keys of
+    /// the members of `committee`.
+    pub fn new(keys: Vec<AuthorityKeyPair>, committee: Committee) -> Self {
+        Self {
+            keys,
+            committee,
+            epochs: 1,
+            checkpoints_per_epoch: 1,
+            transactions_per_checkpoint: 1,
+            next_committee_size: None,
+        }
+    }
+
+    pub fn epochs(mut self, epochs: u64) -> Self {
+        self.epochs = epochs;
+        self
+    }
+
+    pub fn checkpoints_per_epoch(mut self, checkpoints: u64) -> Self {
+        self.checkpoints_per_epoch = checkpoints;
+        self
+    }
+
+    pub fn transactions_per_checkpoint(mut self, transactions: usize) -> Self {
+        self.transactions_per_checkpoint = transactions;
+        self
+    }
+
+    /// Hand over to a new committee of `size` authorities at every epoch change, instead of
+    /// keeping the same authorities.
+    pub fn rotate_committee(mut self, size: usize) -> Self {
+        self.next_committee_size = Some(size);
+        self
+    }
+
+    pub fn build<R>(self, rng: &mut R) -> CheckpointChain
+    where
+        R: rand::CryptoRng + rand::RngCore,
+    {
+        assert!(self.epochs > 0 && self.checkpoints_per_epoch > 0);
+        let mut keys = self.keys;
+        let mut committee = self.committee;
+        let mut chain = CheckpointChain {
+            committees: Vec::new(),
+            checkpoints: Vec::new(),
+        };
+        let mut previous_digest = None;
+        for epoch_index in 0..self.epochs {
+            let next = (epoch_index + 1 < self.epochs).then(|| {
+                let (next_keys, next_committee) = match self.next_committee_size {
+                    Some(size) => make_committee_key_num(size, rng),
+                    None => (
+                        keys.iter().map(|key| key.copy()).collect(),
+                        committee.clone(),
+                    ),
+                };
+                let next_committee = Committee::new(
+                    committee.epoch + 1,
+                    next_committee.voting_rights.into_iter().collect(),
+                )
+                .unwrap();
+                (next_keys, next_committee)
+            });
+
+            for index in 0..self.checkpoints_per_epoch {
+                let contents = CheckpointContents::new_with_causally_ordered_transactions(
+                    (0..self.transactions_per_checkpoint).map(|_| {
+                        ExecutionDigests::new(
+                            TransactionDigest::new(rng.gen()),
+                            TransactionEffectsDigest(rng.gen()),
+                        )
+                    }),
+                );
+                let next_epoch_committee = next
+                    .as_ref()
+                    .filter(|_| index + 1 == self.checkpoints_per_epoch)
+                    .map(|(_, next_committee)| next_committee.clone());
+                let sequence_number = chain.checkpoints.len() as CheckpointSequenceNumber;
+                let signed = keys
+                    .iter()
+                    .map(|key| {
+                        SignedCheckpointSummary::new_from_contents(
+                            committee.epoch,
+                            sequence_number,
+                            key.public().into(),
+                            key,
+                            &contents,
+                            previous_digest,
+                            GasCostSummary::default(),
+                            next_epoch_committee.clone(),
+                        )
+                    })
+                    .collect();
+                let checkpoint = CertifiedCheckpointSummary::aggregate(signed, &committee).unwrap();
+                previous_digest = Some(checkpoint.data().digest());
+                chain.checkpoints.push((checkpoint, contents));
+            }
+
+            let (next_keys, next_committee) = match next {
+                Some(next) => next,
+                None => {
+                    chain.committees.push((keys, committee));
+                    break;
+                }
+            };
+            chain
+                .committees
+                .push((std::mem::replace(&mut keys, next_keys), committee));
+            committee = next_committee;
+        }
+        chain
+    }
+}
+
+/// The output of `CheckpointChainBuilder`.
+pub struct CheckpointChain {
+    /// The committee of every epoch of the chain, in order, with the keys of its members.
+    pub committees: Vec<(Vec<AuthorityKeyPair>, Committee)>,
+    pub checkpoints: Vec<(CertifiedCheckpointSummary, CheckpointContents)>,
+}
+
+impl CheckpointChain {
+    pub fn committee(&self, epoch: EpochId) -> Option<&Committee> {
+        self.committees
+            .iter()
+            .map(|(_, committee)| committee)
+            .find(|committee| committee.epoch == epoch)
+    }
+
+    pub fn keys(&self, epoch: EpochId) -> Option<&[AuthorityKeyPair]> {
+        self.committees
+            .iter()
+            .find(|(_, committee)| committee.epoch == epoch)
+            .map(|(keys, _)| &keys[..])
+    }
+
+    /// The signatures of the members of the committee of `epoch` on `message`, e.g. on
+    /// summaries derived from the ones of the chain.
+    pub fn sign<T: Signable<Vec<u8>>>(
+        &self,
+        epoch: EpochId,
+        message: &T,
+    ) -> Vec<AuthoritySignInfo> {
+        self.keys(epoch)
+            .unwrap()
+            .iter()
+            .map(|key| AuthoritySignInfo::new(epoch, message, key.public().into(), key))
+            .collect()
+    }
+
+    /// Certify `summary` with the signatures of all the members of the committee of its epoch,
+    /// e.g. a summary derived from the ones of the chain.
+    pub fn certify(&self, summary: CheckpointSummary) -> CertifiedCheckpointSummary {
+        let members = self.keys(summary.epoch).unwrap().len();
+        self.certify_by(summary, 0..members)
+    }
+
+    /// Certify `summary` with the signatures of the members of the committee of its epoch at
+    /// `signers`, indices into `keys`.
+    pub fn certify_by(
+        &self,
+        summary: CheckpointSummary,
+        signers: impl IntoIterator<Item = usize>,
+    ) -> CertifiedCheckpointSummary {
+        let keys = self.keys(summary.epoch).unwrap();
+        let signed = signers
+            .into_iter()
+            .map(|index| {
+                let key = &keys[index];
+                SignedCheckpointSummary::new_from_summary(summary.clone(), key.public().into(), key)
+            })
+            .collect();
+        CertifiedCheckpointSummary::aggregate(signed, self.committee(summary.epoch).unwrap())
+            .unwrap()
+    }
+
+    /// The last checkpoint of every epoch but the last one.
+    pub fn epoch_change_proofs(&self) -> Vec<CertifiedCheckpointSummary> {
+        self.checkpoints
+            .iter()
+            .filter(|(checkpoint, _)| checkpoint.data().next_epoch_committee.is_some())
+            .map(|(checkpoint, _)| checkpoint.clone())
+            .collect()
+    }
+
+    /// Check every certificate, link and epoch change of the chain, from the first committee.
+    pub fn verify(&self) -> SuiResult {
+        CertifiedCheckpointSummary::verify_chain(
+            self.committees[0].1.clone(),
+            0,
+            None,
+            self.checkpoints
+                .iter()
+                .map(|(checkpoint, contents)| (checkpoint, Some(contents))),
+        )?;
+        Ok(())
+    }
+}
@@ -8,7 +8,7 @@ use thiserror::Error;
 
 use std::collections::{BTreeMap, BTreeSet};
 
-use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 
 use crate::committee::StakeUnit;
 use crate::{base_types::AuthorityName, committee::Committee};
@@ -82,6 +82,18 @@ impl Accumulator {
             self.insert(i);
         }
     }
+
+    /// The compressed encoding of the accumulated point.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.accumulator.compress().to_bytes()
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Result<Accumulator, WaypointError> {
+        CompressedRistretto(bytes)
+            .decompress()
+            .map(|accumulator| Accumulator { accumulator })
+            .ok_or_else(|| WaypointError::generic("Invalid accumulator encoding".to_string()))
+    }
 }
 
 impl Debug for Accumulator {
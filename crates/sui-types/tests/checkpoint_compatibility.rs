@@ -0,0 +1,259 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Serialized samples of every released version of the checkpoint types, kept in
+//! `tests/data/checkpoint_compatibility`. Current code must decode each sample into the value
+//! it was made from, encode it back to the same bytes, and compute the same digest, so that
+//! adding a field to a versioned type cannot silently break the wire format.
+//!
+//! Samples are never changed: a new version of a type gets new samples. A missing sample fails
+//! the test. Running it with `WRITE_CHECKPOINT_SAMPLES=1` writes the missing samples from their
+//! values instead, to be reviewed and checked in; existing samples are never overwritten.
+
+use std::fmt::Debug;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sui_types::base_types::{ExecutionDigests, TransactionDigest, TransactionEffectsDigest};
+use sui_types::checkpoint_summary_versions::{
+    CheckpointSummaryAccess, CheckpointSummaryV1, CheckpointSummaryV2, CheckpointSummaryV3,
+    VersionedCheckpointSummary,
+};
+use sui_types::gas::GasCostSummary;
+use sui_types::messages_checkpoint::{
+    CheckpointContents, CheckpointContentsV2, CheckpointSummary, ConsensusCommitRef,
+};
+
+/// Set to write missing samples rather than fail on them.
+const WRITE_SAMPLES_VAR: &str = "WRITE_CHECKPOINT_SAMPLES";
+
+struct Sample {
+    bcs: Vec<u8>,
+    digest: Vec<u8>,
+}
+
+fn sample_path(name: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests/data/checkpoint_compatibility");
+    path.push(name);
+    path
+}
+
+/// Samples are text files of `key: hex` lines, with `#` comments.
+fn read_sample(name: &str) -> Option<Sample> {
+    let text = std::fs::read_to_string(sample_path(name)).ok()?;
+    let field = |key: &str| {
+        let line = text
+            .lines()
+            .find_map(|line| line.strip_prefix(&format!("{key}: ")))
+            .unwrap_or_else(|| panic!("Sample {name} has no {key}"));
+        hex::decode(line.trim()).unwrap()
+    };
+    Some(Sample {
+        bcs: field("bcs"),
+        digest: field("digest"),
+    })
+}
+
+fn check_sample<T, C>(name: &str, value: &T, digest: impl Fn(&T) -> [u8; 32], comparable: C)
+where
+    T: Serialize + DeserializeOwned,
+    C: Fn(&T) -> Box<dyn Debug + '_>,
+{
+    let sample = match read_sample(name) {
+        Some(sample) => sample,
+        None if std::env::var_os(WRITE_SAMPLES_VAR).is_none() => {
+            panic!(
+                "Sample {name} is missing: run with {WRITE_SAMPLES_VAR}=1 to write it, \
+                 then review and check it in"
+            );
+        }
+        None => {
+            std::fs::write(
+                sample_path(name),
+                format!(
+                    "# Written by the compatibility harness.\nbcs: {}\ndigest: {}\n",
+                    hex::encode(bcs::to_bytes(value).unwrap()),
+                    hex::encode(digest(value))
+                ),
+            )
+            .unwrap();
+            panic!("Sample {name} has been written: review and check it in");
+        }
+    };
+    let decoded: T = bcs::from_bytes(&sample.bcs)
+        .unwrap_or_else(|e| panic!("Sample {name} no longer decodes: {e}"));
+    assert_eq!(
+        format!("{:?}", comparable(&decoded)),
+        format!("{:?}", comparable(value)),
+        "Sample {name} decodes to another value"
+    );
+    assert_eq!(
+        bcs::to_bytes(&decoded).unwrap(),
+        sample.bcs,
+        "Sample {name} encodes differently"
+    );
+    assert_eq!(
+        digest(&decoded).to_vec(),
+        sample.digest,
+        "Sample {name} has another digest"
+    );
+}
+
+fn digests(transaction: u8, effects: u8) -> ExecutionDigests {
+    ExecutionDigests::new(
+        TransactionDigest::new([transaction; 32]),
+        TransactionEffectsDigest([effects; 32]),
+    )
+}
+
+fn contents_v1() -> CheckpointContents {
+    CheckpointContents::new_with_causally_ordered_transactions(
+        [digests(0x11, 0x12), digests(0x21, 0x22)].into_iter(),
+    )
+}
+
+fn contents_v2() -> CheckpointContentsV2 {
+    CheckpointContentsV2 {
+        contents: CheckpointContents::new_with_causally_ordered_transactions(
+            [digests(0x31, 0x32)].into_iter(),
+        ),
+        consensus_commits: vec![
+            ConsensusCommitRef {
+                round: 4,
+                digest: [0x41; 32],
+            },
+            ConsensusCommitRef {
+                round: 6,
+                digest: [0x42; 32],
+            },
+        ],
+    }
+}
+
+fn gas_cost_summary() -> GasCostSummary {
+    GasCostSummary {
+        computation_cost: 100,
+        storage_cost: 200,
+        storage_rebate: 50,
+    }
+}
+
+fn summary_v1() -> CheckpointSummaryV1 {
+    CheckpointSummaryV1 {
+        epoch: 1,
+        sequence_number: 2,
+        content_digest: contents_v1().digest(),
+        previous_digest: Some([5; 32]),
+        gas_cost_summary: gas_cost_summary(),
+        next_epoch_committee: None,
+    }
+}
+
+fn summary_v2() -> CheckpointSummaryV2 {
+    CheckpointSummaryV2 {
+        epoch: 1,
+        sequence_number: 3,
+        content_digest: contents_v2().digest(),
+        contents_merkle_root: [7; 32],
+        previous_digest: Some(summary_v1().digest()),
+        gas_cost_summary: gas_cost_summary(),
+        timestamp_ms: 1_650_000_000_000,
+        next_epoch_committee: None,
+        end_of_epoch_data: None,
+    }
+}
+
+fn summary_v3() -> CheckpointSummaryV3 {
+    CheckpointSummaryV3 {
+        epoch: 1,
+        sequence_number: 4,
+        content_digest: contents_v2().digest(),
+        contents_merkle_root: [7; 32],
+        object_changes_commitment: Some([9; 32]),
+        previous_digest: Some(summary_v2().digest()),
+        gas_cost_summary: gas_cost_summary(),
+        timestamp_ms: 1_650_000_000_000,
+        next_epoch_committee: None,
+        end_of_epoch_data: None,
+    }
+}
+
+fn summary_v4() -> CheckpointSummary {
+    CheckpointSummary {
+        epoch: 1,
+        sequence_number: 5,
+        content_digest: contents_v1().digest(),
+        contents_merkle_root: contents_v1().commitments().merkle_root,
+        object_changes_commitment: Some([9; 32]),
+        previous_digest: Some(summary_v3().digest()),
+        gas_cost_summary: gas_cost_summary(),
+        next_epoch_committee: None,
+    }
+}
+
+fn contents_fields(contents: &CheckpointContents) -> Box<dyn Debug + '_> {
+    Box::new(contents.iter().collect::<Vec<_>>())
+}
+
+#[test]
+fn test_checkpoint_contents_compatibility() {
+    check_sample(
+        "contents_v1.txt",
+        &contents_v1(),
+        CheckpointContents::digest,
+        contents_fields,
+    );
+    check_sample(
+        "contents_v2.txt",
+        &contents_v2(),
+        CheckpointContentsV2::digest,
+        |contents| {
+            Box::new((
+                contents_fields(&contents.contents),
+                contents.consensus_commits.clone(),
+            ))
+        },
+    );
+}
+
+#[test]
+fn test_checkpoint_summary_compatibility() {
+    check_sample(
+        "summary_v1.txt",
+        &summary_v1(),
+        CheckpointSummaryAccess::digest,
+        |summary| Box::new(summary.clone()),
+    );
+    check_sample(
+        "versioned_summary_v1.txt",
+        &VersionedCheckpointSummary::from(summary_v1()),
+        CheckpointSummaryAccess::digest,
+        |summary| Box::new(summary.clone()),
+    );
+    check_sample(
+        "versioned_summary_v2.txt",
+        &VersionedCheckpointSummary::from(summary_v2()),
+        CheckpointSummaryAccess::digest,
+        |summary| Box::new(summary.clone()),
+    );
+    check_sample(
+        "versioned_summary_v3.txt",
+        &VersionedCheckpointSummary::from(summary_v3()),
+        CheckpointSummaryAccess::digest,
+        |summary| Box::new(summary.clone()),
+    );
+    check_sample(
+        "summary_v4.txt",
+        &summary_v4(),
+        CheckpointSummary::digest,
+        |summary| Box::new(summary.clone()),
+    );
+    check_sample(
+        "versioned_summary_v4.txt",
+        &VersionedCheckpointSummary::from(summary_v4()),
+        CheckpointSummaryAccess::digest,
+        |summary| Box::new(summary.clone()),
+    );
+}
@@ -19,7 +19,7 @@ use sui_types::{
         CallArg, CheckpointStreamRequest, CheckpointStreamResponseItem, ExecutionStatus, ObjectArg,
         VerifiedTransaction,
     },
-    messages_checkpoint::AuthenticatedCheckpoint,
+    messages_checkpoint::{AuthenticatedCheckpoint, RemoteProposal},
 };
 use test_utils::transaction::{publish_counter_package, submit_shared_object_transaction};
 use test_utils::{
@@ -171,7 +171,8 @@ async fn sequence_fragments() {
     // Make a checkpoint fragment and sequence it.
     let p1 = proposals.pop().unwrap();
     let p2 = proposals.pop().unwrap();
-    let fragment = p1.fragment_with(&p2);
+    let remote = RemoteProposal::new(p2.into_inner(), p1.name()).unwrap();
+    let fragment = p1.fragment_with_remote(&remote);
 
     for handle in handles.iter_mut() {
         let _response = handle.with(|node| {
@@ -257,8 +258,8 @@ async fn end_to_end() {
 
     let checkpoint_stream = checkpoint_stream.lock().unwrap();
     assert!(checkpoint_stream.len() >= 2);
-    assert_eq!(checkpoint_stream[0].summary.sequence_number, 0);
-    assert_eq!(checkpoint_stream[1].summary.sequence_number, 1);
+    assert_eq!(checkpoint_stream[0].data().sequence_number, 0);
+    assert_eq!(checkpoint_stream[1].data().sequence_number, 1);
 }
 
 #[sim_test]
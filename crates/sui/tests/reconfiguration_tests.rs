@@ -165,7 +165,7 @@ async fn reconfig_end_to_end_tests() {
                 .unwrap()
             {
                 assert_eq!(
-                    cert.summary.next_epoch_committee.unwrap(),
+                    cert.data().next_epoch_committee.clone().unwrap(),
                     expected_committee
                 );
             } else {